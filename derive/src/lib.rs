@@ -0,0 +1,206 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Derives `dds_omg::dcps::type_support::DdsType`, along with CDR serialization,
+ for structs used as topic types.
+
+ Members are serialized in declaration order. Members marked with `#[key]`
+ form the key of the topic, which makes it a WITH_KEY topic. The struct also
+ implements `DescribeType`, as a final structure whose member ids follow
+ declaration order (the members of tuple structs are named by their index),
+ so every member must implement `DescribeType` too. The type name
+ defaults to the name of the struct, and may be overridden with
+ `#[dds(type_name = "...")]`:
+
+ ```ignore
+ #[derive(DdsType)]
+ #[dds(type_name = "ShapeType")]
+ struct Shape {
+     #[key]
+     color: String,
+     x: i32,
+     y: i32,
+     size: i32,
+ }
+ ```
+ */
+#![allow(clippy::redundant_field_names)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Data, DeriveInput, Fields, Ident, Index, LitStr, Type};
+use syn::spanned::Spanned;
+
+#[proc_macro_derive(DdsType, attributes(key, dds))]
+pub fn derive_dds_type(input: TokenStream) -> TokenStream {
+	let input = syn::parse_macro_input!(input as DeriveInput);
+	match expand(&input) {
+		Ok(tokens) => tokens.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+/// A member of the struct, and whether it is part of the key.
+struct Member {
+	/// `self.<access>` reaches the member.
+	access: TokenStream2,
+	name: Option<Ident>,
+	ty: Type,
+	is_key: bool,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let fields = match input.data {
+		Data::Struct(ref s) => &s.fields,
+		_ => return Err(syn::Error::new(input.span(), "DdsType can only be derived for structs")),
+	};
+
+	let mut members = Vec::new();
+	for (i, f) in fields.iter().enumerate() {
+		let access = match f.ident {
+			Some(ref ident) => quote!(#ident),
+			None => {
+				let index = Index::from(i);
+				quote!(#index)
+			},
+		};
+		let mut is_key = false;
+		for attr in &f.attrs {
+			if attr.path().is_ident("key") {
+				attr.meta.require_path_only()?;
+				is_key = true;
+			}
+		}
+		members.push(Member {access: access, name: f.ident.clone(), ty: f.ty.clone(), is_key: is_key});
+	}
+
+	let type_name = type_name(input)?;
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let serialize = members.iter().map(|m| {
+		let access = &m.access;
+		quote!(::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.#access, enc);)
+	});
+	let serialize_key = members.iter().filter(|m| m.is_key).map(|m| {
+		let access = &m.access;
+		quote!(::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.#access, enc);)
+	});
+	let max_end = members.iter().map(|m| {
+		let ty = &m.ty;
+		quote!(let position = <#ty as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;)
+	});
+	let key_max_end = members.iter().filter(|m| m.is_key).map(|m| {
+		let ty = &m.ty;
+		quote!(let position = <#ty as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;)
+	});
+	let deserialize = members.iter().map(|m| {
+		let value = quote!(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?);
+		match m.name {
+			Some(ref ident) => quote!(#ident: #value,),
+			None => quote!(#value,),
+		}
+	});
+	let construct = match *fields {
+		Fields::Named(_) => quote!(#name { #(#deserialize)* }),
+		Fields::Unnamed(_) => quote!(#name ( #(#deserialize)* )),
+		Fields::Unit => quote!(#name),
+	};
+	let describe = members.iter().enumerate().map(|(i, m)| {
+		let ty = &m.ty;
+		let member = match m.name {
+			Some(ref ident) => ident.to_string(),
+			None => i.to_string(),
+		};
+		let id = i as u32;
+		let is_key = m.is_key;
+		quote! {
+			{
+				let mut m = ::dds_omg::dcps::dynamic_type::MemberDescriptor::new(#member, #id,
+					<#ty as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type());
+				m.is_key = #is_key;
+				m
+			},
+		}
+	});
+	let topic_kind = if members.iter().any(|m| m.is_key) {
+		quote!(::dds_omg::rtps::TopicKind_t::WITH_KEY)
+	} else {
+		quote!(::dds_omg::rtps::TopicKind_t::NO_KEY)
+	};
+
+	Ok(quote! {
+		impl #impl_generics ::dds_omg::cdr::encoder::CdrSerialize for #name #ty_generics #where_clause {
+			fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				#(#serialize)*
+			}
+
+			fn max_end_position(position: usize) -> ::std::option::Option<usize> {
+				#(#max_end)*
+				::std::option::Option::Some(position)
+			}
+		}
+
+		impl #impl_generics ::dds_omg::cdr::decoder::CdrDeserialize for #name #ty_generics #where_clause {
+			fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder)
+					-> ::std::result::Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+				::std::result::Result::Ok(#construct)
+			}
+		}
+
+		impl #impl_generics ::dds_omg::dcps::dynamic_type::DescribeType for #name #ty_generics #where_clause {
+			fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+				::dds_omg::dcps::dynamic_type::DynamicType::structure(#type_name,
+					::dds_omg::cdr::encapsulation::Extensibility::Final,
+					::std::option::Option::None,
+					::std::vec![#(#describe)*])
+			}
+		}
+
+		impl #impl_generics ::dds_omg::dcps::type_support::DdsType for #name #ty_generics #where_clause {
+			fn type_name() -> &'static str {
+				#type_name
+			}
+
+			fn topic_kind() -> ::dds_omg::rtps::TopicKind_t {
+				#topic_kind
+			}
+
+			#[allow(unused_variables)]
+			fn serialize_key(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				#(#serialize_key)*
+			}
+
+			fn key_max_size() -> ::std::option::Option<usize> {
+				let position = 0;
+				#(#key_max_end)*
+				::std::option::Option::Some(position)
+			}
+		}
+	})
+}
+
+/// The name given by `#[dds(type_name = "...")]`, otherwise the name of the
+/// struct.
+fn type_name(input: &DeriveInput) -> syn::Result<String> {
+	let mut type_name = input.ident.to_string();
+	for attr in &input.attrs {
+		if !attr.path().is_ident("dds") {
+			continue;
+		}
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("type_name") {
+				type_name = meta.value()?.parse::<LitStr>()?.value();
+				Ok(())
+			} else {
+				Err(meta.error("unsupported dds attribute"))
+			}
+		})?;
+	}
+	Ok(type_name)
+}
@@ -0,0 +1,66 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ Compiles IDL into Rust, for use with this crate (see the idl module).
+
+ Usage: idlc <input.idl> [-o <output.rs>]
+
+ The generated Rust is written to standard output unless an output file is
+ given.
+ */
+extern crate dds_omg;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+
+use dds_omg::idl;
+
+fn usage() -> ! {
+	eprintln!("usage: idlc <input.idl> [-o <output.rs>]");
+	process::exit(2)
+}
+
+fn main() {
+	let mut input = None;
+	let mut output = None;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-o" => output = Some(args.next().unwrap_or_else(|| usage())),
+			"-h" | "--help" => usage(),
+			_ if input.is_none() => input = Some(arg),
+			_ => usage(),
+		}
+	}
+	let input = input.unwrap_or_else(|| usage());
+
+	let source = match fs::read_to_string(&input) {
+		Ok(s) => s,
+		Err(e) => {
+			eprintln!("idlc: can't read {}: {}", input, e);
+			process::exit(1)
+		},
+	};
+
+	let code = match idl::compile(&source) {
+		Ok(c) => c,
+		Err(ref e) if e.line == 0 => {
+			eprintln!("{}: {}", input, e);
+			process::exit(1)
+		},
+		Err(e) => {
+			eprintln!("{}:{}", input, e);
+			process::exit(1)
+		},
+	};
+
+	let written = match output {
+		Some(ref path) => fs::write(path, code),
+		None => io::stdout().write_all(code.as_bytes()),
+	};
+	if let Err(e) = written {
+		eprintln!("idlc: can't write output: {}", e);
+		process::exit(1)
+	}
+}
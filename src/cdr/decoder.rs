@@ -0,0 +1,599 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ Deserializes user data types from CDR.
+
+ The decoder mirrors `encoder`: primitives are aligned to their size relative
+ to the end of the encapsulation header, and the members of mutable types
+ are read as a parameter list (XCDR1) or preceeded by EMHEADERs (XCDR2). Each
+ member is handed out as a decoder of it's own, limited to the member's
+ length, so unknown members can be skipped.
+
+ While reading an XCDR2 delimited type, the decoder is limited to the length
+ given by the DHEADER. A reader of an appendable type can then tell whether
+ members added by a newer version of the type are present, and any it doesn't
+ know about are skipped by `end_delimited`.
+ */
+use std::cmp;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::str;
+use bytes::Bytes;
+use rtps::messages::submessage::header::Endianness;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+
+/// Describes why a value could not be deserialized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CdrError {
+	/// Fewer bytes remained than were needed to decode a value.
+	UnexpectedEnd {needed: usize, remaining: usize},
+	/// The encapsulation header names a representation which isn't supported.
+	UnsupportedEncapsulation(u16),
+	/// The encoded value violates CDR, or can't be represented by the type.
+	Invalid(&'static str),
+}
+
+impl fmt::Display for CdrError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			CdrError::UnexpectedEnd{needed, remaining} =>
+				write!(f, "needed {} bytes but only {} remain", needed, remaining),
+			CdrError::UnsupportedEncapsulation(id) =>
+				write!(f, "unsupported encapsulation 0x{:04x}", id),
+			CdrError::Invalid(reason) =>
+				write!(f, "invalid CDR: {}", reason),
+		}
+	}
+}
+
+impl error::Error for CdrError {}
+
+/// Implemented by every type which can be read from CDR.
+pub trait CdrDeserialize: Sized {
+	/// Read the CDR representation of Self from the decoder.
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError>;
+
+	/// Must agree with `CdrSerialize::is_primitive`.
+	fn is_primitive() -> bool {
+		false
+	}
+
+	/// Must agree with `CdrSerialize::is_primitive_innermost`.
+	fn is_primitive_innermost() -> bool {
+		Self::is_primitive()
+	}
+
+	/// Must agree with `CdrSerialize::serialize_elements`.
+	fn deserialize_elements(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		Self::deserialize(dec)
+	}
+}
+
+/// Reads CDR encoded values out of a `Bytes` buffer.
+pub struct CdrDecoder {
+	buf: Bytes,
+	pos: usize,
+	/// Reads stop here, which is before the end of buf while within a
+	/// delimited type.
+	limit: usize,
+	/// Distance from the alignment origin to the start of buf.
+	offset: usize,
+	encapsulation: Encapsulation,
+}
+
+/// A member of a mutable type.
+pub struct Member {
+	pub id: u32,
+	pub must_understand: bool,
+	/// Decoder limited to the value of the member.
+	pub value: CdrDecoder,
+}
+
+/// A DHEADER read by `begin_delimited` or `begin_members`, which must be
+/// handed back to `end_delimited` or `end_members`.
+pub struct Delimited {
+	end: Option<usize>,
+	limit: usize,
+}
+
+impl CdrDecoder {
+	/// Reads the encapsulation header at the start of a serialized payload.
+	pub fn from_payload(payload: Bytes) -> Result<CdrDecoder, CdrError> {
+		if payload.len() < ENCAPSULATION_HEADER_SIZE {
+			return Err(CdrError::UnexpectedEnd {
+				needed: ENCAPSULATION_HEADER_SIZE,
+				remaining: payload.len()
+			});
+		}
+
+		let id = u16::from_be_bytes([payload[0], payload[1]]);
+		let encapsulation = Encapsulation::from_id(id)
+			.ok_or(CdrError::UnsupportedEncapsulation(id))?;
+		Ok(CdrDecoder::new(payload.slice_from(ENCAPSULATION_HEADER_SIZE), encapsulation))
+	}
+
+	/// Creates a decoder for the CDR which follows an encapsulation header.
+	pub fn new(buf: Bytes, encapsulation: Encapsulation) -> CdrDecoder {
+		let limit = buf.len();
+		CdrDecoder {buf, pos: 0, limit, offset: 0, encapsulation}
+	}
+
+	pub fn encapsulation(&self) -> Encapsulation {
+		self.encapsulation
+	}
+
+	pub fn endianness(&self) -> Endianness {
+		self.encapsulation.endianness()
+	}
+
+	pub fn version(&self) -> CdrVersion {
+		self.encapsulation.version()
+	}
+
+	/// Number of bytes which have not yet been read, up to the end of the
+	/// delimited type currently being read.
+	pub fn remaining(&self) -> usize {
+		self.limit - self.pos
+	}
+
+	/// Skips padding until the position (relative to the alignment origin)
+	/// is a multiple of alignment, which is limited to the largest alignment
+	/// of the encapsulation.
+	pub fn align(&mut self, alignment: usize) -> Result<(), CdrError> {
+		let alignment = cmp::min(alignment, self.encapsulation.max_alignment());
+		let position = self.offset + self.pos;
+		let padding = (alignment - (position % alignment)) % alignment;
+		self.skip(padding)
+	}
+
+	pub fn skip(&mut self, len: usize) -> Result<(), CdrError> {
+		self.check(len)?;
+		self.pos += len;
+		Ok(())
+	}
+
+	/// Takes len octets, without copying or alignment.
+	pub fn get_octets(&mut self, len: usize) -> Result<Bytes, CdrError> {
+		self.check(len)?;
+		let b = self.buf.slice(self.pos, self.pos + len);
+		self.pos += len;
+		Ok(b)
+	}
+
+	pub fn get_bool(&mut self) -> Result<bool, CdrError> {
+		match self.get_u8()? {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(CdrError::Invalid("boolean must be 0 or 1")),
+		}
+	}
+
+	pub fn get_u8(&mut self) -> Result<u8, CdrError> {
+		self.check(1)?;
+		let v = self.buf[self.pos];
+		self.pos += 1;
+		Ok(v)
+	}
+
+	pub fn get_i8(&mut self) -> Result<i8, CdrError> {
+		self.get_u8().map(|v| v as i8)
+	}
+
+	pub fn get_u16(&mut self) -> Result<u16, CdrError> {
+		let mut b = [0u8; 2];
+		self.get_aligned(&mut b)?;
+		Ok(match self.endianness() {
+			Endianness::BigEndian => u16::from_be_bytes(b),
+			Endianness::LittleEndian => u16::from_le_bytes(b),
+		})
+	}
+
+	pub fn get_i16(&mut self) -> Result<i16, CdrError> {
+		self.get_u16().map(|v| v as i16)
+	}
+
+	pub fn get_u32(&mut self) -> Result<u32, CdrError> {
+		let mut b = [0u8; 4];
+		self.get_aligned(&mut b)?;
+		Ok(match self.endianness() {
+			Endianness::BigEndian => u32::from_be_bytes(b),
+			Endianness::LittleEndian => u32::from_le_bytes(b),
+		})
+	}
+
+	pub fn get_i32(&mut self) -> Result<i32, CdrError> {
+		self.get_u32().map(|v| v as i32)
+	}
+
+	pub fn get_u64(&mut self) -> Result<u64, CdrError> {
+		let mut b = [0u8; 8];
+		self.get_aligned(&mut b)?;
+		Ok(match self.endianness() {
+			Endianness::BigEndian => u64::from_be_bytes(b),
+			Endianness::LittleEndian => u64::from_le_bytes(b),
+		})
+	}
+
+	pub fn get_i64(&mut self) -> Result<i64, CdrError> {
+		self.get_u64().map(|v| v as i64)
+	}
+
+	pub fn get_f32(&mut self) -> Result<f32, CdrError> {
+		self.get_u32().map(f32::from_bits)
+	}
+
+	pub fn get_f64(&mut self) -> Result<f64, CdrError> {
+		self.get_u64().map(f64::from_bits)
+	}
+
+	/// Reads a NUL terminated string, preceeded by it's length.
+	pub fn get_string(&mut self) -> Result<String, CdrError> {
+		let len = self.get_u32()? as usize;
+		if len == 0 {
+			return Err(CdrError::Invalid("string is missing it's terminating NUL"));
+		}
+
+		let b = self.get_octets(len)?;
+		if b[len - 1] != 0 {
+			return Err(CdrError::Invalid("string is missing it's terminating NUL"));
+		}
+		str::from_utf8(&b[..len - 1])
+			.map(String::from)
+			.map_err(|_| CdrError::Invalid("string is not UTF-8"))
+	}
+
+	/// Reads the number of elements within a sequence. Every element takes
+	/// at least min_element_size bytes, which is used to reject lengths which
+	/// can't possibly fit in what remains.
+	pub fn get_sequence_len(&mut self, min_element_size: usize) -> Result<usize, CdrError> {
+		let len = self.get_u32()? as usize;
+		if len.saturating_mul(min_element_size) > self.remaining() {
+			return Err(CdrError::Invalid("sequence is longer than the remaining data"));
+		}
+		Ok(len)
+	}
+
+	/// Starts reading an appendable type. With XCDR2, the DHEADER is read and
+	/// the decoder limited to the type's length, until `end_delimited`.
+	pub fn begin_delimited(&mut self) -> Result<Delimited, CdrError> {
+		let limit = self.limit;
+		match self.version() {
+			CdrVersion::XCDR1 => Ok(Delimited {end: None, limit}),
+			CdrVersion::XCDR2 => {
+				let len = self.get_u32()? as usize;
+				self.check(len)?;
+				self.limit = self.pos + len;
+				Ok(Delimited {end: Some(self.limit), limit})
+			},
+		}
+	}
+
+	/// Skips anything within the delimited type which wasn't read (e.g.
+	/// members appended by a newer version of the type), and lifts the limit
+	/// set by `begin_delimited`.
+	pub fn end_delimited(&mut self, delimited: Delimited) -> Result<(), CdrError> {
+		if let Some(end) = delimited.end {
+			if self.pos > end {
+				return Err(CdrError::Invalid("read past the end of a delimited type"));
+			}
+			self.pos = end;
+		}
+		self.limit = delimited.limit;
+		Ok(())
+	}
+
+	/// Starts reading the members of a mutable type, which are read with
+	/// `next_member`. The returned value must be handed to `end_members` once
+	/// `next_member` has returned None.
+	pub fn begin_members(&mut self) -> Result<Delimited, CdrError> {
+		self.begin_delimited()
+	}
+
+	pub fn end_members(&mut self, members: Delimited) -> Result<(), CdrError> {
+		self.end_delimited(members)
+	}
+
+	/// Reads the next member of a mutable type, returning None once the
+	/// members have ended. With XCDR1 that is once the PID_LIST_END has been
+	/// read, and PID_IGNORE members are skipped. With XCDR2 it is at the end
+	/// of the DHEADER read by `begin_members`.
+	pub fn next_member(&mut self) -> Result<Option<Member>, CdrError> {
+		if self.version() == CdrVersion::XCDR2 {
+			return self.next_em_member();
+		}
+
+		loop {
+			self.align(4)?;
+			let pid = self.get_u16()?;
+			let mut len = self.get_u16()? as usize;
+			let must_understand = pid & PID_FLAG_MUST_UNDERSTAND != 0;
+
+			let id = match pid & PID_MASK {
+				PID_LIST_END => return Ok(None),
+				PID_IGNORE => {
+					self.skip(len)?;
+					continue;
+				},
+				PID_EXTENDED => {
+					if len != 8 {
+						return Err(CdrError::Invalid("extended parameter header must have length 8"));
+					}
+					let id = self.get_u32()?;
+					len = self.get_u32()? as usize;
+					id
+				},
+				id => id as u32,
+			};
+
+			let value = self.take_member(len)?;
+			return Ok(Some(Member {id, must_understand, value}));
+		}
+	}
+
+	/// Reads an EMHEADER, and the member which follows. Length codes 0 to 3
+	/// give a fixed length of 1, 2, 4 or 8 bytes, while 4 to 7 use the
+	/// NEXTINT. With length codes 5 to 7 the NEXTINT is also the start of the
+	/// member (the length of a sequence or a DHEADER), which is multiplied by
+	/// 1, 4 or 8 to find the length.
+	fn next_em_member(&mut self) -> Result<Option<Member>, CdrError> {
+		if self.remaining() == 0 {
+			return Ok(None);
+		}
+		self.align(4)?;
+		if self.remaining() == 0 {
+			return Ok(None);
+		}
+
+		let header = self.get_u32()?;
+		let must_understand = header & EMHEADER_FLAG_MUST_UNDERSTAND != 0;
+		let id = header & EMHEADER_MEMBER_ID_MASK;
+
+		let len = match (header >> EMHEADER_LC_SHIFT) & 0x07 {
+			lc @ 0..=3 => 1 << lc,
+			4 => self.get_u32()? as usize,
+			lc => {
+				let next_int = self.get_u32()? as usize;
+				self.pos -= 4;
+				let element_size = match lc {
+					5 => 1,
+					6 => 4,
+					_ => 8,
+				};
+				next_int.checked_mul(element_size)
+					.and_then(|n| n.checked_add(4))
+					.ok_or(CdrError::Invalid("member length overflows"))?
+			},
+		};
+
+		let value = self.take_member(len)?;
+		Ok(Some(Member {id, must_understand, value}))
+	}
+
+	/// Hands out the next len bytes as a decoder of their own.
+	fn take_member(&mut self, len: usize) -> Result<CdrDecoder, CdrError> {
+		self.check(len)?;
+		let value = CdrDecoder {
+			buf: self.buf.slice(self.pos, self.pos + len),
+			pos: 0,
+			limit: len,
+			offset: self.offset + self.pos,
+			encapsulation: self.encapsulation
+		};
+		self.pos += len;
+		Ok(value)
+	}
+
+	/// Reads an optional member, written by `CdrEncoder::put_optional`.
+	pub fn get_optional<T: CdrDeserialize>(&mut self, member_id: u32) -> Result<Option<T>, CdrError> {
+		self.get_optional_with(member_id, T::deserialize)
+	}
+
+	/// Reads an optional member, using read to read the value if present.
+	pub fn get_optional_with<T, F>(&mut self, member_id: u32, read: F) -> Result<Option<T>, CdrError>
+			where F: FnOnce(&mut CdrDecoder) -> Result<T, CdrError> {
+		if self.version() == CdrVersion::XCDR2 {
+			return if self.get_bool()? {
+				read(self).map(Some)
+			} else {
+				Ok(None)
+			};
+		}
+
+		match self.next_member()? {
+			Some(mut m) => {
+				if m.id != member_id {
+					return Err(CdrError::Invalid("unexpected member id for optional member"));
+				}
+				if m.value.remaining() == 0 {
+					Ok(None)
+				} else {
+					read(&mut m.value).map(Some)
+				}
+			},
+			None => Err(CdrError::Invalid("missing optional member")),
+		}
+	}
+
+	fn get_aligned(&mut self, b: &mut [u8]) -> Result<(), CdrError> {
+		self.align(b.len())?;
+		self.check(b.len())?;
+		b.copy_from_slice(&self.buf[self.pos..self.pos + b.len()]);
+		self.pos += b.len();
+		Ok(())
+	}
+
+	fn check(&self, needed: usize) -> Result<(), CdrError> {
+		if self.remaining() < needed {
+			Err(CdrError::UnexpectedEnd {needed, remaining: self.remaining()})
+		} else {
+			Ok(())
+		}
+	}
+}
+
+impl CdrDeserialize for bool {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_bool()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for u8 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_u8()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for i8 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_i8()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for u16 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_u16()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for i16 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_i16()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for u32 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_u32()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for i32 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_i32()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for u64 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_u64()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for i64 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_i64()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for f32 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_f32()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for f64 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_f64()
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+}
+
+impl CdrDeserialize for String {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		dec.get_string()
+	}
+}
+
+impl<T: CdrDeserialize> CdrDeserialize for Vec<T> {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = element_delimiter(dec, T::is_primitive())?;
+		let len = dec.get_sequence_len(1)?;
+		let mut v = Vec::with_capacity(len);
+		for _ in 0..len {
+			v.push(T::deserialize(dec)?);
+		}
+		dec.end_delimited(d)?;
+		Ok(v)
+	}
+}
+
+impl<T: CdrDeserialize, const N: usize> CdrDeserialize for [T; N] {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = element_delimiter(dec, T::is_primitive_innermost())?;
+		let v = Self::deserialize_elements(dec)?;
+		dec.end_delimited(d)?;
+		Ok(v)
+	}
+
+	fn is_primitive_innermost() -> bool {
+		T::is_primitive_innermost()
+	}
+
+	fn deserialize_elements(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let mut v = Vec::with_capacity(N);
+		for _ in 0..N {
+			v.push(T::deserialize_elements(dec)?);
+		}
+		<[T; N]>::try_from(v).map_err(|_| CdrError::Invalid("array has the wrong length"))
+	}
+}
+
+/// Sequences and arrays of non-primitive elements are delimited.
+fn element_delimiter(dec: &mut CdrDecoder, primitive: bool) -> Result<Delimited, CdrError> {
+	if primitive {
+		Ok(Delimited {end: None, limit: dec.limit})
+	} else {
+		dec.begin_delimited()
+	}
+}
+
+impl<T: CdrDeserialize> CdrDeserialize for Box<T> {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		T::deserialize(dec).map(Box::new)
+	}
+
+}
@@ -0,0 +1,144 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ The encapsulation header preceeding every serialized payload.
+
+ The header is 4 bytes: a 2 byte representation identifier, which is always
+ big endian, followed by 2 bytes of options (see RTPS v2.2 section 10.2 and
+ DDS-XTypes v1.3 section 7.6.3.1.2). When using XCDR2, the two least 
+ significant bits of the options hold the number of padding bytes appended to
+ the end of the payload.
+ */
+use rtps::messages::submessage::header::Endianness;
+
+/// Size of the encapsulation header, in bytes.
+pub const ENCAPSULATION_HEADER_SIZE: usize = 4;
+
+/// Identifies how the serialized payload which follows was encoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encapsulation {
+	/// Plain CDR, big endian.
+	CDR_BE,
+	/// Plain CDR, little endian.
+	CDR_LE,
+	/// CDR, with members encoded as a parameter list, big endian.
+	PL_CDR_BE,
+	/// CDR, with members encoded as a parameter list, little endian.
+	PL_CDR_LE,
+	/// XCDR2 of a final type, big endian.
+	CDR2_BE,
+	/// XCDR2 of a final type, little endian.
+	CDR2_LE,
+	/// XCDR2 of a mutable type, with EMHEADERs, big endian.
+	PL_CDR2_BE,
+	/// XCDR2 of a mutable type, with EMHEADERs, little endian.
+	PL_CDR2_LE,
+	/// XCDR2 of an appendable type, with a DHEADER, big endian.
+	D_CDR2_BE,
+	/// XCDR2 of an appendable type, with a DHEADER, little endian.
+	D_CDR2_LE,
+}
+
+/// Version of the extended CDR encoding rules.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CdrVersion {
+	XCDR1,
+	XCDR2,
+}
+
+/// How a type may evolve, which decides how it's members are encoded (see
+/// DDS-XTypes v1.3 section 7.2.2.4.4).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Extensibility {
+	/// Members may not be added or removed.
+	Final,
+	/// Members may be appended to the end of the type.
+	Appendable,
+	/// Members may be added, removed or reordered.
+	Mutable,
+}
+
+impl Encapsulation {
+	/// The encapsulation used for a top level type of the given extensibility.
+	/// XCDR1 has no separate encapsulation for appendable types.
+	pub fn new(version: CdrVersion, extensibility: Extensibility, endianness: Endianness) -> Encapsulation {
+		let little = endianness == Endianness::LittleEndian;
+		match (version, extensibility, little) {
+			(CdrVersion::XCDR1, Extensibility::Mutable, false) => Encapsulation::PL_CDR_BE,
+			(CdrVersion::XCDR1, Extensibility::Mutable, true) => Encapsulation::PL_CDR_LE,
+			(CdrVersion::XCDR1, _, false) => Encapsulation::CDR_BE,
+			(CdrVersion::XCDR1, _, true) => Encapsulation::CDR_LE,
+			(CdrVersion::XCDR2, Extensibility::Final, false) => Encapsulation::CDR2_BE,
+			(CdrVersion::XCDR2, Extensibility::Final, true) => Encapsulation::CDR2_LE,
+			(CdrVersion::XCDR2, Extensibility::Appendable, false) => Encapsulation::D_CDR2_BE,
+			(CdrVersion::XCDR2, Extensibility::Appendable, true) => Encapsulation::D_CDR2_LE,
+			(CdrVersion::XCDR2, Extensibility::Mutable, false) => Encapsulation::PL_CDR2_BE,
+			(CdrVersion::XCDR2, Extensibility::Mutable, true) => Encapsulation::PL_CDR2_LE,
+		}
+	}
+
+	/// The representation identifier, as it appears on the wire.
+	pub fn id(&self) -> u16 {
+		match *self {
+			Encapsulation::CDR_BE => 0x0000,
+			Encapsulation::CDR_LE => 0x0001,
+			Encapsulation::PL_CDR_BE => 0x0002,
+			Encapsulation::PL_CDR_LE => 0x0003,
+			Encapsulation::CDR2_BE => 0x0010,
+			Encapsulation::CDR2_LE => 0x0011,
+			Encapsulation::PL_CDR2_BE => 0x0012,
+			Encapsulation::PL_CDR2_LE => 0x0013,
+			Encapsulation::D_CDR2_BE => 0x0014,
+			Encapsulation::D_CDR2_LE => 0x0015,
+		}
+	}
+
+	/// Returns None for representations which aren't supported.
+	pub fn from_id(id: u16) -> Option<Encapsulation> {
+		match id {
+			0x0000 => Some(Encapsulation::CDR_BE),
+			0x0001 => Some(Encapsulation::CDR_LE),
+			0x0002 => Some(Encapsulation::PL_CDR_BE),
+			0x0003 => Some(Encapsulation::PL_CDR_LE),
+			0x0010 => Some(Encapsulation::CDR2_BE),
+			0x0011 => Some(Encapsulation::CDR2_LE),
+			0x0012 => Some(Encapsulation::PL_CDR2_BE),
+			0x0013 => Some(Encapsulation::PL_CDR2_LE),
+			0x0014 => Some(Encapsulation::D_CDR2_BE),
+			0x0015 => Some(Encapsulation::D_CDR2_LE),
+			_ => None,
+		}
+	}
+
+	/// The least significant bit of every identifier selects little endian.
+	pub fn endianness(&self) -> Endianness {
+		if self.id() & 0x0001 == 0 {
+			Endianness::BigEndian
+		} else {
+			Endianness::LittleEndian
+		}
+	}
+
+	pub fn version(&self) -> CdrVersion {
+		if self.id() >= 0x0010 {
+			CdrVersion::XCDR2
+		} else {
+			CdrVersion::XCDR1
+		}
+	}
+
+	/// True if the members of the top level type are encoded as a parameter
+	/// list (i.e. the type is mutable).
+	pub fn is_parameter_list(&self) -> bool {
+		matches!(*self, Encapsulation::PL_CDR_BE | Encapsulation::PL_CDR_LE |
+						Encapsulation::PL_CDR2_BE | Encapsulation::PL_CDR2_LE)
+	}
+
+	/// Largest alignment applied to primitives. XCDR2 aligns 8 byte
+	/// primitives to 4 bytes.
+	pub fn max_alignment(&self) -> usize {
+		match self.version() {
+			CdrVersion::XCDR1 => 8,
+			CdrVersion::XCDR2 => 4,
+		}
+	}
+}
@@ -0,0 +1,612 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ Serializes user data types into CDR.
+
+ Primitives are aligned to their size, relative to the end of the
+ encapsulation header, and written in the byte order selected by the
+ encapsulation. XCDR1 aligns up to 8 bytes, XCDR2 up to 4.
+
+ With XCDR1, members of mutable types are written as a parameter list: each
+ member is preceeded by a 4 byte aligned parameter header holding the member
+ id and the length of the member, and the list is terminated by PID_LIST_END
+ (see DDS-XTypes v1.2 section 7.4.1.2).
+
+ With XCDR2, appendable types, mutable types and sequences or arrays of
+ non-primitive elements are preceeded by a DHEADER, a u32 holding their length
+ in bytes. Each member of a mutable type is preceeded by an EMHEADER, which
+ holds the must understand flag, a length code and the member id, followed by
+ the length of the member (see DDS-XTypes v1.3 section 7.4.3.5).
+ */
+use std::cmp;
+use bytes::{Bytes, BytesMut};
+use rtps::messages::submessage::header::Endianness;
+use cdr::encapsulation::*;
+
+/// Set within a parameter header when the reader must understand the member
+/// to make sense of the rest of the type.
+pub const PID_FLAG_MUST_UNDERSTAND: u16 = 	0x4000;
+/// Mask of the member id within a short parameter header.
+pub const PID_MASK: u16 = 					0x3fff;
+/// Preceeds an extended parameter header, used for member ids and lengths
+/// which don't fit in the short form.
+pub const PID_EXTENDED: u16 = 				0x3f01;
+/// Terminates the members of a mutable type.
+pub const PID_LIST_END: u16 = 				0x3f02;
+/// A parameter to be skipped by the reader.
+pub const PID_IGNORE: u16 = 				0x3f03;
+
+/// Member ids from here up can only be written using the extended form.
+pub const PID_SHORT_MAX: u32 = 0x3f00;
+
+/// Set within an EMHEADER when the reader must understand the member.
+pub const EMHEADER_FLAG_MUST_UNDERSTAND: u32 = 	0x8000_0000;
+/// Mask of the member id within an EMHEADER.
+pub const EMHEADER_MEMBER_ID_MASK: u32 = 		0x0fff_ffff;
+/// Position of the 3 bit length code within an EMHEADER.
+pub const EMHEADER_LC_SHIFT: u32 = 				28;
+/// Length code for members whose length is given by the following NEXTINT.
+pub const EMHEADER_LC_NEXTINT: u32 = 			4;
+
+/// Implemented by every type which can be written as CDR.
+pub trait CdrSerialize {
+	/// Append the CDR representation of self to the encoder.
+	fn serialize(&self, enc: &mut CdrEncoder);
+
+	/// True for primitive types. XCDR2 doesn't precede sequences and arrays
+	/// of primitives with a DHEADER.
+	fn is_primitive() -> bool where Self: Sized {
+		false
+	}
+
+	/// True if the innermost element type of an array (of arrays) is
+	/// primitive, or for any other type, if it is primitive itself. XCDR2
+	/// writes a multi-dimensional array as a single array of it's innermost
+	/// element type, so only that decides whether it has a DHEADER.
+	fn is_primitive_innermost() -> bool where Self: Sized {
+		Self::is_primitive()
+	}
+
+	/// Appends the elements of an array without a DHEADER, as is done for the
+	/// inner dimensions of a multi-dimensional array. Other types are written
+	/// as usual.
+	fn serialize_elements(&self, enc: &mut CdrEncoder) {
+		self.serialize(enc)
+	}
+
+	/// Position following the largest possible XCDR1 serialization of the
+	/// type, when it is written starting at position. None if the size of the
+	/// type is unbounded. Used to decide how the key hash is computed.
+	fn max_end_position(position: usize) -> Option<usize> where Self: Sized {
+		let _ = position;
+		None
+	}
+}
+
+/// Appends CDR encoded values to a buffer which starts with the encapsulation
+/// header.
+pub struct CdrEncoder {
+	buf: BytesMut,
+	encapsulation: Encapsulation,
+}
+
+/// A member which has been started with `begin_member`, but not yet ended.
+pub struct OpenMember {
+	header_start: usize,
+	value_start: usize,
+	extended: bool,
+}
+
+/// A DHEADER which has been written by `begin_delimited` or `begin_members`,
+/// but not yet back-filled. Holds nothing when using XCDR1.
+pub struct OpenDelimited {
+	start: Option<usize>,
+}
+
+impl CdrEncoder {
+	/// Creates an encoder, writing the encapsulation header.
+	pub fn new(encapsulation: Encapsulation) -> CdrEncoder {
+		let mut buf = BytesMut::with_capacity(64);
+		buf.extend_from_slice(&encapsulation.id().to_be_bytes());
+		buf.extend_from_slice(&[0, 0]);
+		CdrEncoder {buf, encapsulation}
+	}
+
+	pub fn encapsulation(&self) -> Encapsulation {
+		self.encapsulation
+	}
+
+	pub fn endianness(&self) -> Endianness {
+		self.encapsulation.endianness()
+	}
+
+	pub fn version(&self) -> CdrVersion {
+		self.encapsulation.version()
+	}
+
+	/// Number of bytes written after the encapsulation header, which is the
+	/// origin used for alignment.
+	pub fn position(&self) -> usize {
+		self.buf.len() - ENCAPSULATION_HEADER_SIZE
+	}
+
+	/// Appends zeros until the position is a multiple of alignment, which is
+	/// limited to the largest alignment of the encapsulation.
+	pub fn align(&mut self, alignment: usize) {
+		let alignment = cmp::min(alignment, self.encapsulation.max_alignment());
+		let padding = (alignment - (self.position() % alignment)) % alignment;
+		for _ in 0..padding {
+			self.buf.extend_from_slice(&[0]);
+		}
+	}
+
+	pub fn put_bool(&mut self, v: bool) {
+		self.put_u8(v as u8)
+	}
+
+	pub fn put_u8(&mut self, v: u8) {
+		self.buf.extend_from_slice(&[v])
+	}
+
+	pub fn put_i8(&mut self, v: i8) {
+		self.put_u8(v as u8)
+	}
+
+	pub fn put_u16(&mut self, v: u16) {
+		self.align(2);
+		match self.endianness() {
+			Endianness::BigEndian => self.buf.extend_from_slice(&v.to_be_bytes()),
+			Endianness::LittleEndian => self.buf.extend_from_slice(&v.to_le_bytes()),
+		}
+	}
+
+	pub fn put_i16(&mut self, v: i16) {
+		self.put_u16(v as u16)
+	}
+
+	pub fn put_u32(&mut self, v: u32) {
+		self.align(4);
+		match self.endianness() {
+			Endianness::BigEndian => self.buf.extend_from_slice(&v.to_be_bytes()),
+			Endianness::LittleEndian => self.buf.extend_from_slice(&v.to_le_bytes()),
+		}
+	}
+
+	pub fn put_i32(&mut self, v: i32) {
+		self.put_u32(v as u32)
+	}
+
+	pub fn put_u64(&mut self, v: u64) {
+		self.align(8);
+		match self.endianness() {
+			Endianness::BigEndian => self.buf.extend_from_slice(&v.to_be_bytes()),
+			Endianness::LittleEndian => self.buf.extend_from_slice(&v.to_le_bytes()),
+		}
+	}
+
+	pub fn put_i64(&mut self, v: i64) {
+		self.put_u64(v as u64)
+	}
+
+	pub fn put_f32(&mut self, v: f32) {
+		self.put_u32(v.to_bits())
+	}
+
+	pub fn put_f64(&mut self, v: f64) {
+		self.put_u64(v.to_bits())
+	}
+
+	/// Copies the octets as-is, without alignment.
+	pub fn put_octets(&mut self, v: &[u8]) {
+		self.buf.extend_from_slice(v)
+	}
+
+	/// Strings are written as a u32 length (including the terminating NUL),
+	/// followed by the characters and a NUL.
+	pub fn put_string(&mut self, v: &str) {
+		self.put_u32(v.len() as u32 + 1);
+		self.put_octets(v.as_bytes());
+		self.put_u8(0);
+	}
+
+	/// Writes the number of elements within a sequence, which preceeds the
+	/// elements themselves.
+	pub fn put_sequence_len(&mut self, len: usize) {
+		self.put_u32(len as u32)
+	}
+
+	/// Starts an appendable type, returning the DHEADER which must be handed
+	/// to `end_delimited` once the type has been written. Nothing is written
+	/// when using XCDR1.
+	pub fn begin_delimited(&mut self) -> OpenDelimited {
+		match self.version() {
+			CdrVersion::XCDR1 => OpenDelimited {start: None},
+			CdrVersion::XCDR2 => {
+				self.put_u32(0);
+				OpenDelimited {start: Some(self.buf.len())}
+			},
+		}
+	}
+
+	/// Back-fills the DHEADER with the length of everything written since
+	/// `begin_delimited`.
+	pub fn end_delimited(&mut self, delimited: OpenDelimited) {
+		if let Some(start) = delimited.start {
+			let len = self.buf.len() - start;
+			self.put_u32_at(start - 4, len as u32);
+		}
+	}
+
+	/// Starts the members of a mutable type, which are written with
+	/// `begin_member` and `end_member`. The returned value must be handed to
+	/// `end_members` once every member has been written.
+	///
+	/// XCDR2 precedes the members with a DHEADER.
+	pub fn begin_members(&mut self) -> OpenDelimited {
+		self.begin_delimited()
+	}
+
+	/// Terminates the members of a mutable type, with PID_LIST_END when using
+	/// XCDR1, or by back-filling the DHEADER when using XCDR2.
+	pub fn end_members(&mut self, members: OpenDelimited) {
+		match self.version() {
+			CdrVersion::XCDR1 => {
+				self.align(4);
+				self.put_u16(PID_LIST_END);
+				self.put_u16(0);
+			},
+			CdrVersion::XCDR2 => self.end_delimited(members),
+		}
+	}
+
+	/// Starts a member of a mutable type, returning the member which must be
+	/// handed to `end_member` once the member's value has been written.
+	///
+	/// XCDR2 member ids are limited to 28 bits. The EMHEADER always uses the
+	/// NEXTINT length code, so members of any type and length may be written.
+	pub fn begin_member(&mut self, member_id: u32, must_understand: bool) -> OpenMember {
+		self.align(4);
+		let header_start = self.buf.len();
+
+		if self.version() == CdrVersion::XCDR2 {
+			let flags = if must_understand {EMHEADER_FLAG_MUST_UNDERSTAND} else {0};
+			self.put_u32(flags | (EMHEADER_LC_NEXTINT << EMHEADER_LC_SHIFT) |
+						 (member_id & EMHEADER_MEMBER_ID_MASK));
+			self.put_u32(0);
+			return OpenMember {header_start, value_start: self.buf.len(), extended: true};
+		}
+
+		let flags = if must_understand {PID_FLAG_MUST_UNDERSTAND} else {0};
+		let extended = member_id >= PID_SHORT_MAX;
+
+		if extended {
+			self.put_u16(PID_EXTENDED | flags);
+			self.put_u16(8);
+			self.put_u32(member_id);
+			self.put_u32(0);
+		} else {
+			self.put_u16(member_id as u16 | flags);
+			self.put_u16(0);
+		}
+
+		OpenMember {header_start, value_start: self.buf.len(), extended}
+	}
+
+	/// Back-fills the length of the member. XCDR1 first pads the member to a
+	/// 4 byte boundry.
+	///
+	/// XCDR1 members too long for the short form are moved behind an extended
+	/// header. The extended header is 8 bytes longer, so the alignment of the
+	/// member contents is unaffected.
+	pub fn end_member(&mut self, member: OpenMember) {
+		if self.version() == CdrVersion::XCDR1 {
+			self.align(4);
+		}
+		let len = self.buf.len() - member.value_start;
+
+		if member.extended {
+			self.put_u32_at(member.value_start - 4, len as u32);
+		} else if len <= 0xffff {
+			self.put_u16_at(member.value_start - 2, len as u16);
+		} else {
+			let value = self.buf.split_off(member.value_start);
+			let pid = self.get_u16_at(member.header_start);
+			self.buf.truncate(member.header_start);
+			self.put_u16(PID_EXTENDED | (pid & PID_FLAG_MUST_UNDERSTAND));
+			self.put_u16(8);
+			self.put_u32((pid & PID_MASK) as u32);
+			self.put_u32(len as u32);
+			self.buf.extend_from_slice(&value);
+		}
+	}
+
+	/// With XCDR1, optional members are written as a member, which is empty
+	/// when the value is absent. With XCDR2, the value is preceeded by a
+	/// boolean which is true when the value is present.
+	///
+	/// Optional members of XCDR2 mutable types are written by omitting the
+	/// member instead.
+	pub fn put_optional<T: CdrSerialize>(&mut self, member_id: u32, value: &Option<T>) {
+		if self.version() == CdrVersion::XCDR2 {
+			self.put_bool(value.is_some());
+			if let Some(ref v) = *value {
+				v.serialize(self);
+			}
+			return;
+		}
+
+		let member = self.begin_member(member_id, false);
+		if let Some(ref v) = *value {
+			v.serialize(self);
+		}
+		self.end_member(member);
+	}
+
+	/// The encapsulation header followed by everything written so far, ready
+	/// to be used as a SerializedPayload.
+	///
+	/// XCDR2 payloads are padded to a multiple of 4 bytes, with the amount of
+	/// padding recorded in the encapsulation options.
+	pub fn finish(mut self) -> Bytes {
+		if self.version() == CdrVersion::XCDR2 {
+			let padding = (4 - self.position() % 4) % 4;
+			self.align(4);
+			self.buf[3] |= padding as u8;
+		}
+		self.buf.freeze()
+	}
+
+	fn put_u16_at(&mut self, offset: usize, v: u16) {
+		let b = match self.endianness() {
+			Endianness::BigEndian => v.to_be_bytes(),
+			Endianness::LittleEndian => v.to_le_bytes(),
+		};
+		self.buf[offset..offset + 2].copy_from_slice(&b);
+	}
+
+	fn put_u32_at(&mut self, offset: usize, v: u32) {
+		let b = match self.endianness() {
+			Endianness::BigEndian => v.to_be_bytes(),
+			Endianness::LittleEndian => v.to_le_bytes(),
+		};
+		self.buf[offset..offset + 4].copy_from_slice(&b);
+	}
+
+	fn get_u16_at(&self, offset: usize) -> u16 {
+		let b = [self.buf[offset], self.buf[offset + 1]];
+		match self.endianness() {
+			Endianness::BigEndian => u16::from_be_bytes(b),
+			Endianness::LittleEndian => u16::from_le_bytes(b),
+		}
+	}
+}
+
+impl CdrSerialize for bool {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_bool(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 1))
+	}
+}
+
+impl CdrSerialize for u8 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_u8(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 1))
+	}
+}
+
+impl CdrSerialize for i8 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_i8(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 1))
+	}
+}
+
+impl CdrSerialize for u16 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_u16(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 2))
+	}
+}
+
+impl CdrSerialize for i16 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_i16(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 2))
+	}
+}
+
+impl CdrSerialize for u32 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_u32(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 4))
+	}
+}
+
+impl CdrSerialize for i32 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_i32(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 4))
+	}
+}
+
+impl CdrSerialize for u64 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_u64(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 8))
+	}
+}
+
+impl CdrSerialize for i64 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_i64(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 8))
+	}
+}
+
+impl CdrSerialize for f32 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_f32(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 4))
+	}
+}
+
+impl CdrSerialize for f64 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_f64(*self)
+	}
+
+	fn is_primitive() -> bool {
+		true
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		Some(primitive_end(position, 8))
+	}
+}
+
+impl CdrSerialize for str {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_string(self)
+	}
+}
+
+impl CdrSerialize for String {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		enc.put_string(self)
+	}
+}
+
+/// Sequences are preceeded by their length.
+impl<T: CdrSerialize> CdrSerialize for Vec<T> {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let d = element_delimiter(enc, T::is_primitive());
+		enc.put_sequence_len(self.len());
+		for v in self {
+			v.serialize(enc);
+		}
+		enc.end_delimited(d);
+	}
+}
+
+/// Arrays have a fixed length, which isn't written.
+impl<T: CdrSerialize, const N: usize> CdrSerialize for [T; N] {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let d = element_delimiter(enc, T::is_primitive_innermost());
+		self.serialize_elements(enc);
+		enc.end_delimited(d);
+	}
+
+	fn is_primitive_innermost() -> bool {
+		T::is_primitive_innermost()
+	}
+
+	fn serialize_elements(&self, enc: &mut CdrEncoder) {
+		for v in self.iter() {
+			v.serialize_elements(enc);
+		}
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		(0..N).try_fold(position, |p, _| T::max_end_position(p))
+	}
+}
+
+/// Position following a primitive of size bytes, aligned to it's size.
+fn primitive_end(position: usize, size: usize) -> usize {
+	position.next_multiple_of(size) + size
+}
+
+/// Sequences and arrays of non-primitive elements are delimited.
+fn element_delimiter(enc: &mut CdrEncoder, primitive: bool) -> OpenDelimited {
+	if primitive {
+		OpenDelimited {start: None}
+	} else {
+		enc.begin_delimited()
+	}
+}
+
+impl<T: CdrSerialize + ?Sized> CdrSerialize for &T {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		(**self).serialize(enc)
+	}
+}
+
+impl<T: CdrSerialize> CdrSerialize for Box<T> {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		(**self).serialize(enc)
+	}
+
+	fn max_end_position(position: usize) -> Option<usize> {
+		T::max_end_position(position)
+	}
+}
@@ -0,0 +1,40 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ The Common Data Representation (CDR), used to serialize user data types into
+ the SerializedPayload of Data and DataFrag submessages.
+
+ Both the original version of CDR (XCDR1, see DDS-XTypes v1.2 section 7.4.1)
+ and XCDR2 (DDS-XTypes v1.3 section 7.4.3) are implemented. Types implement
+ `CdrSerialize` and `CdrDeserialize`, usually by serializing each of their
+ members in order. Implementations are provided for the primitive types,
+ strings, sequences (Vec) and arrays. Appendable types are written between
+ `begin_delimited` and `end_delimited`, and mutable types between
+ `begin_members` and `end_members`, so the same implementation works with
+ either version.
+
+ A serialized payload always starts with a 4 byte encapsulation header, which
+ gives the version, the byte order and the extensibility of the type.
+ */
+
+pub mod encapsulation;
+pub mod encoder;
+pub mod decoder;
+
+use bytes::Bytes;
+use cdr::encapsulation::Encapsulation;
+use cdr::encoder::*;
+use cdr::decoder::*;
+
+/// Serializes value, preceeded by the encapsulation header.
+pub fn to_payload<T: CdrSerialize + ?Sized>(value: &T, encapsulation: Encapsulation) -> Bytes {
+	let mut enc = CdrEncoder::new(encapsulation);
+	value.serialize(&mut enc);
+	enc.finish()
+}
+
+/// Deserializes a value from a serialized payload, using the byte order given
+/// by it's encapsulation header. Anything following the value is ignored.
+pub fn from_payload<T: CdrDeserialize>(payload: Bytes) -> Result<T, CdrError> {
+	let mut dec = CdrDecoder::from_payload(payload)?;
+	T::deserialize(&mut dec)
+}
@@ -0,0 +1,722 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Samples of types described at runtime by a `DynamicType`.
+
+ A `DynamicData` pairs a `Value` with it's type, and is serialized the same
+ way as a Rust type generated from the same IDL would be, so it can be used
+ in place of the generated type by tools such as bridges and recorders.
+ Members are accessed by name, with the names of nested members joined by
+ ".":
+
+ ```ignore
+ let mut sample = DynamicData::from_payload(&shape_type, payload)?;
+ assert_eq!(sample.get("color"), Some(&Value::String(String::from("RED"))));
+ sample.set("position.x", Value::Int32(3))?;
+ let payload = sample.to_payload(Encapsulation::CDR_LE);
+ ```
+
+ Mutable unions are written as appendable unions, since neither DynamicData
+ nor the IDL compiler support them.
+ */
+use std::error;
+use std::fmt;
+use bytes::Bytes;
+use cdr;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+use cdr::decoder::*;
+use dcps::dynamic_type::*;
+
+/// The value of a dynamic type, or of one of it's members.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+	Boolean(bool),
+	Byte(u8),
+	Int8(i8),
+	UInt8(u8),
+	Int16(i16),
+	UInt16(u16),
+	Int32(i32),
+	UInt32(u32),
+	Int64(i64),
+	UInt64(u64),
+	Float32(f32),
+	Float64(f64),
+	Char8(u8),
+	String(String),
+	/// An enumerator, given by it's value.
+	Enum(u32),
+	/// The elements of a sequence or array. A multi-dimensional array holds
+	/// an array of the remaining dimensions for each element of the first.
+	Sequence(Vec<Value>),
+	/// The members of a structure, by name, in the order they are
+	/// serialized. Absent optional members are left out.
+	Struct(Vec<(String, Value)>),
+	/// The discriminator of a union, and the member it selects (if any).
+	Union {discriminator: Box<Value>, member: Option<(String, Box<Value>)>},
+}
+
+/// Describes why a value could not be given to a member.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DynamicError {
+	/// The type has no member of that name.
+	UnknownMember(String),
+	/// A member which isn't optional has no value.
+	MissingMember(String),
+	/// The value doesn't match the type of the member, or exceeds it's bound.
+	WrongType(String),
+}
+
+impl fmt::Display for DynamicError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			DynamicError::UnknownMember(ref m) => write!(f, "there is no member {}", m),
+			DynamicError::MissingMember(ref m) => write!(f, "{} must have a value", m),
+			DynamicError::WrongType(ref m) if m.is_empty() => write!(f, "the value doesn't match the type"),
+			DynamicError::WrongType(ref m) => write!(f, "the value doesn't match the type of {}", m),
+		}
+	}
+}
+
+impl error::Error for DynamicError {}
+
+/// A value, along with the type it conforms to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicData {
+	dynamic_type: DynamicType,
+	value: Value,
+}
+
+impl Value {
+	/// The value a member of type ty has when it is first created: zero,
+	/// empty, the first enumerator or the first member of a union.
+	pub fn default_for(ty: &DynamicType) -> Value {
+		let ty = ty.resolved();
+		match ty.kind() {
+			TypeKind::TK_BOOLEAN => Value::Boolean(false),
+			TypeKind::TK_BYTE => Value::Byte(0),
+			TypeKind::TK_INT8 => Value::Int8(0),
+			TypeKind::TK_UINT8 => Value::UInt8(0),
+			TypeKind::TK_INT16 => Value::Int16(0),
+			TypeKind::TK_UINT16 => Value::UInt16(0),
+			TypeKind::TK_INT32 | TypeKind::TK_ALIAS => Value::Int32(0),
+			TypeKind::TK_UINT32 => Value::UInt32(0),
+			TypeKind::TK_INT64 => Value::Int64(0),
+			TypeKind::TK_UINT64 => Value::UInt64(0),
+			TypeKind::TK_FLOAT32 => Value::Float32(0.0),
+			TypeKind::TK_FLOAT64 => Value::Float64(0.0),
+			TypeKind::TK_CHAR8 => Value::Char8(0),
+			TypeKind::TK_STRING8 => Value::String(String::new()),
+			TypeKind::TK_ENUM => Value::Enum(ty.members().first().map_or(0, |m| m.id)),
+			TypeKind::TK_SEQUENCE => Value::Sequence(Vec::new()),
+			TypeKind::TK_ARRAY => match ty.element_type() {
+				Some(element) => default_array(element, &ty.descriptor().bound),
+				None => Value::Sequence(Vec::new()),
+			},
+			TypeKind::TK_STRUCTURE => Value::Struct(ty.all_members().iter()
+				.filter(|m| !m.is_optional)
+				.map(|m| {
+					let v = m.default_value.clone().unwrap_or_else(|| Value::default_for(&m.member_type));
+					(m.name.clone(), v)
+				})
+				.collect()),
+			TypeKind::TK_UNION => {
+				let member = ty.members().iter().find(|m| !m.label.is_empty())
+					.or_else(|| ty.members().iter().find(|m| m.is_default_label));
+				let label = match member {
+					Some(m) => m.label.first().map_or_else(|| unused_label(ty), |l| *l as i64),
+					None => 0,
+				};
+				let discriminator = match ty.discriminator_type() {
+					Some(d) => value_for_label(d, label),
+					None => Value::Int32(label as i32),
+				};
+				Value::Union {
+					discriminator: Box::new(discriminator),
+					member: member.map(|m| (m.name.clone(), Box::new(Value::default_for(&m.member_type)))),
+				}
+			},
+		}
+	}
+
+	/// The member called name of a structure, or of a union if it is the
+	/// selected member.
+	pub fn field(&self, name: &str) -> Option<&Value> {
+		match *self {
+			Value::Struct(ref fields) => fields.iter().find(|f| f.0 == name).map(|f| &f.1),
+			Value::Union {member: Some((ref n, ref v)), ..} if n == name => Some(v),
+			_ => None,
+		}
+	}
+}
+
+impl DynamicData {
+	/// A sample of dynamic_type, holding the default value of each member.
+	pub fn new(dynamic_type: DynamicType) -> DynamicData {
+		let value = Value::default_for(&dynamic_type);
+		DynamicData {dynamic_type, value}
+	}
+
+	/// A sample of dynamic_type holding value, which must match the type.
+	pub fn from_value(dynamic_type: DynamicType, value: Value) -> Result<DynamicData, DynamicError> {
+		check(&dynamic_type, &value, "")?;
+		Ok(DynamicData {dynamic_type, value})
+	}
+
+	/// Reads a sample of dynamic_type from a serialized payload.
+	pub fn from_payload(dynamic_type: &DynamicType, payload: Bytes) -> Result<DynamicData, CdrError> {
+		let mut dec = CdrDecoder::from_payload(payload)?;
+		DynamicData::deserialize(dynamic_type, &mut dec)
+	}
+
+	/// Reads a sample of dynamic_type.
+	pub fn deserialize(dynamic_type: &DynamicType, dec: &mut CdrDecoder) -> Result<DynamicData, CdrError> {
+		let value = read(dec, dynamic_type)?;
+		Ok(DynamicData {dynamic_type: dynamic_type.clone(), value})
+	}
+
+	/// The sample, preceeded by the encapsulation header.
+	pub fn to_payload(&self, encapsulation: Encapsulation) -> Bytes {
+		cdr::to_payload(self, encapsulation)
+	}
+
+	pub fn dynamic_type(&self) -> &DynamicType {
+		&self.dynamic_type
+	}
+
+	pub fn value(&self) -> &Value {
+		&self.value
+	}
+
+	pub fn into_value(self) -> Value {
+		self.value
+	}
+
+	/// The member at path, e.g. "color" or "position.x". None if there is no
+	/// such member, the member is an absent optional member, or is a member
+	/// of a union which isn't selected.
+	pub fn get(&self, path: &str) -> Option<&Value> {
+		path.split('.').try_fold(&self.value, |v, name| v.field(name))
+	}
+
+	/// Gives the member at path a new value. Setting a member of a union
+	/// selects that member, changing the discriminator if necessary.
+	pub fn set(&mut self, path: &str, value: Value) -> Result<(), DynamicError> {
+		let names: Vec<&str> = path.split('.').collect();
+		update(&self.dynamic_type, &mut self.value, &names, Some(value), path)
+	}
+
+	/// Removes the value of the optional member at path.
+	pub fn clear(&mut self, path: &str) -> Result<(), DynamicError> {
+		let names: Vec<&str> = path.split('.').collect();
+		update(&self.dynamic_type, &mut self.value, &names, None, path)
+	}
+}
+
+impl CdrSerialize for DynamicData {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		write(enc, &self.dynamic_type, &self.value)
+	}
+}
+
+/// A value and it's type, so values can be handed to `put_optional`.
+struct Typed<'a>(&'a DynamicType, &'a Value);
+
+impl<'a> CdrSerialize for Typed<'a> {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		write(enc, self.0, self.1)
+	}
+}
+
+fn child(path: &str, name: &str) -> String {
+	if path.is_empty() {
+		String::from(name)
+	} else {
+		format!("{}.{}", path, name)
+	}
+}
+
+fn default_array(element: &DynamicType, dimensions: &[u32]) -> Value {
+	let (first, rest) = match dimensions.split_first() {
+		Some(d) => d,
+		None => return Value::default_for(element),
+	};
+	Value::Sequence((0..*first).map(|_| default_array(element, rest)).collect())
+}
+
+/// The discriminator value a union label stands for.
+fn value_for_label(discriminator: &DynamicType, label: i64) -> Value {
+	match discriminator.resolved().kind() {
+		TypeKind::TK_BOOLEAN => Value::Boolean(label != 0),
+		TypeKind::TK_BYTE => Value::Byte(label as u8),
+		TypeKind::TK_INT8 => Value::Int8(label as i8),
+		TypeKind::TK_UINT8 => Value::UInt8(label as u8),
+		TypeKind::TK_INT16 => Value::Int16(label as i16),
+		TypeKind::TK_UINT16 => Value::UInt16(label as u16),
+		TypeKind::TK_UINT32 => Value::UInt32(label as u32),
+		TypeKind::TK_INT64 => Value::Int64(label),
+		TypeKind::TK_UINT64 => Value::UInt64(label as u64),
+		TypeKind::TK_CHAR8 => Value::Char8(label as u8),
+		TypeKind::TK_ENUM => Value::Enum(label as u32),
+		_ => Value::Int32(label as i32),
+	}
+}
+
+/// The label a discriminator value stands for.
+fn label_of(discriminator: &Value) -> Option<i64> {
+	Some(match *discriminator {
+		Value::Boolean(v) => v as i64,
+		Value::Byte(v) | Value::UInt8(v) | Value::Char8(v) => v as i64,
+		Value::Int8(v) => v as i64,
+		Value::Int16(v) => v as i64,
+		Value::UInt16(v) => v as i64,
+		Value::Int32(v) => v as i64,
+		Value::UInt32(v) | Value::Enum(v) => v as i64,
+		Value::Int64(v) => v,
+		Value::UInt64(v) => v as i64,
+		_ => return None,
+	})
+}
+
+/// The first label, counting up from 0, which doesn't select a member of
+/// the union.
+fn unused_label(union: &DynamicType) -> i64 {
+	(0..).find(|l| !union.members().iter().any(|m| m.label.iter().any(|x| *x as i64 == *l)))
+		.unwrap_or(0)
+}
+
+/// The member of union selected by discriminator.
+fn select<'a>(union: &'a DynamicType, discriminator: &Value) -> Option<&'a MemberDescriptor> {
+	let label = label_of(discriminator)?;
+	let members = union.members();
+	members.iter().find(|m| m.label.iter().any(|l| *l as i64 == label))
+		.or_else(|| members.iter().find(|m| m.is_default_label))
+}
+
+/// Checks value matches ty. path names the value, for errors.
+fn check(ty: &DynamicType, value: &Value, path: &str) -> Result<(), DynamicError> {
+	let ty = ty.resolved();
+	let ok = match (ty.kind(), value) {
+		(TypeKind::TK_BOOLEAN, &Value::Boolean(_)) |
+		(TypeKind::TK_BYTE, &Value::Byte(_)) |
+		(TypeKind::TK_INT8, &Value::Int8(_)) |
+		(TypeKind::TK_UINT8, &Value::UInt8(_)) |
+		(TypeKind::TK_INT16, &Value::Int16(_)) |
+		(TypeKind::TK_UINT16, &Value::UInt16(_)) |
+		(TypeKind::TK_INT32, &Value::Int32(_)) |
+		(TypeKind::TK_UINT32, &Value::UInt32(_)) |
+		(TypeKind::TK_INT64, &Value::Int64(_)) |
+		(TypeKind::TK_UINT64, &Value::UInt64(_)) |
+		(TypeKind::TK_FLOAT32, &Value::Float32(_)) |
+		(TypeKind::TK_FLOAT64, &Value::Float64(_)) |
+		(TypeKind::TK_CHAR8, &Value::Char8(_)) => true,
+		(TypeKind::TK_STRING8, Value::String(s)) => ty.bound().is_none_or(|b| s.len() <= b as usize),
+		(TypeKind::TK_ENUM, &Value::Enum(v)) => ty.members().iter().any(|m| m.id == v),
+		(TypeKind::TK_SEQUENCE, Value::Sequence(elements)) => {
+			let element = ty.element_type().ok_or_else(|| DynamicError::WrongType(String::from(path)))?;
+			for e in elements {
+				check(element, e, path)?;
+			}
+			ty.bound().is_none_or(|b| elements.len() <= b as usize)
+		},
+		(TypeKind::TK_ARRAY, Value::Sequence(elements)) => match ty.element_type() {
+			Some(element) => check_array(element, &ty.descriptor().bound, elements, path)?,
+			None => false,
+		},
+		(TypeKind::TK_STRUCTURE, Value::Struct(fields)) => {
+			for f in fields {
+				if ty.member_by_name(&f.0).is_none() {
+					return Err(DynamicError::UnknownMember(child(path, &f.0)));
+				}
+			}
+			for m in ty.all_members() {
+				match value.field(&m.name) {
+					Some(v) => check(&m.member_type, v, &child(path, &m.name))?,
+					None if m.is_optional => {},
+					None => return Err(DynamicError::MissingMember(child(path, &m.name))),
+				}
+			}
+			true
+		},
+		(TypeKind::TK_UNION, Value::Union {discriminator, member}) => {
+			match ty.discriminator_type() {
+				Some(d) => check(d, discriminator, path)?,
+				None => return Err(DynamicError::WrongType(String::from(path))),
+			}
+			match (select(ty, discriminator), member) {
+				(Some(m), Some((n, v))) if m.name == *n => {
+					check(&m.member_type, v, &child(path, n))?;
+					true
+				},
+				(None, None) => true,
+				_ => false,
+			}
+		},
+		_ => false,
+	};
+	if ok {Ok(())} else {Err(DynamicError::WrongType(String::from(path)))}
+}
+
+fn check_array(element: &DynamicType, dimensions: &[u32], elements: &[Value], path: &str) -> Result<bool, DynamicError> {
+	let (first, rest) = match dimensions.split_first() {
+		Some(d) => d,
+		None => return Ok(false),
+	};
+	if elements.len() != *first as usize {
+		return Ok(false);
+	}
+	for e in elements {
+		if rest.is_empty() {
+			check(element, e, path)?;
+		} else {
+			match *e {
+				Value::Sequence(ref inner) => if !check_array(element, rest, inner, path)? {
+					return Ok(false);
+				},
+				_ => return Ok(false),
+			}
+		}
+	}
+	Ok(true)
+}
+
+/// Sets (or with None, clears) the member of current named by names.
+fn update(ty: &DynamicType,
+		  current: &mut Value,
+		  names: &[&str],
+		  new: Option<Value>,
+		  path: &str) -> Result<(), DynamicError> {
+	let ty = ty.resolved();
+	let name = names[0];
+	let member = match ty.kind() {
+		TypeKind::TK_STRUCTURE | TypeKind::TK_UNION => ty.member_by_name(name),
+		_ => None,
+	};
+	let member = member.ok_or_else(|| DynamicError::UnknownMember(String::from(path)))?;
+	let last = names.len() == 1;
+
+	match *current {
+		Value::Struct(ref mut fields) => {
+			let i = fields.iter().position(|f| f.0 == name);
+			if !last {
+				let i = match i {
+					Some(i) => i,
+					None => insert_field(ty, fields, name, Value::default_for(&member.member_type)),
+				};
+				return update(&member.member_type, &mut fields[i].1, &names[1..], new, path);
+			}
+			match (new, i) {
+				(Some(v), Some(i)) => {
+					check(&member.member_type, &v, path)?;
+					fields[i].1 = v;
+				},
+				(Some(v), None) => {
+					check(&member.member_type, &v, path)?;
+					insert_field(ty, fields, name, v);
+				},
+				(None, i) if member.is_optional => {
+					if let Some(i) = i {
+						fields.remove(i);
+					}
+				},
+				(None, _) => return Err(DynamicError::MissingMember(String::from(path))),
+			}
+			Ok(())
+		},
+		Value::Union {ref mut discriminator, member: ref mut selected} => {
+			if !last {
+				return match *selected {
+					Some((ref n, ref mut v)) if n == name => update(&member.member_type, v, &names[1..], new, path),
+					_ => Err(DynamicError::UnknownMember(String::from(path))),
+				};
+			}
+			let v = new.ok_or_else(|| DynamicError::MissingMember(String::from(path)))?;
+			check(&member.member_type, &v, path)?;
+			if select(ty, discriminator).is_none_or(|m| m.name != name) {
+				let label = member.label.first().map_or_else(|| unused_label(ty), |l| *l as i64);
+				if let Some(d) = ty.discriminator_type() {
+					**discriminator = value_for_label(d, label);
+				}
+			}
+			*selected = Some((String::from(name), Box::new(v)));
+			Ok(())
+		},
+		_ => Err(DynamicError::UnknownMember(String::from(path))),
+	}
+}
+
+/// Inserts a member of a structure, keeping the members in the order they
+/// are serialized. Returns where it was inserted.
+fn insert_field(ty: &DynamicType, fields: &mut Vec<(String, Value)>, name: &str, value: Value) -> usize {
+	let order: Vec<String> = ty.all_members().iter().map(|m| m.name.clone()).collect();
+	let rank = |n: &str| order.iter().position(|o| o == n);
+	let at = fields.iter().position(|f| rank(&f.0) > rank(name)).unwrap_or(fields.len());
+	fields.insert(at, (String::from(name), value));
+	at
+}
+
+/// Writes a value which has been checked against it's type.
+fn write(enc: &mut CdrEncoder, ty: &DynamicType, value: &Value) {
+	let ty = ty.resolved();
+	match *value {
+		Value::Boolean(v) => enc.put_bool(v),
+		Value::Byte(v) | Value::UInt8(v) | Value::Char8(v) => enc.put_u8(v),
+		Value::Int8(v) => enc.put_i8(v),
+		Value::Int16(v) => enc.put_i16(v),
+		Value::UInt16(v) => enc.put_u16(v),
+		Value::Int32(v) => enc.put_i32(v),
+		Value::UInt32(v) | Value::Enum(v) => enc.put_u32(v),
+		Value::Int64(v) => enc.put_i64(v),
+		Value::UInt64(v) => enc.put_u64(v),
+		Value::Float32(v) => enc.put_f32(v),
+		Value::Float64(v) => enc.put_f64(v),
+		Value::String(ref s) => enc.put_string(s),
+		Value::Sequence(ref elements) => {
+			let element = match ty.element_type() {
+				Some(e) => e,
+				None => return,
+			};
+			if ty.kind() == TypeKind::TK_ARRAY {
+				write_array(enc, element, &ty.descriptor().bound, elements);
+			} else {
+				let d = begin_elements(enc, element);
+				enc.put_sequence_len(elements.len());
+				for e in elements {
+					write(enc, element, e);
+				}
+				if let Some(d) = d {
+					enc.end_delimited(d);
+				}
+			}
+		},
+		Value::Struct(ref fields) => write_struct(enc, ty, fields),
+		Value::Union {ref discriminator, ref member} => {
+			let d = match ty.extensibility() {
+				Extensibility::Final => None,
+				_ => Some(enc.begin_delimited()),
+			};
+			if let Some(t) = ty.discriminator_type() {
+				write(enc, t, discriminator);
+			}
+			if let Some((ref name, ref v)) = *member {
+				if let Some(m) = ty.member_by_name(name) {
+					write(enc, &m.member_type, v);
+				}
+			}
+			if let Some(d) = d {
+				enc.end_delimited(d);
+			}
+		},
+	}
+}
+
+/// Sequences and arrays are delimited unless their elements are primitives.
+/// A multi-dimensional array is a single array of it's element type, so only
+/// the outermost dimension is delimited.
+fn begin_elements(enc: &mut CdrEncoder, element: &DynamicType) -> Option<OpenDelimited> {
+	if element.resolved().kind().is_primitive() {
+		None
+	} else {
+		Some(enc.begin_delimited())
+	}
+}
+
+fn write_array(enc: &mut CdrEncoder, element: &DynamicType, dimensions: &[u32], elements: &[Value]) {
+	let d = begin_elements(enc, element);
+	write_array_elements(enc, element, dimensions, elements);
+	if let Some(d) = d {
+		enc.end_delimited(d);
+	}
+}
+
+fn write_array_elements(enc: &mut CdrEncoder, element: &DynamicType, dimensions: &[u32], elements: &[Value]) {
+	for e in elements {
+		match *e {
+			Value::Sequence(ref inner) if dimensions.len() > 1 => write_array_elements(enc, element, &dimensions[1..], inner),
+			_ => write(enc, element, e),
+		}
+	}
+}
+
+fn write_struct(enc: &mut CdrEncoder, ty: &DynamicType, fields: &[(String, Value)]) {
+	let ext = ty.extensibility();
+	let d = match ext {
+		Extensibility::Final => None,
+		Extensibility::Appendable => Some(enc.begin_delimited()),
+		Extensibility::Mutable => Some(enc.begin_members()),
+	};
+
+	for m in ty.all_members() {
+		let v = fields.iter().find(|f| f.0 == m.name).map(|f| &f.1);
+		match (ext, m.is_optional, v) {
+			(Extensibility::Mutable, _, Some(v)) => {
+				let member = enc.begin_member(m.id, m.is_key);
+				write(enc, &m.member_type, v);
+				enc.end_member(member);
+			},
+			(Extensibility::Mutable, _, None) => {},
+			(_, true, v) => enc.put_optional(m.id, &v.map(|v| Typed(&m.member_type, v))),
+			(_, false, Some(v)) => write(enc, &m.member_type, v),
+			(_, false, None) => {},
+		}
+	}
+
+	match (ext, d) {
+		(Extensibility::Mutable, Some(d)) => enc.end_members(d),
+		(_, Some(d)) => enc.end_delimited(d),
+		(_, None) => {},
+	}
+}
+
+fn element(ty: &DynamicType) -> Result<&DynamicType, CdrError> {
+	ty.element_type().ok_or(CdrError::Invalid("sequence or array has no element type"))
+}
+
+/// Reads a value of type ty.
+fn read(dec: &mut CdrDecoder, ty: &DynamicType) -> Result<Value, CdrError> {
+	let ty = ty.resolved();
+	Ok(match ty.kind() {
+		TypeKind::TK_BOOLEAN => Value::Boolean(dec.get_bool()?),
+		TypeKind::TK_BYTE => Value::Byte(dec.get_u8()?),
+		TypeKind::TK_INT8 => Value::Int8(dec.get_i8()?),
+		TypeKind::TK_UINT8 => Value::UInt8(dec.get_u8()?),
+		TypeKind::TK_INT16 => Value::Int16(dec.get_i16()?),
+		TypeKind::TK_UINT16 => Value::UInt16(dec.get_u16()?),
+		TypeKind::TK_INT32 => Value::Int32(dec.get_i32()?),
+		TypeKind::TK_UINT32 => Value::UInt32(dec.get_u32()?),
+		TypeKind::TK_INT64 => Value::Int64(dec.get_i64()?),
+		TypeKind::TK_UINT64 => Value::UInt64(dec.get_u64()?),
+		TypeKind::TK_FLOAT32 => Value::Float32(dec.get_f32()?),
+		TypeKind::TK_FLOAT64 => Value::Float64(dec.get_f64()?),
+		TypeKind::TK_CHAR8 => Value::Char8(dec.get_u8()?),
+		TypeKind::TK_STRING8 => {
+			let s = dec.get_string()?;
+			if ty.bound().is_some_and(|b| s.len() > b as usize) {
+				return Err(CdrError::Invalid("string exceeds it's bound"));
+			}
+			Value::String(s)
+		},
+		TypeKind::TK_ENUM => {
+			let v = dec.get_u32()?;
+			if !ty.members().iter().any(|m| m.id == v) {
+				return Err(CdrError::Invalid("unknown enumerator"));
+			}
+			Value::Enum(v)
+		},
+		TypeKind::TK_SEQUENCE => {
+			let element = element(ty)?;
+			let d = read_elements(dec, element)?;
+			let len = dec.get_sequence_len(1)?;
+			if ty.bound().is_some_and(|b| len > b as usize) {
+				return Err(CdrError::Invalid("sequence exceeds it's bound"));
+			}
+			let mut elements = Vec::with_capacity(len);
+			for _ in 0..len {
+				elements.push(read(dec, element)?);
+			}
+			end_elements(dec, d)?;
+			Value::Sequence(elements)
+		},
+		TypeKind::TK_ARRAY => read_array(dec, element(ty)?, &ty.descriptor().bound)?,
+		TypeKind::TK_STRUCTURE => read_struct(dec, ty)?,
+		TypeKind::TK_UNION => {
+			let d = match ty.extensibility() {
+				Extensibility::Final => None,
+				_ => Some(dec.begin_delimited()?),
+			};
+			let d_type = ty.discriminator_type().ok_or(CdrError::Invalid("union has no discriminator type"))?;
+			let discriminator = read(dec, d_type)?;
+			let member = match select(ty, &discriminator) {
+				Some(m) => Some((m.name.clone(), Box::new(read(dec, &m.member_type)?))),
+				None => None,
+			};
+			if let Some(d) = d {
+				dec.end_delimited(d)?;
+			}
+			Value::Union {discriminator: Box::new(discriminator), member}
+		},
+		TypeKind::TK_ALIAS => return Err(CdrError::Invalid("alias has no base type")),
+	})
+}
+
+fn read_elements(dec: &mut CdrDecoder, element: &DynamicType) -> Result<Option<Delimited>, CdrError> {
+	if element.resolved().kind().is_primitive() {
+		Ok(None)
+	} else {
+		dec.begin_delimited().map(Some)
+	}
+}
+
+fn end_elements(dec: &mut CdrDecoder, delimited: Option<Delimited>) -> Result<(), CdrError> {
+	match delimited {
+		Some(d) => dec.end_delimited(d),
+		None => Ok(()),
+	}
+}
+
+fn read_array(dec: &mut CdrDecoder, element: &DynamicType, dimensions: &[u32]) -> Result<Value, CdrError> {
+	let d = read_elements(dec, element)?;
+	let elements = read_array_elements(dec, element, dimensions)?;
+	end_elements(dec, d)?;
+	Ok(elements)
+}
+
+fn read_array_elements(dec: &mut CdrDecoder, element: &DynamicType, dimensions: &[u32]) -> Result<Value, CdrError> {
+	let (first, rest) = dimensions.split_first().ok_or(CdrError::Invalid("array has no dimensions"))?;
+	let mut elements = Vec::new();
+	for _ in 0..*first {
+		elements.push(if rest.is_empty() {read(dec, element)?} else {read_array_elements(dec, element, rest)?});
+	}
+	Ok(Value::Sequence(elements))
+}
+
+/// Missing members of appendable and mutable structures take their default
+/// value, as they do with generated types.
+fn read_struct(dec: &mut CdrDecoder, ty: &DynamicType) -> Result<Value, CdrError> {
+	let members = ty.all_members();
+	let mut fields = Vec::with_capacity(members.len());
+
+	if ty.extensibility() == Extensibility::Mutable {
+		let mut found: Vec<Option<Value>> = vec![None; members.len()];
+		let d = dec.begin_members()?;
+		while let Some(mut m) = dec.next_member()? {
+			match members.iter().position(|d| d.id == m.id) {
+				Some(i) => found[i] = Some(read(&mut m.value, &members[i].member_type)?),
+				None if m.must_understand => return Err(CdrError::Invalid("unknown member")),
+				None => {},
+			}
+		}
+		dec.end_members(d)?;
+
+		for (m, v) in members.iter().zip(found) {
+			match v.or_else(|| m.default_value.clone()) {
+				Some(v) => fields.push((m.name.clone(), v)),
+				None if m.is_optional => {},
+				None => return Err(CdrError::Invalid("member is missing")),
+			}
+		}
+		return Ok(Value::Struct(fields));
+	}
+
+	let appendable = ty.extensibility() == Extensibility::Appendable;
+	let d = if appendable {Some(dec.begin_delimited()?)} else {None};
+	for m in members {
+		let missing = appendable && dec.remaining() == 0;
+		let v = if missing && m.is_optional {
+			None
+		} else if missing && m.default_value.is_some() {
+			m.default_value.clone()
+		} else if m.is_optional {
+			dec.get_optional_with(m.id, |dec| read(dec, &m.member_type))?
+		} else {
+			Some(read(dec, &m.member_type)?)
+		};
+		if let Some(v) = v {
+			fields.push((m.name.clone(), v));
+		}
+	}
+	if let Some(d) = d {
+		dec.end_delimited(d)?;
+	}
+	Ok(Value::Struct(fields))
+}
@@ -0,0 +1,477 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Describes data types at runtime, so tools which weren't compiled against a
+ topic's type can still make sense of it's samples (see DDS-XTypes v1.3
+ section 7.5.2).
+
+ A `DynamicType` is built from a `TypeDescriptor`, along with a
+ `MemberDescriptor` for each member of a structure or union. Types are
+ immutable once built, and cheap to clone, since they are shared by every
+ type which refers to them:
+
+ ```ignore
+ let shape = DynamicType::structure("ShapeType", Extensibility::Final, None, vec![
+     MemberDescriptor {is_key: true, ..MemberDescriptor::new("color", 0, DynamicType::string(Some(128)))},
+     MemberDescriptor::new("x", 1, DynamicType::primitive(TypeKind::TK_INT32)),
+     MemberDescriptor::new("y", 2, DynamicType::primitive(TypeKind::TK_INT32)),
+ ]);
+ ```
+
+ Samples of a dynamic type are held by `dynamic_data::DynamicData`. Types
+ generated by idlc or `#[derive(DdsType)]` describe themselves through
+ `DescribeType`.
+
+ `is_assignable_from` decides whether samples of one type can be read as
+ another (DDS-XTypes v1.3 section 7.2.4), following the defaults of the
+ TypeConsistencyEnforcement policy: bounds of strings and sequences are
+ ignored, and member names must match.
+ */
+use std::sync::Arc;
+use std::collections::HashSet;
+use cdr::encapsulation::Extensibility;
+use dcps::dynamic_data::Value;
+
+/// The kinds of type which can be described.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TypeKind {
+	TK_BOOLEAN,
+	TK_BYTE,
+	TK_INT8,
+	TK_UINT8,
+	TK_INT16,
+	TK_UINT16,
+	TK_INT32,
+	TK_UINT32,
+	TK_INT64,
+	TK_UINT64,
+	TK_FLOAT32,
+	TK_FLOAT64,
+	TK_CHAR8,
+	TK_STRING8,
+	TK_ALIAS,
+	TK_ENUM,
+	TK_SEQUENCE,
+	TK_ARRAY,
+	TK_STRUCTURE,
+	TK_UNION,
+}
+
+impl TypeKind {
+	/// True for the kinds which hold a single number, boolean or character.
+	/// Sequences and arrays of primitives aren't preceeded by a DHEADER.
+	pub fn is_primitive(&self) -> bool {
+		!matches!(*self, TypeKind::TK_STRING8 | TypeKind::TK_ALIAS | TypeKind::TK_ENUM |
+						 TypeKind::TK_SEQUENCE | TypeKind::TK_ARRAY | TypeKind::TK_STRUCTURE |
+						 TypeKind::TK_UNION)
+	}
+}
+
+/// Everything about a type, other than it's members.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeDescriptor {
+	pub kind: TypeKind,
+	/// Fully qualified name of an alias, enum, structure or union.
+	pub name: String,
+	/// The structure a structure inherits from, or the type an alias stands
+	/// for.
+	pub base_type: Option<DynamicType>,
+	/// Type of the discriminator of a union.
+	pub discriminator_type: Option<DynamicType>,
+	/// Bound of a string or sequence, which is empty if it is unbounded, or
+	/// the dimensions of an array.
+	pub bound: Vec<u32>,
+	/// Type of the elements of a sequence or array.
+	pub element_type: Option<DynamicType>,
+	pub extensibility_kind: Extensibility,
+}
+
+/// A member of a structure or union, or a literal of an enum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemberDescriptor {
+	pub name: String,
+	/// Member id, which identifies the member within mutable types. The id
+	/// of an enum literal is it's value.
+	pub id: u32,
+	pub member_type: DynamicType,
+	/// Value the member takes when missing from an appendable or mutable
+	/// structure.
+	pub default_value: Option<Value>,
+	/// Discriminator values selecting a member of a union. Booleans are 0 or
+	/// 1, characters their code and enumerators their value.
+	pub label: Vec<i32>,
+	pub is_key: bool,
+	pub is_optional: bool,
+	/// True if the member of a union is selected by discriminator values
+	/// which don't select any other member.
+	pub is_default_label: bool,
+}
+
+/// A type described at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicType {
+	inner: Arc<Inner>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Inner {
+	descriptor: TypeDescriptor,
+	members: Vec<MemberDescriptor>,
+}
+
+impl TypeDescriptor {
+	pub fn new(kind: TypeKind, name: &str) -> TypeDescriptor {
+		TypeDescriptor {
+			kind,
+			name: String::from(name),
+			base_type: None,
+			discriminator_type: None,
+			bound: Vec::new(),
+			element_type: None,
+			extensibility_kind: Extensibility::Final,
+		}
+	}
+}
+
+impl MemberDescriptor {
+	/// A member which is neither a key nor optional, without a default.
+	pub fn new(name: &str, id: u32, member_type: DynamicType) -> MemberDescriptor {
+		MemberDescriptor {
+			name: String::from(name),
+			id,
+			member_type,
+			default_value: None,
+			label: Vec::new(),
+			is_key: false,
+			is_optional: false,
+			is_default_label: false,
+		}
+	}
+}
+
+impl DynamicType {
+	/// Returns None if the descriptor is missing something it's kind needs:
+	/// the element type of a sequence or array, the base type of an alias or
+	/// the discriminator type of a union.
+	pub fn new(descriptor: TypeDescriptor, members: Vec<MemberDescriptor>) -> Option<DynamicType> {
+		let complete = match descriptor.kind {
+			TypeKind::TK_SEQUENCE | TypeKind::TK_ARRAY => descriptor.element_type.is_some(),
+			TypeKind::TK_ALIAS => descriptor.base_type.is_some(),
+			TypeKind::TK_UNION => descriptor.discriminator_type.is_some(),
+			_ => true,
+		};
+		if complete {
+			Some(DynamicType::build(descriptor, members))
+		} else {
+			None
+		}
+	}
+
+	/// Builds a type whose descriptor is known to be complete.
+	fn build(descriptor: TypeDescriptor, members: Vec<MemberDescriptor>) -> DynamicType {
+		DynamicType {inner: Arc::new(Inner {descriptor, members})}
+	}
+
+	/// A boolean, number or character.
+	pub fn primitive(kind: TypeKind) -> DynamicType {
+		DynamicType::build(TypeDescriptor::new(kind, ""), Vec::new())
+	}
+
+	pub fn string(bound: Option<u32>) -> DynamicType {
+		let mut descriptor = TypeDescriptor::new(TypeKind::TK_STRING8, "");
+		descriptor.bound = bound.into_iter().collect();
+		DynamicType::build(descriptor, Vec::new())
+	}
+
+	pub fn sequence(element_type: DynamicType, bound: Option<u32>) -> DynamicType {
+		let mut descriptor = TypeDescriptor::new(TypeKind::TK_SEQUENCE, "");
+		descriptor.bound = bound.into_iter().collect();
+		descriptor.element_type = Some(element_type);
+		DynamicType::build(descriptor, Vec::new())
+	}
+
+	pub fn array(element_type: DynamicType, dimensions: &[u32]) -> DynamicType {
+		let mut descriptor = TypeDescriptor::new(TypeKind::TK_ARRAY, "");
+		descriptor.bound = dimensions.to_vec();
+		descriptor.element_type = Some(element_type);
+		DynamicType::build(descriptor, Vec::new())
+	}
+
+	pub fn alias(name: &str, base_type: DynamicType) -> DynamicType {
+		let mut descriptor = TypeDescriptor::new(TypeKind::TK_ALIAS, name);
+		descriptor.base_type = Some(base_type);
+		DynamicType::build(descriptor, Vec::new())
+	}
+
+	/// An enum, whose literals take the values 0, 1, 2 and so on.
+	pub fn enumeration(name: &str, literals: &[&str]) -> DynamicType {
+		let members = literals.iter().enumerate()
+			.map(|(i, l)| MemberDescriptor::new(l, i as u32, DynamicType::primitive(TypeKind::TK_INT32)))
+			.collect();
+		DynamicType::build(TypeDescriptor::new(TypeKind::TK_ENUM, name), members)
+	}
+
+	/// A structure, which includes the members of base ahead of it's own.
+	pub fn structure(name: &str,
+					 extensibility: Extensibility,
+					 base: Option<DynamicType>,
+					 members: Vec<MemberDescriptor>) -> DynamicType {
+		let mut descriptor = TypeDescriptor::new(TypeKind::TK_STRUCTURE, name);
+		descriptor.extensibility_kind = extensibility;
+		descriptor.base_type = base;
+		DynamicType::build(descriptor, members)
+	}
+
+	pub fn union(name: &str,
+				 extensibility: Extensibility,
+				 discriminator: DynamicType,
+				 members: Vec<MemberDescriptor>) -> DynamicType {
+		let mut descriptor = TypeDescriptor::new(TypeKind::TK_UNION, name);
+		descriptor.extensibility_kind = extensibility;
+		descriptor.discriminator_type = Some(discriminator);
+		DynamicType::build(descriptor, members)
+	}
+
+	pub fn descriptor(&self) -> &TypeDescriptor {
+		&self.inner.descriptor
+	}
+
+	pub fn kind(&self) -> TypeKind {
+		self.inner.descriptor.kind
+	}
+
+	pub fn name(&self) -> &str {
+		&self.inner.descriptor.name
+	}
+
+	/// The members declared by this type, excluding any it inherits.
+	pub fn members(&self) -> &[MemberDescriptor] {
+		&self.inner.members
+	}
+
+	/// Every member of a structure, starting with those it inherits, in the
+	/// order they are serialized.
+	pub fn all_members(&self) -> Vec<&MemberDescriptor> {
+		let mut members = match self.inner.descriptor.base_type {
+			Some(ref base) if self.kind() == TypeKind::TK_STRUCTURE => base.resolved().all_members(),
+			_ => Vec::new(),
+		};
+		members.extend(self.inner.members.iter());
+		members
+	}
+
+	/// Finds a member (including inherited members) by name.
+	pub fn member_by_name(&self, name: &str) -> Option<&MemberDescriptor> {
+		self.all_members().into_iter().find(|m| m.name == name)
+	}
+
+	/// The type, looking through aliases.
+	pub fn resolved(&self) -> &DynamicType {
+		match self.kind() {
+			TypeKind::TK_ALIAS => match self.inner.descriptor.base_type {
+				Some(ref base) => base.resolved(),
+				None => self,
+			},
+			_ => self,
+		}
+	}
+
+	/// Bound of a string or sequence, None if unbounded.
+	pub fn bound(&self) -> Option<u32> {
+		self.inner.descriptor.bound.first().cloned()
+	}
+
+	pub fn element_type(&self) -> Option<&DynamicType> {
+		self.inner.descriptor.element_type.as_ref()
+	}
+
+	pub fn discriminator_type(&self) -> Option<&DynamicType> {
+		self.inner.descriptor.discriminator_type.as_ref()
+	}
+
+	pub fn extensibility(&self) -> Extensibility {
+		self.inner.descriptor.extensibility_kind
+	}
+}
+
+impl DynamicType {
+	/// True if samples written as other can be read as self.
+	///
+	/// Aliases are looked through, and both types must be of the same kind.
+	/// Sequences and arrays must have assignable elements (and arrays the
+	/// same dimensions). Enums, structures and unions must have the same
+	/// extensibility:
+	///
+	/// - Members (or literals) are matched by id, and matching members must
+	///   have the same name.
+	/// - Final types must have exactly the same members, while one appendable
+	///   type must hold the leading members of the other. Mutable types need
+	///   at least one member in common.
+	/// - Matching members of structures must have assignable types, and agree
+	///   on being a key or optional. A key member of either must be present
+	///   in both.
+	/// - Union discriminator values must select matching members.
+	pub fn is_assignable_from(&self, other: &DynamicType) -> bool {
+		let (reader, writer) = (self.resolved(), other.resolved());
+		if reader.kind() != writer.kind() {
+			return false;
+		}
+		match reader.kind() {
+			k if k.is_primitive() => true,
+			TypeKind::TK_STRING8 => true,
+			TypeKind::TK_SEQUENCE => elements_assignable(reader, writer),
+			TypeKind::TK_ARRAY =>
+				reader.descriptor().bound == writer.descriptor().bound && elements_assignable(reader, writer),
+			TypeKind::TK_ENUM | TypeKind::TK_STRUCTURE | TypeKind::TK_UNION => {
+				if reader.extensibility() != writer.extensibility() {
+					return false;
+				}
+				let (r, w) = (reader.all_members(), writer.all_members());
+				if !members_correspond(reader.extensibility(), &r, &w) {
+					return false;
+				}
+				match reader.kind() {
+					TypeKind::TK_STRUCTURE => structure_assignable(&r, &w),
+					TypeKind::TK_UNION => union_assignable(reader, writer, &r, &w),
+					_ => enum_assignable(&r, &w),
+				}
+			},
+			_ => false,
+		}
+	}
+}
+
+fn elements_assignable(reader: &DynamicType, writer: &DynamicType) -> bool {
+	match (reader.element_type(), writer.element_type()) {
+		(Some(r), Some(w)) => r.is_assignable_from(w),
+		_ => false,
+	}
+}
+
+/// Checks the members shared by both types are where extensibility requires
+/// them to be, and have the same name.
+fn members_correspond(extensibility: Extensibility, reader: &[&MemberDescriptor], writer: &[&MemberDescriptor]) -> bool {
+	let common = match extensibility {
+		Extensibility::Final if reader.len() != writer.len() => return false,
+		Extensibility::Final | Extensibility::Appendable => {
+			let n = reader.len().min(writer.len());
+			if reader.iter().zip(writer.iter()).take(n).any(|(r, w)| r.id != w.id) {
+				return false;
+			}
+			n
+		},
+		Extensibility::Mutable => reader.iter().filter(|r| writer.iter().any(|w| w.id == r.id)).count(),
+	};
+	if common == 0 && !(reader.is_empty() && writer.is_empty()) {
+		return false;
+	}
+	reader.iter().all(|r| match writer.iter().find(|w| w.id == r.id) {
+		Some(w) => w.name == r.name,
+		None => !writer.iter().any(|w| w.name == r.name),
+	})
+}
+
+fn structure_assignable(reader: &[&MemberDescriptor], writer: &[&MemberDescriptor]) -> bool {
+	let paired = |a: &[&MemberDescriptor], b: &[&MemberDescriptor]| a.iter()
+		.filter(|m| m.is_key)
+		.all(|m| b.iter().any(|o| o.id == m.id));
+	if !paired(reader, writer) || !paired(writer, reader) {
+		return false;
+	}
+	reader.iter().all(|r| match writer.iter().find(|w| w.id == r.id) {
+		Some(w) => r.is_key == w.is_key && r.is_optional == w.is_optional &&
+			r.member_type.is_assignable_from(&w.member_type),
+		None => true,
+	})
+}
+
+fn union_assignable(reader: &DynamicType,
+					writer: &DynamicType,
+					r: &[&MemberDescriptor],
+					w: &[&MemberDescriptor]) -> bool {
+	let discriminators = match (reader.discriminator_type(), writer.discriminator_type()) {
+		(Some(rd), Some(wd)) => rd.is_assignable_from(wd),
+		_ => false,
+	};
+	if !discriminators {
+		return false;
+	}
+	// Every label of the writer must select the same member of the reader,
+	// or fall to the reader's default member.
+	let reader_default = r.iter().find(|m| m.is_default_label).map(|m| m.id);
+	let selects = |label: i32| r.iter().find(|m| m.label.contains(&label)).map(|m| m.id).or(reader_default);
+	let labels_agree = w.iter().all(|m| m.label.iter().all(|&l| selects(l).is_none_or(|id| id == m.id)));
+	labels_agree && r.iter().all(|rm| match w.iter().find(|wm| wm.id == rm.id) {
+		Some(wm) => rm.member_type.is_assignable_from(&wm.member_type),
+		None => true,
+	})
+}
+
+/// Literals with the same name must have the same value, and vice versa.
+fn enum_assignable(reader: &[&MemberDescriptor], writer: &[&MemberDescriptor]) -> bool {
+	let values: HashSet<u32> = writer.iter().map(|m| m.id).collect();
+	values.len() == writer.len() && reader.iter().all(|r| match writer.iter().find(|w| w.name == r.name) {
+		Some(w) => w.id == r.id,
+		None => !values.contains(&r.id),
+	})
+}
+
+/// Implemented by types which can describe themselves at runtime. idlc and
+/// `#[derive(DdsType)]` implement it for the types they generate.
+pub trait DescribeType {
+	fn dynamic_type() -> DynamicType;
+}
+
+macro_rules! describe_primitive {
+	($t:ty, $kind:ident) => {
+		impl DescribeType for $t {
+			fn dynamic_type() -> DynamicType {
+				DynamicType::primitive(TypeKind::$kind)
+			}
+		}
+	}
+}
+
+describe_primitive!(bool, TK_BOOLEAN);
+describe_primitive!(u8, TK_UINT8);
+describe_primitive!(i8, TK_INT8);
+describe_primitive!(u16, TK_UINT16);
+describe_primitive!(i16, TK_INT16);
+describe_primitive!(u32, TK_UINT32);
+describe_primitive!(i32, TK_INT32);
+describe_primitive!(u64, TK_UINT64);
+describe_primitive!(i64, TK_INT64);
+describe_primitive!(f32, TK_FLOAT32);
+describe_primitive!(f64, TK_FLOAT64);
+
+impl DescribeType for String {
+	fn dynamic_type() -> DynamicType {
+		DynamicType::string(None)
+	}
+}
+
+impl<T: DescribeType> DescribeType for Vec<T> {
+	fn dynamic_type() -> DynamicType {
+		DynamicType::sequence(T::dynamic_type(), None)
+	}
+}
+
+/// Arrays of arrays are described as a single multi-dimensional array.
+impl<T: DescribeType, const N: usize> DescribeType for [T; N] {
+	fn dynamic_type() -> DynamicType {
+		let element = T::dynamic_type();
+		match element.element_type() {
+			Some(inner) if element.kind() == TypeKind::TK_ARRAY => {
+				let mut dimensions = vec![N as u32];
+				dimensions.extend(element.descriptor().bound.iter().cloned());
+				DynamicType::array(inner.clone(), &dimensions)
+			},
+			_ => DynamicType::array(element, &[N as u32]),
+		}
+	}
+}
+
+impl<T: DescribeType> DescribeType for Box<T> {
+	fn dynamic_type() -> DynamicType {
+		T::dynamic_type()
+	}
+}
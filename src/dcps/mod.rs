@@ -1,6 +1,9 @@
 // Copyright © Travis Gruber 2017, All Rights Reserved.
 /*! 
- 
+ Data Centric Publish/Subscribe, the application facing API of DDS.
  */
 
- 
\ No newline at end of file
+pub mod type_support;
+pub mod dynamic_type;
+pub mod dynamic_data;
+pub mod type_object;
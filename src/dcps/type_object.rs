@@ -0,0 +1,978 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ The XTypes representations of a type (DDS-XTypes v1.3 section 7.3.4), which
+ let participants compare the types of the topics they publish and subscribe
+ to.
+
+ Every type has a `TypeIdentifier`. Primitives, strings, and sequences and
+ arrays of other identifiable types are fully described by their identifier.
+ Aliases, enums, structures and unions are described by a `TypeObject`, and
+ identified by a hash of it. Type objects come in two flavours: minimal,
+ which only holds what is needed to decide assignability (member names are
+ replaced by a hash of the name), and complete, which holds everything.
+
+ The identifiers of a topic's type are announced during discovery, as the
+ `TypeInformation` within PID_TYPE_INFORMATION. Endpoints whose identifiers
+ are equal have the same type. Otherwise `is_assignable` looks up the
+ complete type objects within a `TypeLibrary`, and compares the types they
+ describe.
+
+ Primitive identifiers are written as just their TypeKind, as done by other
+ implementations (the IDL of the spec can't express a union case without a
+ member). Annotations aren't carried, so the `@default` of a member doesn't
+ survive being turned into a type object.
+ */
+use std::collections::HashMap;
+use md5;
+use cdr;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+use cdr::decoder::*;
+use dcps::dynamic_type::*;
+
+pub const TK_NONE: u8 = 					0x00;
+pub const TK_BOOLEAN: u8 = 					0x01;
+pub const TK_BYTE: u8 = 					0x02;
+pub const TK_INT16: u8 = 					0x03;
+pub const TK_INT32: u8 = 					0x04;
+pub const TK_INT64: u8 = 					0x05;
+pub const TK_UINT16: u8 = 					0x06;
+pub const TK_UINT32: u8 = 					0x07;
+pub const TK_UINT64: u8 = 					0x08;
+pub const TK_FLOAT32: u8 = 					0x09;
+pub const TK_FLOAT64: u8 = 					0x0a;
+pub const TK_INT8: u8 = 					0x0c;
+pub const TK_UINT8: u8 = 					0x0d;
+pub const TK_CHAR8: u8 = 					0x10;
+pub const TK_STRING8: u8 = 					0x20;
+pub const TK_ALIAS: u8 = 					0x30;
+pub const TK_ENUM: u8 = 					0x40;
+pub const TK_STRUCTURE: u8 = 				0x51;
+pub const TK_UNION: u8 = 					0x52;
+pub const TK_SEQUENCE: u8 = 				0x60;
+pub const TK_ARRAY: u8 = 					0x61;
+
+pub const TI_STRING8_SMALL: u8 = 			0x70;
+pub const TI_STRING8_LARGE: u8 = 			0x71;
+pub const TI_PLAIN_SEQUENCE_SMALL: u8 = 	0x80;
+pub const TI_PLAIN_SEQUENCE_LARGE: u8 = 	0x81;
+pub const TI_PLAIN_ARRAY_SMALL: u8 = 		0x90;
+pub const TI_PLAIN_ARRAY_LARGE: u8 = 		0x91;
+
+pub const EK_MINIMAL: u8 = 					0xf1;
+pub const EK_COMPLETE: u8 = 				0xf2;
+/// Used by plain collections whose elements are fully described by their
+/// identifier.
+pub const EK_BOTH: u8 = 					0xf3;
+
+/// Members whose value can't be represented are discarded (the default
+/// try construct behaviour).
+pub const TRY_CONSTRUCT1: u16 = 			0x0001;
+pub const IS_OPTIONAL: u16 = 				0x0008;
+pub const IS_MUST_UNDERSTAND: u16 = 		0x0010;
+pub const IS_KEY: u16 = 					0x0020;
+pub const IS_DEFAULT: u16 = 				0x0040;
+
+pub const IS_FINAL: u16 = 					0x0001;
+pub const IS_APPENDABLE: u16 = 				0x0002;
+pub const IS_MUTABLE: u16 = 				0x0004;
+
+const PID_TYPE_INFORMATION_MINIMAL: u32 = 	0x1001;
+const PID_TYPE_INFORMATION_COMPLETE: u32 = 	0x1002;
+
+/// Hash of a serialized type object.
+pub type EquivalenceHash = [u8; 14];
+
+/// Hash of the name of a member, used by minimal type objects.
+pub type NameHash = [u8; 4];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EquivalenceKind {
+	Minimal,
+	Complete,
+}
+
+impl EquivalenceKind {
+	fn octet(&self) -> u8 {
+		match *self {
+			EquivalenceKind::Minimal => EK_MINIMAL,
+			EquivalenceKind::Complete => EK_COMPLETE,
+		}
+	}
+}
+
+/// Identifies a type. Bounds and dimensions under 256 are written using the
+/// small forms of the identifier.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TypeIdentifier {
+	/// TK_NONE, or one of the primitive TypeKinds.
+	Primitive(u8),
+	/// A string, whose bound is 0 if it is unbounded.
+	String(u32),
+	/// A sequence, whose bound is 0 if it is unbounded.
+	PlainSequence {element_flags: u16, bound: u32, element: Box<TypeIdentifier>},
+	PlainArray {element_flags: u16, dimensions: Vec<u32>, element: Box<TypeIdentifier>},
+	/// Identifies the minimal type object with this hash.
+	Minimal(EquivalenceHash),
+	/// Identifies the complete type object with this hash.
+	Complete(EquivalenceHash),
+}
+
+/// The name of a member, or a hash of it within minimal type objects.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MemberName {
+	Hash(NameHash),
+	Name(String),
+}
+
+/// A member of a structure or union, or a literal of an enum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectMember {
+	/// The member id, or the value of a literal.
+	pub id: u32,
+	pub flags: u16,
+	/// TK_NONE for literals.
+	pub type_id: TypeIdentifier,
+	/// Discriminator values selecting a member of a union.
+	pub labels: Vec<i32>,
+	pub name: MemberName,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeObjectKind {
+	Alias(TypeIdentifier),
+	Enum(Vec<ObjectMember>),
+	/// The structure inherited from (TK_NONE if there is none) and the
+	/// members of the structure itself.
+	Struct(TypeIdentifier, Vec<ObjectMember>),
+	/// The type of the discriminator, and the members.
+	Union(TypeIdentifier, Vec<ObjectMember>),
+}
+
+/// Describes an alias, enum, structure or union.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeObject {
+	pub equivalence_kind: EquivalenceKind,
+	/// Fully qualified name of the type, which is empty within minimal type
+	/// objects.
+	pub type_name: String,
+	/// IS_FINAL, IS_APPENDABLE or IS_MUTABLE for structures and unions.
+	pub flags: u16,
+	pub kind: TypeObjectKind,
+}
+
+/// A type identifier, and the size of the type object it identifies (0 if
+/// it is fully descriptive).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeIdentifierWithSize {
+	pub type_id: TypeIdentifier,
+	pub typeobject_serialized_size: u32,
+}
+
+/// The identifier of a type, along with those of every type it depends on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeIdentifierWithDependencies {
+	pub typeid_with_size: TypeIdentifierWithSize,
+	/// Number of dependencies, which may exceed the length of
+	/// dependent_typeids if not all of them are listed, or -1 if unknown.
+	pub dependent_typeid_count: i32,
+	pub dependent_typeids: Vec<TypeIdentifierWithSize>,
+}
+
+/// Identifies the type of an endpoint, as sent in PID_TYPE_INFORMATION.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeInformation {
+	pub minimal: TypeIdentifierWithDependencies,
+	pub complete: TypeIdentifierWithDependencies,
+}
+
+/// Type objects, found by their identifier.
+pub struct TypeLibrary {
+	objects: HashMap<TypeIdentifier, TypeObject>,
+}
+
+fn kind_octet(kind: TypeKind) -> u8 {
+	match kind {
+		TypeKind::TK_BOOLEAN => TK_BOOLEAN,
+		TypeKind::TK_BYTE => TK_BYTE,
+		TypeKind::TK_INT8 => TK_INT8,
+		TypeKind::TK_UINT8 => TK_UINT8,
+		TypeKind::TK_INT16 => TK_INT16,
+		TypeKind::TK_UINT16 => TK_UINT16,
+		TypeKind::TK_INT32 => TK_INT32,
+		TypeKind::TK_UINT32 => TK_UINT32,
+		TypeKind::TK_INT64 => TK_INT64,
+		TypeKind::TK_UINT64 => TK_UINT64,
+		TypeKind::TK_FLOAT32 => TK_FLOAT32,
+		TypeKind::TK_FLOAT64 => TK_FLOAT64,
+		TypeKind::TK_CHAR8 => TK_CHAR8,
+		TypeKind::TK_STRING8 => TK_STRING8,
+		TypeKind::TK_ALIAS => TK_ALIAS,
+		TypeKind::TK_ENUM => TK_ENUM,
+		TypeKind::TK_SEQUENCE => TK_SEQUENCE,
+		TypeKind::TK_ARRAY => TK_ARRAY,
+		TypeKind::TK_STRUCTURE => TK_STRUCTURE,
+		TypeKind::TK_UNION => TK_UNION,
+	}
+}
+
+/// The primitive kind written as octet.
+fn primitive_kind(octet: u8) -> Option<TypeKind> {
+	Some(match octet {
+		TK_BOOLEAN => TypeKind::TK_BOOLEAN,
+		TK_BYTE => TypeKind::TK_BYTE,
+		TK_INT8 => TypeKind::TK_INT8,
+		TK_UINT8 => TypeKind::TK_UINT8,
+		TK_INT16 => TypeKind::TK_INT16,
+		TK_UINT16 => TypeKind::TK_UINT16,
+		TK_INT32 => TypeKind::TK_INT32,
+		TK_UINT32 => TypeKind::TK_UINT32,
+		TK_INT64 => TypeKind::TK_INT64,
+		TK_UINT64 => TypeKind::TK_UINT64,
+		TK_FLOAT32 => TypeKind::TK_FLOAT32,
+		TK_FLOAT64 => TypeKind::TK_FLOAT64,
+		TK_CHAR8 => TypeKind::TK_CHAR8,
+		_ => return None,
+	})
+}
+
+fn type_flags(extensibility: Extensibility) -> u16 {
+	match extensibility {
+		Extensibility::Final => IS_FINAL,
+		Extensibility::Appendable => IS_APPENDABLE,
+		Extensibility::Mutable => IS_MUTABLE,
+	}
+}
+
+fn name_hash(name: &str) -> NameHash {
+	let mut hash = [0u8; 4];
+	hash.copy_from_slice(&md5::compute(name.as_bytes()).0[..4]);
+	hash
+}
+
+impl TypeIdentifier {
+	/// Identifies ty, using hashes of the kind of type object given.
+	///
+	/// `DynamicType::new` makes sure every type has what it's identifier
+	/// needs, so the TK_NONE fallback is never used.
+	pub fn new(ty: &DynamicType, kind: EquivalenceKind) -> TypeIdentifier {
+		let element = |e: &DynamicType| Box::new(TypeIdentifier::new(e, kind));
+		match (ty.kind(), ty.element_type()) {
+			(TypeKind::TK_STRING8, _) => TypeIdentifier::String(ty.bound().unwrap_or(0)),
+			(TypeKind::TK_SEQUENCE, Some(e)) => TypeIdentifier::PlainSequence {
+				element_flags: TRY_CONSTRUCT1,
+				bound: ty.bound().unwrap_or(0),
+				element: element(e),
+			},
+			(TypeKind::TK_ARRAY, Some(e)) => TypeIdentifier::PlainArray {
+				element_flags: TRY_CONSTRUCT1,
+				dimensions: ty.descriptor().bound.clone(),
+				element: element(e),
+			},
+			(k, _) if k.is_primitive() => TypeIdentifier::Primitive(kind_octet(k)),
+			_ => match TypeObject::new(ty, kind) {
+				Some(object) => object.type_identifier(),
+				None => TypeIdentifier::Primitive(TK_NONE),
+			},
+		}
+	}
+
+	/// True if the identifier is a hash, rather than describing the type.
+	pub fn is_hash(&self) -> bool {
+		matches!(*self, TypeIdentifier::Minimal(_) | TypeIdentifier::Complete(_))
+	}
+
+	/// The equivalence kind of a plain collection of element.
+	fn collection_kind(element: &TypeIdentifier) -> u8 {
+		match *element {
+			TypeIdentifier::Minimal(_) => EK_MINIMAL,
+			TypeIdentifier::Complete(_) => EK_COMPLETE,
+			TypeIdentifier::PlainSequence {ref element, ..} |
+			TypeIdentifier::PlainArray {ref element, ..} => TypeIdentifier::collection_kind(element),
+			_ => EK_BOTH,
+		}
+	}
+}
+
+impl CdrSerialize for TypeIdentifier {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		match *self {
+			TypeIdentifier::Primitive(k) => enc.put_u8(k),
+			TypeIdentifier::String(bound) if bound < 256 => {
+				enc.put_u8(TI_STRING8_SMALL);
+				enc.put_u8(bound as u8);
+			},
+			TypeIdentifier::String(bound) => {
+				enc.put_u8(TI_STRING8_LARGE);
+				enc.put_u32(bound);
+			},
+			TypeIdentifier::PlainSequence {element_flags, bound, ref element} => {
+				let small = bound < 256;
+				enc.put_u8(if small {TI_PLAIN_SEQUENCE_SMALL} else {TI_PLAIN_SEQUENCE_LARGE});
+				enc.put_u8(TypeIdentifier::collection_kind(element));
+				enc.put_u16(element_flags);
+				if small {
+					enc.put_u8(bound as u8);
+				} else {
+					enc.put_u32(bound);
+				}
+				element.serialize(enc);
+			},
+			TypeIdentifier::PlainArray {element_flags, ref dimensions, ref element} => {
+				let small = dimensions.iter().all(|&d| d < 256);
+				enc.put_u8(if small {TI_PLAIN_ARRAY_SMALL} else {TI_PLAIN_ARRAY_LARGE});
+				enc.put_u8(TypeIdentifier::collection_kind(element));
+				enc.put_u16(element_flags);
+				enc.put_sequence_len(dimensions.len());
+				for &d in dimensions {
+					if small {
+						enc.put_u8(d as u8);
+					} else {
+						enc.put_u32(d);
+					}
+				}
+				element.serialize(enc);
+			},
+			TypeIdentifier::Minimal(ref hash) => {
+				enc.put_u8(EK_MINIMAL);
+				enc.put_octets(hash);
+			},
+			TypeIdentifier::Complete(ref hash) => {
+				enc.put_u8(EK_COMPLETE);
+				enc.put_octets(hash);
+			},
+		}
+	}
+}
+
+impl CdrDeserialize for TypeIdentifier {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = dec.get_u8()?;
+		Ok(match d {
+			TI_STRING8_SMALL => TypeIdentifier::String(dec.get_u8()? as u32),
+			TI_STRING8_LARGE => TypeIdentifier::String(dec.get_u32()?),
+			TI_PLAIN_SEQUENCE_SMALL | TI_PLAIN_SEQUENCE_LARGE => {
+				dec.get_u8()?;
+				let element_flags = dec.get_u16()?;
+				let bound = if d == TI_PLAIN_SEQUENCE_SMALL {dec.get_u8()? as u32} else {dec.get_u32()?};
+				TypeIdentifier::PlainSequence {
+					element_flags,
+					bound,
+					element: Box::new(TypeIdentifier::deserialize(dec)?),
+				}
+			},
+			TI_PLAIN_ARRAY_SMALL | TI_PLAIN_ARRAY_LARGE => {
+				dec.get_u8()?;
+				let element_flags = dec.get_u16()?;
+				let small = d == TI_PLAIN_ARRAY_SMALL;
+				let len = dec.get_sequence_len(if small {1} else {4})?;
+				let mut dimensions = Vec::with_capacity(len);
+				for _ in 0..len {
+					dimensions.push(if small {dec.get_u8()? as u32} else {dec.get_u32()?});
+				}
+				TypeIdentifier::PlainArray {
+					element_flags,
+					dimensions,
+					element: Box::new(TypeIdentifier::deserialize(dec)?),
+				}
+			},
+			EK_MINIMAL | EK_COMPLETE => {
+				let mut hash = [0u8; 14];
+				hash.copy_from_slice(&dec.get_octets(14)?);
+				if d == EK_MINIMAL {TypeIdentifier::Minimal(hash)} else {TypeIdentifier::Complete(hash)}
+			},
+			k if k == TK_NONE || primitive_kind(k).is_some() => TypeIdentifier::Primitive(k),
+			_ => return Err(CdrError::Invalid("unknown type identifier")),
+		})
+	}
+}
+
+impl TypeObject {
+	/// The type object describing ty, or None if ty is fully described by
+	/// it's identifier.
+	pub fn new(ty: &DynamicType, kind: EquivalenceKind) -> Option<TypeObject> {
+		let id = |ty: &DynamicType| TypeIdentifier::new(ty, kind);
+		let name = |n: &str| match kind {
+			EquivalenceKind::Minimal => MemberName::Hash(name_hash(n)),
+			EquivalenceKind::Complete => MemberName::Name(String::from(n)),
+		};
+		let object_kind = match ty.kind() {
+			TypeKind::TK_ALIAS => TypeObjectKind::Alias(id(ty.descriptor().base_type.as_ref()?)),
+			TypeKind::TK_ENUM => TypeObjectKind::Enum(ty.members().iter().map(|m| ObjectMember {
+				id: m.id,
+				flags: 0,
+				type_id: TypeIdentifier::Primitive(TK_NONE),
+				labels: Vec::new(),
+				name: name(&m.name),
+			}).collect()),
+			TypeKind::TK_STRUCTURE => {
+				let base = match ty.descriptor().base_type {
+					Some(ref b) => id(b),
+					None => TypeIdentifier::Primitive(TK_NONE),
+				};
+				TypeObjectKind::Struct(base, ty.members().iter().map(|m| {
+					let mut flags = TRY_CONSTRUCT1;
+					if m.is_optional {
+						flags |= IS_OPTIONAL;
+					}
+					if m.is_key {
+						flags |= IS_KEY | IS_MUST_UNDERSTAND;
+					}
+					ObjectMember {
+						id: m.id,
+						flags,
+						type_id: id(&m.member_type),
+						labels: Vec::new(),
+						name: name(&m.name),
+					}
+				}).collect())
+			},
+			TypeKind::TK_UNION => TypeObjectKind::Union(id(ty.discriminator_type()?), ty.members().iter().map(|m| {
+				ObjectMember {
+					id: m.id,
+					flags: if m.is_default_label {TRY_CONSTRUCT1 | IS_DEFAULT} else {TRY_CONSTRUCT1},
+					type_id: id(&m.member_type),
+					labels: m.label.clone(),
+					name: name(&m.name),
+				}
+			}).collect()),
+			_ => return None,
+		};
+
+		let flags = match ty.kind() {
+			TypeKind::TK_STRUCTURE | TypeKind::TK_UNION => type_flags(ty.extensibility()),
+			_ => 0,
+		};
+		Some(TypeObject {
+			equivalence_kind: kind,
+			type_name: match kind {
+				EquivalenceKind::Minimal => String::new(),
+				EquivalenceKind::Complete => String::from(ty.name()),
+			},
+			flags,
+			kind: object_kind,
+		})
+	}
+
+	/// The type object serialized as little endian XCDR2, without an
+	/// encapsulation header, which is what the equivalence hash is computed
+	/// over.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let payload = cdr::to_payload(self, Encapsulation::CDR2_LE);
+		// Padding at the end of the payload isn't part of the type object.
+		let padding = (payload[3] & 0x03) as usize;
+		payload[ENCAPSULATION_HEADER_SIZE..payload.len() - padding].to_vec()
+	}
+
+	/// Identifies the type object by the first 14 bytes of the MD5 hash of
+	/// it's serialization.
+	pub fn type_identifier(&self) -> TypeIdentifier {
+		let mut hash = [0u8; 14];
+		hash.copy_from_slice(&md5::compute(self.to_bytes()).0[..14]);
+		match self.equivalence_kind {
+			EquivalenceKind::Minimal => TypeIdentifier::Minimal(hash),
+			EquivalenceKind::Complete => TypeIdentifier::Complete(hash),
+		}
+	}
+
+	fn is_complete(&self) -> bool {
+		self.equivalence_kind == EquivalenceKind::Complete
+	}
+
+	/// Writes the detail of a complete type, without any annotations.
+	fn put_type_detail(&self, enc: &mut CdrEncoder) {
+		if self.is_complete() {
+			enc.put_optional::<u8>(0, &None);
+			enc.put_optional::<u8>(1, &None);
+			enc.put_string(&self.type_name);
+		}
+	}
+
+	fn get_type_detail(&mut self, dec: &mut CdrDecoder) -> Result<(), CdrError> {
+		if self.is_complete() {
+			skip_annotations(dec)?;
+			self.type_name = dec.get_string()?;
+		}
+		Ok(())
+	}
+
+	fn put_members(&self, enc: &mut CdrEncoder, members: &[ObjectMember]) {
+		let seq = enc.begin_delimited();
+		enc.put_sequence_len(members.len());
+		for m in members {
+			let d = enc.begin_delimited();
+			match self.kind {
+				TypeObjectKind::Enum(_) => {
+					enc.put_i32(m.id as i32);
+					enc.put_u16(m.flags);
+				},
+				TypeObjectKind::Union(..) => {
+					enc.put_u32(m.id);
+					enc.put_u16(m.flags);
+					m.type_id.serialize(enc);
+					m.labels.serialize(enc);
+				},
+				_ => {
+					enc.put_u32(m.id);
+					enc.put_u16(m.flags);
+					m.type_id.serialize(enc);
+				},
+			}
+			match m.name {
+				MemberName::Hash(ref h) => enc.put_octets(h),
+				MemberName::Name(ref n) => {
+					enc.put_string(n);
+					enc.put_optional::<u8>(0, &None);
+					enc.put_optional::<u8>(1, &None);
+				},
+			}
+			enc.end_delimited(d);
+		}
+		enc.end_delimited(seq);
+	}
+
+	fn get_members(&self, dec: &mut CdrDecoder, kind: u8) -> Result<Vec<ObjectMember>, CdrError> {
+		let seq = dec.begin_delimited()?;
+		let len = dec.get_sequence_len(4)?;
+		let mut members = Vec::with_capacity(len);
+		for _ in 0..len {
+			let d = dec.begin_delimited()?;
+			let mut m = ObjectMember {
+				id: 0,
+				flags: 0,
+				type_id: TypeIdentifier::Primitive(TK_NONE),
+				labels: Vec::new(),
+				name: MemberName::Hash([0; 4]),
+			};
+			m.id = dec.get_u32()?;
+			m.flags = dec.get_u16()?;
+			if kind != TK_ENUM {
+				m.type_id = TypeIdentifier::deserialize(dec)?;
+			}
+			if kind == TK_UNION {
+				m.labels = Vec::deserialize(dec)?;
+			}
+			m.name = if self.is_complete() {
+				let name = dec.get_string()?;
+				skip_annotations(dec)?;
+				MemberName::Name(name)
+			} else {
+				let mut h = [0u8; 4];
+				h.copy_from_slice(&dec.get_octets(4)?);
+				MemberName::Hash(h)
+			};
+			dec.end_delimited(d)?;
+			members.push(m);
+		}
+		dec.end_delimited(seq)?;
+		Ok(members)
+	}
+}
+
+/// Skips the optional builtin and custom annotations, which aren't kept.
+fn skip_annotations(dec: &mut CdrDecoder) -> Result<(), CdrError> {
+	for id in 0..2 {
+		dec.get_optional_with(id, |dec| {
+			let remaining = dec.remaining();
+			if dec.version() == CdrVersion::XCDR2 {
+				// Both are appendable, or sequences of appendable types.
+				let d = dec.begin_delimited()?;
+				dec.end_delimited(d)
+			} else {
+				dec.skip(remaining)
+			}
+		})?;
+	}
+	Ok(())
+}
+
+/// Written as the TypeObject union, holding either a MinimalTypeObject or a
+/// CompleteTypeObject, which is a union of the kinds of type object.
+impl CdrSerialize for TypeObject {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let d = enc.begin_delimited();
+		enc.put_u8(self.equivalence_kind.octet());
+		match self.kind {
+			TypeObjectKind::Alias(ref related) => {
+				enc.put_u8(TK_ALIAS);
+				enc.put_u16(self.flags);
+				let header = enc.begin_delimited();
+				self.put_type_detail(enc);
+				enc.end_delimited(header);
+				let body = enc.begin_delimited();
+				enc.put_u16(0);
+				related.serialize(enc);
+				if self.is_complete() {
+					enc.put_optional::<u8>(0, &None);
+					enc.put_optional::<u8>(1, &None);
+				}
+				enc.end_delimited(body);
+			},
+			TypeObjectKind::Enum(ref literals) => {
+				enc.put_u8(TK_ENUM);
+				enc.put_u16(self.flags);
+				let header = enc.begin_delimited();
+				// bit_bound
+				enc.put_u16(32);
+				self.put_type_detail(enc);
+				enc.end_delimited(header);
+				self.put_members(enc, literals);
+			},
+			TypeObjectKind::Struct(ref base, ref members) => {
+				enc.put_u8(TK_STRUCTURE);
+				enc.put_u16(self.flags);
+				let header = enc.begin_delimited();
+				base.serialize(enc);
+				self.put_type_detail(enc);
+				enc.end_delimited(header);
+				self.put_members(enc, members);
+			},
+			TypeObjectKind::Union(ref discriminator, ref members) => {
+				enc.put_u8(TK_UNION);
+				enc.put_u16(self.flags);
+				let header = enc.begin_delimited();
+				self.put_type_detail(enc);
+				enc.end_delimited(header);
+				let disc = enc.begin_delimited();
+				enc.put_u16(TRY_CONSTRUCT1);
+				discriminator.serialize(enc);
+				if self.is_complete() {
+					enc.put_optional::<u8>(0, &None);
+					enc.put_optional::<u8>(1, &None);
+				}
+				enc.end_delimited(disc);
+				self.put_members(enc, members);
+			},
+		}
+		enc.end_delimited(d);
+	}
+}
+
+impl CdrDeserialize for TypeObject {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = dec.begin_delimited()?;
+		let equivalence_kind = match dec.get_u8()? {
+			EK_MINIMAL => EquivalenceKind::Minimal,
+			EK_COMPLETE => EquivalenceKind::Complete,
+			_ => return Err(CdrError::Invalid("unknown equivalence kind")),
+		};
+		let tk = dec.get_u8()?;
+		let mut object = TypeObject {
+			equivalence_kind,
+			type_name: String::new(),
+			flags: dec.get_u16()?,
+			kind: TypeObjectKind::Enum(Vec::new()),
+		};
+		object.kind = match tk {
+			TK_ALIAS => {
+				let header = dec.begin_delimited()?;
+				object.get_type_detail(dec)?;
+				dec.end_delimited(header)?;
+				let body = dec.begin_delimited()?;
+				dec.get_u16()?;
+				let related = TypeIdentifier::deserialize(dec)?;
+				if object.is_complete() {
+					skip_annotations(dec)?;
+				}
+				dec.end_delimited(body)?;
+				TypeObjectKind::Alias(related)
+			},
+			TK_ENUM => {
+				let header = dec.begin_delimited()?;
+				dec.get_u16()?;
+				object.get_type_detail(dec)?;
+				dec.end_delimited(header)?;
+				TypeObjectKind::Enum(object.get_members(dec, tk)?)
+			},
+			TK_STRUCTURE => {
+				let header = dec.begin_delimited()?;
+				let base = TypeIdentifier::deserialize(dec)?;
+				object.get_type_detail(dec)?;
+				dec.end_delimited(header)?;
+				TypeObjectKind::Struct(base, object.get_members(dec, tk)?)
+			},
+			TK_UNION => {
+				let header = dec.begin_delimited()?;
+				object.get_type_detail(dec)?;
+				dec.end_delimited(header)?;
+				let disc = dec.begin_delimited()?;
+				dec.get_u16()?;
+				let discriminator = TypeIdentifier::deserialize(dec)?;
+				if object.is_complete() {
+					skip_annotations(dec)?;
+				}
+				dec.end_delimited(disc)?;
+				TypeObjectKind::Union(discriminator, object.get_members(dec, tk)?)
+			},
+			_ => return Err(CdrError::Invalid("unsupported kind of type object")),
+		};
+		dec.end_delimited(d)?;
+		Ok(object)
+	}
+}
+
+impl TypeIdentifierWithSize {
+	fn new(ty: &DynamicType, kind: EquivalenceKind) -> TypeIdentifierWithSize {
+		match TypeObject::new(ty, kind) {
+			Some(object) => TypeIdentifierWithSize {
+				type_id: object.type_identifier(),
+				typeobject_serialized_size: object.to_bytes().len() as u32,
+			},
+			None => TypeIdentifierWithSize {type_id: TypeIdentifier::new(ty, kind), typeobject_serialized_size: 0},
+		}
+	}
+}
+
+impl CdrSerialize for TypeIdentifierWithSize {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let d = enc.begin_delimited();
+		self.type_id.serialize(enc);
+		enc.put_u32(self.typeobject_serialized_size);
+		enc.end_delimited(d);
+	}
+}
+
+impl CdrDeserialize for TypeIdentifierWithSize {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = dec.begin_delimited()?;
+		let type_id = TypeIdentifier::deserialize(dec)?;
+		let size = dec.get_u32()?;
+		dec.end_delimited(d)?;
+		Ok(TypeIdentifierWithSize {type_id, typeobject_serialized_size: size})
+	}
+}
+
+impl TypeIdentifierWithDependencies {
+	/// Identifies ty, along with every hashed type it refers to.
+	pub fn new(ty: &DynamicType, kind: EquivalenceKind) -> TypeIdentifierWithDependencies {
+		let mut dependencies: Vec<TypeIdentifierWithSize> = Vec::new();
+		for d in &dependencies_of(ty) {
+			let d = TypeIdentifierWithSize::new(d, kind);
+			if !dependencies.contains(&d) {
+				dependencies.push(d);
+			}
+		}
+		TypeIdentifierWithDependencies {
+			typeid_with_size: TypeIdentifierWithSize::new(ty, kind),
+			dependent_typeid_count: dependencies.len() as i32,
+			dependent_typeids: dependencies,
+		}
+	}
+}
+
+/// Every type ty refers to which is described by a type object, excluding
+/// ty itself.
+fn dependencies_of(ty: &DynamicType) -> Vec<DynamicType> {
+	let mut found = Vec::new();
+	let mut pending: Vec<DynamicType> = referenced(ty);
+	while let Some(t) = pending.pop() {
+		let described = matches!(t.kind(), TypeKind::TK_ALIAS | TypeKind::TK_ENUM |
+										   TypeKind::TK_STRUCTURE | TypeKind::TK_UNION);
+		if described {
+			if found.contains(&t) {
+				continue;
+			}
+			found.push(t.clone());
+		}
+		pending.extend(referenced(&t));
+	}
+	found
+}
+
+/// The types ty refers to directly.
+fn referenced(ty: &DynamicType) -> Vec<DynamicType> {
+	let d = ty.descriptor();
+	d.base_type.iter()
+		.chain(d.discriminator_type.iter())
+		.chain(d.element_type.iter())
+		.cloned()
+		.chain(ty.members().iter().filter(|_| ty.kind() != TypeKind::TK_ENUM).map(|m| m.member_type.clone()))
+		.collect()
+}
+
+impl CdrSerialize for TypeIdentifierWithDependencies {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let d = enc.begin_delimited();
+		self.typeid_with_size.serialize(enc);
+		enc.put_i32(self.dependent_typeid_count);
+		self.dependent_typeids.serialize(enc);
+		enc.end_delimited(d);
+	}
+}
+
+impl CdrDeserialize for TypeIdentifierWithDependencies {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = dec.begin_delimited()?;
+		let typeid_with_size = TypeIdentifierWithSize::deserialize(dec)?;
+		let count = dec.get_i32()?;
+		let dependent_typeids = Vec::deserialize(dec)?;
+		dec.end_delimited(d)?;
+		Ok(TypeIdentifierWithDependencies {
+			typeid_with_size,
+			dependent_typeid_count: count,
+			dependent_typeids,
+		})
+	}
+}
+
+impl TypeInformation {
+	pub fn new(ty: &DynamicType) -> TypeInformation {
+		TypeInformation {
+			minimal: TypeIdentifierWithDependencies::new(ty, EquivalenceKind::Minimal),
+			complete: TypeIdentifierWithDependencies::new(ty, EquivalenceKind::Complete),
+		}
+	}
+}
+
+impl CdrSerialize for TypeInformation {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let members = enc.begin_members();
+		let m = enc.begin_member(PID_TYPE_INFORMATION_MINIMAL, false);
+		self.minimal.serialize(enc);
+		enc.end_member(m);
+		let m = enc.begin_member(PID_TYPE_INFORMATION_COMPLETE, false);
+		self.complete.serialize(enc);
+		enc.end_member(m);
+		enc.end_members(members);
+	}
+}
+
+impl CdrDeserialize for TypeInformation {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let mut minimal = None;
+		let mut complete = None;
+		let members = dec.begin_members()?;
+		while let Some(mut m) = dec.next_member()? {
+			match m.id {
+				PID_TYPE_INFORMATION_MINIMAL => minimal = Some(TypeIdentifierWithDependencies::deserialize(&mut m.value)?),
+				PID_TYPE_INFORMATION_COMPLETE => complete = Some(TypeIdentifierWithDependencies::deserialize(&mut m.value)?),
+				_ if m.must_understand => return Err(CdrError::Invalid("unknown member of TypeInformation")),
+				_ => {},
+			}
+		}
+		dec.end_members(members)?;
+		Ok(TypeInformation {
+			minimal: minimal.ok_or(CdrError::Invalid("minimal is missing"))?,
+			complete: complete.ok_or(CdrError::Invalid("complete is missing"))?,
+		})
+	}
+}
+
+impl Default for TypeLibrary {
+	fn default() -> TypeLibrary {
+		TypeLibrary::new()
+	}
+}
+
+impl TypeLibrary {
+	pub fn new() -> TypeLibrary {
+		TypeLibrary {objects: HashMap::new()}
+	}
+
+	/// Adds an object, returning it's identifier.
+	pub fn insert(&mut self, object: TypeObject) -> TypeIdentifier {
+		let id = object.type_identifier();
+		self.objects.insert(id.clone(), object);
+		id
+	}
+
+	/// Adds the minimal and complete type objects of ty and everything it
+	/// depends on.
+	pub fn insert_type(&mut self, ty: &DynamicType) {
+		let mut types = dependencies_of(ty);
+		types.push(ty.clone());
+		for t in &types {
+			for kind in &[EquivalenceKind::Minimal, EquivalenceKind::Complete] {
+				if let Some(object) = TypeObject::new(t, *kind) {
+					self.insert(object);
+				}
+			}
+		}
+	}
+
+	pub fn get(&self, id: &TypeIdentifier) -> Option<&TypeObject> {
+		self.objects.get(id)
+	}
+
+	/// Rebuilds the type identified by id, which is only possible for
+	/// complete identifiers whose type objects (and those of their
+	/// dependencies) are within the library.
+	pub fn dynamic_type(&self, id: &TypeIdentifier) -> Option<DynamicType> {
+		self.dynamic_type_at(id, 0)
+	}
+
+	fn dynamic_type_at(&self, id: &TypeIdentifier, depth: usize) -> Option<DynamicType> {
+		// Types can't refer to themselves, so this only guards against
+		// objects crafted to do so.
+		if depth > 64 {
+			return None;
+		}
+		let resolve = |id: &TypeIdentifier| self.dynamic_type_at(id, depth + 1);
+		Some(match *id {
+			TypeIdentifier::Primitive(k) => DynamicType::primitive(primitive_kind(k)?),
+			TypeIdentifier::String(bound) => DynamicType::string(if bound == 0 {None} else {Some(bound)}),
+			TypeIdentifier::PlainSequence {bound, ref element, ..} =>
+				DynamicType::sequence(resolve(element)?, if bound == 0 {None} else {Some(bound)}),
+			TypeIdentifier::PlainArray {ref dimensions, ref element, ..} => DynamicType::array(resolve(element)?, dimensions),
+			TypeIdentifier::Minimal(_) => return None,
+			TypeIdentifier::Complete(_) => {
+				let object = self.objects.get(id)?;
+				let extensibility = match object.flags & (IS_APPENDABLE | IS_MUTABLE) {
+					IS_APPENDABLE => Extensibility::Appendable,
+					IS_MUTABLE => Extensibility::Mutable,
+					_ => Extensibility::Final,
+				};
+				let members = |members: &[ObjectMember]| members.iter().map(|m| {
+					let name = match m.name {
+						MemberName::Name(ref n) => n.clone(),
+						MemberName::Hash(_) => return None,
+					};
+					let member_type = match object.kind {
+						TypeObjectKind::Enum(_) => DynamicType::primitive(TypeKind::TK_INT32),
+						_ => resolve(&m.type_id)?,
+					};
+					let mut descriptor = MemberDescriptor::new(&name, m.id, member_type);
+					descriptor.label = m.labels.clone();
+					descriptor.is_key = m.flags & IS_KEY != 0;
+					descriptor.is_optional = m.flags & IS_OPTIONAL != 0;
+					descriptor.is_default_label = m.flags & IS_DEFAULT != 0;
+					Some(descriptor)
+				}).collect::<Option<Vec<MemberDescriptor>>>();
+
+				match object.kind {
+					TypeObjectKind::Alias(ref related) => DynamicType::alias(&object.type_name, resolve(related)?),
+					TypeObjectKind::Enum(ref literals) =>
+						DynamicType::new(TypeDescriptor::new(TypeKind::TK_ENUM, &object.type_name), members(literals)?)?,
+					TypeObjectKind::Struct(ref base, ref m) => {
+						let base = match *base {
+							TypeIdentifier::Primitive(TK_NONE) => None,
+							ref b => Some(resolve(b)?),
+						};
+						DynamicType::structure(&object.type_name, extensibility, base, members(m)?)
+					},
+					TypeObjectKind::Union(ref disc, ref m) =>
+						DynamicType::union(&object.type_name, extensibility, resolve(disc)?, members(m)?),
+				}
+			},
+		})
+	}
+}
+
+/// True if samples written by an endpoint whose type is identified by
+/// writer can be read by an endpoint whose type is identified by reader.
+///
+/// Types with the same minimal identifier are assignable. Otherwise both
+/// types are rebuilt from their complete type objects within library, and
+/// compared by `DynamicType::is_assignable_from`. Types which can't be found
+/// aren't assignable.
+pub fn is_assignable(reader: &TypeInformation, writer: &TypeInformation, library: &TypeLibrary) -> bool {
+	if reader.minimal.typeid_with_size.type_id == writer.minimal.typeid_with_size.type_id {
+		return true;
+	}
+	let r = library.dynamic_type(&reader.complete.typeid_with_size.type_id);
+	let w = library.dynamic_type(&writer.complete.typeid_with_size.type_id);
+	match (r, w) {
+		(Some(r), Some(w)) => r.is_assignable_from(&w),
+		_ => false,
+	}
+}
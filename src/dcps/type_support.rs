@@ -0,0 +1,84 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ Describes the data types which may be published on a topic.
+
+ Topic types are usually structs which derive `DdsType`, which also derives
+ their CDR serialization. Members marked with `#[key]` form the key of the
+ topic, distinguishing one instance of the topic from another:
+
+ ```ignore
+ #[derive(DdsType)]
+ struct Temperature {
+     #[key]
+     sensor_id: u32,
+     celsius: f32,
+ }
+ ```
+ */
+use bytes::Bytes;
+use rtps::*;
+use rtps::messages::submessage::KeyHash_t;
+use rtps::messages::submessage::header::Endianness;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+use cdr::decoder::*;
+use dcps::dynamic_type::DescribeType;
+use dcps::type_object::TypeInformation;
+
+pub use dds_omg_derive::DdsType;
+
+/// Implemented by every type which may be published on a topic.
+pub trait DdsType: CdrSerialize + CdrDeserialize + DescribeType {
+	/// Name of the type, as announced during discovery.
+	fn type_name() -> &'static str;
+
+	/// WITH_KEY if the type has at least one key member.
+	fn topic_kind() -> TopicKind_t;
+
+	/// Writes the key members, in declaration order. Writes nothing for
+	/// NO_KEY topics.
+	fn serialize_key(&self, enc: &mut CdrEncoder);
+
+	/// The largest the key of the type can be when serialized as big endian
+	/// CDR, or None if it is unbounded (e.g. contains a string).
+	fn key_max_size() -> Option<usize>;
+
+	/// How the type may evolve. Derived types are final.
+	fn extensibility() -> Extensibility {
+		Extensibility::Final
+	}
+
+	/// The encapsulation to serialize samples of the type with.
+	fn encapsulation(version: CdrVersion, endianness: Endianness) -> Encapsulation {
+		Encapsulation::new(version, Self::extensibility(), endianness)
+	}
+
+	/// Identifies the type during discovery, so endpoints can check their
+	/// types are compatible.
+	fn type_information() -> TypeInformation {
+		TypeInformation::new(&Self::dynamic_type())
+	}
+
+	/// The key members serialized as big endian CDR, without an
+	/// encapsulation header.
+	fn key(&self) -> Bytes {
+		let mut enc = CdrEncoder::new(Encapsulation::CDR_BE);
+		self.serialize_key(&mut enc);
+		enc.finish().slice_from(ENCAPSULATION_HEADER_SIZE)
+	}
+
+	/// The key hash of the instance self belongs to, as sent in PID_KEY_HASH.
+	fn key_hash(&self) -> KeyHash_t {
+		KeyHash_t::compute(&self.key(), Self::key_max_size())
+	}
+
+	/// Handle of the instance self belongs to, derived from it's key hash.
+	/// Every sample of a NO_KEY topic belongs to the same instance,
+	/// HANDLE_NIL.
+	fn instance_handle(&self) -> InstanceHandle_t {
+		match Self::topic_kind() {
+			TopicKind_t::NO_KEY => HANDLE_NIL,
+			TopicKind_t::WITH_KEY => InstanceHandle_t::from(self.key_hash()),
+		}
+	}
+}
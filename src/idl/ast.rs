@@ -0,0 +1,213 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ The syntax tree of an IDL specification, as produced by the parser.
+
+ Names are kept as written; they are resolved, and constant expressions
+ evaluated, by the code generator. Declarations record the line and column
+ of the name they declare, so errors found by the code generator can point
+ at them.
+ */
+
+/// A possibly qualified name, e.g. `Shape`, `M::Shape` or `::M::Shape`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScopedName {
+	/// True if the name starts with "::", i.e. is relative to the global scope.
+	pub absolute: bool,
+	pub parts: Vec<String>,
+}
+
+/// An annotation, e.g. `@key` or `@topic(platform = "DDS")`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+	pub name: String,
+	/// Parameters, named or positional.
+	pub params: Vec<(Option<String>, ConstExpr)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Definition {
+	Module(Module),
+	Struct(Struct),
+	Union(Union),
+	Enum(Enum),
+	Typedef(Typedef),
+	Const(Const),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Module {
+	pub name: String,
+	pub definitions: Vec<Definition>,
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Struct {
+	pub annotations: Vec<Annotation>,
+	pub name: String,
+	/// The struct this one inherits from, whose members come first.
+	pub base: Option<ScopedName>,
+	pub members: Vec<Member>,
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+	pub annotations: Vec<Annotation>,
+	pub type_spec: TypeSpec,
+	pub declarator: Declarator,
+}
+
+/// A discriminated union, e.g. `union U switch (long) { case 1: long a; };`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Union {
+	pub annotations: Vec<Annotation>,
+	pub name: String,
+	pub discriminator: TypeSpec,
+	pub cases: Vec<Case>,
+	pub line: usize,
+	pub column: usize,
+}
+
+/// A member of a union, and the discriminator values which select it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Case {
+	pub labels: Vec<ConstExpr>,
+	/// True if the member is selected by the `default` label.
+	pub is_default: bool,
+	pub member: Member,
+}
+
+/// A name, followed by the dimensions of an array (if any).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Declarator {
+	pub name: String,
+	pub dimensions: Vec<ConstExpr>,
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enum {
+	pub annotations: Vec<Annotation>,
+	pub name: String,
+	pub enumerators: Vec<Enumerator>,
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enumerator {
+	pub annotations: Vec<Annotation>,
+	pub name: String,
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Typedef {
+	pub annotations: Vec<Annotation>,
+	pub type_spec: TypeSpec,
+	pub declarator: Declarator,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Const {
+	pub type_spec: TypeSpec,
+	pub name: String,
+	pub value: ConstExpr,
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Primitive {
+	Boolean,
+	Octet,
+	Char,
+	Int8,
+	UInt8,
+	Short,
+	UShort,
+	Long,
+	ULong,
+	LongLong,
+	ULongLong,
+	Float,
+	Double,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeSpec {
+	Primitive(Primitive),
+	/// A string, with an optional bound.
+	String(Option<ConstExpr>),
+	/// A sequence, with an optional bound.
+	Sequence(Box<TypeSpec>, Option<ConstExpr>),
+	/// A reference to a struct, union, enum or typedef.
+	Named(ScopedName),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+	Or,
+	Xor,
+	And,
+	Shl,
+	Shr,
+	Add,
+	Sub,
+	Mul,
+	Div,
+	Mod,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UnaryOp {
+	Neg,
+	Not,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstExpr {
+	Integer(u64),
+	Float(f64),
+	Str(String),
+	Char(u8),
+	Bool(bool),
+	/// A constant or an enumerator.
+	Name(ScopedName),
+	Unary(UnaryOp, Box<ConstExpr>),
+	Binary(BinaryOp, Box<ConstExpr>, Box<ConstExpr>),
+}
+
+impl Annotation {
+	/// The value of the parameter named name, or of the first positional
+	/// parameter if name is None.
+	pub fn param(&self, name: Option<&str>) -> Option<&ConstExpr> {
+		self.params.iter()
+			.find(|p| p.0.as_deref() == name)
+			.map(|p| &p.1)
+	}
+}
+
+impl Definition {
+	/// Line and column of the name the definition declares.
+	pub fn position(&self) -> (usize, usize) {
+		match *self {
+			Definition::Module(ref m) => (m.line, m.column),
+			Definition::Struct(ref s) => (s.line, s.column),
+			Definition::Union(ref u) => (u.line, u.column),
+			Definition::Enum(ref e) => (e.line, e.column),
+			Definition::Typedef(ref t) => (t.declarator.line, t.declarator.column),
+			Definition::Const(ref c) => (c.line, c.column),
+		}
+	}
+}
+
+/// Finds the annotation called name.
+pub fn find_annotation<'a>(annotations: &'a [Annotation], name: &str) -> Option<&'a Annotation> {
+	annotations.iter().find(|a| a.name == name)
+}
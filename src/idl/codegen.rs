@@ -0,0 +1,1246 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Generates Rust from a parsed IDL specification.
+
+ IDL modules become Rust modules, structs become structs with public fields,
+ unions become enums with a variant per member, enums become fieldless enums,
+ typedefs become type aliases and constants become constants. Every struct,
+ union and enum implements `CdrSerialize`, `CdrDeserialize` and
+ `DescribeType`, and topic types also implement `DdsType`. Topic types are the structs annotated with
+ `@topic`, or every struct (except those annotated with `@nested`) when no
+ struct is annotated with `@topic`.
+
+ Structs include the members of the struct they inherit from, ahead of their
+ own. Optional members become an `Option`. Appendable types are written
+ between `begin_delimited` and `end_delimited`, and mutable structs as a list
+ of members identified by their member id, which is given by `@id` or
+ follows on from the previous member. Members missing from an appendable or
+ mutable struct take the value given by `@default`. Union members are
+ described with member ids starting at 1, as the discriminator is member 0.
+
+ Names are referred to relative to the module of the generated code, so the
+ output may be placed anywhere within a crate.
+ */
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use cdr::encapsulation::Extensibility;
+use cdr::encoder::{EMHEADER_MEMBER_ID_MASK, PID_SHORT_MAX};
+use idl::IdlError;
+use idl::ast::*;
+
+const SER: &str = "::dds_omg::cdr::encoder::CdrSerialize";
+const DE: &str = "::dds_omg::cdr::decoder::CdrDeserialize";
+const ENCODER: &str = "::dds_omg::cdr::encoder::CdrEncoder";
+const DECODER: &str = "::dds_omg::cdr::decoder::CdrDecoder";
+const ERROR: &str = "::dds_omg::cdr::decoder::CdrError";
+const DESCRIBE: &str = "::dds_omg::dcps::dynamic_type::DescribeType";
+const DYNAMIC: &str = "::dds_omg::dcps::dynamic_type::DynamicType";
+const MEMBER: &str = "::dds_omg::dcps::dynamic_type::MemberDescriptor";
+const KIND: &str = "::dds_omg::dcps::dynamic_type::TypeKind";
+const VALUE: &str = "::dds_omg::dcps::dynamic_data::Value";
+const EXTENSIBILITY: &str = "::dds_omg::cdr::encapsulation::Extensibility";
+
+/// Generates the Rust source for definitions.
+pub fn generate(definitions: &[Definition]) -> Result<String, IdlError> {
+	let definitions = &merge_modules(definitions);
+	let mut symbols = HashMap::new();
+	collect(&mut Vec::new(), definitions, &mut symbols)?;
+	let any_topic = symbols.values().any(|s| match *s {
+		Symbol::Struct(s) => find_annotation(&s.annotations, "topic").is_some(),
+		_ => false,
+	});
+
+	let mut gen = Generator {symbols, any_topic, out: String::new(), indent: 0};
+	gen.definitions(&mut Vec::new(), definitions)?;
+	Ok(gen.out)
+}
+
+/// Anything which may be named.
+#[derive(Copy, Clone)]
+enum Symbol<'a> {
+	Module,
+	Struct(&'a Struct),
+	Union(&'a Union),
+	Enum(&'a Enum),
+	/// An enum, and the index of the enumerator within it.
+	Enumerator(&'a Enum, usize),
+	Typedef(&'a Typedef),
+	Const(&'a Const),
+}
+
+/// A type, with every name resolved and every bound evaluated.
+#[derive(Clone, Debug, PartialEq)]
+enum Ty {
+	Primitive(Primitive),
+	String(Option<u64>),
+	Sequence(Box<Ty>, Option<u64>),
+	Array(Box<Ty>, u64),
+	Struct(Vec<String>),
+	Union(Vec<String>),
+	Enum(Vec<String>),
+	/// A typedef, and the type it stands for.
+	Alias(Vec<String>, Box<Ty>),
+}
+
+/// The value of a constant expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+	Int(i128),
+	Float(f64),
+	Str(String),
+	Char(u8),
+	Bool(bool),
+	/// An enumerator, given by the enum and it's index.
+	Enum(Vec<String>, usize),
+}
+
+fn error(message: String) -> IdlError {
+	IdlError {line: 0, column: 0, message}
+}
+
+/// Gives errors found within a declaration it's position, unless they
+/// already have one.
+fn at(line: usize, column: usize) -> impl Fn(IdlError) -> IdlError {
+	move |e| if e.line == 0 {IdlError {line, column, ..e}} else {e}
+}
+
+fn join(path: &[String]) -> String {
+	path.join("::")
+}
+
+/// IDL modules may be reopened, but Rust modules can't, so the contents of
+/// modules with the same name are merged into the first.
+fn merge_modules(definitions: &[Definition]) -> Vec<Definition> {
+	let mut merged: Vec<Definition> = Vec::new();
+	for d in definitions {
+		if let Definition::Module(ref m) = *d {
+			let first = merged.iter_mut().find_map(|d| match *d {
+				Definition::Module(ref mut first) if first.name == m.name => Some(first),
+				_ => None,
+			});
+			if let Some(first) = first {
+				first.definitions.extend(m.definitions.iter().cloned());
+				first.definitions = merge_modules(&first.definitions);
+				continue;
+			}
+			merged.push(Definition::Module(Module {
+				name: m.name.clone(),
+				definitions: merge_modules(&m.definitions),
+				line: m.line,
+				column: m.column
+			}));
+		} else {
+			merged.push(d.clone());
+		}
+	}
+	merged
+}
+
+/// Records the fully qualified name of every definition.
+fn collect<'a>(scope: &mut Vec<String>,
+			   definitions: &'a [Definition],
+			   symbols: &mut HashMap<Vec<String>, Symbol<'a>>) -> Result<(), IdlError> {
+	for d in definitions {
+		let (line, column) = d.position();
+		let (name, symbol) = match *d {
+			Definition::Module(ref m) => {
+				scope.push(m.name.clone());
+				collect(scope, &m.definitions, symbols)?;
+				scope.pop();
+				(&m.name, Symbol::Module)
+			},
+			Definition::Struct(ref s) => (&s.name, Symbol::Struct(s)),
+			Definition::Union(ref u) => (&u.name, Symbol::Union(u)),
+			Definition::Enum(ref e) => {
+				// Enumerators belong to the scope enclosing the enum.
+				for (i, en) in e.enumerators.iter().enumerate() {
+					let mut path = scope.clone();
+					path.push(en.name.clone());
+					if symbols.insert(path.clone(), Symbol::Enumerator(e, i)).is_some() {
+						return Err(at(en.line, en.column)(error(format!("{} is defined more than once", join(&path)))));
+					}
+				}
+				(&e.name, Symbol::Enum(e))
+			},
+			Definition::Typedef(ref t) => (&t.declarator.name, Symbol::Typedef(t)),
+			Definition::Const(ref c) => (&c.name, Symbol::Const(c)),
+		};
+
+		let mut path = scope.clone();
+		path.push(name.clone());
+		if symbols.insert(path.clone(), symbol).is_some() {
+			return Err(at(line, column)(error(format!("{} is defined more than once", join(&path)))));
+		}
+	}
+	Ok(())
+}
+
+/// Escapes IDL identifiers which are Rust keywords.
+pub fn rust_ident(name: &str) -> String {
+	const KEYWORDS: &[&str] = &[
+		"abstract", "as", "async", "await", "become", "box", "break", "const",
+		"continue", "do", "dyn", "else", "enum", "extern", "false", "final",
+		"fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match",
+		"mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+		"static", "struct", "trait", "true", "try", "type", "typeof", "unsafe",
+		"unsized", "use", "virtual", "where", "while", "yield",
+	];
+	match name {
+		"self" | "Self" | "super" | "crate" => format!("{}_", name),
+		n if KEYWORDS.contains(&n) => format!("r#{}", n),
+		n => String::from(n),
+	}
+}
+
+fn primitive_type(p: Primitive) -> &'static str {
+	match p {
+		Primitive::Boolean => "bool",
+		Primitive::Octet | Primitive::Char | Primitive::UInt8 => "u8",
+		Primitive::Int8 => "i8",
+		Primitive::Short => "i16",
+		Primitive::UShort => "u16",
+		Primitive::Long => "i32",
+		Primitive::ULong => "u32",
+		Primitive::LongLong => "i64",
+		Primitive::ULongLong => "u64",
+		Primitive::Float => "f32",
+		Primitive::Double => "f64",
+	}
+}
+
+/// The TypeKind, and the variant of `dynamic_data::Value`, of a primitive.
+fn dynamic_kind(p: Primitive) -> (&'static str, &'static str) {
+	match p {
+		Primitive::Boolean => ("TK_BOOLEAN", "Boolean"),
+		Primitive::Octet => ("TK_BYTE", "Byte"),
+		Primitive::Char => ("TK_CHAR8", "Char8"),
+		Primitive::UInt8 => ("TK_UINT8", "UInt8"),
+		Primitive::Int8 => ("TK_INT8", "Int8"),
+		Primitive::Short => ("TK_INT16", "Int16"),
+		Primitive::UShort => ("TK_UINT16", "UInt16"),
+		Primitive::Long => ("TK_INT32", "Int32"),
+		Primitive::ULong => ("TK_UINT32", "UInt32"),
+		Primitive::LongLong => ("TK_INT64", "Int64"),
+		Primitive::ULongLong => ("TK_UINT64", "UInt64"),
+		Primitive::Float => ("TK_FLOAT32", "Float32"),
+		Primitive::Double => ("TK_FLOAT64", "Float64"),
+	}
+}
+
+/// Range of values an integer primitive can hold.
+fn integer_range(p: Primitive) -> Option<(i128, i128)> {
+	match p {
+		Primitive::Octet | Primitive::UInt8 => Some((0, u8::MAX as i128)),
+		Primitive::Int8 => Some((i8::MIN as i128, i8::MAX as i128)),
+		Primitive::Short => Some((i16::MIN as i128, i16::MAX as i128)),
+		Primitive::UShort => Some((0, u16::MAX as i128)),
+		Primitive::Long => Some((i32::MIN as i128, i32::MAX as i128)),
+		Primitive::ULong => Some((0, u32::MAX as i128)),
+		Primitive::LongLong => Some((i64::MIN as i128, i64::MAX as i128)),
+		Primitive::ULongLong => Some((0, u64::MAX as i128)),
+		_ => None,
+	}
+}
+
+impl Ty {
+	/// The type, looking through typedefs.
+	fn resolved(&self) -> &Ty {
+		match *self {
+			Ty::Alias(_, ref t) => t.resolved(),
+			ref t => t,
+		}
+	}
+}
+
+struct Generator<'a> {
+	symbols: HashMap<Vec<String>, Symbol<'a>>,
+	any_topic: bool,
+	out: String,
+	indent: usize,
+}
+
+impl<'a> Generator<'a> {
+	fn line(&mut self, s: &str) {
+		if !s.is_empty() {
+			for _ in 0..self.indent {
+				self.out.push('\t');
+			}
+			self.out.push_str(s);
+		}
+		self.out.push('\n');
+	}
+
+	fn open(&mut self, s: &str) {
+		self.line(s);
+		self.indent += 1;
+	}
+
+	fn close(&mut self, s: &str) {
+		self.indent -= 1;
+		self.line(s);
+	}
+
+	/// Finds name, searching from scope outwards.
+	fn lookup(&self, scope: &[String], name: &ScopedName) -> Result<(Vec<String>, Symbol<'a>), IdlError> {
+		let outermost = if name.absolute {0} else {scope.len()};
+		for i in (0..=outermost).rev() {
+			let mut path = scope[..i].to_vec();
+			path.extend(name.parts.iter().cloned());
+			if let Some(s) = self.symbols.get(&path) {
+				return Ok((path, *s));
+			}
+		}
+		Err(error(format!("unknown name {}", join(&name.parts))))
+	}
+
+	/// A path to the Rust item for path, relative to the module for scope.
+	fn rust_path(&self, scope: &[String], path: &[String]) -> String {
+		let common = scope.iter().zip(path.iter()).take_while(|&(a, b)| a == b).count();
+		let common = common.min(path.len() - 1);
+		let mut parts: Vec<String> = (common..scope.len()).map(|_| String::from("super")).collect();
+		parts.extend(path[common..].iter().map(|p| rust_ident(p)));
+		parts.join("::")
+	}
+
+	fn resolve(&self, scope: &[String], spec: &TypeSpec) -> Result<Ty, IdlError> {
+		Ok(match *spec {
+			TypeSpec::Primitive(p) => Ty::Primitive(p),
+			TypeSpec::String(ref bound) => Ty::String(self.bound(scope, bound)?),
+			TypeSpec::Sequence(ref element, ref bound) =>
+				Ty::Sequence(Box::new(self.resolve(scope, element)?), self.bound(scope, bound)?),
+			TypeSpec::Named(ref name) => {
+				let (path, symbol) = self.lookup(scope, name)?;
+				match symbol {
+					Symbol::Struct(_) => Ty::Struct(path),
+					Symbol::Union(_) => Ty::Union(path),
+					Symbol::Enum(_) => Ty::Enum(path),
+					Symbol::Typedef(t) => {
+						let inner = self.declared(&path[..path.len() - 1], &t.type_spec, &t.declarator)?;
+						Ty::Alias(path, Box::new(inner))
+					},
+					_ => return Err(error(format!("{} is not a type", join(&path)))),
+				}
+			},
+		})
+	}
+
+	/// The type of a declarator, which is an array if it has dimensions.
+	fn declared(&self, scope: &[String], spec: &TypeSpec, declarator: &Declarator) -> Result<Ty, IdlError> {
+		let mut ty = self.resolve(scope, spec)?;
+		for d in declarator.dimensions.iter().rev() {
+			let n = self.positive(scope, d)?;
+			ty = Ty::Array(Box::new(ty), n);
+		}
+		Ok(ty)
+	}
+
+	fn bound(&self, scope: &[String], bound: &Option<ConstExpr>) -> Result<Option<u64>, IdlError> {
+		match *bound {
+			Some(ref b) => self.positive(scope, b).map(Some),
+			None => Ok(None),
+		}
+	}
+
+	fn positive(&self, scope: &[String], expr: &ConstExpr) -> Result<u64, IdlError> {
+		match self.eval(scope, expr, 0)? {
+			Value::Int(v) if v > 0 && v <= u32::MAX as i128 => Ok(v as u64),
+			v => Err(error(format!("expected a positive integer, found {:?}", v))),
+		}
+	}
+
+	fn eval(&self, scope: &[String], expr: &ConstExpr, depth: usize) -> Result<Value, IdlError> {
+		if depth > 64 {
+			return Err(error(String::from("constant expression refers to itself")));
+		}
+		Ok(match *expr {
+			ConstExpr::Integer(v) => Value::Int(v as i128),
+			ConstExpr::Float(v) => Value::Float(v),
+			ConstExpr::Str(ref s) => Value::Str(s.clone()),
+			ConstExpr::Char(c) => Value::Char(c),
+			ConstExpr::Bool(b) => Value::Bool(b),
+			ConstExpr::Name(ref name) => {
+				let (path, symbol) = self.lookup(scope, name)?;
+				match symbol {
+					Symbol::Const(c) => self.eval(&path[..path.len() - 1], &c.value, depth + 1)?,
+					Symbol::Enumerator(e, i) => {
+						let mut enum_path = path[..path.len() - 1].to_vec();
+						enum_path.push(e.name.clone());
+						Value::Enum(enum_path, i)
+					},
+					_ => return Err(error(format!("{} is not a constant", join(&path)))),
+				}
+			},
+			ConstExpr::Unary(op, ref e) => match (op, self.eval(scope, e, depth + 1)?) {
+				(UnaryOp::Neg, Value::Int(v)) => Value::Int(-v),
+				(UnaryOp::Neg, Value::Float(v)) => Value::Float(-v),
+				(UnaryOp::Not, Value::Int(v)) => Value::Int(!v),
+				(_, v) => return Err(error(format!("invalid operand {:?}", v))),
+			},
+			ConstExpr::Binary(op, ref l, ref r) => {
+				let l = self.eval(scope, l, depth + 1)?;
+				let r = self.eval(scope, r, depth + 1)?;
+				match (l, r) {
+					(Value::Int(l), Value::Int(r)) => {
+						let v = match op {
+							BinaryOp::Or => Some(l | r),
+							BinaryOp::Xor => Some(l ^ r),
+							BinaryOp::And => Some(l & r),
+							BinaryOp::Shl => u32::try_from(r).ok().and_then(|r| l.checked_shl(r)),
+							BinaryOp::Shr => u32::try_from(r).ok().and_then(|r| l.checked_shr(r)),
+							BinaryOp::Add => l.checked_add(r),
+							BinaryOp::Sub => l.checked_sub(r),
+							BinaryOp::Mul => l.checked_mul(r),
+							BinaryOp::Div => l.checked_div(r),
+							BinaryOp::Mod => l.checked_rem(r),
+						};
+						Value::Int(v.ok_or_else(|| error(String::from("constant expression overflows")))?)
+					},
+					(Value::Float(l), Value::Float(r)) => Value::Float(match op {
+						BinaryOp::Add => l + r,
+						BinaryOp::Sub => l - r,
+						BinaryOp::Mul => l * r,
+						BinaryOp::Div => l / r,
+						_ => return Err(error(String::from("invalid floating point operator"))),
+					}),
+					(l, r) => return Err(error(format!("invalid operands {:?} and {:?}", l, r))),
+				}
+			},
+		})
+	}
+
+	fn rust_type(&self, scope: &[String], ty: &Ty) -> String {
+		match *ty {
+			Ty::Primitive(p) => String::from(primitive_type(p)),
+			Ty::String(_) => String::from("String"),
+			Ty::Sequence(ref t, _) => format!("Vec<{}>", self.rust_type(scope, t)),
+			Ty::Array(ref t, n) => format!("[{}; {}]", self.rust_type(scope, t), n),
+			Ty::Struct(ref path) | Ty::Union(ref path) | Ty::Enum(ref path) | Ty::Alias(ref path, _) =>
+				self.rust_path(scope, path),
+		}
+	}
+
+	/// An expression building the `DynamicType` of ty. Nested arrays become
+	/// a single multi-dimensional array.
+	fn dynamic_type(&self, scope: &[String], ty: &Ty) -> String {
+		let bound = |b: Option<u64>| b.map_or(String::from("None"), |b| format!("Some({})", b));
+		match *ty {
+			Ty::Primitive(p) => format!("{}::primitive({}::{})", DYNAMIC, KIND, dynamic_kind(p).0),
+			Ty::String(b) => format!("{}::string({})", DYNAMIC, bound(b)),
+			Ty::Sequence(ref t, b) => format!("{}::sequence({}, {})", DYNAMIC, self.dynamic_type(scope, t), bound(b)),
+			Ty::Array(..) => {
+				let mut dimensions = Vec::new();
+				let mut element = ty;
+				while let Ty::Array(ref t, n) = *element {
+					dimensions.push(n.to_string());
+					element = t;
+				}
+				format!("{}::array({}, &[{}])", DYNAMIC, self.dynamic_type(scope, element), dimensions.join(", "))
+			},
+			Ty::Struct(ref path) | Ty::Union(ref path) | Ty::Enum(ref path) =>
+				format!("<{} as {}>::dynamic_type()", self.rust_path(scope, path), DESCRIBE),
+			Ty::Alias(ref path, ref t) => format!("{}::alias({:?}, {})", DYNAMIC, join(path), self.dynamic_type(scope, t)),
+		}
+	}
+
+	/// Writes the `DescribeType` impl for name, calling constructor (a
+	/// function of `DynamicType`) with args, followed by members.
+	fn describe_impl(&mut self, name: &str, constructor: &str, args: &str, members: Option<&[DescribedMember]>) {
+		self.line("");
+		self.open(&format!("impl {} for {} {{", DESCRIBE, name));
+		self.open(&format!("fn dynamic_type() -> {} {{", DYNAMIC));
+		match members {
+			None => self.line(&format!("{}::{}({})", DYNAMIC, constructor, args)),
+			Some(members) => {
+				self.open(&format!("{}::{}({},", DYNAMIC, constructor, args));
+				if members.is_empty() {
+					self.line("Vec::new(),");
+				} else {
+					self.open("vec![");
+					for m in members {
+						let new = format!("{}::new({:?}, {}, {})", MEMBER, m.name, m.id, m.ty);
+						if m.set.is_empty() {
+							self.line(&format!("{},", new));
+						} else {
+							let set: Vec<String> = m.set.iter().map(|&(f, ref v)| format!("{}: {}", f, v)).collect();
+							self.line(&format!("{} {{{}, ..{}}},", MEMBER, set.join(", "), new));
+						}
+					}
+					self.close("],");
+				}
+				self.close(")");
+			},
+		}
+		self.close("}");
+		self.close("}");
+	}
+
+	/// Statements advancing `position` past the largest possible encoding of
+	/// ty, or None if ty is unbounded.
+	fn max_end(&self, scope: &[String], ty: &Ty) -> Option<Vec<String>> {
+		let step = |t: &str| format!("let position = <{} as {}>::max_end_position(position)?;", t, SER);
+		Some(match *ty.resolved() {
+			Ty::Primitive(p) => vec![step(primitive_type(p))],
+			Ty::String(Some(n)) => vec![
+				step("u32"),
+				format!("let position = position + {};", n + 1),
+			],
+			Ty::Sequence(ref t, Some(n)) => {
+				let mut v = vec![step("u32")];
+				v.extend(self.max_end_fold(scope, t, n)?);
+				v
+			},
+			Ty::Array(ref t, n) => self.max_end_fold(scope, t, n)?,
+			Ty::Struct(ref path) | Ty::Union(ref path) | Ty::Enum(ref path) =>
+				vec![step(&self.rust_path(scope, path))],
+			_ => return None,
+		})
+	}
+
+	fn max_end_fold(&self, scope: &[String], element: &Ty, n: u64) -> Option<Vec<String>> {
+		let inner = self.max_end(scope, element)?;
+		Some(vec![format!("let position = (0..{}).try_fold(position, |position, _| {{ {} Some(position) }})?;",
+						  n, inner.join(" "))])
+	}
+
+	fn definitions(&mut self, scope: &mut Vec<String>, definitions: &[Definition]) -> Result<(), IdlError> {
+		for d in definitions {
+			if !self.out.is_empty() && !self.out.ends_with("{\n") {
+				self.line("");
+			}
+			let (line, column) = d.position();
+			match *d {
+				Definition::Module(ref m) => {
+					self.line("#[allow(non_snake_case)]");
+					self.open(&format!("pub mod {} {{", rust_ident(&m.name)));
+					scope.push(m.name.clone());
+					self.definitions(scope, &m.definitions)?;
+					scope.pop();
+					self.close("}");
+				},
+				Definition::Struct(ref s) => self.struct_def(scope, s).map_err(at(line, column))?,
+				Definition::Union(ref u) => self.union_def(scope, u).map_err(at(line, column))?,
+				Definition::Enum(ref e) => self.enum_def(scope, e),
+				Definition::Typedef(ref t) => {
+					let ty = self.declared(scope, &t.type_spec, &t.declarator).map_err(at(line, column))?;
+					let ty = self.rust_type(scope, &ty);
+					self.line("#[allow(non_camel_case_types)]");
+					self.line(&format!("pub type {} = {};", rust_ident(&t.declarator.name), ty));
+				},
+				Definition::Const(ref c) => self.const_def(scope, c).map_err(at(line, column))?,
+			}
+		}
+		Ok(())
+	}
+
+	fn const_def(&mut self, scope: &[String], c: &Const) -> Result<(), IdlError> {
+		let ty = self.resolve(scope, &c.type_spec)?;
+		let value = self.eval(scope, &c.value, 0)?;
+		let literal = self.literal(scope, &ty, value, &c.name)?;
+		let rust_type = match *ty.resolved() {
+			Ty::String(_) => String::from("&str"),
+			_ => self.rust_type(scope, &ty),
+		};
+		self.line(&format!("pub const {}: {} = {};", rust_ident(&c.name), rust_type, literal));
+		Ok(())
+	}
+
+	/// The Rust literal for value, which is given as a ty for what (the name
+	/// of a constant, member or union label).
+	fn literal(&self, scope: &[String], ty: &Ty, value: Value, what: &str) -> Result<String, IdlError> {
+		Ok(match (ty.resolved(), value) {
+			(&Ty::Primitive(Primitive::Boolean), Value::Bool(b)) => b.to_string(),
+			(&Ty::Primitive(Primitive::Char), Value::Char(c)) => match c {
+				b' '..=b'~' if c != b'\'' && c != b'\\' => format!("b'{}'", c as char),
+				c => format!("b'\\x{:02x}'", c),
+			},
+			(&Ty::Primitive(p @ Primitive::Float), Value::Float(v)) |
+			(&Ty::Primitive(p @ Primitive::Double), Value::Float(v)) => format!("{:?}{}", v, primitive_type(p)),
+			(&Ty::Primitive(p), Value::Int(v)) => {
+				match integer_range(p) {
+					Some((min, max)) if v >= min && v <= max => v.to_string(),
+					Some(_) => return Err(error(format!("{} is out of range for {}", v, what))),
+					None => return Err(error(format!("{} can't hold an integer", what))),
+				}
+			},
+			(&Ty::String(_), Value::Str(s)) => format!("{:?}", s),
+			(Ty::Enum(p), Value::Enum(ref e, i)) if p == e => {
+				let en = match self.symbols.get(e) {
+					Some(Symbol::Enum(en)) => en.enumerators[i].name.clone(),
+					_ => unreachable!(),
+				};
+				format!("{}::{}", self.rust_path(scope, e), rust_ident(&en))
+			},
+			(_, v) => return Err(error(format!("{:?} doesn't match the type of {}", v, what))),
+		})
+	}
+
+	fn enum_def(&mut self, scope: &[String], e: &Enum) {
+		let name = rust_ident(&e.name);
+		self.line("#[allow(non_camel_case_types, clippy::upper_case_acronyms)]");
+		self.line("#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]");
+		self.open(&format!("pub enum {} {{", name));
+		for en in &e.enumerators {
+			self.line(&format!("{},", rust_ident(&en.name)));
+		}
+		self.close("}");
+		self.line("");
+
+		self.open(&format!("impl {} for {} {{", SER, name));
+		self.open(&format!("fn serialize(&self, enc: &mut {}) {{", ENCODER));
+		self.line("enc.put_u32(*self as u32)");
+		self.close("}");
+		self.line("");
+		self.open("fn max_end_position(position: usize) -> Option<usize> {");
+		self.line(&format!("<u32 as {}>::max_end_position(position)", SER));
+		self.close("}");
+		self.close("}");
+		self.line("");
+
+		self.open(&format!("impl {} for {} {{", DE, name));
+		self.open(&format!("fn deserialize(dec: &mut {}) -> Result<Self, {}> {{", DECODER, ERROR));
+		self.open("match dec.get_u32()? {");
+		for (i, en) in e.enumerators.iter().enumerate() {
+			self.line(&format!("{} => Ok({}::{}),", i, name, rust_ident(&en.name)));
+		}
+		self.line(&format!("_ => Err({}::Invalid(\"unknown {} enumerator\")),", ERROR, join(&self.qualified(scope, &e.name))));
+		self.close("}");
+		self.close("}");
+		self.close("}");
+
+		let literals: Vec<String> = e.enumerators.iter().map(|en| format!("{:?}", en.name)).collect();
+		let args = format!("{:?}, &[{}]", join(&self.qualified(scope, &e.name)), literals.join(", "));
+		self.describe_impl(&name, "enumeration", &args, None);
+	}
+
+	fn qualified(&self, scope: &[String], name: &str) -> Vec<String> {
+		let mut path = scope.to_vec();
+		path.push(String::from(name));
+		path
+	}
+
+	/// The members of s, preceeded by those inherited from it's base. Default
+	/// values are written as Rust relative to render, the scope of the
+	/// generated struct.
+	fn fields(&self, scope: &[String], s: &Struct, render: &[String], depth: usize) -> Result<Vec<Field>, IdlError> {
+		if depth > 64 {
+			return Err(error(format!("{} inherits from itself", s.name)));
+		}
+		let mut fields = match s.base {
+			Some(ref base) => {
+				let (path, symbol) = self.lookup(scope, base)?;
+				match symbol {
+					Symbol::Struct(b) => {
+						if extensibility(&b.annotations)? != extensibility(&s.annotations)? {
+							return Err(error(format!("{} must have the same extensibility as {}", s.name, join(&path))));
+						}
+						self.fields(&path[..path.len() - 1], b, render, depth + 1)?
+					},
+					_ => return Err(error(format!("{} is not a struct", join(&path)))),
+				}
+			},
+			None => Vec::new(),
+		};
+
+		// Member ids follow on from the previous member, unless given by @id.
+		let mut next_id = fields.last().map_or(0, |f| f.id + 1);
+		for m in &s.members {
+			let field = self.field(scope, s, m, render, &fields, next_id)
+				.map_err(at(m.declarator.line, m.declarator.column))?;
+			next_id = field.id + 1;
+			fields.push(field);
+		}
+		Ok(fields)
+	}
+
+	/// The member m of s, which follows fields.
+	fn field(&self, scope: &[String], s: &Struct, m: &Member, render: &[String], fields: &[Field], next_id: u32) -> Result<Field, IdlError> {
+		let name = &m.declarator.name;
+		let ty = self.declared(scope, &m.type_spec, &m.declarator)?;
+		let key = self.flag(scope, &m.annotations, "key")?;
+		let optional = self.flag(scope, &m.annotations, "optional")?;
+		if key && optional {
+			return Err(error(format!("key member {} can't be optional", name)));
+		}
+
+		let id = match find_annotation(&m.annotations, "id").map(|a| a.param(None)) {
+			Some(Some(e)) => match self.eval(scope, e, 0)? {
+				Value::Int(v) if v >= 0 && v <= EMHEADER_MEMBER_ID_MASK as i128 => v as u32,
+				v => return Err(error(format!("{:?} is not a valid member id for {}", v, name))),
+			},
+			Some(None) => return Err(error(format!("@id of {} needs a value", name))),
+			None => next_id,
+		};
+		if fields.iter().any(|f| f.name == *name) {
+			return Err(error(format!("{} is declared more than once within {}", name, s.name)));
+		}
+		if fields.iter().any(|f| f.id == id) {
+			return Err(error(format!("member id {} is used more than once within {}", id, s.name)));
+		}
+
+		let default = match find_annotation(&m.annotations, "default").map(|a| a.param(None)) {
+			Some(Some(e)) => {
+				let literal = self.literal(render, &ty, self.eval(scope, e, 0)?, name)?;
+				Some(match *ty.resolved() {
+					Ty::String(_) => (format!("String::from({})", literal), format!("{}::String(String::from({}))", VALUE, literal)),
+					Ty::Enum(_) => (literal.clone(), format!("{}::Enum({} as u32)", VALUE, literal)),
+					Ty::Primitive(p) => (literal.clone(), format!("{}::{}({})", VALUE, dynamic_kind(p).1, literal)),
+					_ => unreachable!(),
+				})
+			},
+			Some(None) => return Err(error(format!("@default of {} needs a value", name))),
+			None => None,
+		};
+		let (default, dynamic_default) = default.unzip();
+
+		Ok(Field {
+			name: name.clone(),
+			ty,
+			id,
+			key,
+			optional,
+			default,
+			dynamic_default
+		})
+	}
+
+	/// True if annotations include `@name` or `@name(TRUE)`.
+	fn flag(&self, scope: &[String], annotations: &[Annotation], name: &str) -> Result<bool, IdlError> {
+		Ok(match find_annotation(annotations, name) {
+			Some(a) => match a.param(None) {
+				Some(e) => self.eval(scope, e, 0)? == Value::Bool(true),
+				None => true,
+			},
+			None => false,
+		})
+	}
+
+	fn field_type(&self, scope: &[String], f: &Field) -> String {
+		let t = self.rust_type(scope, &f.ty);
+		if f.optional {format!("Option<{}>", t)} else {t}
+	}
+
+	/// Returns an error from deserialize if the local v_name, of type ty,
+	/// exceeds it's bound.
+	fn bound_check(&mut self, name: &str, ty: &Ty, optional: bool) {
+		let bound = match *ty.resolved() {
+			Ty::String(Some(b)) | Ty::Sequence(_, Some(b)) => b,
+			_ => return,
+		};
+		if optional {
+			self.open(&format!("if v_{}.as_ref().is_some_and(|v| v.len() > {}) {{", name, bound));
+		} else {
+			self.open(&format!("if v_{}.len() > {} {{", name, bound));
+		}
+		self.line(&format!("return Err({}::Invalid(\"{} exceeds it's bound\"));", ERROR, name));
+		self.close("}");
+	}
+
+	fn struct_def(&mut self, scope: &[String], s: &Struct) -> Result<(), IdlError> {
+		let name = rust_ident(&s.name);
+		let ext = extensibility(&s.annotations)?;
+		let fields = self.fields(scope, s, scope, 0)?;
+
+		self.line("#[allow(non_camel_case_types, non_snake_case)]");
+		self.line("#[derive(Clone, Debug, PartialEq)]");
+		self.open(&format!("pub struct {} {{", name));
+		for f in &fields {
+			let t = self.field_type(scope, f);
+			self.line(&format!("pub {}: {},", rust_ident(&f.name), t));
+		}
+		self.close("}");
+		self.line("");
+
+		let enc = if fields.is_empty() && ext == Extensibility::Final {"_enc"} else {"enc"};
+		self.open(&format!("impl {} for {} {{", SER, name));
+		self.open(&format!("fn serialize(&self, {}: &mut {}) {{", enc, ENCODER));
+		match ext {
+			Extensibility::Final => {},
+			Extensibility::Appendable => self.line("let d = enc.begin_delimited();"),
+			Extensibility::Mutable => self.line("let members = enc.begin_members();"),
+		}
+		for f in &fields {
+			let n = rust_ident(&f.name);
+			match (ext, f.optional) {
+				(Extensibility::Mutable, false) => {
+					self.line(&format!("let m = enc.begin_member({}, {});", f.id, f.key));
+					self.line(&format!("{}::serialize(&self.{}, enc);", SER, n));
+					self.line("enc.end_member(m);");
+				},
+				(Extensibility::Mutable, true) => {
+					self.open(&format!("if let Some(ref v) = self.{} {{", n));
+					self.line(&format!("let m = enc.begin_member({}, false);", f.id));
+					self.line(&format!("{}::serialize(v, enc);", SER));
+					self.line("enc.end_member(m);");
+					self.close("}");
+				},
+				(_, true) => self.line(&format!("enc.put_optional({}, &self.{});", f.id, n)),
+				(_, false) => self.line(&format!("{}::serialize(&self.{}, enc);", SER, n)),
+			}
+		}
+		match ext {
+			Extensibility::Final => {},
+			Extensibility::Appendable => self.line("enc.end_delimited(d);"),
+			Extensibility::Mutable => self.line("enc.end_members(members);"),
+		}
+		self.close("}");
+		self.line("");
+		let body = self.fields_max_end(scope, &fields, ext);
+		self.max_end_fn("fn max_end_position(position: usize) -> Option<usize> {", body);
+		self.close("}");
+		self.line("");
+
+		let dec = if fields.is_empty() && ext == Extensibility::Final {"_dec"} else {"dec"};
+		self.open(&format!("impl {} for {} {{", DE, name));
+		self.open(&format!("fn deserialize({}: &mut {}) -> Result<Self, {}> {{", dec, DECODER, ERROR));
+		if ext == Extensibility::Mutable {
+			self.mutable_de(scope, s, &fields);
+		} else {
+			let appendable = ext == Extensibility::Appendable;
+			if appendable {
+				self.line("let d = dec.begin_delimited()?;");
+			}
+			for f in &fields {
+				let t = self.field_type(scope, f);
+				let value = if f.optional {
+					format!("dec.get_optional({})?", f.id)
+				} else {
+					format!("{}::deserialize(dec)?", DE)
+				};
+				// Members appended by a newer version of the type may be
+				// missing, if the reader knows what they should be.
+				let fallback = if f.optional {Some("None")} else {f.default.as_deref()};
+				let value = match fallback {
+					Some(fallback) if appendable => format!("if dec.remaining() == 0 {{{}}} else {{{}}}", fallback, value),
+					_ => value,
+				};
+				self.line(&format!("let v_{}: {} = {};", f.name, t, value));
+				self.bound_check(&f.name, &f.ty, f.optional);
+			}
+			if appendable {
+				self.line("dec.end_delimited(d)?;");
+			}
+		}
+		let init: Vec<String> = fields.iter()
+			.map(|f| format!("{}: v_{}", rust_ident(&f.name), f.name))
+			.collect();
+		self.line(&format!("Ok({} {{{}}})", name, init.join(", ")));
+		self.close("}");
+		self.close("}");
+
+		// Inherited members are described by the base.
+		let base = match s.base {
+			Some(ref b) => format!("Some(<{} as {}>::dynamic_type())", self.rust_path(scope, &self.lookup(scope, b)?.0), DESCRIBE),
+			None => String::from("None"),
+		};
+		let own: Vec<DescribedMember> = fields[fields.len() - s.members.len()..].iter().map(|f| {
+			let mut set = Vec::new();
+			if f.key {
+				set.push(("is_key", String::from("true")));
+			}
+			if f.optional {
+				set.push(("is_optional", String::from("true")));
+			}
+			if let Some(ref d) = f.dynamic_default {
+				set.push(("default_value", format!("Some({})", d)));
+			}
+			DescribedMember {name: f.name.clone(), id: f.id, ty: self.dynamic_type(scope, &f.ty), set}
+		}).collect();
+		let args = format!("{:?}, {}::{:?}, {}", join(&self.qualified(scope, &s.name)), EXTENSIBILITY, ext, base);
+		self.describe_impl(&name, "structure", &args, Some(&own));
+
+		let is_topic = if self.any_topic {
+			find_annotation(&s.annotations, "topic").is_some()
+		} else {
+			find_annotation(&s.annotations, "nested").is_none()
+		};
+		if !is_topic {
+			return Ok(());
+		}
+
+		let keys: Vec<&Field> = fields.iter().filter(|f| f.key).collect();
+		self.line("");
+		self.open(&format!("impl ::dds_omg::dcps::type_support::DdsType for {} {{", name));
+		self.open("fn type_name() -> &'static str {");
+		self.line(&format!("{:?}", join(&self.qualified(scope, &s.name))));
+		self.close("}");
+		self.line("");
+		self.open("fn topic_kind() -> ::dds_omg::rtps::TopicKind_t {");
+		self.line(if keys.is_empty() {
+			"::dds_omg::rtps::TopicKind_t::NO_KEY"
+		} else {
+			"::dds_omg::rtps::TopicKind_t::WITH_KEY"
+		});
+		self.close("}");
+		self.line("");
+		let enc = if keys.is_empty() {"_enc"} else {"enc"};
+		self.open(&format!("fn serialize_key(&self, {}: &mut {}) {{", enc, ENCODER));
+		for k in &keys {
+			self.line(&format!("{}::serialize(&self.{}, enc);", SER, rust_ident(&k.name)));
+		}
+		self.close("}");
+		self.line("");
+		let body = keys.iter()
+			.map(|k| self.max_end(scope, &k.ty))
+			.collect::<Option<Vec<Vec<String>>>>()
+			.map(|stmts| stmts.concat());
+		self.max_end_fn("fn key_max_size() -> Option<usize> {", body);
+		if ext != Extensibility::Final {
+			self.line("");
+			self.open("fn extensibility() -> ::dds_omg::cdr::encapsulation::Extensibility {");
+			self.line(&format!("::dds_omg::cdr::encapsulation::Extensibility::{:?}", ext));
+			self.close("}");
+		}
+		self.close("}");
+		Ok(())
+	}
+
+	/// Reads the members of a mutable struct, in whatever order they were
+	/// written. Missing members take their default value, if they have one.
+	fn mutable_de(&mut self, scope: &[String], s: &Struct, fields: &[Field]) {
+		for f in fields {
+			let t = self.rust_type(scope, &f.ty);
+			self.line(&format!("let mut v_{}: Option<{}> = None;", f.name, t));
+		}
+		self.line("let members = dec.begin_members()?;");
+		let m = if fields.is_empty() {"m"} else {"mut m"};
+		self.open(&format!("while let Some({}) = dec.next_member()? {{", m));
+		self.open("match m.id {");
+		for f in fields {
+			self.line(&format!("{} => v_{} = Some({}::deserialize(&mut m.value)?),", f.id, f.name, DE));
+		}
+		self.line(&format!("_ if m.must_understand => return Err({}::Invalid(\"unknown member of {}\")),",
+						   ERROR, join(&self.qualified(scope, &s.name))));
+		self.line("_ => {},");
+		self.close("}");
+		self.close("}");
+		self.line("dec.end_members(members)?;");
+
+		for f in fields {
+			let t = self.rust_type(scope, &f.ty);
+			match (f.optional, f.default.as_deref()) {
+				(true, _) => {},
+				(false, Some(d)) if d.starts_with("String::from") =>
+					self.line(&format!("let v_{}: {} = v_{}.unwrap_or_else(|| {});", f.name, t, f.name, d)),
+				(false, Some(d)) => self.line(&format!("let v_{}: {} = v_{}.unwrap_or({});", f.name, t, f.name, d)),
+				(false, None) => self.line(&format!("let v_{}: {} = v_{}.ok_or({}::Invalid(\"{} is missing\"))?;",
+													f.name, t, f.name, ERROR, f.name)),
+			}
+			self.bound_check(&f.name, &f.ty, f.optional);
+		}
+	}
+
+	/// Statements advancing position past the largest XCDR1 encoding of
+	/// fields. Members of mutable types, and optional members, are preceeded
+	/// by a parameter header.
+	fn fields_max_end(&self, scope: &[String], fields: &[Field], ext: Extensibility) -> Option<Vec<String>> {
+		let mutable = ext == Extensibility::Mutable;
+		let mut body = Vec::new();
+		for f in fields {
+			let header = mutable || f.optional;
+			if header {
+				let size = if f.id >= PID_SHORT_MAX {12} else {4};
+				body.push(format!("let position = position.next_multiple_of(4) + {};", size));
+			}
+			body.extend(self.max_end(scope, &f.ty)?);
+			if header {
+				body.push(String::from("let position = position.next_multiple_of(4);"));
+			}
+		}
+		if mutable {
+			// PID_LIST_END
+			body.push(String::from("let position = position.next_multiple_of(4) + 4;"));
+		}
+		Some(body)
+	}
+
+	/// Unions become enums, with a variant for each member. The discriminator
+	/// written for a variant is it's first label, or for the default member
+	/// (without labels of it's own) the first value not used by another label.
+	fn union_def(&mut self, scope: &[String], u: &Union) -> Result<(), IdlError> {
+		let name = rust_ident(&u.name);
+		let qualified = join(&self.qualified(scope, &u.name));
+		let ext = extensibility(&u.annotations)?;
+		if ext == Extensibility::Mutable {
+			return Err(error(format!("{} can't be mutable, only structs may be", qualified)));
+		}
+
+		let disc = self.resolve(scope, &u.discriminator)?;
+		let domain = match *disc.resolved() {
+			Ty::Primitive(Primitive::Boolean) => Some(2),
+			Ty::Primitive(Primitive::Char) | Ty::Primitive(Primitive::Octet) |
+			Ty::Primitive(Primitive::Int8) | Ty::Primitive(Primitive::UInt8) => Some(256),
+			Ty::Primitive(p) if integer_range(p).is_some() => None,
+			Ty::Enum(ref path) => match self.symbols.get(path) {
+				Some(Symbol::Enum(e)) => Some(e.enumerators.len()),
+				_ => unreachable!(),
+			},
+			_ => return Err(error(format!("{} can't be the discriminator of {}", self.rust_type(scope, &disc), qualified))),
+		};
+		let disc_type = self.rust_type(scope, &disc);
+
+		let mut used: Vec<String> = Vec::new();
+		let mut branches = Vec::new();
+		for c in &u.cases {
+			let member = &c.member.declarator.name;
+			let position = at(c.member.declarator.line, c.member.declarator.column);
+			if branches.iter().any(|b: &Branch| b.name == *member) {
+				return Err(position(error(format!("{} is declared more than once within {}", member, qualified))));
+			}
+			let mut labels = Vec::new();
+			let mut values = Vec::new();
+			for l in &c.labels {
+				let value = self.eval(scope, l, 0).map_err(&position)?;
+				values.push(match value {
+					Value::Int(v) => v as i32,
+					Value::Bool(b) => b as i32,
+					Value::Char(c) => c as i32,
+					Value::Enum(_, i) => i as i32,
+					_ => 0,
+				});
+				let literal = self.literal(scope, &disc, value, member).map_err(&position)?;
+				if used.contains(&literal) {
+					return Err(position(error(format!("{} labels more than one member of {}", literal, qualified))));
+				}
+				used.push(literal.clone());
+				labels.push(literal);
+			}
+			branches.push(Branch {
+				name: member.clone(),
+				ty: self.declared(scope, &c.member.type_spec, &c.member.declarator).map_err(&position)?,
+				labels,
+				values,
+				is_default: c.is_default
+			});
+		}
+		if branches.iter().filter(|b| b.is_default).count() > 1 {
+			return Err(error(format!("{} has more than one default member", qualified)));
+		}
+
+		let covered = domain == Some(used.len());
+		let mut default_label = None;
+		if let Some(b) = branches.iter().find(|b| b.is_default) {
+			if covered {
+				return Err(error(format!("the default member of {} can never be selected", qualified)));
+			}
+			if b.labels.is_empty() {
+				default_label = self.unused_label(scope, &disc, &used);
+				if default_label.is_none() {
+					return Err(error(format!("no discriminator is left for the default member of {}", qualified)));
+				}
+			}
+		}
+
+		self.line("#[allow(non_camel_case_types, clippy::upper_case_acronyms)]");
+		self.line("#[derive(Clone, Debug, PartialEq)]");
+		self.open(&format!("pub enum {} {{", name));
+		for b in &branches {
+			let t = self.rust_type(scope, &b.ty);
+			self.line(&format!("{}({}),", rust_ident(&b.name), t));
+		}
+		self.close("}");
+		self.line("");
+
+		self.open(&format!("impl {} for {} {{", SER, name));
+		self.open(&format!("fn serialize(&self, enc: &mut {}) {{", ENCODER));
+		if ext == Extensibility::Appendable {
+			self.line("let d = enc.begin_delimited();");
+		}
+		self.open("match *self {");
+		for b in &branches {
+			let label = b.labels.first().or(default_label.as_ref()).cloned().unwrap_or_default();
+			self.open(&format!("{}::{}(ref v) => {{", name, rust_ident(&b.name)));
+			self.line(&format!("<{} as {}>::serialize(&{}, enc);", disc_type, SER, label));
+			self.line(&format!("{}::serialize(v, enc);", SER));
+			self.close("},");
+		}
+		self.close("}");
+		if ext == Extensibility::Appendable {
+			self.line("enc.end_delimited(d);");
+		}
+		self.close("}");
+		self.line("");
+		let bodies = branches.iter()
+			.map(|b| self.max_end(scope, &b.ty))
+			.collect::<Option<Vec<Vec<String>>>>();
+		match bodies {
+			Some(bodies) => {
+				self.open("fn max_end_position(position: usize) -> Option<usize> {");
+				self.line(&format!("let position = <{} as {}>::max_end_position(position)?;", disc_type, SER));
+				self.line("let mut end = position;");
+				for body in &bodies {
+					self.open("{");
+					for stmt in body {
+						self.line(stmt);
+					}
+					self.line("end = end.max(position);");
+					self.close("}");
+				}
+				self.line("Some(end)");
+				self.close("}");
+			},
+			None => self.max_end_fn("fn max_end_position(position: usize) -> Option<usize> {", None),
+		}
+		self.close("}");
+		self.line("");
+
+		self.open(&format!("impl {} for {} {{", DE, name));
+		self.open(&format!("fn deserialize(dec: &mut {}) -> Result<Self, {}> {{", DECODER, ERROR));
+		if ext == Extensibility::Appendable {
+			self.line("let d = dec.begin_delimited()?;");
+		}
+		self.open(&format!("let v = match <{} as {}>::deserialize(dec)? {{", disc_type, DE));
+		let default = branches.iter().find(|b| b.is_default);
+		for b in branches.iter().filter(|b| !b.is_default).chain(default) {
+			let pattern = if b.is_default {String::from("_")} else {b.labels.join(" | ")};
+			let variant = format!("{}::{}", name, rust_ident(&b.name));
+			let bounded = matches!(*b.ty.resolved(), Ty::String(Some(_)) | Ty::Sequence(_, Some(_)));
+			if bounded {
+				self.open(&format!("{} => {{", pattern));
+				let t = self.rust_type(scope, &b.ty);
+				self.line(&format!("let v_{}: {} = {}::deserialize(dec)?;", b.name, t, DE));
+				self.bound_check(&b.name, &b.ty, false);
+				self.line(&format!("{}(v_{})", variant, b.name));
+				self.close("},");
+			} else {
+				self.line(&format!("{} => {}({}::deserialize(dec)?),", pattern, variant, DE));
+			}
+		}
+		if default.is_none() && !covered {
+			self.line(&format!("_ => return Err({}::Invalid(\"unknown discriminator of {}\")),", ERROR, qualified));
+		}
+		self.close("};");
+		if ext == Extensibility::Appendable {
+			self.line("dec.end_delimited(d)?;");
+		}
+		self.line("Ok(v)");
+		self.close("}");
+		self.close("}");
+
+		let members: Vec<DescribedMember> = branches.iter().enumerate().map(|(i, b)| {
+			let values: Vec<String> = b.values.iter().map(|v| v.to_string()).collect();
+			let mut set = Vec::new();
+			if !values.is_empty() {
+				set.push(("label", format!("vec![{}]", values.join(", "))));
+			}
+			if b.is_default {
+				set.push(("is_default_label", String::from("true")));
+			}
+			DescribedMember {name: b.name.clone(), id: i as u32 + 1, ty: self.dynamic_type(scope, &b.ty), set}
+		}).collect();
+		let args = format!("{:?}, {}::{:?}, {}", qualified, EXTENSIBILITY, ext, self.dynamic_type(scope, &disc));
+		self.describe_impl(&name, "union", &args, Some(&members));
+		Ok(())
+	}
+
+	/// The first value of the discriminator type which isn't in used.
+	fn unused_label(&self, scope: &[String], disc: &Ty, used: &[String]) -> Option<String> {
+		let candidates: Box<dyn Iterator<Item = Value>> = match *disc.resolved() {
+			Ty::Primitive(Primitive::Boolean) => Box::new(vec![Value::Bool(false), Value::Bool(true)].into_iter()),
+			Ty::Primitive(Primitive::Char) => Box::new((0..=255).map(Value::Char)),
+			Ty::Enum(ref path) => {
+				let path = path.clone();
+				Box::new((0..used.len() + 1).map(move |i| Value::Enum(path.clone(), i)))
+			},
+			_ => {
+				let n = used.len() as i128 + 1;
+				Box::new((0..n).chain((1..=n).map(|v| -v)).map(Value::Int))
+			},
+		};
+		candidates
+			.filter_map(|v| self.literal(scope, disc, v, "").ok())
+			.find(|l| !used.contains(l))
+	}
+
+	/// Writes a function which runs the statements in body, leaving position
+	/// past the largest possible encoding. Without a position parameter,
+	/// position starts at 0. A body of None means the encoding is unbounded.
+	fn max_end_fn(&mut self, signature: &str, body: Option<Vec<String>>) {
+		let starts_at_zero = !signature.contains("position");
+		match body {
+			None => {
+				let signature = if starts_at_zero {
+					String::from(signature)
+				} else {
+					signature.replace("position: usize", "_position: usize")
+				};
+				self.open(&signature);
+				self.line("None");
+			},
+			Some(body) => {
+				self.open(signature);
+				if starts_at_zero {
+					self.line("let position = 0;");
+				}
+				for stmt in &body {
+					self.line(stmt);
+				}
+				self.line("Some(position)");
+			},
+		}
+		self.close("}");
+	}
+}
+
+/// A member of a struct, including those inherited from it's base.
+struct Field {
+	name: String,
+	ty: Ty,
+	/// Given by @id, or following on from the previous member.
+	id: u32,
+	key: bool,
+	optional: bool,
+	/// The value given by @default, as Rust.
+	default: Option<String>,
+	/// The value given by @default, as a `dynamic_data::Value`.
+	dynamic_default: Option<String>,
+}
+
+/// A member of a struct or union, as written by `describe_impl`.
+struct DescribedMember {
+	name: String,
+	id: u32,
+	/// Expression building the member's `DynamicType`.
+	ty: String,
+	/// Fields of the `MemberDescriptor` which differ from `new`, and their
+	/// values.
+	set: Vec<(&'static str, String)>,
+}
+
+/// A member of a union, and the discriminator values (as Rust) which select
+/// it.
+struct Branch {
+	name: String,
+	ty: Ty,
+	labels: Vec<String>,
+	/// The labels as held by `MemberDescriptor::label`.
+	values: Vec<i32>,
+	is_default: bool,
+}
+
+/// The extensibility given by `@final`, `@appendable`, `@mutable` or
+/// `@extensibility(...)`. Types are final unless annotated otherwise.
+fn extensibility(annotations: &[Annotation]) -> Result<Extensibility, IdlError> {
+	let mut found = None;
+	for a in annotations {
+		let e = match a.name.as_str() {
+			"final" => Extensibility::Final,
+			"appendable" => Extensibility::Appendable,
+			"mutable" => Extensibility::Mutable,
+			"extensibility" => match a.param(None) {
+				Some(ConstExpr::Name(n)) if n.parts.len() == 1 => match n.parts[0].as_str() {
+					"FINAL" => Extensibility::Final,
+					"APPENDABLE" => Extensibility::Appendable,
+					"MUTABLE" => Extensibility::Mutable,
+					k => return Err(error(format!("unknown extensibility {}", k))),
+				},
+				_ => return Err(error(String::from("@extensibility needs FINAL, APPENDABLE or MUTABLE"))),
+			},
+			_ => continue,
+		};
+		if found.is_some_and(|f| f != e) {
+			return Err(error(String::from("conflicting extensibility annotations")));
+		}
+		found = Some(e);
+	}
+	Ok(found.unwrap_or(Extensibility::Final))
+}
@@ -0,0 +1,235 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Splits IDL source into tokens.
+
+ Comments are discarded, as are preprocessor directives (lines starting with
+ `#`), since `#include` and friends aren't supported.
+ */
+use idl::IdlError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+	/// Identifiers, including keywords.
+	Ident(String),
+	Integer(u64),
+	Float(f64),
+	Str(String),
+	Char(u8),
+	/// Punctuation and operators, e.g. "{", "::" or "<<".
+	Punct(&'static str),
+}
+
+/// A token, and where it starts within the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned {
+	pub token: Token,
+	pub line: usize,
+	pub column: usize,
+}
+
+/// Longer punctuation first, so "::" isn't read as two ":".
+const PUNCTUATION: &[&str] = &[
+	"::", "<<", ">>",
+	"{", "}", "(", ")", "[", "]", "<", ">", ";", ",", ":", "=", "@",
+	"+", "-", "*", "/", "%", "|", "&", "^", "~",
+];
+
+struct Lexer<'a> {
+	src: &'a [u8],
+	pos: usize,
+	line: usize,
+	column: usize,
+}
+
+/// Tokenizes the whole of source.
+pub fn tokenize(source: &str) -> Result<Vec<Spanned>, IdlError> {
+	let mut lexer = Lexer {src: source.as_bytes(), pos: 0, line: 1, column: 1};
+	let mut tokens = Vec::new();
+
+	while let Some(t) = lexer.next_token()? {
+		tokens.push(t);
+	}
+	Ok(tokens)
+}
+
+impl<'a> Lexer<'a> {
+	fn peek(&self, ahead: usize) -> Option<u8> {
+		self.src.get(self.pos + ahead).cloned()
+	}
+
+	fn bump(&mut self) -> Option<u8> {
+		let c = self.peek(0)?;
+		self.pos += 1;
+		if c == b'\n' {
+			self.line += 1;
+			self.column = 1;
+		} else {
+			self.column += 1;
+		}
+		Some(c)
+	}
+
+	fn error(&self, message: String) -> IdlError {
+		IdlError {line: self.line, column: self.column, message}
+	}
+
+	/// Skips whitespace, comments and preprocessor directives.
+	fn skip_trivia(&mut self) -> Result<(), IdlError> {
+		let mut line_start = self.column == 1;
+		loop {
+			match (self.peek(0), self.peek(1)) {
+				(Some(b'\n'), _) => {
+					self.bump();
+					line_start = true;
+				},
+				(Some(c), _) if c.is_ascii_whitespace() => {
+					self.bump();
+				},
+				(Some(b'/'), Some(b'/')) => self.skip_line(),
+				(Some(b'/'), Some(b'*')) => {
+					self.bump();
+					self.bump();
+					loop {
+						match (self.peek(0), self.peek(1)) {
+							(Some(b'*'), Some(b'/')) => {
+								self.bump();
+								self.bump();
+								break;
+							},
+							(Some(_), _) => {
+								self.bump();
+							},
+							(None, _) => return Err(self.error(String::from("unterminated comment"))),
+						}
+					}
+				},
+				(Some(b'#'), _) if line_start => self.skip_line(),
+				_ => return Ok(()),
+			}
+		}
+	}
+
+	fn skip_line(&mut self) {
+		while let Some(c) = self.peek(0) {
+			if c == b'\n' {
+				break;
+			}
+			self.bump();
+		}
+	}
+
+	fn next_token(&mut self) -> Result<Option<Spanned>, IdlError> {
+		self.skip_trivia()?;
+		let (line, column) = (self.line, self.column);
+		let c = match self.peek(0) {
+			Some(c) => c,
+			None => return Ok(None),
+		};
+
+		let token = if c.is_ascii_alphabetic() || c == b'_' {
+			let start = self.pos;
+			while self.peek(0).is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_') {
+				self.bump();
+			}
+			Token::Ident(String::from_utf8_lossy(&self.src[start..self.pos]).into_owned())
+		} else if c.is_ascii_digit() || (c == b'.' && self.peek(1).is_some_and(|c| c.is_ascii_digit())) {
+			self.number()?
+		} else if c == b'"' {
+			self.bump();
+			let mut s = Vec::new();
+			loop {
+				match self.bump() {
+					Some(b'"') => break,
+					Some(b'\\') => s.push(self.escape()?),
+					Some(c) => s.push(c),
+					None => return Err(self.error(String::from("unterminated string literal"))),
+				}
+			}
+			Token::Str(String::from_utf8_lossy(&s).into_owned())
+		} else if c == b'\'' {
+			self.bump();
+			let v = match self.bump() {
+				Some(b'\\') => self.escape()?,
+				Some(c) => c,
+				None => return Err(self.error(String::from("unterminated character literal"))),
+			};
+			if self.bump() != Some(b'\'') {
+				return Err(self.error(String::from("unterminated character literal")));
+			}
+			Token::Char(v)
+		} else {
+			let rest = &self.src[self.pos..];
+			let p = PUNCTUATION.iter().find(|p| rest.starts_with(p.as_bytes()));
+			match p {
+				Some(p) => {
+					for _ in 0..p.len() {
+						self.bump();
+					}
+					Token::Punct(p)
+				},
+				None => return Err(self.error(format!("unexpected character '{}'", c as char))),
+			}
+		};
+
+		Ok(Some(Spanned {token, line, column}))
+	}
+
+	fn number(&mut self) -> Result<Token, IdlError> {
+		let start = self.pos;
+		if self.peek(0) == Some(b'0') && matches!(self.peek(1), Some(b'x') | Some(b'X')) {
+			self.bump();
+			self.bump();
+			let digits = self.pos;
+			while self.peek(0).is_some_and(|c| c.is_ascii_hexdigit()) {
+				self.bump();
+			}
+			let text = String::from_utf8_lossy(&self.src[digits..self.pos]).into_owned();
+			return u64::from_str_radix(&text, 16)
+				.map(Token::Integer)
+				.map_err(|_| self.error(format!("invalid hexadecimal literal 0x{}", text)));
+		}
+
+		let mut float = false;
+		while let Some(c) = self.peek(0) {
+			if c.is_ascii_digit() {
+				self.bump();
+			} else if c == b'.' || c == b'e' || c == b'E' {
+				float = true;
+				self.bump();
+				if (c == b'e' || c == b'E') && matches!(self.peek(0), Some(b'+') | Some(b'-')) {
+					self.bump();
+				}
+			} else {
+				break;
+			}
+		}
+		let text = String::from_utf8_lossy(&self.src[start..self.pos]).into_owned();
+
+		// Float suffixes, e.g. 1.5d, aren't meaningful here.
+		if float && matches!(self.peek(0), Some(b'd') | Some(b'D') | Some(b'f') | Some(b'F')) {
+			self.bump();
+		}
+
+		if float {
+			text.parse().map(Token::Float)
+				.map_err(|_| self.error(format!("invalid floating point literal {}", text)))
+		} else if text.len() > 1 && text.starts_with('0') {
+			u64::from_str_radix(&text[1..], 8).map(Token::Integer)
+				.map_err(|_| self.error(format!("invalid octal literal {}", text)))
+		} else {
+			text.parse().map(Token::Integer)
+				.map_err(|_| self.error(format!("invalid integer literal {}", text)))
+		}
+	}
+
+	fn escape(&mut self) -> Result<u8, IdlError> {
+		match self.bump() {
+			Some(b'n') => Ok(b'\n'),
+			Some(b't') => Ok(b'\t'),
+			Some(b'r') => Ok(b'\r'),
+			Some(b'0') => Ok(0),
+			Some(c @ b'\\') | Some(c @ b'\'') | Some(c @ b'"') => Ok(c),
+			_ => Err(self.error(String::from("unsupported escape sequence"))),
+		}
+	}
+}
@@ -0,0 +1,62 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ The IDL compiler, which generates Rust types (with their CDR serialization)
+ from the IDL used to describe DDS topic types.
+
+ The supported subset of IDL 4 covers modules, structs (including
+ inheritance), discriminated unions, enums, typedefs, sequences, bounded
+ strings, arrays and constants. The annotations understood are `@key`,
+ `@topic`, `@nested`, `@optional`, `@id`, `@default`, and the extensibility
+ annotations `@final`, `@appendable` and `@mutable`. Other annotations are
+ accepted and ignored. Preprocessor directives are skipped, so `#include`
+ isn't supported.
+
+ The `idlc` binary wraps `compile`, writing the generated Rust to a file:
+
+ ```text
+ idlc shapes.idl -o src/shapes.rs
+ ```
+
+ The generated code refers to this crate as `::dds_omg`.
+ */
+
+pub mod ast;
+pub mod codegen;
+pub mod lexer;
+pub mod parser;
+
+use std::error;
+use std::fmt;
+
+/// Describes why IDL could not be compiled. Errors found after parsing (e.g.
+/// unknown names) are given the position of the declaration they were found
+/// in. Errors without any position have a line of 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdlError {
+	pub line: usize,
+	pub column: usize,
+	pub message: String,
+}
+
+impl fmt::Display for IdlError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.line == 0 {
+			write!(f, "{}", self.message)
+		} else {
+			write!(f, "{}:{}: {}", self.line, self.column, self.message)
+		}
+	}
+}
+
+impl error::Error for IdlError {}
+
+/// Header placed at the top of every generated file.
+pub const GENERATED_HEADER: &str = "// Generated by idlc, do not edit.\n\n";
+
+/// Compiles IDL source into Rust source.
+pub fn compile(source: &str) -> Result<String, IdlError> {
+	let tokens = lexer::tokenize(source)?;
+	let definitions = parser::parse(&tokens)?;
+	let code = codegen::generate(&definitions)?;
+	Ok(format!("{}{}", GENERATED_HEADER, code))
+}
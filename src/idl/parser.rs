@@ -0,0 +1,450 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ A recursive descent parser for the subset of IDL 4 used to describe DDS
+ topic types (see the IDL v4.2 specification, in particular the building
+ blocks "Core Data Types" and "Annotations").
+ */
+use idl::IdlError;
+use idl::ast::*;
+use idl::lexer::{Token, Spanned};
+
+/// Parses a tokenized IDL specification.
+pub fn parse(tokens: &[Spanned]) -> Result<Vec<Definition>, IdlError> {
+	let mut parser = Parser {tokens, pos: 0};
+	let mut definitions = Vec::new();
+	while !parser.at_end() {
+		definitions.extend(parser.definition()?);
+	}
+	Ok(definitions)
+}
+
+struct Parser<'a> {
+	tokens: &'a [Spanned],
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn at_end(&self) -> bool {
+		self.pos >= self.tokens.len()
+	}
+
+	fn peek(&self) -> Option<&'a Token> {
+		self.tokens.get(self.pos).map(|t| &t.token)
+	}
+
+	fn peek_at(&self, ahead: usize) -> Option<&'a Token> {
+		self.tokens.get(self.pos + ahead).map(|t| &t.token)
+	}
+
+	/// Line and column of the current token.
+	fn position(&self) -> (usize, usize) {
+		match self.tokens.get(self.pos).or_else(|| self.tokens.last()) {
+			Some(t) => (t.line, t.column),
+			None => (1, 1),
+		}
+	}
+
+	/// An error at the current token.
+	fn error(&self, message: &str) -> IdlError {
+		let (line, column) = self.position();
+		IdlError {line, column, message: String::from(message)}
+	}
+
+	fn is_punct(&self, p: &str) -> bool {
+		matches!(self.peek(), Some(&Token::Punct(q)) if q == p)
+	}
+
+	fn is_keyword(&self, k: &str) -> bool {
+		matches!(self.peek(), Some(Token::Ident(i)) if i == k)
+	}
+
+	fn eat_punct(&mut self, p: &str) -> bool {
+		if self.is_punct(p) {
+			self.pos += 1;
+			true
+		} else {
+			false
+		}
+	}
+
+	fn eat_keyword(&mut self, k: &str) -> bool {
+		if self.is_keyword(k) {
+			self.pos += 1;
+			true
+		} else {
+			false
+		}
+	}
+
+	fn expect_punct(&mut self, p: &str) -> Result<(), IdlError> {
+		if self.eat_punct(p) {
+			Ok(())
+		} else {
+			Err(self.error(&format!("expected '{}'", p)))
+		}
+	}
+
+	fn ident(&mut self) -> Result<String, IdlError> {
+		match self.peek() {
+			Some(Token::Ident(i)) => {
+				self.pos += 1;
+				// Identifiers which clash with keywords may be escaped with
+				// a leading underscore.
+				Ok(i.strip_prefix('_').unwrap_or(i).to_string())
+			},
+			_ => Err(self.error("expected an identifier")),
+		}
+	}
+
+	fn scoped_name(&mut self) -> Result<ScopedName, IdlError> {
+		let absolute = self.eat_punct("::");
+		let mut parts = vec![self.ident()?];
+		while self.eat_punct("::") {
+			parts.push(self.ident()?);
+		}
+		Ok(ScopedName {absolute, parts})
+	}
+
+	fn annotations(&mut self) -> Result<Vec<Annotation>, IdlError> {
+		let mut annotations = Vec::new();
+		while self.eat_punct("@") {
+			let name = self.ident()?;
+			let mut params = Vec::new();
+			if self.eat_punct("(") {
+				while !self.eat_punct(")") {
+					let named = matches!(self.peek(), Some(Token::Ident(_)))
+						&& matches!(self.peek_at(1), Some(&Token::Punct("=")));
+					if named {
+						let n = self.ident()?;
+						self.expect_punct("=")?;
+						params.push((Some(n), self.const_expr()?));
+					} else {
+						params.push((None, self.const_expr()?));
+					}
+					if !self.eat_punct(",") {
+						self.expect_punct(")")?;
+						break;
+					}
+				}
+			}
+			annotations.push(Annotation {name, params});
+		}
+		Ok(annotations)
+	}
+
+	/// A definition, followed by it's semicolon. Forward declarations give
+	/// no definitions, and typedefs with several declarators give several.
+	fn definition(&mut self) -> Result<Vec<Definition>, IdlError> {
+		let annotations = self.annotations()?;
+		let defs = if self.eat_keyword("module") {
+			let (line, column) = self.position();
+			let name = self.ident()?;
+			self.expect_punct("{")?;
+			let mut definitions = Vec::new();
+			while !self.eat_punct("}") {
+				definitions.extend(self.definition()?);
+			}
+			vec![Definition::Module(Module {name, definitions, line, column})]
+		} else if self.eat_keyword("struct") {
+			self.struct_def(annotations)?
+		} else if self.eat_keyword("union") {
+			self.union_def(annotations)?
+		} else if self.eat_keyword("enum") {
+			vec![Definition::Enum(self.enum_def(annotations)?)]
+		} else if self.eat_keyword("typedef") {
+			let type_spec = self.type_spec()?;
+			self.declarators()?.into_iter()
+				.map(|d| Definition::Typedef(Typedef {
+					annotations: annotations.clone(),
+					type_spec: type_spec.clone(),
+					declarator: d
+				}))
+				.collect()
+		} else if self.eat_keyword("const") {
+			let type_spec = self.type_spec()?;
+			let (line, column) = self.position();
+			let name = self.ident()?;
+			self.expect_punct("=")?;
+			let value = self.const_expr()?;
+			vec![Definition::Const(Const {type_spec, name, value, line, column})]
+		} else {
+			return Err(self.error("expected a definition"));
+		};
+		self.expect_punct(";")?;
+		Ok(defs)
+	}
+
+	fn struct_def(&mut self, annotations: Vec<Annotation>) -> Result<Vec<Definition>, IdlError> {
+		let (line, column) = self.position();
+		let name = self.ident()?;
+		if self.is_punct(";") {
+			return Ok(Vec::new());
+		}
+
+		let base = if self.eat_punct(":") {Some(self.scoped_name()?)} else {None};
+		self.expect_punct("{")?;
+		let mut members = Vec::new();
+		while !self.eat_punct("}") {
+			let annotations = self.annotations()?;
+			let type_spec = self.type_spec()?;
+			for d in self.declarators()? {
+				members.push(Member {
+					annotations: annotations.clone(),
+					type_spec: type_spec.clone(),
+					declarator: d
+				});
+			}
+			self.expect_punct(";")?;
+		}
+		Ok(vec![Definition::Struct(Struct {
+			annotations,
+			name,
+			base,
+			members,
+			line,
+			column
+		})])
+	}
+
+	fn union_def(&mut self, annotations: Vec<Annotation>) -> Result<Vec<Definition>, IdlError> {
+		let (line, column) = self.position();
+		let name = self.ident()?;
+		if self.is_punct(";") {
+			return Ok(Vec::new());
+		}
+
+		if !self.eat_keyword("switch") {
+			return Err(self.error("expected switch"));
+		}
+		self.expect_punct("(")?;
+		let discriminator = self.type_spec()?;
+		self.expect_punct(")")?;
+		self.expect_punct("{")?;
+		let mut cases = Vec::new();
+		while !self.eat_punct("}") {
+			let mut labels = Vec::new();
+			let mut is_default = false;
+			loop {
+				if self.eat_keyword("case") {
+					labels.push(self.const_expr()?);
+				} else if self.eat_keyword("default") {
+					is_default = true;
+				} else if labels.is_empty() && !is_default {
+					return Err(self.error("expected case or default"));
+				} else {
+					break;
+				}
+				self.expect_punct(":")?;
+			}
+			let annotations = self.annotations()?;
+			let type_spec = self.type_spec()?;
+			let mut declarators = self.declarators()?;
+			if declarators.len() != 1 {
+				return Err(self.error("expected a single declarator"));
+			}
+			self.expect_punct(";")?;
+			cases.push(Case {
+				labels,
+				is_default,
+				member: Member {annotations, type_spec, declarator: declarators.remove(0)}
+			});
+		}
+		if cases.is_empty() {
+			return Err(self.error("a union must have at least one member"));
+		}
+		Ok(vec![Definition::Union(Union {
+			annotations,
+			name,
+			discriminator,
+			cases,
+			line,
+			column
+		})])
+	}
+
+	fn enum_def(&mut self, annotations: Vec<Annotation>) -> Result<Enum, IdlError> {
+		let (line, column) = self.position();
+		let name = self.ident()?;
+		self.expect_punct("{")?;
+		let mut enumerators = Vec::new();
+		loop {
+			let annotations = self.annotations()?;
+			let (line, column) = self.position();
+			enumerators.push(Enumerator {annotations, name: self.ident()?, line, column});
+			if !self.eat_punct(",") {
+				break;
+			}
+		}
+		self.expect_punct("}")?;
+		Ok(Enum {annotations, name, enumerators, line, column})
+	}
+
+	fn declarators(&mut self) -> Result<Vec<Declarator>, IdlError> {
+		let mut declarators = Vec::new();
+		loop {
+			let (line, column) = self.position();
+			let name = self.ident()?;
+			let mut dimensions = Vec::new();
+			while self.eat_punct("[") {
+				dimensions.push(self.const_expr()?);
+				self.expect_punct("]")?;
+			}
+			declarators.push(Declarator {name, dimensions, line, column});
+			if !self.eat_punct(",") {
+				return Ok(declarators);
+			}
+		}
+	}
+
+	fn type_spec(&mut self) -> Result<TypeSpec, IdlError> {
+		let word = match self.peek() {
+			Some(Token::Ident(i)) => i.as_str(),
+			Some(&Token::Punct("::")) => return self.scoped_name().map(TypeSpec::Named),
+			_ => return Err(self.error("expected a type")),
+		};
+
+		let primitive = match word {
+			"boolean" => Primitive::Boolean,
+			"octet" => Primitive::Octet,
+			"char" => Primitive::Char,
+			"int8" => Primitive::Int8,
+			"uint8" => Primitive::UInt8,
+			"short" | "int16" => Primitive::Short,
+			"uint16" => Primitive::UShort,
+			"int32" => Primitive::Long,
+			"uint32" => Primitive::ULong,
+			"int64" => Primitive::LongLong,
+			"uint64" => Primitive::ULongLong,
+			"float" => Primitive::Float,
+			"double" => Primitive::Double,
+			"long" => {
+				self.pos += 1;
+				if self.is_keyword("double") {
+					return Err(self.error("long double is not supported"));
+				}
+				return Ok(TypeSpec::Primitive(if self.eat_keyword("long") {
+					Primitive::LongLong
+				} else {
+					Primitive::Long
+				}));
+			},
+			"unsigned" => {
+				self.pos += 1;
+				return Ok(TypeSpec::Primitive(if self.eat_keyword("short") {
+					Primitive::UShort
+				} else if self.eat_keyword("long") {
+					if self.eat_keyword("long") {Primitive::ULongLong} else {Primitive::ULong}
+				} else {
+					return Err(self.error("expected short or long"));
+				}));
+			},
+			"string" => {
+				self.pos += 1;
+				let bound = if self.eat_punct("<") {
+					let b = self.const_expr()?;
+					self.expect_punct(">")?;
+					Some(b)
+				} else {
+					None
+				};
+				return Ok(TypeSpec::String(bound));
+			},
+			"sequence" => {
+				self.pos += 1;
+				self.expect_punct("<")?;
+				let element = self.type_spec()?;
+				let bound = if self.eat_punct(",") {Some(self.const_expr()?)} else {None};
+				self.expect_punct(">")?;
+				return Ok(TypeSpec::Sequence(Box::new(element), bound));
+			},
+			"wchar" | "wstring" | "fixed" | "any" | "map" | "bitset" | "bitmask" =>
+				return Err(self.error(&format!("{} is not supported", word))),
+			_ => return self.scoped_name().map(TypeSpec::Named),
+		};
+		self.pos += 1;
+		Ok(TypeSpec::Primitive(primitive))
+	}
+
+	/// Constant expressions, lowest precedence first.
+	fn const_expr(&mut self) -> Result<ConstExpr, IdlError> {
+		self.binary(0)
+	}
+
+	fn binary(&mut self, level: usize) -> Result<ConstExpr, IdlError> {
+		const LEVELS: &[&[(&str, BinaryOp)]] = &[
+			&[("|", BinaryOp::Or)],
+			&[("^", BinaryOp::Xor)],
+			&[("&", BinaryOp::And)],
+			&[("<<", BinaryOp::Shl), (">>", BinaryOp::Shr)],
+			&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+			&[("*", BinaryOp::Mul), ("/", BinaryOp::Div), ("%", BinaryOp::Mod)],
+		];
+
+		if level == LEVELS.len() {
+			return self.unary();
+		}
+		let mut lhs = self.binary(level + 1)?;
+		'outer: loop {
+			for &(p, op) in LEVELS[level] {
+				if self.eat_punct(p) {
+					let rhs = self.binary(level + 1)?;
+					lhs = ConstExpr::Binary(op, Box::new(lhs), Box::new(rhs));
+					continue 'outer;
+				}
+			}
+			return Ok(lhs);
+		}
+	}
+
+	fn unary(&mut self) -> Result<ConstExpr, IdlError> {
+		if self.eat_punct("-") {
+			Ok(ConstExpr::Unary(UnaryOp::Neg, Box::new(self.unary()?)))
+		} else if self.eat_punct("~") {
+			Ok(ConstExpr::Unary(UnaryOp::Not, Box::new(self.unary()?)))
+		} else if self.eat_punct("+") {
+			self.unary()
+		} else {
+			self.primary()
+		}
+	}
+
+	fn primary(&mut self) -> Result<ConstExpr, IdlError> {
+		if self.eat_punct("(") {
+			let e = self.const_expr()?;
+			self.expect_punct(")")?;
+			return Ok(e);
+		}
+		if self.eat_keyword("TRUE") {
+			return Ok(ConstExpr::Bool(true));
+		}
+		if self.eat_keyword("FALSE") {
+			return Ok(ConstExpr::Bool(false));
+		}
+
+		match self.peek() {
+			Some(&Token::Integer(v)) => {
+				self.pos += 1;
+				Ok(ConstExpr::Integer(v))
+			},
+			Some(&Token::Float(v)) => {
+				self.pos += 1;
+				Ok(ConstExpr::Float(v))
+			},
+			Some(Token::Str(_)) => {
+				// Adjacent string literals are concatenated.
+				let mut s = String::new();
+				while let Some(Token::Str(part)) = self.peek() {
+					s.push_str(part);
+					self.pos += 1;
+				}
+				Ok(ConstExpr::Str(s))
+			},
+			Some(&Token::Char(c)) => {
+				self.pos += 1;
+				Ok(ConstExpr::Char(c))
+			},
+			Some(Token::Ident(_)) | Some(&Token::Punct("::")) => self.scoped_name().map(ConstExpr::Name),
+			_ => Err(self.error("expected a constant expression")),
+		}
+	}
+}
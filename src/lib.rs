@@ -35,6 +35,11 @@ extern crate rand;
 extern crate bytes;
 extern crate futures;
 extern crate tokio_core;
+extern crate md5;
+extern crate dds_omg_derive;
+
+// Lets code generated by dds_omg_derive name this crate from within it.
+extern crate self as dds_omg;
 
 // See docs within module for more detail.
 pub mod dcps;
@@ -42,6 +47,12 @@ pub mod dcps;
 // See docs within module for more detail.
 pub mod rtps;
 
+// See docs within module for more detail.
+pub mod cdr;
+
+// See docs within module for more detail.
+pub mod idl;
+
 
 #[cfg(test)]
 mod tests;
\ No newline at end of file
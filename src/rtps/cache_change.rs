@@ -4,12 +4,16 @@
  */
 use rtps::*;
 use rtps::guid::*;
+use rtps::messages::submessage::KeyHash_t;
 use std::cmp::Ordering;
 
 pub struct CacheChange {
 	pub kind: ChangeKind_t,
 	pub writer_guid: GUID_t,
 	pub instance_handle: InstanceHandle_t,
+	/// Key hash of the instance, sent in PID_KEY_HASH. None for NO_KEY
+	/// topics, or when the writer didn't send one.
+	pub key_hash: Option<KeyHash_t>,
 	pub sequence_number: SequenceNumber_t,
 	pub data_value: Data,
 }
@@ -3,13 +3,23 @@
 RTPS Endpoint represents the possible communication endpoints from the point of view of the RTPS protocol. There are two kinds of RTPS Endpoint entities: Writer endpoints and Reader endpoints.
 RTPS Writer endpoints send CacheChange messages to RTPS Reader endpoints and potentially receive acknowledgments for the changes they send. RTPS Reader endpoints receive CacheChange and change-availability announcements from Writer endpoints and potentially acknowledge the changes and/or request missed changes.
  */
-mod reader;
-mod writer;
+pub mod reader;
+pub mod writer;
+pub mod stateless_reader;
+pub mod stateless_writer;
 
+use std::error;
+use std::fmt;
+use std::io;
 use std::sync::Arc;
+use std::net::{SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr};
+use tokio_core::reactor::Handle;
+use tokio_core::net::UdpSocket;
+
 use rtps::*;
 use rtps::entity::Entity;
 use rtps::entity::participant::Participant;
+use rtps::messages::builder::BuildError;
 
 pub trait Endpoint: Entity {
 	/// List of unicast locators (transport, address, port combinations) 
@@ -30,5 +40,82 @@ pub trait Endpoint: Entity {
 	fn topic_kind(&self) -> &TopicKind_t;
 
 	/// Get the RTPS participant containing this Endpoint.
-	fn participant(&self) -> Arc<Participant>;
-}
\ No newline at end of file
+	fn participant(&self) -> Arc<dyn Participant>;
+}
+
+/// Describes why a datagram could not be sent to a remote endpoint.
+#[derive(Debug)]
+pub enum SendError {
+	/// The locator isn't a UDP locator.
+	UnsupportedLocator(Locator_t),
+	/// The remote endpoint has no locators to send to.
+	NoLocator,
+	/// A change couldn't be fitted within a datagram.
+	Build(BuildError),
+	/// The socket failed to send the datagram (including when it would block).
+	Io(io::Error),
+}
+
+impl fmt::Display for SendError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			SendError::UnsupportedLocator(ref l) => write!(f, "can't send to locator {:?}", l),
+			SendError::NoLocator => write!(f, "endpoint has no locators"),
+			SendError::Build(ref e) => e.fmt(f),
+			SendError::Io(ref e) => e.fmt(f),
+		}
+	}
+}
+
+impl error::Error for SendError {}
+
+impl From<BuildError> for SendError {
+	fn from(e: BuildError) -> Self {
+		SendError::Build(e)
+	}
+}
+
+impl From<io::Error> for SendError {
+	fn from(e: io::Error) -> Self {
+		SendError::Io(e)
+	}
+}
+
+/// Opens the socket an endpoint sends from.
+fn bind_socket(use_ip_v6: bool, handle: &Handle) -> UdpSocket {
+	if use_ip_v6 {
+		UdpSocket::bind(&SocketAddr::new(
+			IpAddr::V6(Ipv6Addr::new(0,0,0,0,0,0,0,0)), 0), 
+					   handle).unwrap()
+	} else {
+		// binding to 0.0.0.0:0 instructs OS to open the socket on
+		// a port of it's coosing, accross all available network
+		// interfaces.
+		UdpSocket::bind(&SocketAddr::new(
+			IpAddr::V4(Ipv4Addr::new(0,0,0,0)), 0), 
+					   handle).unwrap()
+	}
+}
+
+/// Sends datagram to locator.
+///
+/// Must be called from within a task running on the reactor the socket was
+/// bound with. A datagram the socket isn't ready to send is reported as a
+/// WouldBlock error rather than queued, as with any other lost datagram.
+fn send_datagram(socket: &UdpSocket, locator: &Locator_t, datagram: &[u8]) -> Result<(), SendError> {
+	let addr = locator.socket_addr().ok_or(SendError::UnsupportedLocator(*locator))?;
+	socket.send_to(datagram, &addr)?;
+	Ok(())
+}
+
+/// Sends datagram to each of locators, returning the error encountered
+/// sending to each locator it couldn't be sent to (see send_datagram), or
+/// NoLocator if there aren't any.
+fn send_to_locators(socket: &UdpSocket, locators: &[Locator_t], datagram: &[u8]) -> Vec<SendError> {
+	if locators.is_empty() {
+		return vec![SendError::NoLocator];
+	}
+	locators.iter()
+		.filter_map(|l| send_datagram(socket, l, datagram).err())
+		.collect()
+}
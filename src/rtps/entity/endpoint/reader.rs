@@ -0,0 +1,590 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+RTPS Reader specializes RTPS Endpoint and represents the actor that receives 
+CacheChange messages from one or more RTPS Writer endpoints. Changes received 
+are added to the reader's HistoryCache, where they are available to the 
+related DDS DataReader.
+
+This is the RTPS StatefulReader, which keeps a WriterProxy for each matched 
+writer, tracking the status of every change from that writer (see RTPS v2.2 
+section 8.4.10). Changes are only accepted from matched writers. A reliable 
+reader learns of the changes it has missed from the writer's Heartbeats and 
+Gaps, and requests them with an AckNack sent heartbeat_response_delay after 
+the Heartbeat. Heartbeats arriving within heartbeat_suppression_duration of 
+the last one are ignored.
+
+Nothing is sent when submessages are processed. Instead `send` is called 
+periodically, and sends whatever is due at that time.
+*/
+
+use std::cmp;
+use std::sync::Arc;
+use bytes::Bytes;
+use tokio_core::reactor::Handle;
+use tokio_core::net::UdpSocket;
+
+use rtps::*;
+use rtps::guid::*;
+use rtps::entity::Entity;
+use rtps::entity::participant::Participant;
+use rtps::entity::endpoint::*;
+use rtps::history_cache::HistoryCache;
+use rtps::messages::builder::MessageBuilder;
+use rtps::messages::header::Header;
+use rtps::messages::submessage::{Time_t, Count_t};
+use rtps::messages::submessage::element::{SequenceNumberSet, MAX_BITMAP_BITS};
+use rtps::messages::submessage::ack_nack::AckNack;
+use rtps::messages::submessage::data;
+use rtps::messages::submessage::gap::Gap;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+
+pub struct Reader {
+	// Support for Entity.
+	guid: GUID_t,
+
+	// Support for Endpoint.
+	unicast_locator_list: Vec<Locator_t>,
+	multicast_locator_list: Vec<Locator_t>,
+	reliability_level: ReliabilityKind_t,
+	topic_kind: TopicKind_t,
+
+	reader_cache: HistoryCache,
+	expects_inline_qos: bool,
+	heartbeat_response_delay: Duration_t,
+	heartbeat_suppression_duration: Duration_t,
+	matched_writers: Vec<WriterProxy>,
+
+	participant: Arc<dyn Participant>,
+
+	socket: UdpSocket,
+}
+
+/// The RTPS WriterProxy class represents the information an RTPS 
+/// StatefulReader maintains on each matched RTPS Writer. The matching of an 
+/// RTPS StatefulReader with an RTPS Writer means that the RTPS StatefulReader 
+/// will accept the CacheChange changes sent by the writer, and (if reliable) 
+/// request any it has missed.
+pub struct WriterProxy {
+	remote_writer_guid: GUID_t,
+	unicast_locator_list: Vec<Locator_t>,
+	multicast_locator_list: Vec<Locator_t>,
+	/// Changes RECEIVED after available_changes_max, in order of sequence
+	/// number. Only the 256 changes following available_changes_max are
+	/// tracked.
+	changes_from_writer: Vec<ChangeFromWriter>,
+	/// Every change up to and including this one is RECEIVED or LOST.
+	available_changes_max: SequenceNumber_t,
+	/// The LOST changes up to available_changes_max, as sorted ranges of
+	/// sequence numbers (first and last inclusive). The rest are RECEIVED.
+	lost_changes: Vec<(SequenceNumber_t, SequenceNumber_t)>,
+	/// The last change the writer announced it has. Changes after
+	/// available_changes_max up to this one which haven't been received are
+	/// MISSING.
+	last_available_seq_num: SequenceNumber_t,
+	/// Count of the last Heartbeat processed, to ignore duplicates.
+	last_heartbeat_count: Option<Count_t>,
+	/// When the last Heartbeat was processed, to ignore those that follow
+	/// too soon.
+	last_heartbeat_time: Option<Time_t>,
+	acknack_count: Count_t,
+	/// When an AckNack is due to be sent.
+	acknack_time: Option<Time_t>,
+}
+
+pub struct ChangeFromWriter {
+	status: ChangeFromWriterStatusKind,
+	is_relevant: bool,
+	sequence_number: SequenceNumber_t,
+}
+
+impl Entity for Reader {
+	fn guid(&self) -> &GUID_t {
+		&self.guid
+	}
+}
+
+impl Endpoint for Reader {
+	fn unicast_locator_list(&self) -> &Vec<Locator_t> {
+		&self.unicast_locator_list
+	}
+
+	fn multicast_locator_list(&self) -> &Vec<Locator_t> {
+		&self.multicast_locator_list
+	}
+
+	fn reliability_level(&self) -> &ReliabilityKind_t {
+		&self.reliability_level
+	}
+
+	fn topic_kind(&self) -> &TopicKind_t {
+		&self.topic_kind
+	}
+
+	fn participant(&self) -> Arc<dyn Participant> {
+		self.participant.clone()
+	}
+}
+
+impl Reader {
+	/// Creates a new RTPS Reader.
+	#[allow(clippy::too_many_arguments)]
+	pub fn new(guid: GUID_t,
+		       unicast_locator_list: Vec<Locator_t>,
+		       multicast_locator_list: Vec<Locator_t>,
+		       reliability_level: ReliabilityKind_t,
+		       topic_kind: TopicKind_t,
+		       expects_inline_qos: bool,
+		       heartbeat_response_delay: Duration_t,
+		       heartbeat_suppression_duration: Duration_t,
+		       participant: Arc<dyn Participant>,
+		       use_ip_v6: bool,
+		       handle: &Handle) -> Reader {
+		let soc = bind_socket(use_ip_v6, handle);
+
+		Reader {
+			guid,
+			unicast_locator_list,
+			multicast_locator_list,
+			reliability_level,
+			topic_kind,
+			reader_cache: HistoryCache::new(),
+			expects_inline_qos,
+			heartbeat_response_delay,
+			heartbeat_suppression_duration,
+			matched_writers: Vec::new(),
+			participant,
+			socket: soc
+		}
+	}
+
+	/// Get a reference to the change cache within this reader.
+	pub fn reader_cache(&self) -> &HistoryCache {
+		&self.reader_cache
+	}
+
+	/// True if the reader expects writers to send the inline QoS of each
+	/// change (e.g. the key hash) along with it.
+	pub fn expects_inline_qos(&self) -> bool {
+		self.expects_inline_qos
+	}
+
+	/// How long a reliable reader waits before responding to a Heartbeat
+	/// with an AckNack.
+	pub fn heartbeat_response_delay(&self) -> Duration_t {
+		self.heartbeat_response_delay
+	}
+
+	/// Heartbeats arriving within this duration of the last one responded
+	/// to are ignored.
+	pub fn heartbeat_suppression_duration(&self) -> Duration_t {
+		self.heartbeat_suppression_duration
+	}
+
+	/// Adds a_writer_proxy to matched_writers.
+	pub fn matched_writer_add(&mut self, a_writer_proxy: WriterProxy) {
+		self.matched_writers.push(a_writer_proxy)
+	}
+
+	/// Removes a_writer_proxy from matched_writers.
+	pub fn matched_writer_remove(&mut self, a_writer_proxy: &WriterProxy) {
+		self.matched_writers.retain(|proxy|
+			proxy.remote_writer_guid != a_writer_proxy.remote_writer_guid)
+	}
+
+	/// Get WriterProxy by it's writer GUID.
+	pub fn matched_writer_lookup(&self, a_writer_guid: GUID_t) -> Option<&WriterProxy> {
+		self.matched_writers.iter().find(|proxy|
+			proxy.remote_writer_guid == a_writer_guid)
+	}
+
+	/// Handles a Data sent by the writer identified by writer_guid (see
+	/// `receiver::Received`), adding the change it carries to the reader
+	/// cache. Returns false if the Data is addressed to another reader, the
+	/// writer isn't matched, or the change was already received.
+	///
+	/// Reliable readers ignore changes more than 256 past
+	/// available_changes_max, which are requested once they are closer.
+	/// Best-effort readers only accept changes newer than those already
+	/// received, and treat any they skip over as LOST.
+	pub fn process_data(&mut self, data: &data::Data, writer_guid: GUID_t) -> bool {
+		if data.reader_id != *self.guid.entity_id() && data.reader_id != ENTITYID_UNKNOWN {
+			return false;
+		}
+		let reliable = self.reliability_level == ReliabilityKind_t::RELIABLE;
+		let proxy = match self.matched_writers.iter_mut().find(|p| p.remote_writer_guid == writer_guid) {
+			Some(p) => p,
+			None => return false,
+		};
+
+		let sn = data.writer_sn;
+		if reliable {
+			if !proxy.is_expected(sn) || proxy.status(sn) == ChangeFromWriterStatusKind::RECEIVED {
+				return false;
+			}
+		} else {
+			if sn <= proxy.available_changes_max {
+				return false;
+			}
+			proxy.lost_changes_update(sn);
+		}
+		proxy.received_change_set(sn);
+		self.reader_cache.add_change(Arc::new(data.to_change(writer_guid)));
+		true
+	}
+
+	/// Handles a Gap sent by the writer identified by writer_guid, whose
+	/// changes are irrelevant to this reader and so won't be sent.
+	pub fn process_gap(&mut self, gap: &Gap, writer_guid: GUID_t) {
+		let proxy = match self.matched_writers.iter_mut().find(|p| p.remote_writer_guid == writer_guid) {
+			Some(p) => p,
+			None => return,
+		};
+		let base = gap.gap_list.bitmap_base();
+		if gap.gap_start < base {
+			proxy.irrelevant_changes_set(gap.gap_start, SequenceNumber_t(base.0 - 1));
+		}
+		for sn in gap.gap_list.iter() {
+			proxy.irrelevant_change_set(sn);
+		}
+	}
+
+	/// Handles a Heartbeat sent by the writer identified by writer_guid at
+	/// time now, if this reader is reliable.
+	///
+	/// Changes the writer has, but which haven't been received, are MISSING.
+	/// Those it no longer has are LOST. An AckNack is sent
+	/// heartbeat_response_delay later if the writer requires a response, or
+	/// if changes are missing (unless the Heartbeat only asserts
+	/// liveliness). Heartbeats with a count no greater than the last one
+	/// processed are duplicates, and ignored, as are those arriving within
+	/// heartbeat_suppression_duration of the last one.
+	pub fn process_heartbeat(&mut self, heartbeat: &Heartbeat, writer_guid: GUID_t, now: Time_t) {
+		if self.reliability_level != ReliabilityKind_t::RELIABLE {
+			return;
+		}
+		let proxy = match self.matched_writers.iter_mut().find(|p| p.remote_writer_guid == writer_guid) {
+			Some(p) => p,
+			None => return,
+		};
+		if proxy.last_heartbeat_count.is_some_and(|c| !heartbeat.count.follows(c)) {
+			return;
+		}
+		let suppression = self.heartbeat_suppression_duration;
+		if proxy.last_heartbeat_time.is_some_and(|t| now < t + suppression) {
+			return;
+		}
+		proxy.last_heartbeat_count = Some(heartbeat.count);
+		proxy.last_heartbeat_time = Some(now);
+
+		proxy.lost_changes_update(heartbeat.first_sn);
+		proxy.missing_changes_update(heartbeat.last_sn);
+
+		let missing = !proxy.missing_changes().is_empty();
+		if !heartbeat.is_final() || (missing && !heartbeat.has_liveliness()) {
+			proxy.acknack_time.get_or_insert(now + self.heartbeat_response_delay);
+		}
+	}
+
+	/// Sends the AckNacks due at time now (see messages) to each matched
+	/// writer's locators. Returns the errors encountered sending to each
+	/// writer, which don't prevent sending to the rest.
+	///
+	/// Must be called from within a task running on the reactor the reader
+	/// was created with.
+	pub fn send(&mut self, now: Time_t) -> Vec<(GUID_t, SendError)> {
+		let mut errors = Vec::new();
+		for (guid, msg) in self.messages(now) {
+			let locators = match self.matched_writer_lookup(guid) {
+				Some(proxy) => proxy.locators().to_vec(),
+				None => Vec::new(),
+			};
+			match msg {
+				Ok(m) => errors.extend(send_to_locators(&self.socket, &locators, &m).into_iter().map(|e| (guid, e))),
+				Err(e) => errors.push((guid, e)),
+			}
+		}
+		errors
+	}
+
+	/// Builds the AckNacks due to be sent to each matched writer at time
+	/// now. Each acknowledges every change up to available_changes_max, and
+	/// requests the missing changes that follow (as many as fit within a
+	/// SequenceNumberSet). The AckNack is final, requiring no response, if
+	/// nothing is missing.
+	pub fn messages(&mut self, now: Time_t) -> Vec<(GUID_t, Result<Bytes, SendError>)> {
+		let mut out = Vec::new();
+		for proxy in &mut self.matched_writers {
+			if proxy.acknack_time.is_none_or(|t| now < t) {
+				continue;
+			}
+			proxy.acknack_time = None;
+			proxy.acknack_count += Count_t::from(1);
+
+			let mut set = SequenceNumberSet::new(SequenceNumber_t(proxy.available_changes_max.0.saturating_add(1)));
+			for sn in proxy.missing_changes() {
+				if !set.insert(sn) {
+					break;
+				}
+			}
+			let acknack = AckNack::new(set.is_empty(),
+				*self.guid.entity_id(),
+				*proxy.remote_writer_guid.entity_id(),
+				set,
+				proxy.acknack_count);
+
+			let mut builder = MessageBuilder::new(Header::new(*self.guid.prefix()));
+			builder.set_destination(*proxy.remote_writer_guid.prefix());
+			let guid = proxy.remote_writer_guid;
+			match builder.add(&acknack) {
+				Ok(()) => out.extend(builder.finish().into_iter().map(|m| (guid, Ok(m)))),
+				Err(e) => out.push((guid, Err(SendError::from(e)))),
+			}
+		}
+		out
+	}
+}
+
+impl WriterProxy {
+	/// Creates a new WriterProxy, for a writer none of whose changes have
+	/// been received.
+	pub fn new(remote_writer_guid: GUID_t,
+			   unicast_locator_list: Vec<Locator_t>,
+			   multicast_locator_list: Vec<Locator_t>) -> WriterProxy {
+		WriterProxy {
+			remote_writer_guid,
+			unicast_locator_list,
+			multicast_locator_list,
+			changes_from_writer: Vec::new(),
+			available_changes_max: SequenceNumber_t(0),
+			lost_changes: Vec::new(),
+			last_available_seq_num: SequenceNumber_t(0),
+			last_heartbeat_count: None,
+			last_heartbeat_time: None,
+			acknack_count: Count_t::from(0),
+			acknack_time: None
+		}
+	}
+
+	pub fn remote_writer_guid(&self) -> &GUID_t {
+		&self.remote_writer_guid
+	}
+
+	/// The locators messages are sent to: the unicast locators, or the
+	/// multicast locators if there aren't any.
+	pub fn locators(&self) -> &[Locator_t] {
+		if self.unicast_locator_list.is_empty() {
+			&self.multicast_locator_list
+		} else {
+			&self.unicast_locator_list
+		}
+	}
+
+	/// The status of the change with sequence number sn. Changes no greater
+	/// than available_changes_max are LOST if they were skipped over without
+	/// being received, and otherwise RECEIVED.
+	pub fn status(&self, sn: SequenceNumber_t) -> ChangeFromWriterStatusKind {
+		if sn <= self.available_changes_max {
+			let lost = self.lost_changes.binary_search_by(|&(first, last)| {
+				if last < sn {
+					cmp::Ordering::Less
+				} else if first > sn {
+					cmp::Ordering::Greater
+				} else {
+					cmp::Ordering::Equal
+				}
+			});
+			return if lost.is_ok() {
+				ChangeFromWriterStatusKind::LOST
+			} else {
+				ChangeFromWriterStatusKind::RECEIVED
+			};
+		}
+		match self.change_from_writer(sn) {
+			Some(cfw) => cfw.status,
+			None if sn <= self.last_available_seq_num => ChangeFromWriterStatusKind::MISSING,
+			None => ChangeFromWriterStatusKind::UNKNOWN,
+		}
+	}
+
+	/// Get a ChangeFromWriter by it's sequence number, if it was received
+	/// ahead of available_changes_max.
+	pub fn change_from_writer(&self, sn: SequenceNumber_t) -> Option<&ChangeFromWriter> {
+		self.changes_from_writer.binary_search_by(|cfw| cfw.sequence_number.cmp(&sn))
+			.ok()
+			.map(|i| &self.changes_from_writer[i])
+	}
+
+	/// This operation returns the maximum SequenceNumber_t among the 
+	/// changes_from_writer changes in the RTPS WriterProxy that are available 
+	/// for access by the DDS DataReader. These are the changes whose status 
+	/// is RECEIVED or LOST, with all changes preceeding them also RECEIVED 
+	/// or LOST.
+	pub fn available_changes_max(&self) -> SequenceNumber_t {
+		self.available_changes_max
+	}
+
+	/// This operation modifies the status of a ChangeFromWriter to indicate 
+	/// that the CacheChange with the SequenceNumber_t ‘a_seq_num’ is 
+	/// irrelevant to the RTPS Reader.
+	pub fn irrelevant_change_set(&mut self, a_seq_num: SequenceNumber_t) {
+		self.insert(a_seq_num, false);
+		self.advance();
+	}
+
+	/// Marks the changes from first to last (inclusive) irrelevant, as
+	/// irrelevant_change_set does for each. A range starting no later than
+	/// the change following available_changes_max makes the whole range
+	/// available at once.
+	pub fn irrelevant_changes_set(&mut self, first: SequenceNumber_t, last: SequenceNumber_t) {
+		if first.0 <= self.available_changes_max.0.saturating_add(1) {
+			self.available_changes_max = cmp::max(self.available_changes_max, last);
+		} else {
+			let end = cmp::min(last.0, self.window_max());
+			for sn in first.0..=end {
+				self.insert(SequenceNumber_t(sn), false);
+			}
+		}
+		self.advance();
+	}
+
+	/// This operation modifies the status stored in ChangeFromWriter for any 
+	/// changes in the WriterProxy whose status is MISSING or UNKNOWN and have 
+	/// sequence numbers lower than ‘first_available_seq_num.’ The status of 
+	/// those changes is modified to LOST indicating that the changes are no 
+	/// longer available in the WriterHistoryCache of the RTPS Writer 
+	/// represented by the RTPS WriterProxy.
+	pub fn lost_changes_update(&mut self, first_available_seq_num: SequenceNumber_t) {
+		// Every change before first_available_seq_num is either RECEIVED or
+		// LOST, so is available.
+		let lost_max = SequenceNumber_t(first_available_seq_num.0.saturating_sub(1));
+		if lost_max <= self.available_changes_max {
+			return;
+		}
+		let received: Vec<i64> = self.changes_from_writer.iter()
+			.map(|cfw| cfw.sequence_number.0)
+			.take_while(|sn| *sn <= lost_max.0)
+			.collect();
+		let mut first = self.available_changes_max.0 + 1;
+		for sn in received {
+			if sn > first {
+				self.lost_changes_add(first, sn - 1);
+			}
+			first = sn + 1;
+		}
+		if first <= lost_max.0 {
+			self.lost_changes_add(first, lost_max.0);
+		}
+		self.available_changes_max = lost_max;
+		self.advance();
+	}
+
+	/// This operation returns the subset of changes for the WriterProxy that 
+	/// have status ‘MISSING.’ The changes with status ‘MISSING’ represent 
+	/// the set of changes available in the HistoryCache of the RTPS Writer 
+	/// represented by the RTPS WriterProxy that have not been received by 
+	/// the RTPS Reader.
+	///
+	/// Only the missing changes which an AckNack could request (i.e. within
+	/// the 256 changes following available_changes_max) are returned.
+	pub fn missing_changes(&self) -> Vec<SequenceNumber_t> {
+		let last = cmp::min(self.last_available_seq_num.0, self.window_max());
+		let mut missing = Vec::new();
+		let mut sn = self.available_changes_max.0;
+		while sn < last {
+			sn += 1;
+			if self.change_from_writer(SequenceNumber_t(sn)).is_none() {
+				missing.push(SequenceNumber_t(sn));
+			}
+		}
+		missing
+	}
+
+	/// This operation modifies the status stored in ChangeFromWriter for any 
+	/// changes in the WriterProxy whose status is UNKNOWN and have sequence 
+	/// numbers smaller or equal to ‘last_available_seq_num.’ The status of 
+	/// those changes is modified from UNKNOWN to MISSING indicating that the 
+	/// changes are available at the WriterHistoryCache of the RTPS Writer 
+	/// represented by the RTPS WriterProxy but have not been received by 
+	/// the RTPS Reader.
+	pub fn missing_changes_update(&mut self, last_available_seq_num: SequenceNumber_t) {
+		self.last_available_seq_num = cmp::max(self.last_available_seq_num, last_available_seq_num);
+	}
+
+	/// This operation modifies the status of the ChangeFromWriter that refers 
+	/// to the CacheChange with the SequenceNumber_t ‘a_seq_num.’ The status 
+	/// of the change is set to ‘RECEIVED,’ indicating it has been received.
+	pub fn received_change_set(&mut self, a_seq_num: SequenceNumber_t) {
+		self.insert(a_seq_num, true);
+		self.advance();
+	}
+
+	/// Lists the changes from first to last (inclusive) as LOST. They must
+	/// follow those already listed.
+	fn lost_changes_add(&mut self, first: i64, last: i64) {
+		if let Some(range) = self.lost_changes.last_mut() {
+			if range.1.0 + 1 == first {
+				range.1 = SequenceNumber_t(last);
+				return;
+			}
+		}
+		self.lost_changes.push((SequenceNumber_t(first), SequenceNumber_t(last)));
+	}
+
+	/// True if the change sn could still be received, and would be tracked
+	/// if it were.
+	fn is_expected(&self, sn: SequenceNumber_t) -> bool {
+		sn > self.available_changes_max && sn.0 <= self.window_max()
+	}
+
+	/// The largest sequence number tracked: 256 changes past
+	/// available_changes_max, as many as an AckNack can request.
+	fn window_max(&self) -> i64 {
+		self.available_changes_max.0.saturating_add(MAX_BITMAP_BITS as i64)
+	}
+
+	/// Lists the change sn as RECEIVED, in order, if it is expected.
+	fn insert(&mut self, sn: SequenceNumber_t, is_relevant: bool) {
+		if !self.is_expected(sn) {
+			return;
+		}
+		if let Err(i) = self.changes_from_writer.binary_search_by(|cfw| cfw.sequence_number.cmp(&sn)) {
+			self.changes_from_writer.insert(i, ChangeFromWriter {
+				status: ChangeFromWriterStatusKind::RECEIVED,
+				is_relevant,
+				sequence_number: sn
+			});
+		}
+	}
+
+	/// Moves available_changes_max past the RECEIVED changes which follow
+	/// it, and drops the changes it has moved past.
+	fn advance(&mut self) {
+		let max = self.available_changes_max;
+		self.changes_from_writer.retain(|cfw| cfw.sequence_number > max);
+
+		let mut received = 0;
+		for cfw in &self.changes_from_writer {
+			if Some(cfw.sequence_number.0) != self.available_changes_max.0.checked_add(1) {
+				break;
+			}
+			self.available_changes_max = cfw.sequence_number;
+			received += 1;
+		}
+		self.changes_from_writer.drain(..received);
+	}
+}
+
+impl ChangeFromWriter {
+	pub fn status(&self) -> ChangeFromWriterStatusKind {
+		self.status
+	}
+
+	/// False if the writer sent a Gap in place of the change.
+	pub fn is_relevant(&self) -> bool {
+		self.is_relevant
+	}
+
+	pub fn sequence_number(&self) -> SequenceNumber_t {
+		self.sequence_number
+	}
+}
@@ -0,0 +1,129 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+RTPS StatelessReader specializes RTPS Reader for situations where the reader 
+keeps no state on the remote writers it receives from (see RTPS v2.2 section 
+8.4.11). It accepts changes from any writer, so needs no matching, but can 
+only be best-effort as it never acknowledges anything.
+
+This is what the builtin participant discovery (SPDP) reader uses, as do 
+best-effort topics received over a multicast address shared with many 
+writers.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use rtps::*;
+use rtps::guid::*;
+use rtps::entity::Entity;
+use rtps::entity::participant::Participant;
+use rtps::entity::endpoint::*;
+use rtps::history_cache::HistoryCache;
+use rtps::messages::submessage::data;
+
+/// How many of the sequence numbers last received from each writer are
+/// remembered to detect duplicates.
+pub const DUPLICATE_WINDOW: usize = 256;
+
+pub struct StatelessReader {
+	// Support for Entity.
+	guid: GUID_t,
+
+	// Support for Endpoint.
+	unicast_locator_list: Vec<Locator_t>,
+	multicast_locator_list: Vec<Locator_t>,
+	reliability_level: ReliabilityKind_t,
+	topic_kind: TopicKind_t,
+
+	reader_cache: HistoryCache,
+	expects_inline_qos: bool,
+	/// The sequence numbers last received from each writer, oldest first,
+	/// up to DUPLICATE_WINDOW of them. Kept apart from the reader cache, so
+	/// changes removed from the cache aren't accepted again when writers
+	/// resend them.
+	received: HashMap<GUID_t, VecDeque<SequenceNumber_t>>,
+
+	participant: Arc<dyn Participant>,
+}
+
+impl Entity for StatelessReader {
+	fn guid(&self) -> &GUID_t {
+		&self.guid
+	}
+}
+
+impl Endpoint for StatelessReader {
+	fn unicast_locator_list(&self) -> &Vec<Locator_t> {
+		&self.unicast_locator_list
+	}
+
+	fn multicast_locator_list(&self) -> &Vec<Locator_t> {
+		&self.multicast_locator_list
+	}
+
+	fn reliability_level(&self) -> &ReliabilityKind_t {
+		&self.reliability_level
+	}
+
+	fn topic_kind(&self) -> &TopicKind_t {
+		&self.topic_kind
+	}
+
+	fn participant(&self) -> Arc<dyn Participant> {
+		self.participant.clone()
+	}
+}
+
+impl StatelessReader {
+	/// Creates a new RTPS StatelessReader, which is always best-effort.
+	pub fn new(guid: GUID_t,
+		       unicast_locator_list: Vec<Locator_t>,
+		       multicast_locator_list: Vec<Locator_t>,
+		       topic_kind: TopicKind_t,
+		       expects_inline_qos: bool,
+		       participant: Arc<dyn Participant>) -> StatelessReader {
+		StatelessReader {
+			guid,
+			unicast_locator_list,
+			multicast_locator_list,
+			reliability_level: ReliabilityKind_t::BEST_EFFORT,
+			topic_kind,
+			reader_cache: HistoryCache::new(),
+			expects_inline_qos,
+			received: HashMap::new(),
+			participant
+		}
+	}
+
+	/// Get a reference to the change cache within this reader.
+	pub fn reader_cache(&self) -> &HistoryCache {
+		&self.reader_cache
+	}
+
+	/// True if the reader expects writers to send the inline QoS of each
+	/// change (e.g. the key hash) along with it.
+	pub fn expects_inline_qos(&self) -> bool {
+		self.expects_inline_qos
+	}
+
+	/// Handles a Data sent by the writer identified by writer_guid (see
+	/// `receiver::Received`), adding the change it carries to the reader
+	/// cache. Returns false if the Data is addressed to another reader, or
+	/// the same change from the same writer was already received (among the
+	/// last DUPLICATE_WINDOW changes received from it).
+	pub fn process_data(&mut self, data: &data::Data, writer_guid: GUID_t) -> bool {
+		if data.reader_id != *self.guid.entity_id() && data.reader_id != ENTITYID_UNKNOWN {
+			return false;
+		}
+		let received = self.received.entry(writer_guid).or_default();
+		if received.contains(&data.writer_sn) {
+			return false;
+		}
+		if received.len() == DUPLICATE_WINDOW {
+			received.pop_front();
+		}
+		received.push_back(data.writer_sn);
+		self.reader_cache.add_change(Arc::new(data.to_change(writer_guid)));
+		true
+	}
+}
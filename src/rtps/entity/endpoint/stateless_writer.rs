@@ -0,0 +1,430 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+RTPS StatelessWriter specializes RTPS Writer for situations where the writer 
+keeps no state on the remote readers it is matched with. Instead it keeps a 
+list of ReaderLocators, the addresses readers are listening on, and sends 
+every change once to each of them (see RTPS v2.2 section 8.4.7.2).
+
+This is what the builtin participant discovery (SPDP) writer uses, as do 
+best-effort topics with many readers sharing a multicast address. A reliable 
+StatelessWriter also announces it's changes with Heartbeats, and resends 
+changes that readers request with AckNacks, to whichever locator the request 
+came from.
+
+Nothing is sent when changes are pushed. Instead `send` is called 
+periodically, and sends whatever is due at that time.
+*/
+
+use std::sync::Arc;
+use bytes::Bytes;
+use tokio_core::reactor::Handle;
+use tokio_core::net::UdpSocket;
+
+use rtps::*;
+use rtps::guid::*;
+use rtps::entity::Entity;
+use rtps::entity::participant::Participant;
+use rtps::entity::endpoint::*;
+use rtps::history_cache::HistoryCache;
+use rtps::cache_change::CacheChange;
+use rtps::messages::builder::MessageBuilder;
+use rtps::messages::header::Header;
+use rtps::messages::submessage::{Time_t, Count_t, KeyHash_t};
+use rtps::messages::submessage::element::SequenceNumberSet;
+use rtps::messages::submessage::ack_nack::AckNack;
+use rtps::messages::submessage::data;
+use rtps::messages::submessage::gap::Gap;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+
+pub struct StatelessWriter {
+	// Support for Entity.
+	guid: GUID_t,
+
+	// Support for Endpoint.
+	unicast_locator_list: Vec<Locator_t>,
+	multicast_locator_list: Vec<Locator_t>,
+	reliability_level: ReliabilityKind_t,
+	topic_kind: TopicKind_t,
+
+	writer_cache: HistoryCache,
+	push_mode: bool,
+	heartbeat_period: Duration_t,
+	nack_response_delay: Duration_t,
+	resend_data_period: Duration_t,
+	last_change_sequence_number: SequenceNumber_t,
+	reader_locators: Vec<ReaderLocator>,
+
+	heartbeat_count: Count_t,
+	next_heartbeat: Time_t,
+	next_resend: Option<Time_t>,
+
+	participant: Arc<dyn Participant>,
+
+	socket: UdpSocket,
+}
+
+/// The RTPS ReaderLocator class represents a locator a StatelessWriter sends 
+/// it's changes to. Any number of readers may be listening on the locator, 
+/// none of which the writer knows about.
+pub struct ReaderLocator {
+	locator: Locator_t,
+	expects_inline_qos: bool,
+	unsent_changes: Vec<Arc<CacheChange>>,
+	requested_changes: Vec<SequenceNumber_t>,
+	/// When the requested changes are due to be sent.
+	repair_time: Option<Time_t>,
+}
+
+impl Entity for StatelessWriter {
+	fn guid(&self) -> &GUID_t {
+		&self.guid
+	}
+}
+
+impl Endpoint for StatelessWriter {
+	fn unicast_locator_list(&self) -> &Vec<Locator_t> {
+		&self.unicast_locator_list
+	}
+
+	fn multicast_locator_list(&self) -> &Vec<Locator_t> {
+		&self.multicast_locator_list
+	}
+
+	fn reliability_level(&self) -> &ReliabilityKind_t {
+		&self.reliability_level
+	}
+
+	fn topic_kind(&self) -> &TopicKind_t {
+		&self.topic_kind
+	}
+
+	fn participant(&self) -> Arc<dyn Participant> {
+		self.participant.clone()
+	}
+}
+
+impl StatelessWriter {
+	/// Creates a new RTPS StatelessWriter.
+	///
+	/// Every change is sent again to every locator each resend_data_period,
+	/// unless it is DURATION_INFINITE.
+	/// Pass a tokio reactor handle for handling asynch IO.
+	#[allow(clippy::too_many_arguments)]
+	pub fn new(guid: GUID_t,
+		       unicast_locator_list: Vec<Locator_t>,
+		       multicast_locator_list: Vec<Locator_t>,
+		       reliability_level: ReliabilityKind_t,
+		       topic_kind: TopicKind_t,
+		       push_mode: bool,
+		       heartbeat_period: Duration_t,
+		       nack_response_delay: Duration_t,
+		       resend_data_period: Duration_t,
+		       participant: Arc<dyn Participant>,
+		       use_ip_v6: bool,
+		       handle: &Handle) -> StatelessWriter {
+		StatelessWriter {
+			guid,
+			unicast_locator_list,
+			multicast_locator_list,
+			reliability_level,
+			topic_kind,
+			push_mode,
+			heartbeat_period,
+			nack_response_delay,
+			resend_data_period,
+			last_change_sequence_number: SequenceNumber_t(0),
+			writer_cache: HistoryCache::new(),
+			reader_locators: Vec::new(),
+			heartbeat_count: Count_t::from(0),
+			next_heartbeat: Time_t::new(0, 0),
+			next_resend: None,
+			participant,
+			socket: bind_socket(use_ip_v6, handle)
+		}
+	}
+
+	/// This operation creates a new CacheChange to be appended to the RTPS 
+	/// Writer’s HistoryCache. The sequence number of the CacheChange is 
+	/// automatically set to be the sequenceNumber of the previous change 
+	/// plus one.
+	pub fn new_change(&mut self, 
+					  kind: ChangeKind_t,
+					  data: Data,
+					  handle: InstanceHandle_t,
+					  key_hash: Option<KeyHash_t>) -> Arc<CacheChange> {
+		self.last_change_sequence_number += SequenceNumber_t(1);
+
+		Arc::new(CacheChange {
+			kind,
+			writer_guid: self.guid,
+			instance_handle: handle,
+			key_hash,
+			sequence_number: self.last_change_sequence_number,
+			data_value: data
+		})
+	}
+
+	/// This operation creates a new CacheChange, adds it to the change cache
+	/// within this writer, and marks it unsent for every reader locator.
+	pub fn push_change(&mut self,
+					   kind: ChangeKind_t,
+					   data: Data,
+					   handle: InstanceHandle_t,
+					   key_hash: Option<KeyHash_t>) {
+		let change = self.new_change(kind, data, handle, key_hash);
+		for rl in &mut self.reader_locators {
+			rl.unsent_changes.push(change.clone());
+		}
+		self.writer_cache.add_change(change);
+	}
+
+	/// Get a reference to the change cache within this writer.
+	pub fn writer_cache(&self) -> &HistoryCache {
+		&self.writer_cache
+	}
+
+	/// Adds a locator to reader_locators. Every change within the writer's
+	/// cache is unsent to the new locator. Adding a locator which is already
+	/// present does nothing.
+	pub fn reader_locator_add(&mut self, a_locator: Locator_t, expects_inline_qos: bool) {
+		if self.reader_locator_lookup(&a_locator).is_some() {
+			return;
+		}
+		let mut rl = ReaderLocator::new(a_locator, expects_inline_qos);
+		rl.unsent_changes = self.writer_cache.get_changes();
+		self.reader_locators.push(rl);
+	}
+
+	/// Removes a_locator from reader_locators.
+	pub fn reader_locator_remove(&mut self, a_locator: &Locator_t) {
+		self.reader_locators.retain(|rl| rl.locator != *a_locator);
+	}
+
+	/// Get ReaderLocator by it's locator.
+	pub fn reader_locator_lookup(&self, a_locator: &Locator_t) -> Option<&ReaderLocator> {
+		self.reader_locators.iter().find(|rl| rl.locator == *a_locator)
+	}
+
+	/// This operation modifies the set of ‘unsent’ changes for all the 
+	/// ReaderLocators in the StatelessWriter. The list of unsent changes is 
+	/// reset to match the complete list of changes available in the writer’s 
+	/// HistoryCache.
+	pub fn unsent_changes_reset(&mut self) {
+		for rl in &mut self.reader_locators {
+			rl.unsent_changes = self.writer_cache.get_changes();
+		}
+	}
+
+	/// Handles an AckNack received by a reliable writer. The changes it
+	/// requests are resent after nack_response_delay, to each of the reader
+	/// locators the AckNack says replies should be sent to (see
+	/// `receiver::Received`). A reply locator without a port (i.e. the
+	/// address the AckNack came from) matches any reader locator at that
+	/// address. AckNacks are ignored by best-effort writers.
+	pub fn process_acknack(&mut self, acknack: &AckNack, reply_locators: &[Locator_t], now: Time_t) {
+		if self.reliability_level != ReliabilityKind_t::RELIABLE {
+			return;
+		}
+		let requested: Vec<SequenceNumber_t> = acknack.reader_sn_state.iter()
+			.filter(|sn| *sn <= self.last_change_sequence_number)
+			.collect();
+		if requested.is_empty() {
+			return;
+		}
+
+		let repair_time = now + self.nack_response_delay;
+		for rl in &mut self.reader_locators {
+			if reply_locators.iter().any(|l| replies_reach(l, &rl.locator)) {
+				rl.requested_changes_set(&requested);
+				rl.repair_time.get_or_insert(repair_time);
+			}
+		}
+	}
+
+	/// Sends everything due at time now (see messages). Returns the errors
+	/// encountered sending to each locator, which don't prevent sending to
+	/// the rest.
+	///
+	/// Must be called from within a task running on the reactor the writer
+	/// was created with.
+	pub fn send(&mut self, now: Time_t) -> Vec<(Locator_t, SendError)> {
+		let mut errors = Vec::new();
+		for (locator, msg) in self.messages(now) {
+			if let Err(e) = msg.and_then(|m| send_datagram(&self.socket, &locator, &m)) {
+				errors.push((locator, e));
+			}
+		}
+		errors
+	}
+
+	/// Builds the datagrams due to be sent to each reader locator at time
+	/// now, in the order they should be sent:
+	///
+	///  - Changes requested by readers once nack_response_delay has passed,
+	///    or a Gap for those no longer in the writer's cache.
+	///  - Unsent changes, when in push mode. Otherwise readers learn of them
+	///    from Heartbeats, and request them.
+	///  - A Heartbeat, each heartbeat_period, for reliable writers.
+	///
+	/// Every change is unsent again once resend_data_period has passed.
+	///
+	/// Changes too large to fit within a datagram are reported as an error
+	/// in place of a datagram. Once built, changes are no longer unsent or
+	/// requested.
+	pub fn messages(&mut self, now: Time_t) -> Vec<(Locator_t, Result<Bytes, SendError>)> {
+		match self.next_resend {
+			Some(resend) if now < resend => {},
+			Some(_) => {
+				self.unsent_changes_reset();
+				self.next_resend = Some(now + self.resend_data_period);
+			},
+			None => self.next_resend = Some(now + self.resend_data_period),
+		}
+
+		let reliable = self.reliability_level == ReliabilityKind_t::RELIABLE;
+		let heartbeat = if reliable && now >= self.next_heartbeat && !self.reader_locators.is_empty() {
+			self.heartbeat_count += Count_t::from(1);
+			self.next_heartbeat = now + self.heartbeat_period;
+			Some(self.heartbeat())
+		} else {
+			None
+		};
+
+		let mut out = Vec::new();
+		for rl in &mut self.reader_locators {
+			let mut builder = MessageBuilder::new(Header::new(*self.guid.prefix()));
+			builder.set_timestamp(Some(now));
+			let mut errors = Vec::new();
+
+			if rl.repair_time.is_some_and(|t| now >= t) {
+				rl.repair_time = None;
+				while let Some(sn) = rl.next_requested_change() {
+					let change = self.writer_cache.get_changes().into_iter()
+						.find(|c| c.sequence_number == sn);
+					let added = match change {
+						Some(c) => builder.add(&data::Data::from_change(ENTITYID_UNKNOWN, &c, rl.expects_inline_qos)),
+						None => builder.add(&gap(self.guid.entity_id(), sn)),
+					};
+					if let Err(e) = added {
+						errors.push(SendError::from(e));
+					}
+				}
+			}
+
+			if self.push_mode {
+				while let Some(c) = rl.next_unsent_change() {
+					let d = data::Data::from_change(ENTITYID_UNKNOWN, &c, rl.expects_inline_qos);
+					if let Err(e) = builder.add(&d) {
+						errors.push(SendError::from(e));
+					}
+				}
+			} else {
+				rl.unsent_changes.clear();
+			}
+
+			if let Some(ref hb) = heartbeat {
+				if let Err(e) = builder.add(hb) {
+					errors.push(SendError::from(e));
+				}
+			}
+
+			out.extend(errors.into_iter().map(|e| (rl.locator, Err(e))));
+			out.extend(builder.finish().into_iter().map(|m| (rl.locator, Ok(m))));
+		}
+		out
+	}
+
+	/// Announces the range of changes available from the writer's cache. An
+	/// empty cache is announced with a first_sn one greater than last_sn.
+	fn heartbeat(&self) -> Heartbeat {
+		let mut first_sn = self.last_change_sequence_number;
+		first_sn += SequenceNumber_t(1);
+		Heartbeat::new(false, false,
+			ENTITYID_UNKNOWN,
+			*self.guid.entity_id(),
+			self.writer_cache.get_seq_num_min().unwrap_or(first_sn),
+			self.writer_cache.get_seq_num_max().unwrap_or(self.last_change_sequence_number),
+			self.heartbeat_count)
+	}
+}
+
+/// True if replies sent to reply reach locator. Without an InfoReply, replies
+/// go to the address a message came from, with an invalid port, as readers
+/// needn't send from the port they receive on.
+fn replies_reach(reply: &Locator_t, locator: &Locator_t) -> bool {
+	reply == locator || (reply.port() == LOCATOR_PORT_INVALID
+		&& reply.kind() == locator.kind()
+		&& reply.address() == locator.address())
+}
+
+/// Tells readers the change sn is no longer available.
+fn gap(writer_id: &EntityId_t, sn: SequenceNumber_t) -> Gap {
+	let mut next = sn;
+	next += SequenceNumber_t(1);
+	Gap::new(ENTITYID_UNKNOWN, *writer_id, sn, SequenceNumberSet::new(next))
+}
+
+impl ReaderLocator {
+	/// Creates a new ReaderLocator, with no unsent or requested changes.
+	pub fn new(locator: Locator_t, expects_inline_qos: bool) -> ReaderLocator {
+		ReaderLocator {
+			locator,
+			expects_inline_qos,
+			unsent_changes: Vec::new(),
+			requested_changes: Vec::new(),
+			repair_time: None
+		}
+	}
+
+	pub fn locator(&self) -> &Locator_t {
+		&self.locator
+	}
+
+	/// True if the readers on the locator expect changes to be sent with
+	/// their inline QoS (e.g. the key hash).
+	pub fn expects_inline_qos(&self) -> bool {
+		self.expects_inline_qos
+	}
+
+	/// This operation returns the list of changes that have not yet been 
+	/// sent to the locator, in order of sequence number.
+	pub fn unsent_changes(&self) -> &[Arc<CacheChange>] {
+		&self.unsent_changes
+	}
+
+	/// This operation removes and returns the change that has the lowest 
+	/// sequence number among the unsent changes. This represents the next 
+	/// change that should be sent to the locator.
+	pub fn next_unsent_change(&mut self) -> Option<Arc<CacheChange>> {
+		if self.unsent_changes.is_empty() {
+			return None;
+		}
+		Some(self.unsent_changes.remove(0))
+	}
+
+	/// This operation returns the sequence numbers of the changes requested
+	/// by readers on the locator, in ascending order.
+	pub fn requested_changes(&self) -> &[SequenceNumber_t] {
+		&self.requested_changes
+	}
+
+	/// This operation adds the changes with sequence numbers 
+	/// ‘req_seq_num_set’ to the requested changes.
+	pub fn requested_changes_set(&mut self, req_seq_num_set: &[SequenceNumber_t]) {
+		for sn in req_seq_num_set {
+			if let Err(i) = self.requested_changes.binary_search(sn) {
+				self.requested_changes.insert(i, *sn);
+			}
+		}
+	}
+
+	/// This operation removes and returns the lowest sequence number among 
+	/// the requested changes. This represents the next repair packet that 
+	/// should be sent to the locator in response to a previous AckNack.
+	pub fn next_requested_change(&mut self) -> Option<SequenceNumber_t> {
+		if self.requested_changes.is_empty() {
+			return None;
+		}
+		Some(self.requested_changes.remove(0))
+	}
+}
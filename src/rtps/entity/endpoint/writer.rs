@@ -4,11 +4,27 @@ RTPS Writer specializes RTPS Endpoint and represents the actor that sends
 CacheChange messages to the matched RTPS Reader endpoints. Its role is to 
 transfer all CacheChange changes in its HistoryCache to the HistoryCache 
 of the matching remote RTPS Readers.
+
+This is the RTPS StatefulWriter, which keeps a ReaderProxy for each matched 
+reader, tracking the status of every change for that reader (see RTPS v2.2 
+section 8.4.9). A reliable writer announces it's changes with Heartbeats each 
+heartbeat_period, and resends the changes readers request with AckNacks after 
+nack_response_delay. Requests for a change which arrive within 
+nack_suppression_duration of sending it are ignored, as the reader most 
+likely hadn't received it yet.
+
+A best-effort writer sends each change to the matched readers as it is 
+pushed. Otherwise nothing is sent when changes are pushed. Instead `send` is 
+called periodically from a task on the writer's reactor, and sends whatever 
+is due at that time.
 */
 
+use std::io;
+use std::iter;
+use std::mem;
 use std::sync::Arc;
 use std::iter::FromIterator;
-use std::net::{SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr};
+use bytes::Bytes;
 use tokio_core::reactor::Handle;
 use tokio_core::net::UdpSocket;
 
@@ -16,9 +32,21 @@ use rtps::*;
 use rtps::guid::*;
 use rtps::entity::Entity;
 use rtps::entity::participant::Participant;
-use rtps::entity::endpoint::Endpoint;
+use rtps::entity::endpoint::*;
 use rtps::history_cache::HistoryCache;
 use rtps::cache_change::CacheChange;
+use rtps::messages::builder::{MessageBuilder, BuildError};
+use rtps::messages::header::Header;
+use rtps::messages::submessage::{Time_t, Count_t, KeyHash_t};
+use rtps::messages::submessage::element::SequenceNumberSet;
+use rtps::messages::submessage::ack_nack::AckNack;
+use rtps::messages::submessage::data;
+use rtps::messages::submessage::gap::Gap;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use cdr;
+use cdr::encapsulation::Encapsulation;
+use cdr::encoder::*;
+use dcps::type_support::DdsType;
 
 pub struct Writer {
 	// Support for Entity.
@@ -38,7 +66,10 @@ pub struct Writer {
 	last_change_sequence_number: SequenceNumber_t,
 	matched_readers: Vec<ReaderProxy>,
 
-	participant: Arc<Participant>,
+	heartbeat_count: Count_t,
+	next_heartbeat: Time_t,
+
+	participant: Arc<dyn Participant>,
 
 	socket: UdpSocket,
 }
@@ -58,12 +89,31 @@ pub struct ReaderProxy {
 	multicast_locator_list: Vec<Locator_t>,
 	changes_for_reader: Vec<ChangeForReader>,
 	is_active: bool,
+	/// Count of the last AckNack processed, to ignore duplicates.
+	last_acknack_count: Option<Count_t>,
+	/// Requested changes no longer held for the reader, to be answered with
+	/// a Gap.
+	requested_gaps: Vec<SequenceNumber_t>,
+	/// When the requested changes are due to be sent.
+	repair_time: Option<Time_t>,
 }
 
 pub struct ChangeForReader {
 	status: ChangeForReaderStatusKind,
 	is_relivant: bool,
 	change: Arc<CacheChange>,
+	/// When the change was last sent to the reader.
+	sent_time: Option<Time_t>,
+}
+
+/// A datagram due to be sent to a matched reader (see Writer::messages).
+pub struct Datagram {
+	pub message: Bytes,
+	/// The changes sent within message, along with the status each had
+	/// before.
+	pub changes: Vec<(SequenceNumber_t, ChangeForReaderStatusKind)>,
+	/// Requested changes answered with a Gap within message.
+	pub gaps: Vec<SequenceNumber_t>,
 }
 
 impl Entity for Writer {
@@ -89,13 +139,14 @@ impl Endpoint for Writer {
 		&self.topic_kind
 	}
 
-	fn participant(&self) -> Arc<Participant> {
+	fn participant(&self) -> Arc<dyn Participant> {
 		self.participant.clone()
 	}
 }
 
 impl Writer {
 	/// Creates a new RTPS Writer.
+	#[allow(clippy::too_many_arguments, clippy::redundant_field_names)]
 	pub fn new(guid: GUID_t,
 		       unicast_locator_list: Vec<Locator_t>,
 		       multicast_locator_list: Vec<Locator_t>,
@@ -105,21 +156,10 @@ impl Writer {
 		       heartbeat_period: Duration_t,
 		       nack_response_delay: Duration_t,
 		       nack_suppression_duration: Duration_t,
-		       participant: Arc<Participant>,
+		       participant: Arc<dyn Participant>,
 		       use_ip_v6: bool,
 		       handle: &Handle) -> Writer {
-		let soc = if use_ip_v6 == true {
-			UdpSocket::bind(&SocketAddr::new(
-				IpAddr::V6(Ipv6Addr::new(0,0,0,0,0,0,0,0)), 0), 
-						   handle).unwrap()
-		} else {
-			// binding to 0.0.0.0:0 instructs OS to open the socket on
-			// a port of it's coosing, accross all available network
-			// interfaces.
-			UdpSocket::bind(&SocketAddr::new(
-				IpAddr::V4(Ipv4Addr::new(0,0,0,0)), 0), 
-						   handle).unwrap()
-		};
+		let soc = bind_socket(use_ip_v6, handle);
 
 		Writer {
 			guid: guid,
@@ -134,6 +174,8 @@ impl Writer {
 			last_change_sequence_number: SequenceNumber_t(0),
 			writer_cache: HistoryCache::new(),
 			matched_readers: Vec::new(),
+			heartbeat_count: Count_t::from(0),
+			next_heartbeat: Time_t::new(0, 0),
 			participant: participant,
 			socket: soc
 		}
@@ -143,16 +185,19 @@ impl Writer {
 	/// Writer’s HistoryCache. The sequence number of the CacheChange is 
 	/// automatically set to be the sequenceNumber of the previous change 
 	/// plus one.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new_change(&mut self, 
 					  kind: ChangeKind_t,
 					  data: Data,
-					  handle: InstanceHandle_t) -> Arc<CacheChange> {
+					  handle: InstanceHandle_t,
+					  key_hash: Option<KeyHash_t>) -> Arc<CacheChange> {
 		self.last_change_sequence_number += SequenceNumber_t(1);
 
 		Arc::new(CacheChange {
 			kind: kind,
 			writer_guid: self.guid,
 			instance_handle: handle,
+			key_hash,
 			sequence_number: self.last_change_sequence_number,
 			data_value: data
 		})
@@ -160,13 +205,131 @@ impl Writer {
 
 	/// This operation creates a new CacheChange and automatically adds it 
 	/// to the change cache within this writer.  Furthermore, it will push
-	/// a write operation within all reader proxies affected by this change.
+	/// a write operation within all reader proxies affected by this change:
+	/// the change is UNSENT to every matched reader in push mode, and
+	/// otherwise UNACKNOWLEDGED, to be announced by the next Heartbeat.
+	///
+	/// Best-effort writers always push, and send the unsent changes to each
+	/// matched reader straight away, one per datagram, returning the errors
+	/// encountered for each reader, so must then be called from within a
+	/// task running on the writer's reactor. A change the socket isn't ready
+	/// to send, or which is too large to send, remains UNSENT along with
+	/// those after it, until the next push or call to send. Reliable writers
+	/// send nothing until the next call to send, so return no errors.
+	///
+	/// For ALIVE changes data is the serialized sample. Otherwise it is the
+	/// serialized key (or empty), and the change is sent as a key-only Data
+	/// carrying the key hash and status info as inline QoS (see
+	/// Data::from_change).
 	pub fn push_change(&mut self,
 					   kind: ChangeKind_t,
 					   data: Data,
-					   handle: InstanceHandle_t) {
-		let change = self.new_change(kind, data, handle);
+					   handle: InstanceHandle_t,
+					   key_hash: Option<KeyHash_t>) -> Vec<(GUID_t, SendError)> {
+		let change = self.new_change(kind, data, handle, key_hash);
+		let reliable = self.reliability_level == ReliabilityKind_t::RELIABLE;
+		let status = if self.push_mode || !reliable {
+			ChangeForReaderStatusKind::UNSENT
+		} else {
+			ChangeForReaderStatusKind::UNACKNOWLEDGED
+		};
+		for proxy in &mut self.matched_readers {
+			proxy.changes_for_reader.push(ChangeForReader {
+				status,
+				is_relivant: true,
+				change: change.clone(),
+				sent_time: None
+			});
+		}
 		self.writer_cache.add_change(change);
+
+		if reliable {
+			Vec::new()
+		} else {
+			self.unsent_changes_send(Time_t::now())
+		}
+	}
+
+	/// Sends each matched reader it's unsent changes in order, one per
+	/// datagram, until one can't be sent (see push_change).
+	fn unsent_changes_send(&mut self, now: Time_t) -> Vec<(GUID_t, SendError)> {
+		let mut errors = Vec::new();
+		let writer_guid = self.guid;
+		let socket = &self.socket;
+		for proxy in &mut self.matched_readers {
+			let reader_guid = proxy.remote_reader_guid;
+			while let Some(cfr) = proxy.next_unsent_change() {
+				let sn = cfr.change.sequence_number;
+				let mut builder = MessageBuilder::new(Header::new(*writer_guid.prefix()));
+				builder.set_destination(*reader_guid.prefix());
+				builder.set_timestamp(Some(now));
+				if let Err(e) = add_change(&mut builder, &reader_guid, &writer_guid, cfr, proxy.expects_inline_qos) {
+					errors.push((reader_guid, SendError::from(e)));
+					break;
+				}
+
+				let failed: Vec<SendError> = builder.finish().iter()
+					.flat_map(|m| send_to_locators(socket, proxy.locators(), m))
+					.collect();
+				let blocked = failed.iter().any(would_block);
+				errors.extend(failed.into_iter().map(|e| (reader_guid, e)));
+				if blocked {
+					break;
+				}
+				if let Some(cfr) = proxy.changes_for_reader.iter_mut().find(|cfr| cfr.change.sequence_number == sn) {
+					cfr.status = ChangeForReaderStatusKind::UNDERWAY;
+					cfr.sent_time = Some(now);
+				}
+			}
+		}
+		errors
+	}
+
+	/// Get a reference to the change cache within this writer.
+	pub fn writer_cache(&self) -> &HistoryCache {
+		&self.writer_cache
+	}
+
+	/// Serializes sample using CDR, preceeded by the given encapsulation
+	/// header, then pushes it as a new change (see push_change).
+	pub fn push_sample<T: CdrSerialize>(&mut self,
+										kind: ChangeKind_t,
+										sample: &T,
+										encapsulation: Encapsulation,
+										handle: InstanceHandle_t,
+										key_hash: Option<KeyHash_t>) -> Vec<(GUID_t, SendError)> {
+		let data = cdr::to_payload(sample, encapsulation);
+		self.push_change(kind, data, handle, key_hash)
+	}
+
+	/// Pushes sample as a new ALIVE change, of the instance given by it's
+	/// key (see push_sample).
+	pub fn write<T: DdsType>(&mut self, sample: &T, encapsulation: Encapsulation) -> Vec<(GUID_t, SendError)> {
+		let handle = sample.instance_handle();
+		let key_hash = key_hash_of(sample);
+		self.push_sample(ChangeKind_t::ALIVE, sample, encapsulation, handle, key_hash)
+	}
+
+	/// Pushes a NOT_ALIVE_DISPOSED change of the instance sample belongs
+	/// to. Only the key members of sample are sent.
+	pub fn dispose<T: DdsType>(&mut self, sample: &T, encapsulation: Encapsulation) -> Vec<(GUID_t, SendError)> {
+		self.push_key(ChangeKind_t::NOT_ALIVE_DISPOSED, sample, encapsulation)
+	}
+
+	/// Pushes a NOT_ALIVE_UNREGISTERED change of the instance sample belongs
+	/// to. Only the key members of sample are sent.
+	pub fn unregister_instance<T: DdsType>(&mut self, sample: &T, encapsulation: Encapsulation) -> Vec<(GUID_t, SendError)> {
+		self.push_key(ChangeKind_t::NOT_ALIVE_UNREGISTERED, sample, encapsulation)
+	}
+
+	/// Pushes a change whose data is the key of sample, preceeded by the
+	/// given encapsulation header.
+	fn push_key<T: DdsType>(&mut self, kind: ChangeKind_t, sample: &T, encapsulation: Encapsulation) -> Vec<(GUID_t, SendError)> {
+		let mut enc = CdrEncoder::new(encapsulation);
+		sample.serialize_key(&mut enc);
+		let handle = sample.instance_handle();
+		let key_hash = key_hash_of(sample);
+		self.push_change(kind, enc.finish(), handle, key_hash)
 	}
 
 	/// Adds a_reader_proxy to matched_readers.
@@ -176,7 +339,7 @@ impl Writer {
 
 	/// Removes a_reader_proxy from matched_readers.
 	pub fn matched_reader_remove(&mut self, a_reader_proxy: &ReaderProxy) {
-		let r = self.matched_readers.iter().position(|ref proxy|
+		let r = self.matched_readers.iter().position(|proxy|
 			proxy.remote_reader_guid == a_reader_proxy.remote_reader_guid);
 
 		if let Some(i) = r {
@@ -186,61 +349,299 @@ impl Writer {
 
 	/// Get ReaderProxy by it's reader GUID.
 	pub fn matched_reader_lookup(&self, a_reader_guid: GUID_t) -> Option<&ReaderProxy> {
-		self.matched_readers.iter().find(|ref proxy| 
+		self.matched_readers.iter().find(|proxy| 
 			proxy.remote_reader_guid == a_reader_guid)
 	}
 
+	/// Handles an AckNack sent by the matched reader identified by
+	/// reader_guid (see `receiver::Received`), if this writer is reliable.
+	///
+	/// Changes below the base of the AckNack's set are acknowledged, and
+	/// those within it are requested, to be resent after
+	/// nack_response_delay. Changes still UNDERWAY (i.e. sent within the
+	/// last nack_suppression_duration) aren't requested again. Requested
+	/// changes which aren't held for the reader are answered with a Gap
+	/// instead. AckNacks with a count which doesn't follow the last one
+	/// processed are duplicates, and ignored.
+	pub fn process_acknack(&mut self, acknack: &AckNack, reader_guid: GUID_t, now: Time_t) {
+		if self.reliability_level != ReliabilityKind_t::RELIABLE {
+			return;
+		}
+		let proxy = match self.matched_readers.iter_mut().find(|p| p.remote_reader_guid == reader_guid) {
+			Some(p) => p,
+			None => return,
+		};
+		if proxy.last_acknack_count.is_some_and(|c| !acknack.count.follows(c)) {
+			return;
+		}
+		proxy.last_acknack_count = Some(acknack.count);
+		proxy.underway_changes_expire(now, self.nack_suppression_duration);
+
+		let base = acknack.reader_sn_state.bitmap_base();
+		proxy.acked_changes_set(SequenceNumber_t(base.0 - 1));
+
+		let mut requested = Vec::new();
+		for sn in acknack.reader_sn_state.iter() {
+			match proxy.change_for_reader(sn) {
+				Some(cfr) if cfr.status != ChangeForReaderStatusKind::UNDERWAY => requested.push(sn),
+				Some(_) => {},
+				None if sn <= self.last_change_sequence_number => {
+					if let Err(i) = proxy.requested_gaps.binary_search(&sn) {
+						proxy.requested_gaps.insert(i, sn);
+					}
+				},
+				None => {},
+			}
+		}
+		proxy.requested_changes_set(&requested);
+		if !requested.is_empty() || !proxy.requested_gaps.is_empty() {
+			proxy.repair_time.get_or_insert(now + self.nack_response_delay);
+		}
+	}
+
+	/// Sends everything due at time now (see messages) to each matched
+	/// reader's locators. Returns the errors encountered sending to each
+	/// reader, which don't prevent sending to the rest.
+	///
+	/// Must be called from within a task running on the reactor the writer
+	/// was created with. If the socket isn't ready to send a datagram, the
+	/// changes it carried go back to the status they had before, the Gaps
+	/// it carried are requested again, and the task is woken once it is
+	/// ready.
+	pub fn send(&mut self, now: Time_t) -> Vec<(GUID_t, SendError)> {
+		let mut errors = Vec::new();
+		let messages = self.messages(now);
+		let socket = &self.socket;
+		for (guid, msg) in messages {
+			let proxy = match self.matched_readers.iter_mut().find(|p| p.remote_reader_guid == guid) {
+				Some(p) => p,
+				None => continue,
+			};
+			let datagram = match msg {
+				Ok(d) => d,
+				Err(e) => {
+					errors.push((guid, e));
+					continue;
+				},
+			};
+			let failed = send_to_locators(socket, proxy.locators(), &datagram.message);
+			if failed.iter().any(would_block) {
+				proxy.unsent_datagram_restore(&datagram, now);
+			}
+			errors.extend(failed.into_iter().map(|e| (guid, e)));
+		}
+		errors
+	}
+
+	/// Builds the datagrams due to be sent to each matched reader at time
+	/// now, in the order they should be sent:
+	///
+	///  - Changes requested by the reader once nack_response_delay has
+	///    passed, after a Gap for those no longer held for the reader.
+	///  - Unsent changes.
+	///  - A Heartbeat, each heartbeat_period, for reliable writers.
+	///
+	/// Changes which aren't relevant to the reader are sent as a Gap. Sent
+	/// changes are UNDERWAY, until nack_suppression_duration has passed and
+	/// they become UNACKNOWLEDGED. Changes too large to fit within a
+	/// datagram are reported as an error in place of a datagram, and keep
+	/// their status.
+	pub fn messages(&mut self, now: Time_t) -> Vec<(GUID_t, Result<Datagram, SendError>)> {
+		let reliable = self.reliability_level == ReliabilityKind_t::RELIABLE;
+		let heartbeat = reliable && now >= self.next_heartbeat && !self.matched_readers.is_empty();
+		if heartbeat {
+			self.heartbeat_count += Count_t::from(1);
+			self.next_heartbeat = now + self.heartbeat_period;
+		}
+
+		let suppression = self.nack_suppression_duration;
+		let mut out = Vec::new();
+		for proxy in &mut self.matched_readers {
+			if reliable {
+				proxy.underway_changes_expire(now, suppression);
+			}
+
+			let mut builder = MessageBuilder::new(Header::new(*self.guid.prefix()));
+			builder.set_destination(*proxy.remote_reader_guid.prefix());
+			builder.set_timestamp(Some(now));
+			let mut errors = Vec::new();
+			// What went into each datagram, by it's index.
+			let mut sent: Vec<Vec<(SequenceNumber_t, ChangeForReaderStatusKind)>> = Vec::new();
+			let mut gapped: Vec<Vec<SequenceNumber_t>> = Vec::new();
+
+			let mut due = Vec::new();
+			if proxy.repair_time.is_some_and(|t| now >= t) {
+				proxy.repair_time = None;
+				let missing = mem::take(&mut proxy.requested_gaps);
+				for g in gaps(&proxy.remote_reader_guid, &self.guid, &missing) {
+					match builder.add(&g) {
+						Ok(()) => slot(&mut gapped, builder.datagrams_started() - 1)
+							.extend(iter::once(g.gap_start).chain(g.gap_list.iter())),
+						Err(e) => errors.push(SendError::from(e)),
+					}
+				}
+				due.extend(proxy.changes_for_reader.iter().enumerate()
+					.filter(|&(_, cfr)| cfr.status == ChangeForReaderStatusKind::REQUESTED)
+					.map(|(i, _)| i));
+			}
+			due.extend(proxy.changes_for_reader.iter().enumerate()
+				.filter(|&(_, cfr)| cfr.status == ChangeForReaderStatusKind::UNSENT)
+				.map(|(i, _)| i));
+
+			for i in due {
+				let cfr = &mut proxy.changes_for_reader[i];
+				match add_change(&mut builder, &proxy.remote_reader_guid, &self.guid, cfr, proxy.expects_inline_qos) {
+					Ok(()) => {
+						slot(&mut sent, builder.datagrams_started() - 1)
+							.push((cfr.change.sequence_number, cfr.status));
+						cfr.status = ChangeForReaderStatusKind::UNDERWAY;
+						cfr.sent_time = Some(now);
+					},
+					Err(e) => errors.push(SendError::from(e)),
+				}
+			}
+
+			if heartbeat {
+				let hb = heartbeat_for(&proxy.remote_reader_guid, &self.guid, &self.writer_cache,
+									   self.last_change_sequence_number, self.heartbeat_count);
+				if let Err(e) = builder.add(&hb) {
+					errors.push(SendError::from(e));
+				}
+			}
+
+			let guid = proxy.remote_reader_guid;
+			out.extend(errors.into_iter().map(|e| (guid, Err(e))));
+			let mut sent = sent.into_iter();
+			let mut gapped = gapped.into_iter();
+			out.extend(builder.finish().into_iter().map(|message| (guid, Ok(Datagram {
+				message,
+				changes: sent.next().unwrap_or_default(),
+				gaps: gapped.next().unwrap_or_default(),
+			}))));
+		}
+		out
+	}
+
 	/// This operation takes a CacheChange a_change as a parameter and 
 	/// determines whether all the ReaderProxy have acknowledged the 
 	/// CacheChange. The operation will return true if all ReaderProxy 
 	/// have acknowledged the corresponding CacheChange and false otherwise.
 	pub fn is_acked_by_all(&self, a_change: Arc<CacheChange>) -> bool {
-		self.matched_readers.iter().all(|ref proxy| {
-			let c = proxy.changes_for_reader.iter().find(|ref cfr|
+		self.matched_readers.iter().all(|proxy| {
+			let c = proxy.changes_for_reader.iter().find(|cfr|
 			            cfr.status == ChangeForReaderStatusKind::ACKNOWLEDGED
-			         && cfr.is_relivant == true
+			         && cfr.is_relivant
 				     && cfr.change == a_change); 
 
-			if c.is_some() {true} else {false}
+			c.is_some()
 		})
 	}
 }
 
+/// The key hash sent along with changes to the instance sample belongs to.
+fn key_hash_of<T: DdsType>(sample: &T) -> Option<KeyHash_t> {
+	match T::topic_kind() {
+		TopicKind_t::NO_KEY => None,
+		TopicKind_t::WITH_KEY => Some(sample.key_hash()),
+	}
+}
+
+/// Announces the range of changes available from cache to reader. An empty
+/// cache is announced with a first_sn one greater than last_sn.
+fn heartbeat_for(reader: &GUID_t,
+				 writer: &GUID_t,
+				 cache: &HistoryCache,
+				 last_sn: SequenceNumber_t,
+				 count: Count_t) -> Heartbeat {
+	Heartbeat::new(false, false,
+		*reader.entity_id(),
+		*writer.entity_id(),
+		cache.get_seq_num_min().unwrap_or(SequenceNumber_t(last_sn.0 + 1)),
+		cache.get_seq_num_max().unwrap_or(last_sn),
+		count)
+}
+
+/// Adds cfr to builder as a Data, or as a Gap if it isn't relevant to reader.
+fn add_change(builder: &mut MessageBuilder,
+			  reader: &GUID_t,
+			  writer: &GUID_t,
+			  cfr: &ChangeForReader,
+			  expects_inline_qos: bool) -> Result<(), BuildError> {
+	if cfr.is_relivant {
+		builder.add(&data::Data::from_change(*reader.entity_id(), &cfr.change, expects_inline_qos))
+	} else {
+		gaps(reader, writer, &[cfr.change.sequence_number]).iter().try_for_each(|g| builder.add(g))
+	}
+}
+
+/// The element of v at index i, adding default elements up to it if v is
+/// shorter.
+fn slot<T: Default>(v: &mut Vec<T>, i: usize) -> &mut T {
+	if v.len() <= i {
+		v.resize_with(i + 1, T::default);
+	}
+	&mut v[i]
+}
+
+/// True if error is the socket not being ready to send.
+fn would_block(error: &SendError) -> bool {
+	match *error {
+		SendError::Io(ref e) => e.kind() == io::ErrorKind::WouldBlock,
+		_ => false,
+	}
+}
+
+/// Tells reader the changes sns are irrelevant to it, with as many Gaps as
+/// it takes to cover them. sns must be sorted.
+fn gaps(reader: &GUID_t, writer: &GUID_t, sns: &[SequenceNumber_t]) -> Vec<Gap> {
+	let mut gaps = Vec::new();
+	let mut rest = sns;
+	while let Some((&start, tail)) = rest.split_first() {
+		let mut set = SequenceNumberSet::new(SequenceNumber_t(start.0 + 1));
+		let covered = tail.iter().take_while(|sn| set.insert(**sn)).count();
+		gaps.push(Gap::new(*reader.entity_id(), *writer.entity_id(), start, set));
+		rest = &tail[covered..];
+	}
+	gaps
+}
+
 impl ReaderProxy {
 	/// Creates a new ReaderProxy.
 	/// Pass a tokio reactor handle for handling asynch IO.
+	#[allow(clippy::too_many_arguments, clippy::redundant_field_names)]
 	pub fn new<F>(remote_reader_guid: GUID_t,
 		          expects_inline_qos: bool,
 		          unicast_locator_list: Vec<Locator_t>,
 		          multicast_locator_list: Vec<Locator_t>,
 		          is_active: bool,
-		          changes: &Vec<Arc<CacheChange>>,
+		          changes: &[Arc<CacheChange>],
 		          time_based_filter: F,
 		          content_based_filter: F,
 		          push_mode: bool) -> ReaderProxy 
 					where  F: Fn(&CacheChange) -> bool {
 		let cfr_it = changes.iter()
 			.map(|change| ChangeForReader {
-				status: if push_mode == true {
+				status: if push_mode {
 						ChangeForReaderStatusKind::UNSENT
 					} else {
 						ChangeForReaderStatusKind::UNACKNOWLEDGED
 					},
 				is_relivant: false,
-				change: change.clone()
+				change: change.clone(),
+				sent_time: None
 			});
 		let mut changes_for_reader = Vec::from_iter(cfr_it);
 
 		// changes for reader must be mutated in place because all changes must
 		// be kept whether relivant or not.
 		for cfr in &mut changes_for_reader {
-			if time_based_filter(cfr.change.as_ref()) == true {
+			if time_based_filter(cfr.change.as_ref()) {
 				cfr.is_relivant = true;
 			}
 		}
 
 		for cfr in &mut changes_for_reader {
-			if content_based_filter(cfr.change.as_ref()) == true {
+			if content_based_filter(cfr.change.as_ref()) {
 				cfr.is_relivant = true;
 			}
 		}
@@ -251,7 +652,67 @@ impl ReaderProxy {
 			unicast_locator_list: unicast_locator_list,
 			multicast_locator_list: multicast_locator_list,
 			changes_for_reader: changes_for_reader,
-			is_active: is_active
+			is_active,
+			last_acknack_count: None,
+			requested_gaps: Vec::new(),
+			repair_time: None
+		}
+	}
+
+	pub fn remote_reader_guid(&self) -> &GUID_t {
+		&self.remote_reader_guid
+	}
+
+	/// True if the reader expects changes to be sent with their inline QoS
+	/// (e.g. the key hash).
+	pub fn expects_inline_qos(&self) -> bool {
+		self.expects_inline_qos
+	}
+
+	/// The locators messages are sent to: the unicast locators, or the
+	/// multicast locators if there aren't any.
+	pub fn locators(&self) -> &[Locator_t] {
+		if self.unicast_locator_list.is_empty() {
+			&self.multicast_locator_list
+		} else {
+			&self.unicast_locator_list
+		}
+	}
+
+	/// Get a ChangeForReader by the sequence number of it's change.
+	pub fn change_for_reader(&self, sn: SequenceNumber_t) -> Option<&ChangeForReader> {
+		self.changes_for_reader.iter().find(|cfr| cfr.change.sequence_number == sn)
+	}
+
+	/// Changes UNDERWAY for at least nack_suppression_duration become
+	/// UNACKNOWLEDGED, and may be requested again.
+	fn underway_changes_expire(&mut self, now: Time_t, nack_suppression_duration: Duration_t) {
+		for cfr in &mut self.changes_for_reader {
+			if cfr.status == ChangeForReaderStatusKind::UNDERWAY
+			&& cfr.sent_time.is_some_and(|t| now >= t + nack_suppression_duration) {
+				cfr.status = ChangeForReaderStatusKind::UNACKNOWLEDGED;
+			}
+		}
+	}
+
+	/// What datagram carried never left the writer, so is due to be sent
+	/// again at time now: it's changes go back to the status they had
+	/// before, and it's Gaps are requested again.
+	fn unsent_datagram_restore(&mut self, datagram: &Datagram, now: Time_t) {
+		for &(sn, status) in &datagram.changes {
+			if let Some(cfr) = self.changes_for_reader.iter_mut().find(|cfr| cfr.change.sequence_number == sn) {
+				cfr.status = status;
+				cfr.sent_time = None;
+			}
+		}
+		for &sn in &datagram.gaps {
+			if let Err(i) = self.requested_gaps.binary_search(&sn) {
+				self.requested_gaps.insert(i, sn);
+			}
+		}
+		let requested = datagram.changes.iter().any(|&(_, status)| status == ChangeForReaderStatusKind::REQUESTED);
+		if requested || !datagram.gaps.is_empty() {
+			self.repair_time.get_or_insert(now);
 		}
 	}
 
@@ -260,7 +721,7 @@ impl ReaderProxy {
 	/// not been sent to the RTPS Reader represented by the ReaderProxy.
 	pub fn unsent_changes(&self) -> Vec<&ChangeForReader> {
 		self.changes_for_reader.iter()
-			.filter(|ref cfr| cfr.status == ChangeForReaderStatusKind::UNSENT)
+			.filter(|cfr| cfr.status == ChangeForReaderStatusKind::UNSENT)
 			.collect()
 	}
 
@@ -270,7 +731,7 @@ impl ReaderProxy {
 	/// ReaderProxy.
 	pub fn unacked_changes(&self) -> Vec<&ChangeForReader> {
 		self.changes_for_reader.iter()
-			.filter(|ref cfr| cfr.status == ChangeForReaderStatusKind::UNACKNOWLEDGED)
+			.filter(|cfr| cfr.status == ChangeForReaderStatusKind::UNACKNOWLEDGED)
 			.collect()
 	}
 
@@ -295,7 +756,7 @@ impl ReaderProxy {
 			let r = req_seq_num_set.iter()
 						.find(|sn| cfr.change.sequence_number == **sn);
 
-			if r.is_some() == true {
+			if r.is_some() {
 				cfr.status = ChangeForReaderStatusKind::REQUESTED;
 			}
 		}
@@ -307,7 +768,7 @@ impl ReaderProxy {
 	/// ACKNACK Message.
 	pub fn requested_changes(&self) -> Vec<&ChangeForReader> {
 		self.changes_for_reader.iter()
-			.filter(|ref cfr| cfr.status == ChangeForReaderStatusKind::REQUESTED)
+			.filter(|cfr| cfr.status == ChangeForReaderStatusKind::REQUESTED)
 			.collect()
 	}
 
@@ -317,7 +778,7 @@ impl ReaderProxy {
 	/// represented by the ReaderProxy.
 	pub fn next_unsent_change(&self) -> Option<&ChangeForReader> {
 		self.changes_for_reader.iter()
-			.find(|ref cfr| cfr.status == ChangeForReaderStatusKind::UNSENT)
+			.find(|cfr| cfr.status == ChangeForReaderStatusKind::UNSENT)
 	}
 
 	/// This operation returns the ChangeForReader for the ReaderProxy that has
@@ -327,6 +788,22 @@ impl ReaderProxy {
 	/// message (see 8.3.7.1) from the Reader.
 	pub fn next_requested_change(&self) -> Option<&ChangeForReader> {
 		self.changes_for_reader.iter()
-			.find(|ref cfr| cfr.status == ChangeForReaderStatusKind::REQUESTED)
+			.find(|cfr| cfr.status == ChangeForReaderStatusKind::REQUESTED)
+	}
+}
+
+impl ChangeForReader {
+	pub fn status(&self) -> ChangeForReaderStatusKind {
+		self.status
+	}
+
+	/// False if the change was filtered out for the reader, in which case
+	/// a Gap is sent in it's place.
+	pub fn is_relevant(&self) -> bool {
+		self.is_relivant
+	}
+
+	pub fn change(&self) -> &Arc<CacheChange> {
+		&self.change
 	}
 }
@@ -4,8 +4,8 @@
 
  Entity is the top level structure representing any 'actor' within RTPS. 
  */
-mod endpoint;
-mod participant;
+pub mod endpoint;
+pub mod participant;
 
 use rtps::guid::*;
 
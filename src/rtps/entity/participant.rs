@@ -25,5 +25,5 @@ pub trait Participant: Entity {
 	fn vendor_id(&self) -> &VendorId_t;
 
 	/// Lists all endpoints contained within this participant.
-	fn endpoints(&self) -> Vec<Arc<Endpoint>>;
+	fn endpoints(&self) -> Vec<Arc<dyn Endpoint>>;
 }
\ No newline at end of file
@@ -9,6 +9,7 @@ The HistoryCache is part of the interface between DDS and RTPS. In other words,
 use std::sync::Arc;
 use rtps::cache_change::CacheChange;
 use rtps::SequenceNumber_t;
+use rtps::guid::GUID_t;
 
 pub struct HistoryCache {
 	changes: Vec<Arc<CacheChange>>,
@@ -16,6 +17,7 @@ pub struct HistoryCache {
 
 impl HistoryCache {
 	/// Generates a new HistoryCache with an empty vector of CacheChange(s).
+	#[allow(clippy::new_without_default)]
 	pub fn new() -> HistoryCache {
 		HistoryCache{changes: Vec::new()}
 	}
@@ -36,18 +38,20 @@ impl HistoryCache {
 
 	/// Get the minimum sequence number in the history cache.
 	pub fn get_seq_num_min(&self) -> Option<SequenceNumber_t> {
-		match self.changes.first() {
-			Some(c) => Some(c.sequence_number),
-			None => None,
-		}
+		self.changes.first().map(|c| c.sequence_number)
 	}
 
 	/// Get the maximum sequence number in the history cache.
 	pub fn get_seq_num_max(&self) -> Option<SequenceNumber_t> {
-		match self.changes.last() {
-			Some(c) => Some(c.sequence_number),
-			None => None,
-		}
+		self.changes.last().map(|c| c.sequence_number)
+	}
+
+	/// Get the change made by the writer identified by writer_guid with the
+	/// given sequence number, if the cache contains it.
+	pub fn get_change(&self, writer_guid: &GUID_t, sequence_number: SequenceNumber_t) -> Option<Arc<CacheChange>> {
+		self.changes.iter()
+			.find(|c| c.writer_guid == *writer_guid && c.sequence_number == sequence_number)
+			.cloned()
 	}
 
 	/// Get a references to the changes.
@@ -0,0 +1,173 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Packs submessages into as few datagrams as possible.
+
+ Every datagram starts with the message header, followed by as many
+ submessages as fit within the maximum datagram size. The builder keeps track
+ of the destination GUID prefix and source timestamp a receiver will be using
+ at each point in the datagram (see `receiver`), and only inserts an
+ InfoDestination or InfoTimestamp when the value the caller asked for differs
+ from it. As receivers reset their state at the start of each message, these
+ are repeated in a new datagram when needed.
+ */
+use std::error;
+use std::fmt;
+use bytes::{Bytes, BytesMut};
+
+use rtps::guid::*;
+use rtps::messages::header::Header;
+use rtps::messages::encoder::*;
+use rtps::messages::submessage::{Time_t, TIME_ZERO};
+use rtps::messages::submessage::info_destination::InfoDestination;
+use rtps::messages::submessage::info_timestamp::InfoTimestamp;
+
+/// Largest UDP payload which fits within a 1500 byte ethernet frame, without
+/// IP fragmentation (i.e. 1500 - 20 bytes of IPv4 header - 8 bytes of UDP
+/// header).
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 1472;
+
+/// Describes why a submessage could not be added to a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+	/// The submessage (along with the message header, and any InfoDestination
+	/// or InfoTimestamp it depends on) is larger than the maximum datagram
+	/// size, and should be sent as fragments instead.
+	SubmessageTooLarge {size: usize, max_size: usize},
+}
+
+impl fmt::Display for BuildError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			BuildError::SubmessageTooLarge{size, max_size} =>
+				write!(f, "submessage needs {} bytes but messages are limited to {}", size, max_size),
+		}
+	}
+}
+
+impl error::Error for BuildError {}
+
+/// Builds a sequence of ready-to-send datagrams, all sharing the same message
+/// header.
+pub struct MessageBuilder {
+	header: Header,
+	max_size: usize,
+	messages: Vec<Bytes>,
+	current: BytesMut,
+	/// Destination and timestamp requested for the next submessage.
+	destination: GUIDPrefix_t,
+	timestamp: Option<Time_t>,
+	/// Destination and timestamp in effect at the end of current.
+	current_destination: GUIDPrefix_t,
+	current_timestamp: Option<Time_t>,
+}
+
+impl MessageBuilder {
+	/// Creates a builder limited to DEFAULT_MAX_MESSAGE_SIZE byte datagrams.
+	pub fn new(header: Header) -> MessageBuilder {
+		MessageBuilder::with_max_size(header, DEFAULT_MAX_MESSAGE_SIZE)
+	}
+
+	/// Creates a builder limited to max_size byte datagrams.
+	pub fn with_max_size(header: Header, max_size: usize) -> MessageBuilder {
+		MessageBuilder {
+			header,
+			max_size,
+			messages: Vec::new(),
+			current: BytesMut::new(),
+			destination: GUIDPREFIX_UNKNOWN,
+			timestamp: None,
+			current_destination: GUIDPREFIX_UNKNOWN,
+			current_timestamp: None
+		}
+	}
+
+	/// Participant the submessages added from now on are addressed to.
+	/// GUIDPREFIX_UNKNOWN (the default) addresses whichever participant
+	/// receives the message.
+	pub fn set_destination(&mut self, guid_prefix: GUIDPrefix_t) {
+		self.destination = guid_prefix
+	}
+
+	/// Source timestamp of the submessages added from now on. None (the
+	/// default) means they have no timestamp.
+	pub fn set_timestamp(&mut self, timestamp: Option<Time_t>) {
+		self.timestamp = timestamp
+	}
+
+	/// Appends a submessage, preceeded by an InfoDestination and/or
+	/// InfoTimestamp if either has changed. A new datagram is started if the
+	/// submessage doesn't fit within the current one, or after it if it's
+	/// contents are too long for anything to follow it (see
+	/// `encoder::MAX_SUBMESSAGE_LENGTH`).
+	pub fn add(&mut self, submessage: &dyn Encode) -> Result<(), BuildError> {
+		let mut encoded = BytesMut::new();
+		submessage.encode(&mut Encoder::new(&mut encoded));
+
+		let mut context = self.context();
+		if !self.current.is_empty() && self.current.len() + context.len() + encoded.len() > self.max_size {
+			self.flush();
+			context = self.context();
+		}
+
+		let header_len = if self.current.is_empty() {self.header.size() as usize} else {0};
+		let size = self.current.len() + header_len + context.len() + encoded.len();
+		if size > self.max_size {
+			return Err(BuildError::SubmessageTooLarge {size, max_size: self.max_size});
+		}
+
+		if self.current.is_empty() {
+			self.header.encode(&mut Encoder::new(&mut self.current));
+		}
+		self.current.extend_from_slice(&context);
+		self.current.extend_from_slice(&encoded);
+		self.current_destination = self.destination;
+		self.current_timestamp = self.timestamp;
+		if encoded.len() - 4 > MAX_SUBMESSAGE_LENGTH {
+			self.flush();
+		}
+		Ok(())
+	}
+
+	/// Ends the current datagram, if anything has been added to it. The next
+	/// submessage will start a new datagram.
+	pub fn flush(&mut self) {
+		if !self.current.is_empty() {
+			let msg = self.current.split_off(0).freeze();
+			self.messages.push(msg);
+		}
+		self.current_destination = GUIDPREFIX_UNKNOWN;
+		self.current_timestamp = None;
+	}
+
+	/// The number of datagrams started so far, including the current one.
+	/// The last submessage added is within the last of them.
+	pub fn datagrams_started(&self) -> usize {
+		self.messages.len() + if self.current.is_empty() {0} else {1}
+	}
+
+	/// Ends the current datagram and returns every datagram built so far, in
+	/// the order they should be sent.
+	pub fn finish(mut self) -> Vec<Bytes> {
+		self.flush();
+		self.messages
+	}
+
+	/// The InfoDestination and InfoTimestamp needed (if any) to move the
+	/// receiver from the state in effect to the requested state.
+	fn context(&self) -> BytesMut {
+		let mut buf = BytesMut::new();
+		{
+			let mut enc = Encoder::new(&mut buf);
+			if self.destination != self.current_destination {
+				InfoDestination::new(self.destination).encode(&mut enc);
+			}
+			if self.timestamp != self.current_timestamp {
+				match self.timestamp {
+					Some(t) => InfoTimestamp::new(false, t),
+					None => InfoTimestamp::new(true, TIME_ZERO),
+				}.encode(&mut enc);
+			}
+		}
+		buf
+	}
+}
@@ -0,0 +1,191 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Serializes RTPS messages into their wire representation.
+
+ A message is written as the fixed 20 byte message header followed by any
+ number of submessages. Each submessage is written in the byte order selected
+ by the E flag of its own SubmessageHeader, so a single datagram may freely
+ mix big and little endian submessages (see RTPS v2.2 section 9.4.5.1.2). The
+ message header itself only contains octet arrays and is not affected by
+ endianness.
+ */
+use std::error;
+use std::fmt;
+use bytes::BytesMut;
+use rtps::messages::header::Header;
+use rtps::messages::submessage::header::*;
+
+/// Longest submessage contents the octetsToNextHeader field can describe.
+/// Longer contents are only allowed in the last submessage of a message (see
+/// RTPS v2.2 section 9.4.5.1.3).
+pub const MAX_SUBMESSAGE_LENGTH: usize = 0xffff;
+
+/// Describes why a message could not be written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+	/// The submessage at index has more than MAX_SUBMESSAGE_LENGTH bytes of
+	/// contents, but isn't the last submessage of the message.
+	SubmessageTooLong {index: usize, length: usize},
+}
+
+impl fmt::Display for EncodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			EncodeError::SubmessageTooLong{index, length} =>
+				write!(f, "submessage {} has {} bytes of contents, but only the last submessage may exceed {}", index, length, MAX_SUBMESSAGE_LENGTH),
+		}
+	}
+}
+
+impl error::Error for EncodeError {}
+
+/// Appends primitive values to a `BytesMut`, in the byte order of the
+/// submessage currently being written.
+pub struct Encoder<'a> {
+	buf: &'a mut BytesMut,
+	endianness: Endianness,
+}
+
+/// Implemented by every header, submessage and submessage element which can
+/// be written to the wire.
+pub trait Encode {
+	/// Append the wire representation of self to the encoder.
+	fn encode(&self, enc: &mut Encoder);
+}
+
+impl<'a> Encoder<'a> {
+	/// Creates an encoder which appends to the end of buf. Values are written
+	/// in big endian until a submessage header selects otherwise.
+	pub fn new(buf: &'a mut BytesMut) -> Encoder<'a> {
+		Encoder {buf, endianness: Endianness::BigEndian}
+	}
+
+	/// Byte order used for multi-byte primitives.
+	pub fn endianness(&self) -> Endianness {
+		self.endianness
+	}
+
+	/// Override the byte order used for multi-byte primitives.
+	pub fn set_endianness(&mut self, endianness: Endianness) {
+		self.endianness = endianness
+	}
+
+	/// Number of bytes within the underlying buffer.
+	pub fn len(&self) -> usize {
+		self.buf.len()
+	}
+
+	/// True if nothing has been written to the underlying buffer.
+	pub fn is_empty(&self) -> bool {
+		self.buf.is_empty()
+	}
+
+	pub fn put_u8(&mut self, v: u8) {
+		self.buf.extend_from_slice(&[v])
+	}
+
+	pub fn put_u16(&mut self, v: u16) {
+		match self.endianness {
+			Endianness::BigEndian => self.buf.extend_from_slice(&v.to_be_bytes()),
+			Endianness::LittleEndian => self.buf.extend_from_slice(&v.to_le_bytes()),
+		}
+	}
+
+	pub fn put_i16(&mut self, v: i16) {
+		self.put_u16(v as u16)
+	}
+
+	pub fn put_u32(&mut self, v: u32) {
+		match self.endianness {
+			Endianness::BigEndian => self.buf.extend_from_slice(&v.to_be_bytes()),
+			Endianness::LittleEndian => self.buf.extend_from_slice(&v.to_le_bytes()),
+		}
+	}
+
+	pub fn put_i32(&mut self, v: i32) {
+		self.put_u32(v as u32)
+	}
+
+	/// Copies the octets as-is, regardless of endianness.
+	pub fn put_slice(&mut self, v: &[u8]) {
+		self.buf.extend_from_slice(v)
+	}
+
+	/// Appends zeros until the number of bytes written since origin is a
+	/// multiple of alignment.
+	pub fn align(&mut self, origin: usize, alignment: usize) {
+		let written = self.buf.len() - origin;
+		let padding = (alignment - (written % alignment)) % alignment;
+		for _ in 0..padding {
+			self.put_u8(0)
+		}
+	}
+
+	/// Writes the SubmessageHeader, switching the encoder to the byte order
+	/// given by it's E flag.
+	///
+	/// The length field is written as a placeholder, returning the offset of
+	/// the first byte of the submessage contents, which must be handed back
+	/// to `end_submessage` once the contents have been written.
+	pub fn begin_submessage(&mut self, header: &SubmessageHeader) -> usize {
+		self.endianness = header.endianness();
+		self.put_u8(header.submessage_id);
+		self.put_u8(header.flags);
+		self.put_u16(0);
+		self.buf.len()
+	}
+
+	/// Pads the submessage contents so the next submessage header starts on a
+	/// 4 byte boundry, then back-fills the octetsToNextHeader field of the
+	/// SubmessageHeader.
+	///
+	/// Contents longer than MAX_SUBMESSAGE_LENGTH are given a length of 0,
+	/// meaning they extend to the end of the message, so the submessage must
+	/// be the last one written.
+	pub fn end_submessage(&mut self, body_start: usize) {
+		self.align(body_start, 4);
+		let len = self.buf.len() - body_start;
+		let len = if len > MAX_SUBMESSAGE_LENGTH {0} else {len as u16};
+		self.put_u16_at(body_start - 2, len);
+	}
+
+	/// Overwrites two previously written bytes at offset, used to fill in a
+	/// length once the contents it describes have been written.
+	pub fn put_u16_at(&mut self, offset: usize, v: u16) {
+		let b = match self.endianness {
+			Endianness::BigEndian => v.to_be_bytes(),
+			Endianness::LittleEndian => v.to_le_bytes(),
+		};
+		self.buf[offset..offset + 2].copy_from_slice(&b);
+	}
+}
+
+/// Writes a complete RTPS message (i.e. the contents of a single UDP datagram)
+/// to the end of buf.
+///
+/// The message header is followed by every submessage, in order. Only the
+/// last submessage may have more than MAX_SUBMESSAGE_LENGTH bytes of
+/// contents; if any other does, nothing is written to buf.
+pub fn encode_message(header: &Header,
+					  submessages: &[&dyn Encode],
+					  buf: &mut BytesMut) -> Result<(), EncodeError> {
+	let origin = buf.len();
+	let result = {
+		let mut enc = Encoder::new(buf);
+		header.encode(&mut enc);
+
+		submessages.iter().enumerate().try_for_each(|(index, s)| {
+			let start = enc.len();
+			s.encode(&mut enc);
+			let length = enc.len() - start - 4;
+			if length > MAX_SUBMESSAGE_LENGTH && index + 1 < submessages.len() {
+				return Err(EncodeError::SubmessageTooLong {index, length});
+			}
+			Ok(())
+		})
+	};
+	if result.is_err() {
+		buf.truncate(origin);
+	}
+	result
+}
@@ -4,6 +4,8 @@
  */
 use rtps::*;
 use rtps::guid::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 /// Inserted as the first bytes of any UDP datagram. 
 ///
@@ -14,9 +16,74 @@ use rtps::guid::*;
 /// vender ID represents a unique ID for this spific library (assigned by the
 /// OMG). The GUID prefix is a 12 byte value which allows all other GUIDs 
 /// within the messsage to be expressed as 4 byte entity IDs.
+#[derive(Clone, Debug)]
 pub struct Header {
 	protocol_id: [u8; 4],
 	protocol_version: ProtocolVersion_t,
 	vendor_id: VendorId_t,
 	guid_prefix: GUIDPrefix_t,
 }
+
+/// The protocol_id shared by every RTPS message.
+pub const PROTOCOL_RTPS: [u8; 4] = [b'R', b'T', b'P', b'S'];
+
+impl Header {
+	/// Creates a message header for messages sent by the participant owning
+	/// guid_prefix, using this libraries PROTOCOL_VERSION and vendor id.
+	pub fn new (guid_prefix: GUIDPrefix_t) -> Header {
+		Header {
+			protocol_id: PROTOCOL_RTPS,
+			protocol_version: PROTOCOL_VERSION,
+			vendor_id: VENDORID_UNKNOWN,
+			guid_prefix
+		}
+	}
+
+	/// The header is always 20 bytes.
+	pub fn size (&self) -> u16 {
+		20
+	}
+
+	/// Should always be 'R' 'T' 'P' 'S', unless the message is invalid.
+	pub fn protocol_id(&self) -> [u8; 4] {
+		self.protocol_id
+	}
+
+	/// Version of the protocol used by the sender of the message.
+	pub fn protocol_version(&self) -> &ProtocolVersion_t {
+		&self.protocol_version
+	}
+
+	/// Vendor of the middleware which sent the message.
+	pub fn vendor_id(&self) -> &VendorId_t {
+		&self.vendor_id
+	}
+
+	/// GUID prefix of the participant which sent the message.
+	pub fn guid_prefix(&self) -> &GUIDPrefix_t {
+		&self.guid_prefix
+	}
+}
+
+impl Encode for Header {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_slice(&self.protocol_id);
+		self.protocol_version.encode(enc);
+		self.vendor_id.encode(enc);
+		self.guid_prefix.encode(enc);
+	}
+}
+
+impl Decode for Header {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let mut protocol_id = [0u8; 4];
+		dec.get_octets(&mut protocol_id)?;
+
+		Ok(Header {
+			protocol_id,
+			protocol_version: ProtocolVersion_t::decode(dec)?,
+			vendor_id: VendorId_t::decode(dec)?,
+			guid_prefix: GUIDPrefix_t::decode(dec)?
+		})
+	}
+}
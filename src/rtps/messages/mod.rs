@@ -4,4 +4,17 @@
  */
 
  pub mod submessage;
- pub mod header;
\ No newline at end of file
+ pub mod header;
+ pub mod encoder;
+ pub mod parser;
+ pub mod receiver;
+ pub mod builder;
+
+use rtps::messages::header::Header;
+use rtps::messages::submessage::Submessage;
+
+/// A complete RTPS message, as received within a single UDP datagram.
+pub struct Message {
+	pub header: Header,
+	pub submessages: Vec<Submessage>,
+}
\ No newline at end of file
@@ -0,0 +1,356 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Parses the contents of a UDP datagram into an RTPS message.
+
+ The parser follows the rules for interpreting messages layed out in RTPS v2.2
+ section 8.3.4.1:
+
+ - A message whose header is too short, which does not start with 'R' 'T' 'P'
+   'S', or which uses an incompatible major protocol version is invalid in its
+   entirety.
+ - A submessage whose octetsToNextHeader points past the end of the message
+   invalidates the remainder of the message.
+ - A submessage with an unknown submessage_id is returned as-is, as an
+   UnknownSubmessage, so it can be skipped or forwarded.
+ - A submessage whose contents are invalid is ignored, but parsing continues
+   with the next submessage.
+
+ Serialized payloads are not copied. They are handed out as slices sharing
+ the `Bytes` the datagram was received into (although the bytes crate may
+ choose to store very small slices inline).
+ */
+use std::error;
+use std::fmt;
+use bytes::Bytes;
+
+use rtps::*;
+use rtps::messages::Message;
+use rtps::messages::header::{Header, PROTOCOL_RTPS};
+use rtps::messages::submessage::*;
+use rtps::messages::submessage::header::*;
+use rtps::messages::submessage::ack_nack::AckNack;
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::data_frag::DataFragment;
+use rtps::messages::submessage::gap::Gap;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use rtps::messages::submessage::heartbeat_frag::HeartbeatFrag;
+use rtps::messages::submessage::info_destination::InfoDestination;
+use rtps::messages::submessage::info_reply::InfoReply;
+use rtps::messages::submessage::info_reply_ip4::InfoReplyIp4;
+use rtps::messages::submessage::info_source::InfoSource;
+use rtps::messages::submessage::info_timestamp::InfoTimestamp;
+use rtps::messages::submessage::nack_frag::NackFrag;
+use rtps::messages::submessage::pad::Pad;
+use rtps::messages::submessage::unknown::UnknownSubmessage;
+
+/// Describes why a message, or part of a message, could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+	/// Fewer bytes remained than were needed to decode an element.
+	UnexpectedEnd {needed: usize, remaining: usize},
+	/// The message did not start with 'R' 'T' 'P' 'S'.
+	InvalidProtocolId([u8; 4]),
+	/// The message was sent using an incompatible version of the protocol.
+	UnsupportedVersion(ProtocolVersion_t),
+	/// octetsToNextHeader of a submessage points past the end of the message.
+	InvalidSubmessageLength {submessage_id: u8, length: u16, remaining: usize},
+	/// The contents of a submessage violate the RTPS specification.
+	InvalidSubmessage {submessage_id: u8, reason: &'static str},
+	/// A submessage element violates the RTPS specification.
+	InvalidElement(&'static str),
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ParseError::UnexpectedEnd{needed, remaining} =>
+				write!(f, "needed {} bytes but only {} remain", needed, remaining),
+			ParseError::InvalidProtocolId(id) =>
+				write!(f, "invalid protocol id {:?}", id),
+			ParseError::UnsupportedVersion(v) =>
+				write!(f, "unsupported protocol version {}.{}", v.major, v.minor),
+			ParseError::InvalidSubmessageLength{submessage_id, length, remaining} =>
+				write!(f, "submessage 0x{:02x} has length {} but only {} bytes remain",
+					submessage_id, length, remaining),
+			ParseError::InvalidSubmessage{submessage_id, reason} =>
+				write!(f, "invalid submessage 0x{:02x}: {}", submessage_id, reason),
+			ParseError::InvalidElement(reason) =>
+				write!(f, "invalid submessage element: {}", reason),
+		}
+	}
+}
+
+impl error::Error for ParseError {}
+
+/// Reads primitive values out of a `Bytes` buffer, in the byte order of the
+/// submessage being decoded.
+pub struct Decoder {
+	buf: Bytes,
+	pos: usize,
+	endianness: Endianness,
+}
+
+/// Implemented by every submessage element which can be read from the wire.
+pub trait Decode: Sized {
+	/// Read the wire representation of Self from the decoder.
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError>;
+}
+
+/// Implemented by every submessage which can be read from the wire.
+///
+/// The SubmessageHeader has already been read by the time the contents are
+/// decoded, and the decoder only contains the contents of this submessage.
+pub trait DecodeSubmessage: Sized {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError>;
+}
+
+impl Decoder {
+	pub fn new(buf: Bytes, endianness: Endianness) -> Decoder {
+		Decoder {buf, pos: 0, endianness}
+	}
+
+	/// Byte order used for multi-byte primitives.
+	pub fn endianness(&self) -> Endianness {
+		self.endianness
+	}
+
+	/// Number of bytes consumed so far.
+	pub fn position(&self) -> usize {
+		self.pos
+	}
+
+	/// Number of bytes left to decode.
+	pub fn remaining(&self) -> usize {
+		self.buf.len() - self.pos
+	}
+
+	fn check(&self, needed: usize) -> Result<(), ParseError> {
+		if self.remaining() < needed {
+			Err(ParseError::UnexpectedEnd {needed, remaining: self.remaining()})
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Copies octets into dst, regardless of endianness.
+	pub fn get_octets(&mut self, dst: &mut [u8]) -> Result<(), ParseError> {
+		self.check(dst.len())?;
+		dst.copy_from_slice(&self.buf[self.pos..self.pos + dst.len()]);
+		self.pos += dst.len();
+		Ok(())
+	}
+
+	/// Returns the next len bytes without copying them.
+	pub fn get_bytes(&mut self, len: usize) -> Result<Bytes, ParseError> {
+		self.check(len)?;
+		let b = self.buf.slice(self.pos, self.pos + len);
+		self.pos += len;
+		Ok(b)
+	}
+
+	/// Skips over the next len bytes.
+	pub fn skip(&mut self, len: usize) -> Result<(), ParseError> {
+		self.check(len)?;
+		self.pos += len;
+		Ok(())
+	}
+
+	/// Skips padding until the position is a multiple of alignment.
+	pub fn align(&mut self, alignment: usize) -> Result<(), ParseError> {
+		let padding = (alignment - (self.pos % alignment)) % alignment;
+		self.skip(padding)
+	}
+
+	pub fn get_u8(&mut self) -> Result<u8, ParseError> {
+		let mut b = [0u8; 1];
+		self.get_octets(&mut b)?;
+		Ok(b[0])
+	}
+
+	pub fn get_u16(&mut self) -> Result<u16, ParseError> {
+		let mut b = [0u8; 2];
+		self.get_octets(&mut b)?;
+		Ok(match self.endianness {
+			Endianness::BigEndian => u16::from_be_bytes(b),
+			Endianness::LittleEndian => u16::from_le_bytes(b),
+		})
+	}
+
+	pub fn get_i16(&mut self) -> Result<i16, ParseError> {
+		self.get_u16().map(|v| v as i16)
+	}
+
+	pub fn get_u32(&mut self) -> Result<u32, ParseError> {
+		let mut b = [0u8; 4];
+		self.get_octets(&mut b)?;
+		Ok(match self.endianness {
+			Endianness::BigEndian => u32::from_be_bytes(b),
+			Endianness::LittleEndian => u32::from_le_bytes(b),
+		})
+	}
+
+	pub fn get_i32(&mut self) -> Result<i32, ParseError> {
+		self.get_u32().map(|v| v as i32)
+	}
+}
+
+/// Iterates over the submessages within a single RTPS message.
+///
+/// Each item is either a submessage, or the reason a submessage was ignored.
+/// Submessages with an unknown submessage_id are returned as
+/// Submessage::Unknown.
+/// Once an error invalidating the remainder of the message has been returned,
+/// iteration stops.
+pub struct MessageParser {
+	header: Header,
+	buf: Bytes,
+	pos: usize,
+}
+
+impl MessageParser {
+	/// Validates the message header at the start of the datagram.
+	pub fn new(datagram: Bytes) -> Result<MessageParser, ParseError> {
+		let header = {
+			let mut dec = Decoder::new(datagram.clone(), Endianness::BigEndian);
+			Header::decode(&mut dec)?
+		};
+
+		if header.protocol_id() != PROTOCOL_RTPS {
+			return Err(ParseError::InvalidProtocolId(header.protocol_id()));
+		}
+
+		if header.protocol_version().major != PROTOCOL_VERSION.major {
+			return Err(ParseError::UnsupportedVersion(*header.protocol_version()));
+		}
+
+		Ok(MessageParser {header, buf: datagram, pos: 20})
+	}
+
+	/// The message header.
+	pub fn header(&self) -> &Header {
+		&self.header
+	}
+}
+
+impl Iterator for MessageParser {
+	type Item = Result<Submessage, ParseError>;
+
+	fn next(&mut self) -> Option<Result<Submessage, ParseError>> {
+		let remaining = self.buf.len() - self.pos;
+		if remaining == 0 {
+			return None;
+		}
+
+		if remaining < 4 {
+			self.pos = self.buf.len();
+			return Some(Err(ParseError::UnexpectedEnd {needed: 4, remaining}));
+		}
+
+		let id = self.buf[self.pos];
+		let flags = self.buf[self.pos + 1];
+		let len_bytes = [self.buf[self.pos + 2], self.buf[self.pos + 3]];
+		let mut header = SubmessageHeader {
+			submessage_id: id,
+			flags,
+			submessage_length: 0,
+		};
+		let length = match header.endianness() {
+			Endianness::BigEndian => u16::from_be_bytes(len_bytes),
+			Endianness::LittleEndian => u16::from_le_bytes(len_bytes),
+		};
+		header.submessage_length = length;
+
+		let body_start = self.pos + 4;
+		let body_remaining = remaining - 4;
+
+		// A length of zero means the submessage extends to the end of the
+		// message, unless the submessage can legitimately be empty.
+		let body_len = if length == 0
+			&& id != SubmessageKind::Pad as u8
+			&& id != SubmessageKind::InfoTimestamp as u8 {
+			body_remaining
+		} else {
+			length as usize
+		};
+
+		if body_len > body_remaining {
+			self.pos = self.buf.len();
+			return Some(Err(ParseError::InvalidSubmessageLength {
+				submessage_id: id,
+				length,
+				remaining: body_remaining
+			}));
+		}
+
+		self.pos = body_start + body_len;
+		let body = self.buf.slice(body_start, body_start + body_len);
+		Some(Submessage::decode(header, body))
+	}
+}
+
+/// Parses an entire datagram, keeping only the valid submessages.
+///
+/// Invalid submessages are dropped, as required by RTPS v2.2 section 8.3.4.1.
+/// An error is only returned when the message header itself is invalid.
+pub fn parse_message(datagram: Bytes) -> Result<Message, ParseError> {
+	let parser = MessageParser::new(datagram)?;
+	let header = parser.header().clone();
+	let submessages = parser.filter_map(|r| r.ok()).collect();
+
+	Ok(Message {header, submessages})
+}
+
+impl Submessage {
+	/// Decodes the contents of a submessage given it's header.
+	///
+	/// Submessages with a submessage_id not understood by this implementation
+	/// are returned as Submessage::Unknown.
+	pub fn decode(header: SubmessageHeader, body: Bytes) -> Result<Submessage, ParseError> {
+		let mut dec = Decoder::new(body, header.endianness());
+		let id = header.submessage_id;
+
+		let r = if id == SubmessageKind::Pad as u8 {
+			Pad::decode(header, &mut dec).map(Submessage::Pad)
+		} else if id == SubmessageKind::AckNack as u8 {
+			AckNack::decode(header, &mut dec).map(Submessage::AckNack)
+		} else if id == SubmessageKind::Heartbeat as u8 {
+			Heartbeat::decode(header, &mut dec).map(Submessage::Heartbeat)
+		} else if id == SubmessageKind::Gap as u8 {
+			Gap::decode(header, &mut dec).map(Submessage::Gap)
+		} else if id == SubmessageKind::InfoTimestamp as u8 {
+			InfoTimestamp::decode(header, &mut dec).map(Submessage::InfoTimestamp)
+		} else if id == SubmessageKind::InfoSource as u8 {
+			InfoSource::decode(header, &mut dec).map(Submessage::InfoSource)
+		} else if id == SubmessageKind::InfoDestination as u8 {
+			InfoDestination::decode(header, &mut dec).map(Submessage::InfoDestination)
+		} else if id == SubmessageKind::InfoReply as u8 {
+			InfoReply::decode(header, &mut dec).map(Submessage::InfoReply)
+		} else if id == SubmessageKind::InfoReplyIp4 as u8 {
+			InfoReplyIp4::decode(header, &mut dec).map(Submessage::InfoReplyIp4)
+		} else if id == SubmessageKind::NackFrag as u8 {
+			NackFrag::decode(header, &mut dec).map(Submessage::NackFrag)
+		} else if id == SubmessageKind::HeartbeatFrag as u8 {
+			HeartbeatFrag::decode(header, &mut dec).map(Submessage::HeartbeatFrag)
+		} else if id == SubmessageKind::Data as u8 {
+			Data::decode(header, &mut dec).map(Submessage::Data)
+		} else if id == SubmessageKind::DataFrag as u8 {
+			DataFragment::decode(header, &mut dec).map(Submessage::DataFrag)
+		} else {
+			UnknownSubmessage::decode(header, &mut dec).map(Submessage::Unknown)
+		};
+
+		// Errors from within the submessage are reported against the
+		// submessage as a whole.
+		r.map_err(|e| match e {
+			ParseError::UnexpectedEnd{..} => ParseError::InvalidSubmessage {
+				submessage_id: id,
+				reason: "submessage is shorter than it's contents"
+			},
+			ParseError::InvalidElement(reason) => ParseError::InvalidSubmessage {
+				submessage_id: id,
+				reason
+			},
+			e => e,
+		})
+	}
+}
@@ -0,0 +1,172 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Interprets the submessages within a received RTPS message (see RTPS v2.2
+ section 8.3.4).
+
+ The meaning of an entity submessage (Data, Heartbeat, AckNack, etc.) depends
+ on the submessages that preceed it within the same message. Interpreter
+ submessages (InfoSource, InfoDestination, InfoReply and InfoTimestamp) modify
+ the state of the receiver, which in turn is used to resolve the full GUIDs of
+ the endpoints, the source timestamp and where replies should be sent for the
+ entity submessages that follow.
+ */
+use std::net::SocketAddr;
+
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::Message;
+use rtps::messages::submessage::*;
+
+/// An entity submessage, along with the receiver state which applied to it.
+pub struct Received<'a> {
+	/// GUID of the local endpoint the submessage is addressed to. When the
+	/// entity id is ENTITYID_UNKNOWN, the submessage applies to all local
+	/// readers matched with the source writer.
+	pub destination: GUID_t,
+	/// GUID of the remote endpoint which sent the submessage.
+	pub source: GUID_t,
+	pub source_version: ProtocolVersion_t,
+	pub source_vendor_id: VendorId_t,
+	/// Time the submessage was sent, if the sender provided one.
+	pub timestamp: Option<Time_t>,
+	/// Where replies to the submessage should be sent.
+	pub unicast_reply_locator_list: &'a [Locator_t],
+	pub multicast_reply_locator_list: &'a [Locator_t],
+	pub submessage: Submessage,
+}
+
+/// Receiver state, reset at the start of every message.
+pub struct MessageReceiver {
+	local_guid_prefix: GUIDPrefix_t,
+	source_version: ProtocolVersion_t,
+	source_vendor_id: VendorId_t,
+	source_guid_prefix: GUIDPrefix_t,
+	dest_guid_prefix: GUIDPrefix_t,
+	unicast_reply_locator_list: Vec<Locator_t>,
+	multicast_reply_locator_list: Vec<Locator_t>,
+	timestamp: Option<Time_t>,
+}
+
+impl MessageReceiver {
+	/// Creates a receiver for the participant identified by
+	/// local_guid_prefix. Entity submessages addressed to other participants
+	/// are ignored.
+	pub fn new(local_guid_prefix: GUIDPrefix_t) -> MessageReceiver {
+		MessageReceiver {
+			local_guid_prefix,
+			source_version: PROTOCOL_VERSION,
+			source_vendor_id: VENDORID_UNKNOWN,
+			source_guid_prefix: GUIDPREFIX_UNKNOWN,
+			dest_guid_prefix: local_guid_prefix,
+			unicast_reply_locator_list: Vec::new(),
+			multicast_reply_locator_list: Vec::new(),
+			timestamp: None
+		}
+	}
+
+	/// Process all submessages within msg, in order. Each entity submessage
+	/// addressed to this participant is handed to dispatch.
+	///
+	/// source is the address the message was received from. It is used as
+	/// the default unicast reply locator (with an invalid port), until an
+	/// InfoReply says otherwise.
+	pub fn receive<F>(&mut self, msg: Message, source: &SocketAddr, mut dispatch: F)
+					where F: FnMut(Received) {
+		self.reset(&msg, source);
+
+		for submessage in msg.submessages {
+			match submessage {
+				Submessage::InfoSource(s) => {
+					self.source_version = s.protocol_version;
+					self.source_vendor_id = s.vendor_id;
+					self.source_guid_prefix = s.guid_prefix;
+					self.unicast_reply_locator_list.clear();
+					self.multicast_reply_locator_list.clear();
+					self.timestamp = None;
+				},
+				Submessage::InfoDestination(s) => {
+					self.dest_guid_prefix = if s.guid_prefix == GUIDPREFIX_UNKNOWN {
+						self.local_guid_prefix
+					} else {
+						s.guid_prefix
+					};
+				},
+				Submessage::InfoReply(s) => {
+					let multicast = s.has_multicast();
+					self.unicast_reply_locator_list = s.unicast_locator_list.0;
+					if multicast {
+						self.multicast_reply_locator_list = s.multicast_locator_list.0;
+					} else {
+						self.multicast_reply_locator_list.clear();
+					}
+				},
+				Submessage::InfoReplyIp4(s) => {
+					self.unicast_reply_locator_list = s.unicast_locator.to_locator()
+						.into_iter().collect();
+					if let Some(m) = s.multicast_locator {
+						self.multicast_reply_locator_list = m.to_locator()
+							.into_iter().collect();
+					} else {
+						self.multicast_reply_locator_list.clear();
+					}
+				},
+				Submessage::InfoTimestamp(s) => {
+					self.timestamp = if s.invalidates() {
+						None
+					} else {
+						Some(s.timestamp)
+					};
+				},
+				Submessage::Pad(_) => {},
+				s => {
+					if self.dest_guid_prefix != self.local_guid_prefix {
+						continue;
+					}
+
+					if let Some((dest_id, source_id)) = entity_ids(&s) {
+						dispatch(Received {
+							destination: GUID_t::new(self.dest_guid_prefix, dest_id),
+							source: GUID_t::new(self.source_guid_prefix, source_id),
+							source_version: self.source_version,
+							source_vendor_id: self.source_vendor_id,
+							timestamp: self.timestamp,
+							unicast_reply_locator_list: &self.unicast_reply_locator_list,
+							multicast_reply_locator_list: &self.multicast_reply_locator_list,
+							submessage: s
+						});
+					}
+				},
+			}
+		}
+	}
+
+	/// Initial receiver state is taken from the message header and the
+	/// address the message was received from.
+	fn reset(&mut self, msg: &Message, source: &SocketAddr) {
+		self.source_version = *msg.header.protocol_version();
+		self.source_vendor_id = *msg.header.vendor_id();
+		self.source_guid_prefix = *msg.header.guid_prefix();
+		self.dest_guid_prefix = self.local_guid_prefix;
+		self.unicast_reply_locator_list.clear();
+		self.multicast_reply_locator_list.clear();
+		self.timestamp = None;
+
+		self.unicast_reply_locator_list.push(Locator_t::from(SocketAddr::new(source.ip(), 0)));
+	}
+}
+
+/// The entity ids of the (local) destination and (remote) source of an
+/// entity submessage. Readers are the destination of submessages sent by
+/// writers, and writers the destination of submessages sent by readers.
+fn entity_ids(s: &Submessage) -> Option<(EntityId_t, EntityId_t)> {
+	match *s {
+		Submessage::Data(ref d) => Some((d.reader_id, d.writer_id)),
+		Submessage::DataFrag(ref d) => Some((d.data_header.reader_id, d.data_header.writer_id)),
+		Submessage::Gap(ref g) => Some((g.reader_id, g.writer_id)),
+		Submessage::Heartbeat(ref h) => Some((h.reader_id, h.writer_id)),
+		Submessage::HeartbeatFrag(ref h) => Some((h.reader_id, h.writer_id)),
+		Submessage::AckNack(ref a) => Some((a.writer_id, a.reader_id)),
+		Submessage::NackFrag(ref n) => Some((n.writer_id, n.reader_id)),
+		_ => None,
+	}
+}
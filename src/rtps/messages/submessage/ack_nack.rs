@@ -7,13 +7,15 @@
  */
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct AckNack {
-	header: SubmessageHeader,
-	reader_id: EntityId,
-	writer_id: EntityId,
-	reader_sn_state: SequenceNumberSet,
-	count: Count,
+	pub header: SubmessageHeader,
+	pub reader_id: EntityId,
+	pub writer_id: EntityId,
+	pub reader_sn_state: SequenceNumberSet,
+	pub count: Count,
 }
 
 impl AckNack {
@@ -30,17 +32,18 @@ impl AckNack {
 	///
 	/// count is a way to de-dup the message in case there were multiple 
 	/// transmission paths.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new(is_final: bool, 
 			   rid: EntityId, 
 			   wid: EntityId, 
 			   r_sn_state: SequenceNumberSet,
 			   c: Count) -> AckNack {
-		let flags: u8 = 0x01 | ((is_final as u8) << 1);
+		let flags = AckNackFlags {is_final, ..Default::default()};
 		let msg_len: u16 = rid.size() + wid.size() + r_sn_state.size() + c.size();
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::AckNack as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		AckNack {header: header, 
@@ -50,8 +53,36 @@ impl AckNack {
 				 count: c}
 	}
 
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> AckNackFlags {
+		AckNackFlags::from_bits(self.header.flags)
+	}
+
 	/// indicates if the reader expects a response from the writer.
 	pub fn is_final(&self) -> bool {
-		self.header.flags & 0x02 == 1
+		self.flags().is_final
+	}
+}
+
+impl Encode for AckNack {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.reader_id.encode(enc);
+		self.writer_id.encode(enc);
+		self.reader_sn_state.encode(enc);
+		self.count.encode(enc);
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for AckNack {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		Ok(AckNack {
+			header,
+			reader_id: EntityId::decode(dec)?,
+			writer_id: EntityId::decode(dec)?,
+			reader_sn_state: SequenceNumberSet::decode(dec)?,
+			count: Count::decode(dec)?
+		})
 	}
 }
\ No newline at end of file
@@ -3,9 +3,20 @@
  The purpose of this (sub) message is to notify the RTPS reader of a change to
  a data object belonging to an RTPS writer.  Possible changes include changes 
  to value, or changes to lifecycle. 
+
+ Changes to lifecycle (disposing or unregistering an instance) are sent
+ without a value. Instead the Data carries the key of the instance, along
+ with PID_KEY_HASH and PID_STATUS_INFO inline QoS.
  */
+use rtps::*;
+use rtps::guid::*;
+use rtps::cache_change::CacheChange;
+use rtps::messages::submessage::{KeyHash_t, StatusInfo_t};
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::submessage::parameter::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct Data {
 	pub header: SubmessageHeader,
@@ -41,6 +52,7 @@ impl Data {
 	/// The Data.reader_id can be ENTITYID_UNKNOWN, in which case the Data 
 	/// applies to all readers of that writer GUID within the participant 
 	/// identified by the GuidPrefix_t Receiver.dest_guid_prefix.
+	#[allow(clippy::too_many_arguments, clippy::redundant_field_names)]
 	pub fn new (has_inline_qos: bool,
 			    has_data: bool,
 			    has_key: bool,
@@ -49,12 +61,15 @@ impl Data {
 			    w_sn: SequenceNumber,
 			    i_qos: Option<ParameterList>,
 			    data: Option<SerializedPayload>) -> Data {
-		let flags: u8 = 0x01 
-					  | ((has_inline_qos as u8) << 1)
-					  | ((has_data as u8) << 2)
-					  | ((has_key as u8) << 3);
+		let flags = DataFlags {
+			inline_qos: has_inline_qos,
+			data: has_data,
+			key: has_key,
+			..Default::default()
+		};
 
-		let mut msg_len: u16 = rid.size() + wid.size() + w_sn.size();
+		// extra_flags and octets_to_inline_qos preceed the entity ids.
+		let mut msg_len: u16 = 4 + rid.size() + wid.size() + w_sn.size();
 		if let Some(ref qos) = i_qos {
 			msg_len += qos.size()
 		}
@@ -65,7 +80,7 @@ impl Data {
 
 		let header = SubmessageHeader {
 			submessage_id: SubmessageKind::Data as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len
 		};
 
@@ -81,18 +96,162 @@ impl Data {
 		}
 	}
 
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> DataFlags {
+		DataFlags::from_bits(self.header.flags)
+	}
+
 	/// True if there is a ParameterList present in this submessage.
 	pub fn has_inline_qos(&self) -> bool {
-		self.header.flags & 0x02 == 1
+		self.flags().inline_qos
 	}
 
 	/// True if the serialized_payload represents the value of the data object.
 	pub fn has_data(&self) -> bool {
-		self.header.flags & 0x04 == 1
+		self.flags().data
 	}
 
 	/// True if the serialized_payload represents a key to a registered data object.
 	pub fn has_key(&self) -> bool {
-		self.header.flags & 0x08 == 1
+		self.flags().key
+	}
+
+	/// Creates the Data sending change to the reader identified by rid.
+	///
+	/// ALIVE changes carry the value of the data object, preceeded by the
+	/// key hash as inline QoS if the reader expects it. Other changes carry
+	/// the key of the instance (if any), preceeded by the key hash (if
+	/// known) and status info.
+	pub fn from_change(rid: EntityId, change: &CacheChange, expects_inline_qos: bool) -> Data {
+		let mut qos = ParameterList::new();
+		if change.kind != ChangeKind_t::ALIVE || expects_inline_qos {
+			if let Some(key_hash) = change.key_hash {
+				qos.push(Parameter::KeyHash(key_hash));
+			}
+		}
+		if change.kind != ChangeKind_t::ALIVE {
+			qos.push(Parameter::StatusInfo(StatusInfo_t::from(change.kind)));
+		}
+
+		let alive = change.kind == ChangeKind_t::ALIVE;
+		let has_payload = alive || !change.data_value.is_empty();
+		let has_inline_qos = !qos.0.is_empty();
+		Data::new(has_inline_qos,
+				  alive,
+				  !alive && has_payload,
+				  rid,
+				  *change.writer_guid.entity_id(),
+				  change.sequence_number,
+				  if has_inline_qos {Some(qos)} else {None},
+				  if has_payload {Some(change.data_value.clone())} else {None})
+	}
+
+	/// The key hash sent as inline QoS, if any.
+	pub fn key_hash(&self) -> Option<KeyHash_t> {
+		match self.inline_qos.as_ref().and_then(|qos| qos.get(PID_KEY_HASH)) {
+			Some(&Parameter::KeyHash(k)) => Some(k),
+			_ => None,
+		}
+	}
+
+	/// The kind of change sent, given by the status info sent as inline QoS.
+	/// Data without status info is ALIVE.
+	pub fn change_kind(&self) -> ChangeKind_t {
+		match self.inline_qos.as_ref().and_then(|qos| qos.get(PID_STATUS_INFO)) {
+			Some(&Parameter::StatusInfo(s)) => ChangeKind_t::from(s),
+			_ => ChangeKind_t::ALIVE,
+		}
+	}
+
+	/// The change sent by the writer identified by writer_guid (see
+	/// `receiver::Received`).
+	///
+	/// The instance is identified by the key hash sent as inline QoS. When
+	/// there isn't one, the instance is HANDLE_NIL. The data value of a
+	/// change to lifecycle is the serialized key, if it was sent.
+	pub fn to_change(&self, writer_guid: GUID_t) -> CacheChange {
+		let key_hash = self.key_hash();
+		CacheChange {
+			kind: self.change_kind(),
+			writer_guid,
+			instance_handle: key_hash.map_or(HANDLE_NIL, InstanceHandle_t::from),
+			key_hash,
+			sequence_number: self.writer_sn,
+			data_value: self.serialized_payload.clone().unwrap_or_default()
+		}
+	}
+}
+
+impl Encode for Data {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		enc.put_u16(self.extra_flags);
+		enc.put_u16(self.octets_to_inline_qos);
+		self.reader_id.encode(enc);
+		self.writer_id.encode(enc);
+		self.writer_sn.encode(enc);
+
+		if let Some(ref qos) = self.inline_qos {
+			qos.encode(enc);
+		}
+
+		if let Some(ref data) = self.serialized_payload {
+			enc.put_slice(data);
+		}
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for Data {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let extra_flags = dec.get_u16()?;
+		let octets_to_inline_qos = dec.get_u16()?;
+		let start = dec.position();
+		let reader_id = EntityId::decode(dec)?;
+		let writer_id = EntityId::decode(dec)?;
+		let writer_sn = SequenceNumber::decode(dec)?;
+
+		if writer_sn < SequenceNumber_t(1) {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason: "writer_sn must be positive"
+			});
+		}
+
+		// Later versions of the protocol may add fields ahead of the inline
+		// QoS, which are skipped.
+		let consumed = dec.position() - start;
+		if (octets_to_inline_qos as usize) < consumed {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason: "octets_to_inline_qos is too small"
+			});
+		}
+		dec.skip(octets_to_inline_qos as usize - consumed)?;
+
+		let flags = DataFlags::from_bits(header.flags);
+		let inline_qos = if flags.inline_qos {
+			Some(ParameterList::decode(dec)?)
+		} else {
+			None
+		};
+
+		let serialized_payload = if flags.data || flags.key {
+			let len = dec.remaining();
+			Some(dec.get_bytes(len)?)
+		} else {
+			None
+		};
+
+		Ok(Data {
+			header,
+			extra_flags,
+			octets_to_inline_qos,
+			reader_id,
+			writer_id,
+			writer_sn,
+			inline_qos,
+			serialized_payload
+		})
 	}
 }
\ No newline at end of file
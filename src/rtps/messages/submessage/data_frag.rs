@@ -5,21 +5,25 @@
  allowing data contents to be broken down when it exceeds the size of a UDP
  datagram (or whatever underlying transport is being used). 
  */
+use rtps::SequenceNumber_t;
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 use rtps::messages::submessage::data::*;
 
 pub struct DataFragment {
-	data_header: Data,
-	fragment_starting_num: FragmentNumber,
-	fragments_in_submessage: u16,
-	data_size: u32,
-	fragment_size: u16,
+	pub data_header: Data,
+	pub fragment_starting_num: FragmentNumber,
+	pub fragments_in_submessage: u16,
+	pub data_size: u32,
+	pub fragment_size: u16,
 }
 
 impl DataFragment {
-	/// Accepts same arguments as the Data submessage with the same semantics
-	/// in addition to the following arguments:
+	/// Accepts same arguments as the Data submessage with the same semantics,
+	/// except there is no has_data (the fragments are of the data object 
+	/// unless has_key is set), in addition to the following arguments:
 	///
 	/// fragment_starting_num represents the first fragment number in this 
 	/// submessage. As all fragments within a submessage must be contigious,
@@ -32,8 +36,8 @@ impl DataFragment {
 	/// DataFragment submessages.
 	///
 	/// fragment_size represents the size of each fragment (in bytes).
+	#[allow(clippy::too_many_arguments, clippy::redundant_field_names)]
 	pub fn new (has_inline_qos: bool,
-			    has_data: bool,
 			    has_key: bool,
 			    rid: EntityId,
 			    wid: EntityId,
@@ -44,13 +48,16 @@ impl DataFragment {
 			    frag_size: u16,
 			    i_qos: Option<ParameterList>,
 			    data: Option<SerializedPayload>) -> DataFragment {
-		let flags: u8 = 0x01 
-					  | ((has_inline_qos as u8) << 1)
-					  | ((has_data as u8) << 2)
-					  | ((has_key as u8) << 3);
+		let flags = DataFragFlags {
+			inline_qos: has_inline_qos,
+			key: has_key,
+			..Default::default()
+		};
 
-		let mut msg_len: u16 = rid.size() + wid.size() + w_sn.size()
+		// extra_flags and octets_to_inline_qos preceed the entity ids.
+		let octets_to_inline_qos: u16 = rid.size() + wid.size() + w_sn.size()
 			+ frag_starting_num.size() + 8;
+		let mut msg_len: u16 = 4 + octets_to_inline_qos;
 
 		if let Some(ref qos) = i_qos {
 			msg_len += qos.size()
@@ -62,14 +69,14 @@ impl DataFragment {
 
 		let header = SubmessageHeader {
 			submessage_id: SubmessageKind::DataFrag as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len
 		};
 
 		let data_header = Data {
 			header: header,
 			extra_flags: 0,
-			octets_to_inline_qos: (rid.size() + wid.size() + w_sn.size()),
+			octets_to_inline_qos,
 			reader_id: rid,
 			writer_id: wid,
 			writer_sn: w_sn,
@@ -86,18 +93,117 @@ impl DataFragment {
 		}		
 	}
 
+	/// Typed view of the flags within the submessage header. Unlike Data,
+	/// there is no DataFlag, as the fragments are always present.
+	pub fn flags(&self) -> DataFragFlags {
+		DataFragFlags::from_bits(self.data_header.header.flags)
+	}
+
 	/// True if there is a ParameterList present in this submessage.
 	pub fn has_inline_qos(&self) -> bool {
-		self.data_header.header.flags & 0x02 == 1
+		self.flags().inline_qos
 	}
 
-	/// True if the serialized_payload represents the value of the data object.
-	pub fn has_data(&self) -> bool {
-		self.data_header.header.flags & 0x04 == 1
+	/// True if the fragments are of a key to a registered data object, rather
+	/// than the value of the data object.
+	pub fn has_key(&self) -> bool {
+		self.flags().key
 	}
+}
 
-	/// True if the serialized_payload represents a key to a registered data object.
-	pub fn has_key(&self) -> bool {
-		self.data_header.header.flags & 0x08 == 1
+/// Fragment information follows the writer sequence number, ahead of the
+/// inline QoS: fragment_starting_num, fragments_in_submessage, fragment_size
+/// and finally data_size.
+impl Encode for DataFragment {
+	fn encode(&self, enc: &mut Encoder) {
+		let d = &self.data_header;
+		let start = enc.begin_submessage(&d.header);
+		enc.put_u16(d.extra_flags);
+		enc.put_u16(d.octets_to_inline_qos);
+		d.reader_id.encode(enc);
+		d.writer_id.encode(enc);
+		d.writer_sn.encode(enc);
+		self.fragment_starting_num.encode(enc);
+		enc.put_u16(self.fragments_in_submessage);
+		enc.put_u16(self.fragment_size);
+		enc.put_u32(self.data_size);
+
+		if let Some(ref qos) = d.inline_qos {
+			qos.encode(enc);
+		}
+
+		if let Some(ref data) = d.serialized_payload {
+			enc.put_slice(data);
+		}
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for DataFragment {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let extra_flags = dec.get_u16()?;
+		let octets_to_inline_qos = dec.get_u16()?;
+		let start = dec.position();
+		let reader_id = EntityId::decode(dec)?;
+		let writer_id = EntityId::decode(dec)?;
+		let writer_sn = SequenceNumber::decode(dec)?;
+		let fragment_starting_num = FragmentNumber::decode(dec)?;
+		let fragments_in_submessage = dec.get_u16()?;
+		let fragment_size = dec.get_u16()?;
+		let data_size = dec.get_u32()?;
+
+		let reason = if writer_sn < SequenceNumber_t(1) {
+			Some("writer_sn must be positive")
+		} else if fragment_starting_num.0 < 1 {
+			Some("fragment_starting_num must be positive")
+		} else if fragment_size == 0 || fragment_size as u32 > data_size {
+			Some("fragment_size must be between 1 and data_size")
+		} else {
+			None
+		};
+
+		if let Some(reason) = reason {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason
+			});
+		}
+
+		let consumed = dec.position() - start;
+		if (octets_to_inline_qos as usize) < consumed {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason: "octets_to_inline_qos is too small"
+			});
+		}
+		dec.skip(octets_to_inline_qos as usize - consumed)?;
+
+		let inline_qos = if DataFragFlags::from_bits(header.flags).inline_qos {
+			Some(ParameterList::decode(dec)?)
+		} else {
+			None
+		};
+
+		let len = dec.remaining();
+		let serialized_payload = Some(dec.get_bytes(len)?);
+
+		let data_header = Data {
+			header,
+			extra_flags,
+			octets_to_inline_qos,
+			reader_id,
+			writer_id,
+			writer_sn,
+			inline_qos,
+			serialized_payload
+		};
+
+		Ok(DataFragment {
+			data_header,
+			fragment_starting_num,
+			fragments_in_submessage,
+			data_size,
+			fragment_size
+		})
 	}
 }
\ No newline at end of file
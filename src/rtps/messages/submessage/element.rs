@@ -5,6 +5,8 @@
 use rtps::*;
 use rtps::guid::*;
 use rtps::messages::submessage::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 use bytes::Bytes;
 
 /// Encoded as a 4-byte value.  
@@ -22,7 +24,7 @@ pub type VendorId = VendorId_t;
 
 impl VendorId {
 	pub fn size (&self) -> u16 {
-		2 as u16
+		2
 	}
 }
 
@@ -35,7 +37,7 @@ pub type ProtocolVersion = ProtocolVersion_t;
 
 impl ProtocolVersion {
 	pub fn size (&self) -> u16 {
-		2 as u16
+		2
 	}
 }
 
@@ -46,7 +48,7 @@ pub type SequenceNumber = SequenceNumber_t;
 
 impl SequenceNumber {
 	pub fn size (&self) -> u16 {
-		8 as u16
+		8
 	}
 }
 
@@ -58,26 +60,100 @@ impl SequenceNumber {
 /// range must be from 0...255 of the base sequnce number (i.e. bitmap_base).
 /// The sequnce number is included in the set if the bit shows up within the 
 /// bitmap vector.  The value of the sequnce number depends on where the bit 
-/// appears in the vector (of u32's).  
+/// appears in the vector (of u32's), starting from the most significant bit
+/// of bitmap[0] (see RTPS v2.2 spec 9.4.2.6).
 ///
 /// For example, lets say the bitmap base = 424242. If there is a bit set in 
-/// bitmap[2] = 0000 0000 0000 0000 0000 0001 0000 0000, then sequence number
-/// 424346 is in the set (424242 + ((32*3) + 8)).
+/// bitmap[2] = 0000 0000 1000 0000 0000 0000 0000 0000, then sequence number
+/// 424314 is in the set (424242 + ((32*2) + 8)).
 /// 
-/// During serial encoding, there is a num_bits field, represented as a u32. 
+/// During serial encoding, there is a num_bits field, represented as a u32.
 /// This signifies how many bit positions are used within the bitmap vector.
 /// Note: this value is not necessarilty divisable by 32, as the most 
 /// significant bit may be somewhere in the middle of a u32.  
 /// Note: this value also represents the size of the bitmap vector:
-/// bitmap.len() = num_bits/32.
+/// bitmap.len() = (num_bits + 31)/32.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SequenceNumberSet {
 	bitmap_base: SequenceNumber_t,
+	num_bits: u32,
 	bitmap: Vec<u32>,
 }
 
+/// The most bits a SequenceNumberSet or FragmentNumberSet may contain.
+pub const MAX_BITMAP_BITS: u32 = 256;
+
 impl SequenceNumberSet {
+	/// Creates an empty set, starting at bitmap_base.
+	pub fn new(bitmap_base: SequenceNumber_t) -> SequenceNumberSet {
+		SequenceNumberSet {bitmap_base, num_bits: 0, bitmap: Vec::new()}
+	}
+
+	/// Creates a set containing every number yielded by numbers. Returns None
+	/// if any of them falls outside of the 256 sequence numbers starting at
+	/// bitmap_base.
+	pub fn from_numbers<I>(bitmap_base: SequenceNumber_t, numbers: I) -> Option<SequenceNumberSet>
+						where I: IntoIterator<Item=SequenceNumber_t> {
+		let mut set = SequenceNumberSet::new(bitmap_base);
+		for sn in numbers {
+			if !set.insert(sn) {
+				return None;
+			}
+		}
+		Some(set)
+	}
+
+	pub fn bitmap_base(&self) -> SequenceNumber_t {
+		self.bitmap_base
+	}
+
+	/// Number of bit positions in use, i.e. one past the offset of the
+	/// largest sequence number that has been inserted.
+	pub fn num_bits(&self) -> u32 {
+		self.num_bits
+	}
+
+	/// True if no sequence numbers are in the set.
+	pub fn is_empty(&self) -> bool {
+		self.bitmap.iter().all(|b| *b == 0)
+	}
+
+	pub fn contains(&self, sn: SequenceNumber_t) -> bool {
+		match self.offset(sn) {
+			Some(bit) => bitmap_get(&self.bitmap, self.num_bits, bit),
+			None => false,
+		}
+	}
+
+	/// Adds sn to the set, growing num_bits as needed. Returns false (leaving
+	/// the set unchanged) if sn is outside of the 256 sequence numbers
+	/// starting at bitmap_base.
+	pub fn insert(&mut self, sn: SequenceNumber_t) -> bool {
+		match self.offset(sn) {
+			Some(bit) => {
+				bitmap_set(&mut self.bitmap, &mut self.num_bits, bit);
+				true
+			},
+			None => false,
+		}
+	}
+
+	/// The sequence numbers within the set, in ascending order. Bits which
+	/// would go past the largest sequence number are skipped.
+	pub fn iter<'a>(&'a self) -> impl Iterator<Item=SequenceNumber_t> + 'a {
+		let base = self.bitmap_base.0;
+		bitmap_bits(&self.bitmap, self.num_bits)
+			.filter_map(move |bit| base.checked_add(bit as i64).map(SequenceNumber_t))
+	}
+
+	/// The bit offset of sn from bitmap_base. Sequence numbers too far from
+	/// bitmap_base to subtract are out of range, as well.
+	fn offset(&self, sn: SequenceNumber_t) -> Option<u32> {
+		sn.0.checked_sub(self.bitmap_base.0).and_then(bitmap_offset)
+	}
+
 	pub fn size (&self) -> u16 {
-		8 + (self.bitmap.len() * 4) as u16
+		12 + (self.bitmap.len() * 4) as u16
 	}
 }
 
@@ -88,7 +164,7 @@ pub type FragmentNumber = FragmentNumber_t;
 
 impl FragmentNumber {
 	pub fn size (&self) -> u16 {
-		4 as u16
+		4
 	}
 }
 
@@ -97,15 +173,130 @@ impl FragmentNumber {
 ///
 /// Uses the same compact bitmap representation scheme employed by 
 /// SequenceNumberSet (also see RTPS v2.2 spec 9.4.2.6 and 9.4.2.8)
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FragmentNumberSet {
 	bitmap_base: FragmentNumber_t,
+	num_bits: u32,
 	bitmap: Vec<u32>,
 }
 
 impl FragmentNumberSet {
+	/// Creates an empty set, starting at bitmap_base.
+	pub fn new(bitmap_base: FragmentNumber_t) -> FragmentNumberSet {
+		FragmentNumberSet {bitmap_base, num_bits: 0, bitmap: Vec::new()}
+	}
+
+	/// Creates a set containing every number yielded by numbers. Returns None
+	/// if any of them falls outside of the 256 fragment numbers starting at
+	/// bitmap_base.
+	pub fn from_numbers<I>(bitmap_base: FragmentNumber_t, numbers: I) -> Option<FragmentNumberSet>
+						where I: IntoIterator<Item=FragmentNumber_t> {
+		let mut set = FragmentNumberSet::new(bitmap_base);
+		for n in numbers {
+			if !set.insert(n) {
+				return None;
+			}
+		}
+		Some(set)
+	}
+
+	pub fn bitmap_base(&self) -> FragmentNumber_t {
+		self.bitmap_base
+	}
+
+	/// Number of bit positions in use, i.e. one past the offset of the
+	/// largest fragment number that has been inserted.
+	pub fn num_bits(&self) -> u32 {
+		self.num_bits
+	}
+
+	/// True if no fragment numbers are in the set.
+	pub fn is_empty(&self) -> bool {
+		self.bitmap.iter().all(|b| *b == 0)
+	}
+
+	pub fn contains(&self, n: FragmentNumber_t) -> bool {
+		match bitmap_offset(n.0 as i64 - self.bitmap_base.0 as i64) {
+			Some(bit) => bitmap_get(&self.bitmap, self.num_bits, bit),
+			None => false,
+		}
+	}
+
+	/// Adds n to the set, growing num_bits as needed. Returns false (leaving
+	/// the set unchanged) if n is outside of the 256 fragment numbers
+	/// starting at bitmap_base.
+	pub fn insert(&mut self, n: FragmentNumber_t) -> bool {
+		match bitmap_offset(n.0 as i64 - self.bitmap_base.0 as i64) {
+			Some(bit) => {
+				bitmap_set(&mut self.bitmap, &mut self.num_bits, bit);
+				true
+			},
+			None => false,
+		}
+	}
+
+	/// The fragment numbers within the set, in ascending order.
+	pub fn iter<'a>(&'a self) -> impl Iterator<Item=FragmentNumber_t> + 'a {
+		let base = self.bitmap_base.0;
+		bitmap_bits(&self.bitmap, self.num_bits)
+			.filter_map(move |bit| base.checked_add(bit).map(FragmentNumber_t))
+	}
+
 	pub fn size (&self) -> u16 {
-		4 + (self.bitmap.len() * 4) as u16
+		8 + (self.bitmap.len() * 4) as u16
+	}
+}
+
+/// Bit position of a number offset from the bitmap base, if it fits within
+/// the bitmap.
+fn bitmap_offset(offset: i64) -> Option<u32> {
+	if offset >= 0 && offset < MAX_BITMAP_BITS as i64 {
+		Some(offset as u32)
+	} else {
+		None
+	}
+}
+
+/// Bit 0 is the most significant bit of bitmap[0].
+fn bitmap_mask(bit: u32) -> u32 {
+	0x8000_0000 >> (bit % 32)
+}
+
+fn bitmap_get(bitmap: &[u32], num_bits: u32, bit: u32) -> bool {
+	bit < num_bits && bitmap[(bit / 32) as usize] & bitmap_mask(bit) != 0
+}
+
+fn bitmap_set(bitmap: &mut Vec<u32>, num_bits: &mut u32, bit: u32) {
+	if bit >= *num_bits {
+		*num_bits = bit + 1;
+		bitmap.resize(num_bits.div_ceil(32) as usize, 0);
 	}
+	bitmap[(bit / 32) as usize] |= bitmap_mask(bit);
+}
+
+fn bitmap_bits<'a>(bitmap: &'a [u32], num_bits: u32) -> impl Iterator<Item=u32> + 'a {
+	(0..num_bits).filter(move |bit| bitmap_get(bitmap, num_bits, *bit))
+}
+
+/// Reads num_bits and the bitmap which follows it, shared by both kinds of
+/// set. Bits beyond num_bits are cleared, as the spec says they're ignored.
+fn decode_bitmap(dec: &mut Decoder) -> Result<(u32, Vec<u32>), ParseError> {
+	let num_bits = dec.get_u32()?;
+	if num_bits > MAX_BITMAP_BITS {
+		return Err(ParseError::InvalidElement("bitmap exceeds 256 bits"));
+	}
+
+	let mut bitmap = Vec::new();
+	for _ in 0..num_bits.div_ceil(32) {
+		bitmap.push(dec.get_u32()?);
+	}
+	if num_bits % 32 != 0 {
+		if let Some(last) = bitmap.last_mut() {
+			*last &= !0u32 << (32 - num_bits % 32);
+		}
+	}
+
+	Ok((num_bits, bitmap))
 }
 
 /// Used to timestamp the message.
@@ -117,48 +308,66 @@ pub type Timestamp = Time_t;
 
 impl Timestamp {
 	pub fn size (&self) -> u16 {
-		8 as u16
+		8
+	}
+}
+
+/// Encoded as 8 bytes.
+/// address = 4 bytes (u32)
+/// port = 4 bytes (u32)
+pub type LocatorUDPv4 = LocatorUDPv4_t;
+
+impl LocatorUDPv4 {
+	pub fn size (&self) -> u16 {
+		8
 	}
 }
 
 /// A list of locators over the wire.
 ///
 /// preceded by the list is num_locators, represented as an unsiged long (u32).
-/// within Locator_t, the kind is encoded as i32, the port as u32, and the address,
-/// which is either a u32 (IPv4), or 16 bytes (IPv6).
-pub struct LocatorList(Vec<Locator_t>);
+/// within Locator_t, the kind is encoded as i32, the port as u32, and the address
+/// as 16 bytes (IPv4 addresses use the last 4).
+pub struct LocatorList(pub Vec<Locator_t>);
 
 impl LocatorList {
 	pub fn size (&self) -> u16 {
-		self.0.iter().fold(0, |len, ll| len + ll.size())
+		self.0.iter().fold(4, |len, ll| len + ll.size())
 	}
 }
 
 /// Used to encapsulate QoS parameters.
 ///
-/// Becuase every parameter_id must start on a 4 byte boundry, the length field
-/// must be a multiple of 4.
-pub struct Parameter {
-	parameter_id: ParameterId_t,
-	length: i16,
-	value: Vec<u8>,
-}
-/// Used to enforce alignment of parameter list data on a 4 byte boundry.
-pub const PID_PAD: ParameterId_t = 		ParameterId_t(0);
-/// Used to terminate the parameter list.
-pub const PID_SENTINEL: ParameterId_t = ParameterId_t(1);
-
-impl Parameter {
-	pub fn size (&self) -> u16 {
-		4 + self.value.len() as u16
-	}
-}
+/// Please see the parameter module for the full list of parameter ids and
+/// their typed values.
+pub use rtps::messages::submessage::parameter::Parameter;
+pub use rtps::messages::submessage::parameter::{PID_PAD, PID_SENTINEL};
 
-pub struct ParameterList(Vec<Parameter>);
+/// A list of parameters, terminated on the wire by PID_SENTINEL.
+///
+/// Becuase every parameter_id must start on a 4 byte boundry, each parameter
+/// value is padded to a multiple of 4 bytes.
+pub struct ParameterList(pub Vec<Parameter>);
 
 impl ParameterList {
+	/// Creates an empty parameter list.
+	#[allow(clippy::new_without_default)]
+	pub fn new() -> ParameterList {
+		ParameterList(Vec::new())
+	}
+
+	/// Appends p to the end of the list.
+	pub fn push(&mut self, p: Parameter) {
+		self.0.push(p)
+	}
+
+	/// Get the first parameter with the given parameter id.
+	pub fn get(&self, parameter_id: ParameterId_t) -> Option<&Parameter> {
+		self.0.iter().find(|p| p.parameter_id() == parameter_id)
+	}
+
 	pub fn size (&self) -> u16 {
-		self.0.iter().fold(0, |len, p| len + p.size())
+		self.0.iter().fold(4, |len, p| len + p.size())
 	}
 }
 
@@ -178,6 +387,269 @@ pub type Count = Count_t;
 
 impl Count {
 	pub fn size (&self) -> u16 {
-		4 as u16
+		4
+	}
+}
+
+impl Encode for EntityId {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_slice(&self.to_bytes())
+	}
+}
+
+impl Encode for GUIDPrefix {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_slice(&self.to_bytes())
+	}
+}
+
+impl Encode for VendorId {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_slice(&self.0.to_be_bytes())
+	}
+}
+
+impl Encode for ProtocolVersion {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_u8(self.major);
+		enc.put_u8(self.minor);
+	}
+}
+
+/// Encoded as the high (signed) 32 bits followed by the low (unsigned) 32 bits.
+impl Encode for SequenceNumber {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_i32((self.0 >> 32) as i32);
+		enc.put_u32(self.0 as u32);
+	}
+}
+
+impl Encode for SequenceNumberSet {
+	fn encode(&self, enc: &mut Encoder) {
+		self.bitmap_base.encode(enc);
+		enc.put_u32(self.num_bits);
+		for b in &self.bitmap {
+			enc.put_u32(*b);
+		}
+	}
+}
+
+impl Encode for FragmentNumber {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_u32(self.0)
+	}
+}
+
+impl Encode for FragmentNumberSet {
+	fn encode(&self, enc: &mut Encoder) {
+		self.bitmap_base.encode(enc);
+		enc.put_u32(self.num_bits);
+		for b in &self.bitmap {
+			enc.put_u32(*b);
+		}
+	}
+}
+
+impl Encode for Timestamp {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_i32(self.seconds);
+		enc.put_u32(self.fraction);
+	}
+}
+
+impl Encode for Locator_t {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_i32(self.kind());
+		enc.put_u32(self.port());
+		enc.put_slice(&self.address());
+	}
+}
+
+impl Encode for LocatorUDPv4 {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_u32(self.address);
+		enc.put_u32(self.port);
+	}
+}
+
+impl Encode for LocatorList {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_u32(self.0.len() as u32);
+		for l in &self.0 {
+			l.encode(enc);
+		}
+	}
+}
+
+impl Encode for ParameterList {
+	fn encode(&self, enc: &mut Encoder) {
+		for p in &self.0 {
+			p.encode(enc);
+		}
+		enc.put_i16(PID_SENTINEL.0);
+		enc.put_i16(0);
+	}
+}
+
+impl Encode for Count {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_i32(self.0)
+	}
+}
+
+impl Decode for EntityId {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let mut b = [0u8; 4];
+		dec.get_octets(&mut b)?;
+		Ok(EntityId_t::from_bytes(b))
+	}
+}
+
+impl Decode for GUIDPrefix {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let mut b = [0u8; 12];
+		dec.get_octets(&mut b)?;
+		Ok(GUIDPrefix_t::from_bytes(b))
+	}
+}
+
+impl Decode for VendorId {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let mut b = [0u8; 2];
+		dec.get_octets(&mut b)?;
+		Ok(VendorId_t(u16::from_be_bytes(b)))
+	}
+}
+
+impl Decode for ProtocolVersion {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		Ok(ProtocolVersion_t {major: dec.get_u8()?, minor: dec.get_u8()?})
+	}
+}
+
+impl Decode for SequenceNumber {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let high = dec.get_i32()? as i64;
+		let low = dec.get_u32()? as i64;
+		Ok(SequenceNumber_t((high << 32) | low))
+	}
+}
+
+impl Decode for SequenceNumberSet {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let bitmap_base = SequenceNumber::decode(dec)?;
+		if bitmap_base < SequenceNumber_t(1) {
+			return Err(ParseError::InvalidElement("bitmap_base must be positive"));
+		}
+		let (num_bits, bitmap) = decode_bitmap(dec)?;
+
+		Ok(SequenceNumberSet {bitmap_base, num_bits, bitmap})
+	}
+}
+
+impl Decode for FragmentNumber {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		Ok(FragmentNumber_t(dec.get_u32()?))
+	}
+}
+
+impl Decode for FragmentNumberSet {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let bitmap_base = FragmentNumber::decode(dec)?;
+		if bitmap_base < FragmentNumber_t(1) {
+			return Err(ParseError::InvalidElement("bitmap_base must be positive"));
+		}
+		let (num_bits, bitmap) = decode_bitmap(dec)?;
+
+		Ok(FragmentNumberSet {bitmap_base, num_bits, bitmap})
+	}
+}
+
+impl Decode for Timestamp {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		Ok(Time_t {seconds: dec.get_i32()?, fraction: dec.get_u32()?})
+	}
+}
+
+impl Decode for Locator_t {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let kind = dec.get_i32()?;
+		let port = dec.get_u32()?;
+		let mut address = [0u8; 16];
+		dec.get_octets(&mut address)?;
+
+		Locator_t::from_parts(kind, port, address)
+			.ok_or(ParseError::InvalidElement("locator port out of range"))
+	}
+}
+
+impl Decode for LocatorUDPv4 {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		Ok(LocatorUDPv4_t {address: dec.get_u32()?, port: dec.get_u32()?})
+	}
+}
+
+impl Decode for LocatorList {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let num_locators = dec.get_u32()? as usize;
+		if num_locators > dec.remaining() / 24 {
+			return Err(ParseError::InvalidElement("too many locators"));
+		}
+
+		let mut locators = Vec::with_capacity(num_locators);
+		for _ in 0..num_locators {
+			locators.push(Locator_t::decode(dec)?);
+		}
+		Ok(LocatorList(locators))
+	}
+}
+
+/// Parameters which are not understood are skipped, unless they are marked
+/// as must-understand, in which case the whole list is invalid (see RTPS v2.2
+/// section 9.6.2.2.1). Vendor specific parameters are always skipped, as
+/// their must-understand bit is only meaningful to that vendor. PID_PAD
+/// parameters are also dropped, as they only exist to enforce alignment.
+impl Decode for ParameterList {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let mut params = Vec::new();
+		loop {
+			let parameter_id = ParameterId_t(dec.get_i16()?);
+			let length = dec.get_u16()? as usize;
+			if parameter_id == PID_SENTINEL {
+				break;
+			}
+
+			let value = dec.get_bytes(length)?;
+			let mut value_dec = Decoder::new(value, dec.endianness());
+			match Parameter::decode_value(parameter_id, &mut value_dec)? {
+				Some(p) => params.push(p),
+				None if parameter_id.must_understand() && !parameter_id.is_vendor_specific() => {
+					return Err(ParseError::InvalidElement(
+						"parameter marked must-understand is not supported"));
+				},
+				None => {},
+			}
+		}
+		Ok(ParameterList(params))
+	}
+}
+
+impl Encode for GUID_t {
+	fn encode(&self, enc: &mut Encoder) {
+		self.prefix().encode(enc);
+		self.entity_id().encode(enc);
+	}
+}
+
+impl Decode for GUID_t {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		let prefix = GUIDPrefix::decode(dec)?;
+		Ok(GUID_t::new(prefix, EntityId::decode(dec)?))
+	}
+}
+
+impl Decode for Count {
+	fn decode(dec: &mut Decoder) -> Result<Self, ParseError> {
+		Ok(Count_t(dec.get_i32()?))
 	}
 }
\ No newline at end of file
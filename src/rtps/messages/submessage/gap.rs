@@ -4,15 +4,18 @@
  sequence numbers are no longer relevant. The set may be a contigious range of 
  sequence numbers or a specific set of sequence numbers.
  */
+use rtps::SequenceNumber_t;
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct Gap {
-	header: SubmessageHeader,
-	reader_id: EntityId,
-	writer_id: EntityId,
-	gap_start: SequenceNumber,
-	gap_list: SequenceNumberSet,
+	pub header: SubmessageHeader,
+	pub reader_id: EntityId,
+	pub writer_id: EntityId,
+	pub gap_start: SequenceNumber,
+	pub gap_list: SequenceNumberSet,
 }
 
 impl Gap {
@@ -28,17 +31,18 @@ impl Gap {
 	/// gap_list can identify the last sequence in the interval and can also 
 	/// include additional sequence numbers beyond the last that are not 
 	/// contigious.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new(rid: EntityId,
 			   wid: EntityId,
 			   gap_start: SequenceNumber,
 			   gap_list: SequenceNumberSet) -> Gap {
-		let flags: u8 = 0x01;
+		let flags = GapFlags::default();
 		let msg_len: u16 = rid.size() + wid.size() 
 			+ gap_start.size() + gap_list.size();
 
 		let header = SubmessageHeader {
 			submessage_id: SubmessageKind::Gap as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len
 		};
 
@@ -50,4 +54,44 @@ impl Gap {
 			gap_list: gap_list
 		}
 	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> GapFlags {
+		GapFlags::from_bits(self.header.flags)
+	}
+}
+
+impl Encode for Gap {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.reader_id.encode(enc);
+		self.writer_id.encode(enc);
+		self.gap_start.encode(enc);
+		self.gap_list.encode(enc);
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for Gap {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let reader_id = EntityId::decode(dec)?;
+		let writer_id = EntityId::decode(dec)?;
+		let gap_start = SequenceNumber::decode(dec)?;
+		let gap_list = SequenceNumberSet::decode(dec)?;
+
+		if gap_start < SequenceNumber_t(1) {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason: "gap_start must be positive"
+			});
+		}
+
+		Ok(Gap {
+			header,
+			reader_id,
+			writer_id,
+			gap_start,
+			gap_list
+		})
+	}
 }
@@ -19,13 +19,142 @@ pub struct SubmessageHeader {
 }
 
 impl SubmessageHeader {
-	/// All submessages use the first LSB to mark the endianess of the 
-	/// submessage contents.  Test whether this is big or little endian.
+	/// All submessages use the first LSB (the E flag) to mark the endianess of
+	/// the submessage contents.  When the flag is clear the contents are big 
+	/// endian, when set they are little endian (RTPS v2.2 spec 9.4.5.1.2).
 	pub fn is_big_endian(&self) -> bool {
-		self.flags & 0x01 == 1
+		self.flags & 0x01 == 0
+	}
+
+	/// Byte order of the submessage contents, as selected by the E flag.
+	pub fn endianness(&self) -> Endianness {
+		if self.is_big_endian() {
+			Endianness::BigEndian
+		} else {
+			Endianness::LittleEndian
+		}
+	}
+}
+
+/// Byte order used to encode the contents of a submessage.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Endianness {
+	BigEndian,
+	LittleEndian,
+}
+
+/// The E flag, shared by every kind of submessage.
+pub const FLAG_ENDIANNESS: u8 = 0x01;
+
+/// Typed view of the flags within a SubmessageHeader. Each kind of submessage
+/// has it's own set of flags (see RTPS v2.2 section 9.4.5), all of which share
+/// the E flag.
+///
+/// Bits which aren't defined for the kind of submessage are ignored by
+/// from_bits, as required by the spec.
+pub trait SubmessageFlags: Copy {
+	fn from_bits(bits: u8) -> Self;
+	fn bits(&self) -> u8;
+}
+
+macro_rules! submessage_flags {
+	($(#[$attr:meta])* $name:ident { $($(#[$field_attr:meta])* $field:ident = $bit:expr),* }) => {
+		$(#[$attr])*
+		#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+		pub struct $name {
+			pub endianness: Endianness,
+			$($(#[$field_attr])* pub $field: bool,)*
+		}
+
+		impl SubmessageFlags for $name {
+			fn from_bits(bits: u8) -> Self {
+				$name {
+					endianness: if bits & FLAG_ENDIANNESS == 0 {
+						Endianness::BigEndian
+					} else {
+						Endianness::LittleEndian
+					},
+					$($field: bits & $bit != 0,)*
+				}
+			}
+
+			fn bits(&self) -> u8 {
+				let e = match self.endianness {
+					Endianness::BigEndian => 0,
+					Endianness::LittleEndian => FLAG_ENDIANNESS,
+				};
+				e $(| if self.$field {$bit} else {0})*
+			}
+		}
+
+		/// Little endian, with every other flag clear.
+		impl Default for $name {
+			fn default() -> Self {
+				$name {
+					endianness: Endianness::LittleEndian,
+					$($field: false,)*
+				}
+			}
+		}
 	}
 }
 
+submessage_flags!(AckNackFlags {
+	/// The writer isn't required to respond.
+	is_final = 0x02
+});
+
+submessage_flags!(DataFlags {
+	/// A ParameterList of inline QoS follows the writer sequence number.
+	inline_qos = 0x02,
+	/// The serialized payload is the value of the data object.
+	data = 0x04,
+	/// The serialized payload is the key of the data object.
+	key = 0x08
+});
+
+submessage_flags!(DataFragFlags {
+	/// A ParameterList of inline QoS follows the fragment information.
+	inline_qos = 0x02,
+	/// The fragments are of the serialized key, rather than the value.
+	key = 0x04
+});
+
+submessage_flags!(GapFlags {});
+
+submessage_flags!(HeartbeatFlags {
+	/// The reader isn't required to respond.
+	is_final = 0x02,
+	/// The DDS writer has manually asserted it's liveliness.
+	liveliness = 0x04
+});
+
+submessage_flags!(HeartbeatFragFlags {});
+
+submessage_flags!(InfoDestinationFlags {});
+
+submessage_flags!(InfoReplyFlags {
+	/// A multicast locator list follows the unicast locator list.
+	multicast = 0x02
+});
+
+submessage_flags!(InfoReplyIp4Flags {
+	/// A multicast locator follows the unicast locator.
+	multicast = 0x02
+});
+
+submessage_flags!(InfoSourceFlags {});
+
+submessage_flags!(InfoTimestampFlags {
+	/// There is no timestamp, and subsequent submessages have no source
+	/// timestamp.
+	invalidate = 0x02
+});
+
+submessage_flags!(NackFragFlags {});
+
+submessage_flags!(PadFlags {});
+
 /// Encoding for the submessage_id within SubmessageHeader.
 ///
 /// These values are defined by the RTPS v2.2 spec (9.4.5.1.1)
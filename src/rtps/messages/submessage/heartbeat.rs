@@ -3,16 +3,19 @@
  This (sub) message is sent from the RTPS writer to communicate
  the changes in sequence numbers that it has available, to the reader.
  */
+use rtps::SequenceNumber_t;
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct Heartbeat {
-	header: SubmessageHeader,
-	reader_id: EntityId,
-	writer_id: EntityId,
-	first_sn: SequenceNumber,
-	last_sn: SequenceNumber,
-	count: Count,
+	pub header: SubmessageHeader,
+	pub reader_id: EntityId,
+	pub writer_id: EntityId,
+	pub first_sn: SequenceNumber,
+	pub last_sn: SequenceNumber,
+	pub count: Count,
 }
 
 impl Heartbeat {
@@ -32,6 +35,7 @@ impl Heartbeat {
 	///
 	/// count assists with duplicates that may be recieved via mutiple network
 	/// pathways.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (is_final: bool,
 				has_liveliness: bool,
 				rid: EntityId,
@@ -39,15 +43,17 @@ impl Heartbeat {
 				first_sn: SequenceNumber,
 				last_sn: SequenceNumber,
 				count: Count) -> Heartbeat {
-		let flags: u8 = 0x01 
-			| ((is_final as u8) << 1)
-			| ((has_liveliness as u8) << 2);
+		let flags = HeartbeatFlags {
+			is_final,
+			liveliness: has_liveliness,
+			..Default::default()
+		};
 		let msg_len: u16 = rid.size() + wid.size() + first_sn.size() 
 			+ last_sn.size() + count.size();
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::Heartbeat as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		Heartbeat {
@@ -60,13 +66,66 @@ impl Heartbeat {
 		}
 	}
 
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> HeartbeatFlags {
+		HeartbeatFlags::from_bits(self.header.flags)
+	}
+
 	/// indicates if the reader expects a response from the writer.
 	pub fn is_final(&self) -> bool {
-		self.header.flags & 0x02 == 1
+		self.flags().is_final
 	}
 
 	/// signifies the DDS writer has manually set the lifelyness flag.
 	pub fn has_liveliness(&self) -> bool {
-		self.header.flags & 0x04 == 1
+		self.flags().liveliness
+	}
+}
+
+impl Encode for Heartbeat {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.reader_id.encode(enc);
+		self.writer_id.encode(enc);
+		self.first_sn.encode(enc);
+		self.last_sn.encode(enc);
+		self.count.encode(enc);
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for Heartbeat {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let reader_id = EntityId::decode(dec)?;
+		let writer_id = EntityId::decode(dec)?;
+		let first_sn = SequenceNumber::decode(dec)?;
+		let last_sn = SequenceNumber::decode(dec)?;
+		let count = Count::decode(dec)?;
+
+		let reason = if first_sn < SequenceNumber_t(1) {
+			Some("first_sn must be positive")
+		} else if last_sn < SequenceNumber_t(0) {
+			Some("last_sn must not be negative")
+		} else if last_sn.0 < first_sn.0 - 1 {
+			Some("last_sn preceeds first_sn")
+		} else {
+			None
+		};
+
+		if let Some(reason) = reason {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason
+			});
+		}
+
+		Ok(Heartbeat {
+			header,
+			reader_id,
+			writer_id,
+			first_sn,
+			last_sn,
+			count
+		})
 	}
 }
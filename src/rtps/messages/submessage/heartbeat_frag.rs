@@ -4,16 +4,19 @@
  data fragments that are available, to the reader.  Once all data
  fragments are available, a normal heartbeat message is sent.
  */
+use rtps::SequenceNumber_t;
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct HeartbeatFrag {
-	header: SubmessageHeader,
-	reader_id: EntityId,
-	writer_id: EntityId,
-	writer_sn: SequenceNumber,
-	last_fragment_num: FragmentNumber,
-	count: Count,
+	pub header: SubmessageHeader,
+	pub reader_id: EntityId,
+	pub writer_id: EntityId,
+	pub writer_sn: SequenceNumber,
+	pub last_fragment_num: FragmentNumber,
+	pub count: Count,
 }
 
 impl HeartbeatFrag {
@@ -30,18 +33,19 @@ impl HeartbeatFrag {
 	///
 	/// count assists with duplicates that may be recieved via mutiple network
 	/// pathways.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (rid: EntityId,
 				wid: EntityId,
 				writer_sn: SequenceNumber,
 				last_frag_num: FragmentNumber,
 				count: Count) -> HeartbeatFrag {
-		let flags: u8 = 0x01;
+		let flags = HeartbeatFragFlags::default();
 		let msg_len: u16 = rid.size() + wid.size() + writer_sn.size() 
 			+ last_frag_num.size() + count.size();
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::HeartbeatFrag as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		HeartbeatFrag {
@@ -53,4 +57,55 @@ impl HeartbeatFrag {
 			count: count
 		}
 	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> HeartbeatFragFlags {
+		HeartbeatFragFlags::from_bits(self.header.flags)
+	}
+}
+
+impl Encode for HeartbeatFrag {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.reader_id.encode(enc);
+		self.writer_id.encode(enc);
+		self.writer_sn.encode(enc);
+		self.last_fragment_num.encode(enc);
+		self.count.encode(enc);
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for HeartbeatFrag {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let reader_id = EntityId::decode(dec)?;
+		let writer_id = EntityId::decode(dec)?;
+		let writer_sn = SequenceNumber::decode(dec)?;
+		let last_fragment_num = FragmentNumber::decode(dec)?;
+		let count = Count::decode(dec)?;
+
+		let reason = if writer_sn < SequenceNumber_t(1) {
+			Some("writer_sn must be positive")
+		} else if last_fragment_num.0 < 1 {
+			Some("last_fragment_num must be positive")
+		} else {
+			None
+		};
+
+		if let Some(reason) = reason {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason
+			});
+		}
+
+		Ok(HeartbeatFrag {
+			header,
+			reader_id,
+			writer_id,
+			writer_sn,
+			last_fragment_num,
+			count
+		})
+	}
 }
\ No newline at end of file
@@ -5,23 +5,26 @@
  */
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct InfoDestination {
-	header: SubmessageHeader,
-	guid_prefix: GUIDPrefix,
+	pub header: SubmessageHeader,
+	pub guid_prefix: GUIDPrefix,
 }
 
 impl InfoDestination {
 	
 	/// guid_prefix specifies the new prefix to be used for subsequent 
 	/// EntityId(s) found within the message.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (guid_prefix: GUIDPrefix) -> InfoDestination {
-		let flags: u8 = 0x01;
+		let flags = InfoDestinationFlags::default();
 		let msg_len: u16 = guid_prefix.size();
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::InfoDestination as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		InfoDestination {
@@ -29,4 +32,26 @@ impl InfoDestination {
 			guid_prefix: guid_prefix
 		}
 	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> InfoDestinationFlags {
+		InfoDestinationFlags::from_bits(self.header.flags)
+	}
+}
+
+impl Encode for InfoDestination {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.guid_prefix.encode(enc);
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for InfoDestination {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		Ok(InfoDestination {
+			header,
+			guid_prefix: GUIDPrefix::decode(dec)?
+		})
+	}
 }
\ No newline at end of file
@@ -6,11 +6,13 @@
  */
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct InfoReply {
-	header: SubmessageHeader,
-	unicast_locator_list: LocatorList,
-	multicast_locator_list: LocatorList,
+	pub header: SubmessageHeader,
+	pub unicast_locator_list: LocatorList,
+	pub multicast_locator_list: LocatorList,
 }
 
 impl InfoReply {
@@ -20,17 +22,19 @@ impl InfoReply {
 	/// unicast_locator_list contains all unicast UDP addresses.
 	///
 	/// multicast_locator_list contains all multicast UDP addresses.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (has_multicast: bool,
 			    unicast_locator_list: LocatorList,
 			    multicast_locator_list: LocatorList) -> InfoReply {
-		let flags: u8 = 0x01
-			| ((has_multicast as u8) << 1);
-		let msg_len: u16 = unicast_locator_list.size() 
-			+ multicast_locator_list.size();
+		let flags = InfoReplyFlags {multicast: has_multicast, ..Default::default()};
+		let mut msg_len: u16 = unicast_locator_list.size();
+		if has_multicast {
+			msg_len += multicast_locator_list.size();
+		}
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::InfoReply as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		InfoReply {
@@ -42,6 +46,41 @@ impl InfoReply {
 
 	/// True if this message has locator lists with multicast addresses.
 	pub fn has_multicast(&self) -> bool {
-		self.header.flags & 0x02 == 1
+		self.flags().multicast
+	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> InfoReplyFlags {
+		InfoReplyFlags::from_bits(self.header.flags)
+	}
+}
+
+/// The multicast_locator_list is only present on the wire when the multicast
+/// flag is set.
+impl Encode for InfoReply {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.unicast_locator_list.encode(enc);
+		if self.has_multicast() {
+			self.multicast_locator_list.encode(enc);
+		}
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for InfoReply {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let unicast_locator_list = LocatorList::decode(dec)?;
+		let multicast_locator_list = if InfoReplyFlags::from_bits(header.flags).multicast {
+			LocatorList::decode(dec)?
+		} else {
+			LocatorList(Vec::new())
+		};
+
+		Ok(InfoReply {
+			header,
+			unicast_locator_list,
+			multicast_locator_list
+		})
 	}
 }
\ No newline at end of file
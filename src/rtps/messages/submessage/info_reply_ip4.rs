@@ -0,0 +1,82 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ A compact form of the InfoReply (sub) message, used when replies should be
+ sent to a single UDP/IPv4 address (and optionally a single multicast
+ address).
+ */
+use rtps::LOCATOR_UDP_V4_INVALID;
+use rtps::messages::submessage::header::*;
+use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+
+pub struct InfoReplyIp4 {
+	pub header: SubmessageHeader,
+	pub unicast_locator: LocatorUDPv4,
+	pub multicast_locator: Option<LocatorUDPv4>,
+}
+
+impl InfoReplyIp4 {
+
+	/// unicast_locator is the address and port replies should be sent to.
+	///
+	/// multicast_locator is an optional multicast address and port, which 
+	/// sets the multicast flag when present.
+	pub fn new (unicast_locator: LocatorUDPv4,
+			    multicast_locator: Option<LocatorUDPv4>) -> InfoReplyIp4 {
+		let flags = InfoReplyIp4Flags {
+			multicast: multicast_locator.is_some(),
+			..Default::default()
+		};
+		let mut msg_len: u16 = unicast_locator.size();
+		if let Some(ref m) = multicast_locator {
+			msg_len += m.size();
+		}
+
+		let header = SubmessageHeader{
+			submessage_id: SubmessageKind::InfoReplyIp4 as u8,
+			flags: flags.bits(),
+			submessage_length: msg_len};
+
+		InfoReplyIp4 {
+			header,
+			unicast_locator,
+			multicast_locator
+		}
+	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> InfoReplyIp4Flags {
+		InfoReplyIp4Flags::from_bits(self.header.flags)
+	}
+}
+
+/// The multicast_locator is only present on the wire when the multicast flag
+/// is set.
+impl Encode for InfoReplyIp4 {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.unicast_locator.encode(enc);
+		if self.flags().multicast {
+			self.multicast_locator.unwrap_or(LOCATOR_UDP_V4_INVALID).encode(enc);
+		}
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for InfoReplyIp4 {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let unicast_locator = LocatorUDPv4::decode(dec)?;
+		let multicast_locator = if InfoReplyIp4Flags::from_bits(header.flags).multicast {
+			Some(LocatorUDPv4::decode(dec)?)
+		} else {
+			None
+		};
+
+		Ok(InfoReplyIp4 {
+			header,
+			unicast_locator,
+			multicast_locator
+		})
+	}
+}
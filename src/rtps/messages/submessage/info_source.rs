@@ -4,12 +4,14 @@
  */
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct InfoSource {
-	header: SubmessageHeader,
-	protocol_version: ProtocolVersion,
-	vendor_id: VendorId,
-	guid_prefix: GUIDPrefix,
+	pub header: SubmessageHeader,
+	pub protocol_version: ProtocolVersion,
+	pub vendor_id: VendorId,
+	pub guid_prefix: GUIDPrefix,
 }
 
 impl InfoSource {
@@ -18,16 +20,18 @@ impl InfoSource {
 	/// vender_id unique ID of the RTSP implementation.
 	///
 	/// guid_prefix to all RTPS readers/writers within the participant.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (protocol_version: ProtocolVersion,
 		        vendor_id: VendorId,
 		        guid_prefix: GUIDPrefix) -> InfoSource {
-		let flags: u8 = 0x01;
-		let msg_len: u16 = protocol_version.size() 
+		let flags = InfoSourceFlags::default();
+		// The first 4 bytes of the submessage are unused.
+		let msg_len: u16 = 4 + protocol_version.size() 
 			+ vendor_id.size() + guid_prefix.size();
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::InfoSource as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		InfoSource {
@@ -37,4 +41,33 @@ impl InfoSource {
 			guid_prefix: guid_prefix
 		}
 	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> InfoSourceFlags {
+		InfoSourceFlags::from_bits(self.header.flags)
+	}
+}
+
+impl Encode for InfoSource {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		enc.put_u32(0);
+		self.protocol_version.encode(enc);
+		self.vendor_id.encode(enc);
+		self.guid_prefix.encode(enc);
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for InfoSource {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		dec.skip(4)?;
+
+		Ok(InfoSource {
+			header,
+			protocol_version: ProtocolVersion::decode(dec)?,
+			vendor_id: VendorId::decode(dec)?,
+			guid_prefix: GUIDPrefix::decode(dec)?
+		})
+	}
 }
\ No newline at end of file
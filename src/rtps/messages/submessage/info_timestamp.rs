@@ -4,26 +4,29 @@
  that follow within the same message.
  */
 use rtps::messages::submessage::header::*;
+use rtps::messages::submessage::TIME_INVALID;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct InfoTimestamp {
-	header: SubmessageHeader,
-	timestamp: Timestamp,
+	pub header: SubmessageHeader,
+	pub timestamp: Timestamp,
 }
 
 impl InfoTimestamp {
-	///
-	/// invalidates flag is used to indicate subsequent submessages have a timestamp.
-	///
+	/// invalidates flag is used to indicate subsequent submessages should not
+	/// be considered to have a valid timestamp. When set, the timestamp is
+	/// not sent.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (invalidates: bool,
 				timestamp: Timestamp) -> InfoTimestamp {
-		let flags: u8 = 0x01
-			| ((invalidates as u8) << 1);
-		let msg_len: u16 = timestamp.size();
+		let flags = InfoTimestampFlags {invalidate: invalidates, ..Default::default()};
+		let msg_len: u16 = if invalidates {0} else {timestamp.size()};
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::InfoTimestamp as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		InfoTimestamp {
@@ -32,8 +35,41 @@ impl InfoTimestamp {
 		}
 	}
 
-	/// invalidates flag is used to indicate subsequent submessages have a timestamp.
+	/// invalidates flag is used to indicate subsequent submessages should not
+	/// be considered to have a valid timestamp.
 	pub fn invalidates(&self) -> bool {
-		self.header.flags & 0x02 == 1
+		self.flags().invalidate
+	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> InfoTimestampFlags {
+		InfoTimestampFlags::from_bits(self.header.flags)
+	}
+}
+
+impl Encode for InfoTimestamp {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		if !self.invalidates() {
+			self.timestamp.encode(enc);
+		}
+		enc.end_submessage(start);
+	}
+}
+
+/// When the timestamp has been invalidated, TIME_INVALID is used in it's
+/// place.
+impl DecodeSubmessage for InfoTimestamp {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let timestamp = if InfoTimestampFlags::from_bits(header.flags).invalidate {
+			TIME_INVALID
+		} else {
+			Timestamp::decode(dec)?
+		};
+
+		Ok(InfoTimestamp {
+			header,
+			timestamp
+		})
 	}
 }
\ No newline at end of file
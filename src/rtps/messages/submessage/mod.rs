@@ -13,13 +13,75 @@ pub mod heartbeat;
 pub mod heartbeat_frag;
 pub mod info_destination;
 pub mod info_reply;
+pub mod info_reply_ip4;
 pub mod info_source;
 pub mod info_timestamp;
 pub mod nack_frag;
 pub mod pad;
+pub mod parameter;
+pub mod unknown;
 
+use std::cmp;
+use std::ops::{Add, AddAssign, Sub};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use md5;
 use rtps::*;
 use rtps::guid::*;
+use rtps::messages::encoder::*;
+use rtps::messages::submessage::ack_nack::AckNack;
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::data_frag::DataFragment;
+use rtps::messages::submessage::gap::Gap;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use rtps::messages::submessage::heartbeat_frag::HeartbeatFrag;
+use rtps::messages::submessage::info_destination::InfoDestination;
+use rtps::messages::submessage::info_reply::InfoReply;
+use rtps::messages::submessage::info_reply_ip4::InfoReplyIp4;
+use rtps::messages::submessage::info_source::InfoSource;
+use rtps::messages::submessage::info_timestamp::InfoTimestamp;
+use rtps::messages::submessage::nack_frag::NackFrag;
+use rtps::messages::submessage::pad::Pad;
+use rtps::messages::submessage::unknown::UnknownSubmessage;
+
+/// Any one of the submessages which may appear within an RTPS message.
+pub enum Submessage {
+	AckNack(AckNack),
+	Data(Data),
+	DataFrag(DataFragment),
+	Gap(Gap),
+	Heartbeat(Heartbeat),
+	HeartbeatFrag(HeartbeatFrag),
+	InfoDestination(InfoDestination),
+	InfoReply(InfoReply),
+	InfoReplyIp4(InfoReplyIp4),
+	InfoSource(InfoSource),
+	InfoTimestamp(InfoTimestamp),
+	NackFrag(NackFrag),
+	Pad(Pad),
+	/// Vendor specific submessages, and those from later protocol versions.
+	Unknown(UnknownSubmessage),
+}
+
+impl Encode for Submessage {
+	fn encode(&self, enc: &mut Encoder) {
+		match *self {
+			Submessage::AckNack(ref s) => s.encode(enc),
+			Submessage::Data(ref s) => s.encode(enc),
+			Submessage::DataFrag(ref s) => s.encode(enc),
+			Submessage::Gap(ref s) => s.encode(enc),
+			Submessage::Heartbeat(ref s) => s.encode(enc),
+			Submessage::HeartbeatFrag(ref s) => s.encode(enc),
+			Submessage::InfoDestination(ref s) => s.encode(enc),
+			Submessage::InfoReply(ref s) => s.encode(enc),
+			Submessage::InfoReplyIp4(ref s) => s.encode(enc),
+			Submessage::InfoSource(ref s) => s.encode(enc),
+			Submessage::InfoTimestamp(ref s) => s.encode(enc),
+			Submessage::NackFrag(ref s) => s.encode(enc),
+			Submessage::Pad(ref s) => s.encode(enc),
+			Submessage::Unknown(ref s) => s.encode(enc),
+		}
+	}
+}
 
 /// The representation of time, accoring to the Network Time Protocol (NTP)
 /// standard IETF RFC 1305. Time is expressed using this formula:
@@ -27,6 +89,12 @@ use rtps::guid::*;
 /// time = seconds + (fraction / 2^(32)).
 ///
 /// TIME_ZERO corresponds to the Unix prime epoch 0h, 1 January 1970
+///
+/// Ordering follows the formula above, so TIME_INFINITE is greater than every
+/// other time, and TIME_INVALID sorts just before TIME_ZERO. Arithmetic
+/// involving TIME_INVALID is invalid, arithmetic involving TIME_INFINITE is
+/// infinite and anything which would overflow saturates to TIME_INFINITE.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Time_t {
 	seconds: i32,
 	fraction: u32,
@@ -35,16 +103,277 @@ pub const TIME_ZERO: Time_t = 		Time_t {seconds: 0, fraction: 0};
 pub const TIME_INVALID: Time_t = 	Time_t {seconds: -1, fraction: 0xffffffff};
 pub const TIME_INFINITE: Time_t = 	Time_t {seconds: 0x7fffffff, fraction: 0xffffffff};
 
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+impl Time_t {
+	pub fn new(seconds: i32, fraction: u32) -> Time_t {
+		Time_t {seconds, fraction}
+	}
+
+	/// The current time, as given by the system clock.
+	pub fn now() -> Time_t {
+		Time_t::from(SystemTime::now())
+	}
+
+	pub fn seconds(&self) -> i32 {
+		self.seconds
+	}
+
+	/// Fractions of a second, in units of 1/2^32 seconds.
+	pub fn fraction(&self) -> u32 {
+		self.fraction
+	}
+
+	pub fn is_invalid(&self) -> bool {
+		*self == TIME_INVALID
+	}
+
+	pub fn is_infinite(&self) -> bool {
+		*self == TIME_INFINITE
+	}
+
+	/// Converts to a std Duration. Returns None for negative, invalid and
+	/// infinite times, none of which have a std equivalent.
+	pub fn to_duration(&self) -> Option<Duration> {
+		if self.seconds < 0 || self.is_infinite() {
+			return None;
+		}
+		Some(Duration::new(self.seconds as u64, fraction_to_nanos(self.fraction)))
+	}
+
+	/// Converts to a SystemTime, treating self as time since the Unix epoch.
+	/// Returns None for invalid and infinite times.
+	pub fn to_system_time(&self) -> Option<SystemTime> {
+		if self.is_invalid() || self.is_infinite() {
+			return None;
+		}
+		if self.seconds >= 0 {
+			return self.to_duration().map(|d| UNIX_EPOCH + d);
+		}
+
+		// Before the epoch; the fraction still counts forward from seconds.
+		let before = Duration::from_secs(-(self.seconds as i64) as u64);
+		let after = Duration::new(0, fraction_to_nanos(self.fraction));
+		Some(UNIX_EPOCH - before + after)
+	}
+
+	/// Time as a signed count of 1/2^32 seconds.
+	fn ticks(&self) -> i64 {
+		((self.seconds as i64) << 32) | self.fraction as i64
+	}
+
+	fn from_ticks(ticks: i64) -> Time_t {
+		Time_t {seconds: (ticks >> 32) as i32, fraction: ticks as u32}
+	}
+}
+
+/// Rounds to the nearest nanosecond. The fraction has sub-nanosecond
+/// resolution, so converting nanoseconds to a fraction and back is lossless.
+fn fraction_to_nanos(fraction: u32) -> u32 {
+	let nanos = ((fraction as u64 * NANOS_PER_SEC) + (1 << 31)) >> 32;
+	cmp::min(nanos, NANOS_PER_SEC - 1) as u32
+}
+
+fn nanos_to_fraction(nanos: u32) -> u32 {
+	((((nanos as u64) << 32) + NANOS_PER_SEC / 2) / NANOS_PER_SEC) as u32
+}
+
+impl From<Duration> for Time_t {
+	/// Durations too long to be represented become TIME_INFINITE.
+	fn from(d: Duration) -> Self {
+		if d.as_secs() >= TIME_INFINITE.seconds as u64 {
+			return TIME_INFINITE;
+		}
+		Time_t {seconds: d.as_secs() as i32, fraction: nanos_to_fraction(d.subsec_nanos())}
+	}
+}
+
+impl From<SystemTime> for Time_t {
+	/// Times before the Unix epoch have negative seconds. Times too far from
+	/// the epoch to be represented become TIME_INFINITE or TIME_INVALID.
+	fn from(t: SystemTime) -> Self {
+		match t.duration_since(UNIX_EPOCH) {
+			Ok(d) => Time_t::from(d),
+			Err(e) => {
+				let d = e.duration();
+				if d.as_secs() >= 0x8000_0000 {
+					return TIME_INVALID;
+				}
+				// Never -1 tick (i.e. TIME_INVALID), as a nanosecond is
+				// several ticks.
+				TIME_ZERO - Time_t::from(d)
+			},
+		}
+	}
+}
+
+impl Ord for Time_t {
+	fn cmp(&self, other: &Time_t) -> cmp::Ordering {
+		self.ticks().cmp(&other.ticks())
+	}
+}
+
+impl PartialOrd for Time_t {
+	fn partial_cmp(&self, other: &Time_t) -> Option<cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Add for Time_t {
+	type Output = Time_t;
+
+	fn add(self, rhs: Time_t) -> Time_t {
+		if self.is_invalid() || rhs.is_invalid() {
+			return TIME_INVALID;
+		}
+		if self.is_infinite() || rhs.is_infinite() {
+			return TIME_INFINITE;
+		}
+		match self.ticks().checked_add(rhs.ticks()) {
+			Some(t) if t < TIME_INFINITE.ticks() => Time_t::from_ticks(t),
+			_ => TIME_INFINITE,
+		}
+	}
+}
+
+impl Sub for Time_t {
+	type Output = Time_t;
+
+	/// Subtracting TIME_INFINITE is invalid, as the result is meaningless.
+	/// Results earlier than the smallest representable time are also invalid.
+	/// A result of exactly -1 tick (-1 seconds and 0xffffffff fraction) can't
+	/// be represented either, as it's the encoding of TIME_INVALID.
+	fn sub(self, rhs: Time_t) -> Time_t {
+		if self.is_invalid() || rhs.is_invalid() || rhs.is_infinite() {
+			return TIME_INVALID;
+		}
+		if self.is_infinite() {
+			return TIME_INFINITE;
+		}
+		match self.ticks().checked_sub(rhs.ticks()) {
+			Some(t) if t == TIME_INVALID.ticks() => TIME_INVALID,
+			Some(t) if t >= i32::MIN as i64 * (1 << 32) => Time_t::from_ticks(t),
+			_ => TIME_INVALID,
+		}
+	}
+}
+
+impl AddAssign for Time_t {
+	fn add_assign(&mut self, rhs: Time_t) {
+		*self = *self + rhs
+	}
+}
+
 /// Used when data segments are broken into fragments.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FragmentNumber_t(u32);
 
+impl From<u32> for FragmentNumber_t {
+	fn from(n: u32) -> Self {FragmentNumber_t(n)}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Count_t(i32);
 
-pub struct KeyHash_t([u8; 16]);
+impl From<i32> for Count_t {
+	fn from(c: i32) -> Self {Count_t(c)}
+}
 
-pub struct StatusInfo_t([u8; 4]);
+impl AddAssign for Count_t {
+	fn add_assign(&mut self, rhs: Count_t) {
+		self.0 = self.0.wrapping_add(rhs.0)
+	}
+}
+
+impl Count_t {
+	/// True if self was counted after previous. Counts wrap around, so they
+	/// are compared using serial number arithmetic (see RFC 1982), rather
+	/// than by their value.
+	pub fn follows(&self, previous: Count_t) -> bool {
+		self.0.wrapping_sub(previous.0) > 0
+	}
+}
 
-pub struct ParameterId_t(i16);
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct KeyHash_t(pub [u8; 16]);
+
+/// Keys which may serialize to more than this many bytes are hashed.
+pub const KEY_HASH_MAX_PLAIN_SIZE: usize = 16;
+
+impl KeyHash_t {
+	/// Computes the key hash of an instance from it's key members, serialized
+	/// as big endian CDR (see RTPS v2.2 section 9.6.3.8).
+	///
+	/// max_size is the largest the serialized key of the type could be, or
+	/// None if it is unbounded. When it is at most 16 bytes, the key hash is
+	/// the serialized key padded with zeros. Otherwise it is the MD5 digest
+	/// of the serialized key.
+	pub fn compute(key: &[u8], max_size: Option<usize>) -> KeyHash_t {
+		let mut hash = [0u8; 16];
+		match max_size {
+			Some(max) if max <= KEY_HASH_MAX_PLAIN_SIZE && key.len() <= KEY_HASH_MAX_PLAIN_SIZE =>
+				hash[..key.len()].copy_from_slice(key),
+			_ => hash = md5::compute(key).0,
+		}
+		KeyHash_t(hash)
+	}
+}
+
+/// Flags describing the lifecycle of an instance, sent in PID_STATUS_INFO
+/// (see RTPS v2.2 section 9.6.3.9). The flags are within the last octet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StatusInfo_t(pub [u8; 4]);
+
+/// The writer has disposed of the instance.
+pub const STATUS_INFO_DISPOSED: u8 = 		0x01;
+/// The writer has unregistered the instance.
+pub const STATUS_INFO_UNREGISTERED: u8 = 	0x02;
+
+impl From<ChangeKind_t> for StatusInfo_t {
+	fn from(kind: ChangeKind_t) -> Self {
+		let flags = match kind {
+			ChangeKind_t::ALIVE => 0,
+			ChangeKind_t::NOT_ALIVE_DISPOSED => STATUS_INFO_DISPOSED,
+			ChangeKind_t::NOT_ALIVE_UNREGISTERED => STATUS_INFO_UNREGISTERED,
+		};
+		StatusInfo_t([0, 0, 0, flags])
+	}
+}
+
+/// An instance which is both disposed and unregistered is treated as
+/// disposed. Other flags are ignored.
+impl From<StatusInfo_t> for ChangeKind_t {
+	fn from(status_info: StatusInfo_t) -> Self {
+		let flags = status_info.0[3];
+		if flags & STATUS_INFO_DISPOSED != 0 {
+			ChangeKind_t::NOT_ALIVE_DISPOSED
+		} else if flags & STATUS_INFO_UNREGISTERED != 0 {
+			ChangeKind_t::NOT_ALIVE_UNREGISTERED
+		} else {
+			ChangeKind_t::ALIVE
+		}
+	}
+}
+
+/// Identifies the type of a Parameter within a ParameterList.
+///
+/// The most significant bit marks vendor specific parameters, and the next
+/// bit marks parameters which must be understood by the receiver.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParameterId_t(pub i16);
+
+impl ParameterId_t {
+	/// True if the parameter id is defined by a vendor, rather than the spec.
+	pub fn is_vendor_specific(&self) -> bool {
+		self.0 as u16 & 0x8000 != 0
+	}
+
+	/// True if a receiver which does not understand the parameter must 
+	/// ignore the parameter list containing it.
+	pub fn must_understand(&self) -> bool {
+		self.0 as u16 & 0x4000 != 0
+	}
+}
 
 /// Strings for each field must always be allocated with 256 bytes,
 /// excepting filter expression, which is variable length. 
@@ -75,7 +404,8 @@ pub struct OriginalWriterInfo_t {
 	origional_writer_sn:SequenceNumber_t,
 }
 
-pub struct BuiltinEndpointSet_t(u32);
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BuiltinEndpointSet_t(pub u32);
 pub const DISC_BUILTIN_ENDPOINT_PARTICIPANT_ANNOUNCER: BuiltinEndpointSet_t = 			BuiltinEndpointSet_t(0x00000001);
 pub const DISC_BUILTIN_ENDPOINT_PARTICIPANT_DETECTOR: BuiltinEndpointSet_t = 			BuiltinEndpointSet_t(0x00000002);
 pub const DISC_BUILTIN_ENDPOINT_PUBLICATION_ANNOUNCER: BuiltinEndpointSet_t = 			BuiltinEndpointSet_t(0x00000004);
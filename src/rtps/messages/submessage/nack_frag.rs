@@ -5,16 +5,19 @@
  submessage allows the reader to inform the writer about specific fragment 
  numbers that are still missing.
  */
+use rtps::SequenceNumber_t;
 use rtps::messages::submessage::header::*;
 use rtps::messages::submessage::element::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct NackFrag {
-	header: SubmessageHeader,
-	reader_id: EntityId,
-	writer_id: EntityId,
-	writer_sn: SequenceNumber,
-	fragment_number_state: FragmentNumberSet,
-	count: Count,
+	pub header: SubmessageHeader,
+	pub reader_id: EntityId,
+	pub writer_id: EntityId,
+	pub writer_sn: SequenceNumber,
+	pub fragment_number_state: FragmentNumberSet,
+	pub count: Count,
 }
 
 impl NackFrag {
@@ -28,18 +31,19 @@ impl NackFrag {
 	/// missing fragments on the reader side.
 	///
 	/// count allows detection of duplicate messages sent along multiple channels.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (rid: EntityId,
 				wid: EntityId,
 				writer_sn: SequenceNumber,
 				fn_state: FragmentNumberSet,
 				count: Count) -> NackFrag {
-		let flags: u8 = 0x01;
+		let flags = NackFragFlags::default();
 		let msg_len: u16 = rid.size() + wid.size() + writer_sn.size() 
 			+ fn_state.size() + count.size();
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::NackFrag as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: msg_len};
 
 		NackFrag {
@@ -51,4 +55,47 @@ impl NackFrag {
 			count: count
 		}
 	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> NackFragFlags {
+		NackFragFlags::from_bits(self.header.flags)
+	}
+}
+
+impl Encode for NackFrag {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		self.reader_id.encode(enc);
+		self.writer_id.encode(enc);
+		self.writer_sn.encode(enc);
+		self.fragment_number_state.encode(enc);
+		self.count.encode(enc);
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for NackFrag {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let reader_id = EntityId::decode(dec)?;
+		let writer_id = EntityId::decode(dec)?;
+		let writer_sn = SequenceNumber::decode(dec)?;
+		let fragment_number_state = FragmentNumberSet::decode(dec)?;
+		let count = Count::decode(dec)?;
+
+		if writer_sn < SequenceNumber_t(1) {
+			return Err(ParseError::InvalidSubmessage {
+				submessage_id: header.submessage_id,
+				reason: "writer_sn must be positive"
+			});
+		}
+
+		Ok(NackFrag {
+			header,
+			reader_id,
+			writer_id,
+			writer_sn,
+			fragment_number_state,
+			count
+		})
+	}
 }
\ No newline at end of file
@@ -4,21 +4,47 @@
  necessary to meet any desired memory allignment requirements.
  */
 use rtps::messages::submessage::header::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
 
 pub struct Pad {
-	header: SubmessageHeader,
+	pub header: SubmessageHeader,
 }
 
 impl Pad {
 	/// pad_length length of padding bytes (zeros) within this submessage.
+	#[allow(clippy::redundant_field_names)]
 	pub fn new (pad_length: u16) -> Pad {
-		let flags: u8 = 0x01;
+		let flags = PadFlags::default();
 
 		let header = SubmessageHeader{
 			submessage_id: SubmessageKind::Pad as u8,
-			flags: flags,
+			flags: flags.bits(),
 			submessage_length: pad_length};
 
 		Pad {header: header}
 	}
+
+	/// Typed view of the flags within the submessage header.
+	pub fn flags(&self) -> PadFlags {
+		PadFlags::from_bits(self.header.flags)
+	}
+}
+
+impl Encode for Pad {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		for _ in 0..self.header.submessage_length {
+			enc.put_u8(0);
+		}
+		enc.end_submessage(start);
+	}
+}
+
+impl DecodeSubmessage for Pad {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let len = dec.remaining();
+		dec.skip(len)?;
+		Ok(Pad {header})
+	}
 }
\ No newline at end of file
@@ -0,0 +1,562 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*!
+ Contains the parameter ids defined by the RTPS v2.2 spec (tables 9.12, 9.13
+ and 9.14), along with the typed value carried by each of them.
+
+ Parameters are used both for inline QoS within Data submessages, and for the
+ serialized payload of the builtin discovery endpoints. Each value is encoded
+ using CDR, aligned relative to the start of the value, and padded to a 4 byte
+ boundry.
+ */
+use bytes::Bytes;
+
+use cdr;
+use cdr::encapsulation::*;
+use cdr::decoder::*;
+use dcps::type_object::TypeInformation;
+
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::*;
+use rtps::messages::submessage::header::Endianness;
+
+/// Used to enforce alignment of parameter list data on a 4 byte boundry.
+pub const PID_PAD: ParameterId_t = 								ParameterId_t(0x0000);
+/// Used to terminate the parameter list.
+pub const PID_SENTINEL: ParameterId_t = 						ParameterId_t(0x0001);
+pub const PID_PARTICIPANT_LEASE_DURATION: ParameterId_t = 		ParameterId_t(0x0002);
+pub const PID_TIME_BASED_FILTER: ParameterId_t = 				ParameterId_t(0x0004);
+pub const PID_TOPIC_NAME: ParameterId_t = 						ParameterId_t(0x0005);
+pub const PID_OWNERSHIP_STRENGTH: ParameterId_t = 				ParameterId_t(0x0006);
+pub const PID_TYPE_NAME: ParameterId_t = 						ParameterId_t(0x0007);
+pub const PID_METATRAFFIC_MULTICAST_IPADDRESS: ParameterId_t = 	ParameterId_t(0x000b);
+pub const PID_DEFAULT_UNICAST_IPADDRESS: ParameterId_t = 		ParameterId_t(0x000c);
+pub const PID_METATRAFFIC_UNICAST_PORT: ParameterId_t = 		ParameterId_t(0x000d);
+pub const PID_DEFAULT_UNICAST_PORT: ParameterId_t = 			ParameterId_t(0x000e);
+pub const PID_MULTICAST_IPADDRESS: ParameterId_t = 				ParameterId_t(0x0011);
+pub const PID_PROTOCOL_VERSION: ParameterId_t = 				ParameterId_t(0x0015);
+pub const PID_VENDORID: ParameterId_t = 						ParameterId_t(0x0016);
+pub const PID_RELIABILITY: ParameterId_t = 						ParameterId_t(0x001a);
+pub const PID_LIVELINESS: ParameterId_t = 						ParameterId_t(0x001b);
+pub const PID_DURABILITY: ParameterId_t = 						ParameterId_t(0x001d);
+pub const PID_DURABILITY_SERVICE: ParameterId_t = 				ParameterId_t(0x001e);
+pub const PID_OWNERSHIP: ParameterId_t = 						ParameterId_t(0x001f);
+pub const PID_PRESENTATION: ParameterId_t = 					ParameterId_t(0x0021);
+pub const PID_DEADLINE: ParameterId_t = 						ParameterId_t(0x0023);
+pub const PID_DESTINATION_ORDER: ParameterId_t = 				ParameterId_t(0x0025);
+pub const PID_LATENCY_BUDGET: ParameterId_t = 					ParameterId_t(0x0027);
+pub const PID_PARTITION: ParameterId_t = 						ParameterId_t(0x0029);
+pub const PID_LIFESPAN: ParameterId_t = 						ParameterId_t(0x002b);
+pub const PID_USER_DATA: ParameterId_t = 						ParameterId_t(0x002c);
+pub const PID_GROUP_DATA: ParameterId_t = 						ParameterId_t(0x002d);
+pub const PID_TOPIC_DATA: ParameterId_t = 						ParameterId_t(0x002e);
+pub const PID_UNICAST_LOCATOR: ParameterId_t = 					ParameterId_t(0x002f);
+pub const PID_MULTICAST_LOCATOR: ParameterId_t = 				ParameterId_t(0x0030);
+pub const PID_DEFAULT_UNICAST_LOCATOR: ParameterId_t = 			ParameterId_t(0x0031);
+pub const PID_METATRAFFIC_UNICAST_LOCATOR: ParameterId_t = 		ParameterId_t(0x0032);
+pub const PID_METATRAFFIC_MULTICAST_LOCATOR: ParameterId_t = 	ParameterId_t(0x0033);
+pub const PID_PARTICIPANT_MANUAL_LIVELINESS_COUNT: ParameterId_t = ParameterId_t(0x0034);
+pub const PID_CONTENT_FILTER_PROPERTY: ParameterId_t = 			ParameterId_t(0x0035);
+pub const PID_HISTORY: ParameterId_t = 							ParameterId_t(0x0040);
+pub const PID_RESOURCE_LIMITS: ParameterId_t = 					ParameterId_t(0x0041);
+pub const PID_EXPECTS_INLINE_QOS: ParameterId_t = 				ParameterId_t(0x0043);
+pub const PID_PARTICIPANT_BUILTIN_ENDPOINTS: ParameterId_t = 	ParameterId_t(0x0044);
+pub const PID_METATRAFFIC_UNICAST_IPADDRESS: ParameterId_t = 	ParameterId_t(0x0045);
+pub const PID_METATRAFFIC_MULTICAST_PORT: ParameterId_t = 		ParameterId_t(0x0046);
+pub const PID_DEFAULT_MULTICAST_LOCATOR: ParameterId_t = 		ParameterId_t(0x0048);
+pub const PID_TRANSPORT_PRIORITY: ParameterId_t = 				ParameterId_t(0x0049);
+pub const PID_PARTICIPANT_GUID: ParameterId_t = 				ParameterId_t(0x0050);
+pub const PID_PARTICIPANT_ENTITYID: ParameterId_t = 			ParameterId_t(0x0051);
+pub const PID_GROUP_GUID: ParameterId_t = 						ParameterId_t(0x0052);
+pub const PID_GROUP_ENTITYID: ParameterId_t = 					ParameterId_t(0x0053);
+pub const PID_CONTENT_FILTER_INFO: ParameterId_t = 				ParameterId_t(0x0055);
+pub const PID_COHERENT_SET: ParameterId_t = 					ParameterId_t(0x0056);
+pub const PID_DIRECTED_WRITE: ParameterId_t = 					ParameterId_t(0x0057);
+pub const PID_BUILTIN_ENDPOINT_SET: ParameterId_t = 			ParameterId_t(0x0058);
+pub const PID_PROPERTY_LIST: ParameterId_t = 					ParameterId_t(0x0059);
+pub const PID_ENDPOINT_GUID: ParameterId_t = 					ParameterId_t(0x005a);
+pub const PID_TYPE_MAX_SIZE_SERIALIZED: ParameterId_t = 		ParameterId_t(0x0060);
+pub const PID_ORIGINAL_WRITER_INFO: ParameterId_t = 			ParameterId_t(0x0061);
+pub const PID_ENTITY_NAME: ParameterId_t = 						ParameterId_t(0x0062);
+pub const PID_KEY_HASH: ParameterId_t = 						ParameterId_t(0x0070);
+pub const PID_STATUS_INFO: ParameterId_t = 						ParameterId_t(0x0071);
+/// The XTypes TypeInformation of an endpoint's type (DDS-XTypes v1.3 section
+/// 7.6.3.2.2).
+pub const PID_TYPE_INFORMATION: ParameterId_t = 				ParameterId_t(0x0075);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DurabilityKind {
+	VOLATILE = 0,
+	TRANSIENT_LOCAL = 1,
+	TRANSIENT = 2,
+	PERSISTENT = 3,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LivelinessKind {
+	AUTOMATIC = 0,
+	MANUAL_BY_PARTICIPANT = 1,
+	MANUAL_BY_TOPIC = 2,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OwnershipKind {
+	SHARED = 0,
+	EXCLUSIVE = 1,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DestinationOrderKind {
+	BY_RECEPTION_TIMESTAMP = 0,
+	BY_SOURCE_TIMESTAMP = 1,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HistoryKind {
+	KEEP_LAST = 0,
+	KEEP_ALL = 1,
+}
+
+/// A single parameter within a ParameterList, along with it's typed value.
+///
+/// Parameters defined by the spec whose values have no typed representation
+/// are kept as Other, so they can be forwarded without loss.
+pub enum Parameter {
+	ParticipantLeaseDuration(Duration_t),
+	TimeBasedFilter(Duration_t),
+	TopicName(String),
+	OwnershipStrength(i32),
+	TypeName(String),
+	ProtocolVersion(ProtocolVersion_t),
+	VendorId(VendorId_t),
+	/// The reliability kind along with the max_blocking_time.
+	Reliability(ReliabilityKind_t, Duration_t),
+	/// The liveliness kind along with the lease_duration.
+	Liveliness(LivelinessKind, Duration_t),
+	Durability(DurabilityKind),
+	Ownership(OwnershipKind),
+	Deadline(Duration_t),
+	DestinationOrder(DestinationOrderKind),
+	LatencyBudget(Duration_t),
+	Partition(Vec<String>),
+	Lifespan(Duration_t),
+	UserData(Vec<u8>),
+	GroupData(Vec<u8>),
+	TopicData(Vec<u8>),
+	UnicastLocator(Locator_t),
+	MulticastLocator(Locator_t),
+	DefaultUnicastLocator(Locator_t),
+	DefaultMulticastLocator(Locator_t),
+	MetatrafficUnicastLocator(Locator_t),
+	MetatrafficMulticastLocator(Locator_t),
+	ParticipantManualLivelinessCount(Count_t),
+	/// The history kind along with the depth.
+	History(HistoryKind, i32),
+	/// max_samples, max_instances and max_samples_per_instance.
+	ResourceLimits(i32, i32, i32),
+	ExpectsInlineQos(bool),
+	ParticipantBuiltinEndpoints(BuiltinEndpointSet_t),
+	TransportPriority(i32),
+	ParticipantGuid(GUID_t),
+	ParticipantEntityId(EntityId_t),
+	GroupGuid(GUID_t),
+	GroupEntityId(EntityId_t),
+	BuiltinEndpointSet(BuiltinEndpointSet_t),
+	EndpointGuid(GUID_t),
+	TypeMaxSizeSerialized(i32),
+	EntityName(String),
+	KeyHash(KeyHash_t),
+	StatusInfo(StatusInfo_t),
+	TypeInformation(TypeInformation),
+	Other(ParameterId_t, Bytes),
+}
+
+impl Parameter {
+	/// The parameter id used to identify this parameter on the wire.
+	pub fn parameter_id(&self) -> ParameterId_t {
+		match *self {
+			Parameter::ParticipantLeaseDuration(_) => PID_PARTICIPANT_LEASE_DURATION,
+			Parameter::TimeBasedFilter(_) => PID_TIME_BASED_FILTER,
+			Parameter::TopicName(_) => PID_TOPIC_NAME,
+			Parameter::OwnershipStrength(_) => PID_OWNERSHIP_STRENGTH,
+			Parameter::TypeName(_) => PID_TYPE_NAME,
+			Parameter::ProtocolVersion(_) => PID_PROTOCOL_VERSION,
+			Parameter::VendorId(_) => PID_VENDORID,
+			Parameter::Reliability(..) => PID_RELIABILITY,
+			Parameter::Liveliness(..) => PID_LIVELINESS,
+			Parameter::Durability(_) => PID_DURABILITY,
+			Parameter::Ownership(_) => PID_OWNERSHIP,
+			Parameter::Deadline(_) => PID_DEADLINE,
+			Parameter::DestinationOrder(_) => PID_DESTINATION_ORDER,
+			Parameter::LatencyBudget(_) => PID_LATENCY_BUDGET,
+			Parameter::Partition(_) => PID_PARTITION,
+			Parameter::Lifespan(_) => PID_LIFESPAN,
+			Parameter::UserData(_) => PID_USER_DATA,
+			Parameter::GroupData(_) => PID_GROUP_DATA,
+			Parameter::TopicData(_) => PID_TOPIC_DATA,
+			Parameter::UnicastLocator(_) => PID_UNICAST_LOCATOR,
+			Parameter::MulticastLocator(_) => PID_MULTICAST_LOCATOR,
+			Parameter::DefaultUnicastLocator(_) => PID_DEFAULT_UNICAST_LOCATOR,
+			Parameter::DefaultMulticastLocator(_) => PID_DEFAULT_MULTICAST_LOCATOR,
+			Parameter::MetatrafficUnicastLocator(_) => PID_METATRAFFIC_UNICAST_LOCATOR,
+			Parameter::MetatrafficMulticastLocator(_) => PID_METATRAFFIC_MULTICAST_LOCATOR,
+			Parameter::ParticipantManualLivelinessCount(_) => PID_PARTICIPANT_MANUAL_LIVELINESS_COUNT,
+			Parameter::History(..) => PID_HISTORY,
+			Parameter::ResourceLimits(..) => PID_RESOURCE_LIMITS,
+			Parameter::ExpectsInlineQos(_) => PID_EXPECTS_INLINE_QOS,
+			Parameter::ParticipantBuiltinEndpoints(_) => PID_PARTICIPANT_BUILTIN_ENDPOINTS,
+			Parameter::TransportPriority(_) => PID_TRANSPORT_PRIORITY,
+			Parameter::ParticipantGuid(_) => PID_PARTICIPANT_GUID,
+			Parameter::ParticipantEntityId(_) => PID_PARTICIPANT_ENTITYID,
+			Parameter::GroupGuid(_) => PID_GROUP_GUID,
+			Parameter::GroupEntityId(_) => PID_GROUP_ENTITYID,
+			Parameter::BuiltinEndpointSet(_) => PID_BUILTIN_ENDPOINT_SET,
+			Parameter::EndpointGuid(_) => PID_ENDPOINT_GUID,
+			Parameter::TypeMaxSizeSerialized(_) => PID_TYPE_MAX_SIZE_SERIALIZED,
+			Parameter::EntityName(_) => PID_ENTITY_NAME,
+			Parameter::KeyHash(_) => PID_KEY_HASH,
+			Parameter::StatusInfo(_) => PID_STATUS_INFO,
+			Parameter::TypeInformation(_) => PID_TYPE_INFORMATION,
+			Parameter::Other(pid, _) => pid,
+		}
+	}
+
+	/// Size of the parameter on the wire, including the parameter id, length
+	/// and padding.
+	pub fn size (&self) -> u16 {
+		4 + padded(self.value_size()) as u16
+	}
+
+	/// Size of just the value of the parameter, without any padding.
+	fn value_size(&self) -> usize {
+		match *self {
+			Parameter::ParticipantLeaseDuration(_) |
+			Parameter::TimeBasedFilter(_) |
+			Parameter::Deadline(_) |
+			Parameter::LatencyBudget(_) |
+			Parameter::Lifespan(_) => 8,
+			Parameter::TopicName(ref s) |
+			Parameter::TypeName(ref s) |
+			Parameter::EntityName(ref s) => string_size(s),
+			Parameter::OwnershipStrength(_) |
+			Parameter::TransportPriority(_) |
+			Parameter::TypeMaxSizeSerialized(_) => 4,
+			Parameter::ProtocolVersion(_) |
+			Parameter::VendorId(_) => 2,
+			Parameter::Reliability(..) |
+			Parameter::Liveliness(..) => 12,
+			Parameter::Durability(_) |
+			Parameter::Ownership(_) |
+			Parameter::DestinationOrder(_) => 4,
+			// Each name is aligned relative to the start of the value.
+			Parameter::Partition(ref names) => names.iter()
+				.fold(4, |len, name| padded(len) + string_size(name)),
+			Parameter::UserData(ref v) |
+			Parameter::GroupData(ref v) |
+			Parameter::TopicData(ref v) => 4 + v.len(),
+			Parameter::UnicastLocator(_) |
+			Parameter::MulticastLocator(_) |
+			Parameter::DefaultUnicastLocator(_) |
+			Parameter::DefaultMulticastLocator(_) |
+			Parameter::MetatrafficUnicastLocator(_) |
+			Parameter::MetatrafficMulticastLocator(_) => 24,
+			Parameter::ParticipantManualLivelinessCount(_) => 4,
+			Parameter::History(..) => 8,
+			Parameter::ResourceLimits(..) => 12,
+			Parameter::ExpectsInlineQos(_) => 1,
+			Parameter::ParticipantBuiltinEndpoints(_) |
+			Parameter::BuiltinEndpointSet(_) => 4,
+			Parameter::ParticipantGuid(_) |
+			Parameter::GroupGuid(_) |
+			Parameter::EndpointGuid(_) => 16,
+			Parameter::ParticipantEntityId(_) |
+			Parameter::GroupEntityId(_) => 4,
+			Parameter::KeyHash(_) => 16,
+			Parameter::StatusInfo(_) => 4,
+			// The size of a TypeInformation depends on it's dependencies, so 
+			// it has to be serialized (the byte order doesn't matter).
+			Parameter::TypeInformation(ref t) => {
+				cdr::to_payload(t, Encapsulation::CDR2_LE).len() - ENCAPSULATION_HEADER_SIZE
+			},
+			Parameter::Other(_, ref v) => v.len(),
+		}
+	}
+
+	/// Encodes just the value of the parameter, without any padding.
+	fn encode_value(&self, enc: &mut Encoder) {
+		match *self {
+			Parameter::ParticipantLeaseDuration(ref d) |
+			Parameter::TimeBasedFilter(ref d) |
+			Parameter::Deadline(ref d) |
+			Parameter::LatencyBudget(ref d) |
+			Parameter::Lifespan(ref d) => d.encode(enc),
+			Parameter::TopicName(ref s) |
+			Parameter::TypeName(ref s) |
+			Parameter::EntityName(ref s) => put_string(enc, s),
+			Parameter::OwnershipStrength(v) |
+			Parameter::TransportPriority(v) |
+			Parameter::TypeMaxSizeSerialized(v) => enc.put_i32(v),
+			Parameter::ProtocolVersion(ref v) => v.encode(enc),
+			Parameter::VendorId(ref v) => v.encode(enc),
+			Parameter::Reliability(kind, ref max_blocking_time) => {
+				enc.put_u32(match kind {
+					ReliabilityKind_t::BEST_EFFORT => 1,
+					ReliabilityKind_t::RELIABLE => 2,
+				});
+				max_blocking_time.encode(enc);
+			},
+			Parameter::Liveliness(kind, ref lease_duration) => {
+				enc.put_u32(kind as u32);
+				lease_duration.encode(enc);
+			},
+			Parameter::Durability(kind) => enc.put_u32(kind as u32),
+			Parameter::Ownership(kind) => enc.put_u32(kind as u32),
+			Parameter::DestinationOrder(kind) => enc.put_u32(kind as u32),
+			Parameter::Partition(ref names) => {
+				let start = enc.len();
+				enc.put_u32(names.len() as u32);
+				for name in names {
+					enc.align(start, 4);
+					put_string(enc, name);
+				}
+			},
+			Parameter::UserData(ref v) |
+			Parameter::GroupData(ref v) |
+			Parameter::TopicData(ref v) => {
+				enc.put_u32(v.len() as u32);
+				enc.put_slice(v);
+			},
+			Parameter::UnicastLocator(ref l) |
+			Parameter::MulticastLocator(ref l) |
+			Parameter::DefaultUnicastLocator(ref l) |
+			Parameter::DefaultMulticastLocator(ref l) |
+			Parameter::MetatrafficUnicastLocator(ref l) |
+			Parameter::MetatrafficMulticastLocator(ref l) => l.encode(enc),
+			Parameter::ParticipantManualLivelinessCount(ref c) => c.encode(enc),
+			Parameter::History(kind, depth) => {
+				enc.put_u32(kind as u32);
+				enc.put_i32(depth);
+			},
+			Parameter::ResourceLimits(max_samples, max_instances, max_samples_per_instance) => {
+				enc.put_i32(max_samples);
+				enc.put_i32(max_instances);
+				enc.put_i32(max_samples_per_instance);
+			},
+			Parameter::ExpectsInlineQos(b) => enc.put_u8(b as u8),
+			Parameter::ParticipantBuiltinEndpoints(s) |
+			Parameter::BuiltinEndpointSet(s) => enc.put_u32(s.0),
+			Parameter::ParticipantGuid(ref g) |
+			Parameter::GroupGuid(ref g) |
+			Parameter::EndpointGuid(ref g) => g.encode(enc),
+			Parameter::ParticipantEntityId(ref e) |
+			Parameter::GroupEntityId(ref e) => e.encode(enc),
+			Parameter::KeyHash(ref k) => enc.put_slice(&k.0),
+			Parameter::StatusInfo(ref s) => enc.put_slice(&s.0),
+			Parameter::TypeInformation(ref t) => {
+				let payload = cdr::to_payload(t, xcdr2(enc.endianness()));
+				enc.put_slice(&payload[ENCAPSULATION_HEADER_SIZE..]);
+			},
+			Parameter::Other(_, ref v) => enc.put_slice(v),
+		}
+	}
+
+	/// Decodes the value of a parameter given it's parameter id. The decoder
+	/// must only contain the value.
+	///
+	/// Returns None if the parameter id is not understood by this
+	/// implementation (including PID_PAD).
+	pub fn decode_value(parameter_id: ParameterId_t, dec: &mut Decoder)
+			-> Result<Option<Parameter>, ParseError> {
+		let p = match parameter_id {
+			PID_PARTICIPANT_LEASE_DURATION => Parameter::ParticipantLeaseDuration(Duration_t::decode(dec)?),
+			PID_TIME_BASED_FILTER => Parameter::TimeBasedFilter(Duration_t::decode(dec)?),
+			PID_TOPIC_NAME => Parameter::TopicName(get_string(dec)?),
+			PID_OWNERSHIP_STRENGTH => Parameter::OwnershipStrength(dec.get_i32()?),
+			PID_TYPE_NAME => Parameter::TypeName(get_string(dec)?),
+			PID_PROTOCOL_VERSION => Parameter::ProtocolVersion(ProtocolVersion_t::decode(dec)?),
+			PID_VENDORID => Parameter::VendorId(VendorId_t::decode(dec)?),
+			PID_RELIABILITY => {
+				let kind = match dec.get_u32()? {
+					1 => ReliabilityKind_t::BEST_EFFORT,
+					2 => ReliabilityKind_t::RELIABLE,
+					_ => return Err(ParseError::InvalidElement("unknown reliability kind")),
+				};
+				Parameter::Reliability(kind, Duration_t::decode(dec)?)
+			},
+			PID_LIVELINESS => {
+				let kind = match dec.get_u32()? {
+					0 => LivelinessKind::AUTOMATIC,
+					1 => LivelinessKind::MANUAL_BY_PARTICIPANT,
+					2 => LivelinessKind::MANUAL_BY_TOPIC,
+					_ => return Err(ParseError::InvalidElement("unknown liveliness kind")),
+				};
+				Parameter::Liveliness(kind, Duration_t::decode(dec)?)
+			},
+			PID_DURABILITY => Parameter::Durability(match dec.get_u32()? {
+				0 => DurabilityKind::VOLATILE,
+				1 => DurabilityKind::TRANSIENT_LOCAL,
+				2 => DurabilityKind::TRANSIENT,
+				3 => DurabilityKind::PERSISTENT,
+				_ => return Err(ParseError::InvalidElement("unknown durability kind")),
+			}),
+			PID_OWNERSHIP => Parameter::Ownership(match dec.get_u32()? {
+				0 => OwnershipKind::SHARED,
+				1 => OwnershipKind::EXCLUSIVE,
+				_ => return Err(ParseError::InvalidElement("unknown ownership kind")),
+			}),
+			PID_DEADLINE => Parameter::Deadline(Duration_t::decode(dec)?),
+			PID_DESTINATION_ORDER => Parameter::DestinationOrder(match dec.get_u32()? {
+				0 => DestinationOrderKind::BY_RECEPTION_TIMESTAMP,
+				1 => DestinationOrderKind::BY_SOURCE_TIMESTAMP,
+				_ => return Err(ParseError::InvalidElement("unknown destination order kind")),
+			}),
+			PID_LATENCY_BUDGET => Parameter::LatencyBudget(Duration_t::decode(dec)?),
+			PID_PARTITION => {
+				let count = dec.get_u32()? as usize;
+				// Every name takes at least 5 bytes.
+				if count > dec.remaining() / 5 {
+					return Err(ParseError::InvalidElement("too many partition names"));
+				}
+
+				let mut names = Vec::with_capacity(count);
+				for _ in 0..count {
+					dec.align(4)?;
+					names.push(get_string(dec)?);
+				}
+				Parameter::Partition(names)
+			},
+			PID_LIFESPAN => Parameter::Lifespan(Duration_t::decode(dec)?),
+			PID_USER_DATA => Parameter::UserData(get_octet_seq(dec)?),
+			PID_GROUP_DATA => Parameter::GroupData(get_octet_seq(dec)?),
+			PID_TOPIC_DATA => Parameter::TopicData(get_octet_seq(dec)?),
+			PID_UNICAST_LOCATOR => Parameter::UnicastLocator(Locator_t::decode(dec)?),
+			PID_MULTICAST_LOCATOR => Parameter::MulticastLocator(Locator_t::decode(dec)?),
+			PID_DEFAULT_UNICAST_LOCATOR => Parameter::DefaultUnicastLocator(Locator_t::decode(dec)?),
+			PID_DEFAULT_MULTICAST_LOCATOR => Parameter::DefaultMulticastLocator(Locator_t::decode(dec)?),
+			PID_METATRAFFIC_UNICAST_LOCATOR => Parameter::MetatrafficUnicastLocator(Locator_t::decode(dec)?),
+			PID_METATRAFFIC_MULTICAST_LOCATOR => Parameter::MetatrafficMulticastLocator(Locator_t::decode(dec)?),
+			PID_PARTICIPANT_MANUAL_LIVELINESS_COUNT => Parameter::ParticipantManualLivelinessCount(Count_t::decode(dec)?),
+			PID_HISTORY => {
+				let kind = match dec.get_u32()? {
+					0 => HistoryKind::KEEP_LAST,
+					1 => HistoryKind::KEEP_ALL,
+					_ => return Err(ParseError::InvalidElement("unknown history kind")),
+				};
+				Parameter::History(kind, dec.get_i32()?)
+			},
+			PID_RESOURCE_LIMITS => Parameter::ResourceLimits(dec.get_i32()?, dec.get_i32()?, dec.get_i32()?),
+			PID_EXPECTS_INLINE_QOS => Parameter::ExpectsInlineQos(dec.get_u8()? != 0),
+			PID_PARTICIPANT_BUILTIN_ENDPOINTS => Parameter::ParticipantBuiltinEndpoints(BuiltinEndpointSet_t(dec.get_u32()?)),
+			PID_TRANSPORT_PRIORITY => Parameter::TransportPriority(dec.get_i32()?),
+			PID_PARTICIPANT_GUID => Parameter::ParticipantGuid(GUID_t::decode(dec)?),
+			PID_PARTICIPANT_ENTITYID => Parameter::ParticipantEntityId(EntityId_t::decode(dec)?),
+			PID_GROUP_GUID => Parameter::GroupGuid(GUID_t::decode(dec)?),
+			PID_GROUP_ENTITYID => Parameter::GroupEntityId(EntityId_t::decode(dec)?),
+			PID_BUILTIN_ENDPOINT_SET => Parameter::BuiltinEndpointSet(BuiltinEndpointSet_t(dec.get_u32()?)),
+			PID_ENDPOINT_GUID => Parameter::EndpointGuid(GUID_t::decode(dec)?),
+			PID_TYPE_MAX_SIZE_SERIALIZED => Parameter::TypeMaxSizeSerialized(dec.get_i32()?),
+			PID_ENTITY_NAME => Parameter::EntityName(get_string(dec)?),
+			PID_KEY_HASH => {
+				let mut k = [0u8; 16];
+				dec.get_octets(&mut k)?;
+				Parameter::KeyHash(KeyHash_t(k))
+			},
+			PID_STATUS_INFO => {
+				let mut s = [0u8; 4];
+				dec.get_octets(&mut s)?;
+				Parameter::StatusInfo(StatusInfo_t(s))
+			},
+			PID_TYPE_INFORMATION => {
+				let len = dec.remaining();
+				let mut cdr = CdrDecoder::new(dec.get_bytes(len)?, xcdr2(dec.endianness()));
+				Parameter::TypeInformation(TypeInformation::deserialize(&mut cdr)
+					.map_err(|_| ParseError::InvalidElement("invalid type information"))?)
+			},
+			PID_METATRAFFIC_MULTICAST_IPADDRESS |
+			PID_DEFAULT_UNICAST_IPADDRESS |
+			PID_METATRAFFIC_UNICAST_PORT |
+			PID_DEFAULT_UNICAST_PORT |
+			PID_MULTICAST_IPADDRESS |
+			PID_DURABILITY_SERVICE |
+			PID_PRESENTATION |
+			PID_CONTENT_FILTER_PROPERTY |
+			PID_METATRAFFIC_UNICAST_IPADDRESS |
+			PID_METATRAFFIC_MULTICAST_PORT |
+			PID_CONTENT_FILTER_INFO |
+			PID_COHERENT_SET |
+			PID_DIRECTED_WRITE |
+			PID_PROPERTY_LIST |
+			PID_ORIGINAL_WRITER_INFO => {
+				let len = dec.remaining();
+				Parameter::Other(parameter_id, dec.get_bytes(len)?)
+			},
+			_ => return Ok(None),
+		};
+		Ok(Some(p))
+	}
+}
+
+/// The parameter length is always a multiple of 4, so the value is padded
+/// accordingly.
+impl Encode for Parameter {
+	fn encode(&self, enc: &mut Encoder) {
+		enc.put_i16(self.parameter_id().0);
+		let length_at = enc.len();
+		enc.put_u16(0);
+
+		let start = enc.len();
+		self.encode_value(enc);
+		enc.align(start, 4);
+
+		let length = (enc.len() - start) as u16;
+		enc.put_u16_at(length_at, length);
+	}
+}
+
+/// TypeInformation is written as XCDR2, in the byte order of the parameter
+/// list.
+fn xcdr2(endianness: Endianness) -> Encapsulation {
+	match endianness {
+		Endianness::BigEndian => Encapsulation::CDR2_BE,
+		Endianness::LittleEndian => Encapsulation::CDR2_LE,
+	}
+}
+
+/// Rounds len up to the next 4 byte boundry.
+fn padded(len: usize) -> usize {
+	(len + 3) & !3
+}
+
+/// Size of a CDR string, including it's length and the terminating NUL.
+fn string_size(s: &str) -> usize {
+	4 + s.len() + 1
+}
+
+/// CDR strings are preceeded by their length (including the terminating
+/// NUL) as a u32.
+fn put_string(enc: &mut Encoder, s: &str) {
+	enc.put_u32(s.len() as u32 + 1);
+	enc.put_slice(s.as_bytes());
+	enc.put_u8(0);
+}
+
+fn get_string(dec: &mut Decoder) -> Result<String, ParseError> {
+	let len = dec.get_u32()? as usize;
+	let b = dec.get_bytes(len)?;
+	let s = match b.split_last() {
+		Some((&0, s)) => s,
+		Some(_) => return Err(ParseError::InvalidElement("string is not NUL terminated")),
+		None => &[],
+	};
+
+	String::from_utf8(s.to_vec())
+		.map_err(|_| ParseError::InvalidElement("string is not valid UTF-8"))
+}
+
+/// Sequences of octets are preceeded by their length as a u32.
+fn get_octet_seq(dec: &mut Decoder) -> Result<Vec<u8>, ParseError> {
+	let len = dec.get_u32()? as usize;
+	Ok(dec.get_bytes(len)?.to_vec())
+}
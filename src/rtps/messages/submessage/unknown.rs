@@ -0,0 +1,62 @@
+// Copyright © Travis Gruber 2017, All Rights Reserved.
+/*! 
+ Holds a submessage this implementation doesn't understand, either because
+ it's vendor specific (ids 0x80 to 0xff), or because it was added by a later
+ version of the protocol. The contents are kept as-is, so the submessage can
+ be skipped or forwarded without disturbing the rest of the message.
+ */
+use bytes::Bytes;
+use rtps::messages::submessage::header::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+
+/// Submessage ids from here up are reserved for vendor specific submessages.
+pub const SUBMESSAGE_VENDOR_SPECIFIC_MIN: u8 = 0x80;
+
+pub struct UnknownSubmessage {
+	pub header: SubmessageHeader,
+	pub contents: Bytes,
+}
+
+impl UnknownSubmessage {
+	/// submessage_id and flags are passed through as given, contents follow
+	/// the header and are padded to the next 4 byte boundry when encoded.
+	pub fn new (submessage_id: u8,
+				flags: u8,
+				contents: Bytes) -> UnknownSubmessage {
+		let header = SubmessageHeader{
+			submessage_id,
+			flags,
+			submessage_length: contents.len() as u16};
+
+		UnknownSubmessage {
+			header,
+			contents
+		}
+	}
+
+	/// True if the submessage_id is within the vendor specific range, in 
+	/// which case it's meaning depends on the vendor_id of the source.
+	pub fn is_vendor_specific(&self) -> bool {
+		self.header.submessage_id >= SUBMESSAGE_VENDOR_SPECIFIC_MIN
+	}
+}
+
+impl Encode for UnknownSubmessage {
+	fn encode(&self, enc: &mut Encoder) {
+		let start = enc.begin_submessage(&self.header);
+		enc.put_slice(&self.contents);
+		enc.end_submessage(start);
+	}
+}
+
+/// Takes the remainder of the submessage, without copying.
+impl DecodeSubmessage for UnknownSubmessage {
+	fn decode(header: SubmessageHeader, dec: &mut Decoder) -> Result<Self, ParseError> {
+		let len = dec.remaining();
+		Ok(UnknownSubmessage {
+			header,
+			contents: dec.get_bytes(len)?
+		})
+	}
+}
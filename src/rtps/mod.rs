@@ -7,8 +7,9 @@
   website).
  */
 use std::ops::AddAssign;
+use std::net::{SocketAddr, IpAddr, Ipv4Addr, Ipv6Addr};
 use bytes::Bytes;
-use rtps::messages::submessage::Time_t;
+use rtps::messages::submessage::{Time_t, TIME_ZERO, TIME_INFINITE, KeyHash_t};
 
 pub mod entity;
 pub mod messages;
@@ -17,12 +18,12 @@ pub mod history_cache;
 
 /// Two-byte value identifying the DDS vender and/or implementation,
 /// (i.e. OCI, RTI, PrismTech, Thales)
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct VendorId_t(u16);
 pub const VENDORID_UNKNOWN: VendorId_t = VendorId_t(0);
 
 /// 64bit (signed) sequence number
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SequenceNumber_t(i64);
 pub const SEQUENCE_NUMBER_UNKNOWN: SequenceNumber_t = SequenceNumber_t(-1);
 
@@ -38,23 +39,152 @@ impl AddAssign for SequenceNumber_t {
 
 
 /// Used to express the IP address and port of a remote endpoint.
+///
+/// UDP locators are the only kind this implementation sends to. Any other
+/// kind (reserved or vendor specific) is kept as-is, so it can be passed
+/// along in discovery data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Locator_t {
-	Invalid(i32),
+	Invalid,
 	UDP_V4 {port: u16, address: [u8; 4]},
 	UDP_V6 {port: u16, address: [u8; 16]},
+	Other {kind: i32, port: u32, address: [u8; 16]},
 }
-pub const LOCATOR_INVALID: Locator_t = 		Locator_t::Invalid(-1);
+pub const LOCATOR_INVALID: Locator_t = 		Locator_t::Invalid;
+
+pub const LOCATOR_KIND_INVALID: i32 = 		-1;
+pub const LOCATOR_KIND_RESERVED: i32 = 		0;
+pub const LOCATOR_KIND_UDP_V4: i32 = 		1;
+pub const LOCATOR_KIND_UDP_V6: i32 = 		2;
+/// Kinds with the most significant bit set are reserved for vendor specific
+/// transports.
+pub const LOCATOR_KIND_VENDOR_SPECIFIC: i32 = 	0x8000_0000u32 as i32;
+pub const LOCATOR_PORT_INVALID: u32 = 		0;
+pub const LOCATOR_ADDRESS_INVALID: [u8; 16] = 	[0; 16];
 
 impl Locator_t {
+	/// Every kind of locator is encoded using 24 bytes: a 4 byte kind, a 4
+	/// byte port and a 16 byte address.
 	pub fn size(&self) -> u16 {
+		24
+	}
+
+	/// Builds a locator from the kind, port and address as they appear on the
+	/// wire. Returns None for UDP locators with a port that doesn't fit in 16
+	/// bits.
+	pub fn from_parts(kind: i32, port: u32, address: [u8; 16]) -> Option<Locator_t> {
+		match kind {
+			LOCATOR_KIND_INVALID => Some(Locator_t::Invalid),
+			LOCATOR_KIND_UDP_V4 | LOCATOR_KIND_UDP_V6 if port > u16::MAX as u32 => None,
+			LOCATOR_KIND_UDP_V4 => {
+				let mut v4 = [0u8; 4];
+				v4.copy_from_slice(&address[12..16]);
+				Some(Locator_t::UDP_V4 {port: port as u16, address: v4})
+			},
+			LOCATOR_KIND_UDP_V6 => Some(Locator_t::UDP_V6 {port: port as u16, address}),
+			_ => Some(Locator_t::Other {kind, port, address}),
+		}
+	}
+
+	pub fn kind(&self) -> i32 {
+		match *self {
+			Locator_t::Invalid => LOCATOR_KIND_INVALID,
+			Locator_t::UDP_V4 {..} => LOCATOR_KIND_UDP_V4,
+			Locator_t::UDP_V6 {..} => LOCATOR_KIND_UDP_V6,
+			Locator_t::Other {kind, ..} => kind,
+		}
+	}
+
+	pub fn port(&self) -> u32 {
+		match *self {
+			Locator_t::Invalid => LOCATOR_PORT_INVALID,
+			Locator_t::UDP_V4 {port, ..} | Locator_t::UDP_V6 {port, ..} => port as u32,
+			Locator_t::Other {port, ..} => port,
+		}
+	}
+
+	/// The 16 byte address. IPv4 addresses occupy the last 4 bytes, the rest
+	/// being zero.
+	pub fn address(&self) -> [u8; 16] {
+		match *self {
+			Locator_t::Invalid => LOCATOR_ADDRESS_INVALID,
+			Locator_t::UDP_V4 {address, ..} => {
+				let mut a = [0u8; 16];
+				a[12..16].copy_from_slice(&address);
+				a
+			},
+			Locator_t::UDP_V6 {address, ..} | Locator_t::Other {address, ..} => address,
+		}
+	}
+
+	/// True if the kind is reserved for vendor specific transports.
+	pub fn is_vendor_specific(&self) -> bool {
+		match *self {
+			Locator_t::Other {kind, ..} => kind != LOCATOR_KIND_INVALID &&
+										  kind & LOCATOR_KIND_VENDOR_SPECIFIC != 0,
+			_ => false,
+		}
+	}
+
+	/// True for UDP locators with a multicast address.
+	pub fn is_multicast(&self) -> bool {
 		match *self {
-			Locator_t::UDP_V4{..} => 10 as u16,
-			Locator_t::UDP_V6{..} => 22 as u16,
-			Locator_t::Invalid(_) => 4 as u16,
+			Locator_t::UDP_V4 {address, ..} => Ipv4Addr::from(address).is_multicast(),
+			Locator_t::UDP_V6 {address, ..} => Ipv6Addr::from(address).is_multicast(),
+			_ => false,
+		}
+	}
+
+	/// The address to send to, if this is a UDP locator.
+	pub fn socket_addr(&self) -> Option<SocketAddr> {
+		match *self {
+			Locator_t::UDP_V4 {port, address} => Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::from(address)), port)),
+			Locator_t::UDP_V6 {port, address} => Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(address)), port)),
+			_ => None,
+		}
+	}
+}
+
+impl From<SocketAddr> for Locator_t {
+	fn from(addr: SocketAddr) -> Self {
+		match addr.ip() {
+			IpAddr::V4(a) => Locator_t::UDP_V4 {port: addr.port(), address: a.octets()},
+			IpAddr::V6(a) => Locator_t::UDP_V6 {port: addr.port(), address: a.octets()},
 		}
 	}
 }
 
+/// Compact form of a UDP/IPv4 locator, used by the InfoReplyIp4 submessage.
+/// The address is held as a u32, i.e. 127.0.0.1 = 0x7f000001.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocatorUDPv4_t {
+	pub address: u32,
+	pub port: u32,
+}
+pub const LOCATOR_UDP_V4_INVALID: LocatorUDPv4_t = 	LocatorUDPv4_t {address: 0, port: 0};
+
+impl LocatorUDPv4_t {
+	/// The compact form of locator, if it's a UDP/IPv4 locator.
+	pub fn from_locator(locator: &Locator_t) -> Option<LocatorUDPv4_t> {
+		match *locator {
+			Locator_t::UDP_V4 {port, address} => Some(LocatorUDPv4_t {
+				address: u32::from_be_bytes(address),
+				port: port as u32
+			}),
+			_ => None,
+		}
+	}
+
+	/// The full form of the locator. Returns None for LOCATOR_UDP_V4_INVALID
+	/// and ports which don't fit in 16 bits.
+	pub fn to_locator(&self) -> Option<Locator_t> {
+		if *self == LOCATOR_UDP_V4_INVALID || self.port > u16::MAX as u32 {
+			return None;
+		}
+		Some(Locator_t::UDP_V4 {port: self.port as u16, address: self.address.to_be_bytes()})
+	}
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TopicKind_t {
 	NO_KEY = 1,
@@ -74,8 +204,28 @@ pub enum ReliabilityKind_t {
 	RELIABLE = 3,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct InstanceHandle_t(i64);
+/// Identifies an instance (i.e. a key value) of a topic, locally.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceHandle_t(pub i64);
+/// The handle of the only instance of a NO_KEY topic.
+pub const HANDLE_NIL: InstanceHandle_t = InstanceHandle_t(0);
+
+/// Instances are identified by the 64 bit FNV-1a hash of their key hash, so
+/// equal keys always give the same handle, no matter which participant
+/// computed the key hash. HANDLE_NIL is never used for keyed instances.
+impl From<KeyHash_t> for InstanceHandle_t {
+	fn from(key_hash: KeyHash_t) -> Self {
+		let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+		for b in key_hash.0.iter() {
+			h ^= *b as u64;
+			h = h.wrapping_mul(0x0000_0100_0000_01b3);
+		}
+		if h == 0 {
+			h = 1;
+		}
+		InstanceHandle_t(h as i64)
+	}
+}
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ProtocolVersion_t {
@@ -93,6 +243,8 @@ pub type Data = Bytes;
 pub type ParticipantMessageData = Bytes;
 
 pub type Duration_t = Time_t;
+pub const DURATION_ZERO: Duration_t = 		TIME_ZERO;
+pub const DURATION_INFINITE: Duration_t = 	TIME_INFINITE;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ChangeForReaderStatusKind {
@@ -121,7 +273,7 @@ pub mod guid {
 use rtps::*;
 use rand::{Rng, thread_rng};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GUID_t {
 	prefix: GUIDPrefix_t,
 	entity_id: EntityId_t,
@@ -141,11 +293,21 @@ impl GUID_t {
 	}
 
 	pub fn size (&self) -> u16 {
-		16 as u16
+		16
+	}
+
+	/// The prefix shared by all entities within the participant.
+	pub fn prefix(&self) -> &GUIDPrefix_t {
+		&self.prefix
+	}
+
+	/// Identifies the entity within the participant.
+	pub fn entity_id(&self) -> &EntityId_t {
+		&self.entity_id
 	}
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct GUIDPrefix_t {
 	vendor_id: VendorId_t,
 	bytes: [u8; 10],
@@ -162,6 +324,7 @@ impl GUIDPrefix_t {
 	/// 
 	/// When generating multiple GUIDs for the same participant, be sure to 
 	/// reuse the generated `GUIDPrefix_t` and not call this again.
+	#[allow(clippy::new_without_default)]
 	pub fn new () -> GUIDPrefix_t {
 		// Generate a unique prefix.
 		let mut b = [0u8; 10];
@@ -173,11 +336,26 @@ impl GUIDPrefix_t {
 	}
 
 	pub fn size (&self) -> u16 {
-		12 as u16
+		12
+	}
+
+	/// The 12 octets of the prefix, as they appear on the wire.
+	pub fn to_bytes(&self) -> [u8; 12] {
+		let mut b = [0u8; 12];
+		b[0..2].copy_from_slice(&(self.vendor_id.0).to_be_bytes());
+		b[2..12].copy_from_slice(&self.bytes);
+		b
+	}
+
+	/// Reconstructs a prefix from the 12 octets found on the wire.
+	pub fn from_bytes(b: [u8; 12]) -> GUIDPrefix_t {
+		let mut bytes = [0u8; 10];
+		bytes.copy_from_slice(&b[2..12]);
+		GUIDPrefix_t {vendor_id: VendorId_t(u16::from_be_bytes([b[0], b[1]])), bytes}
 	}
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EntityId_t {
 	key: [u8; 3],
 	kind: EntityKind_t,
@@ -202,11 +380,22 @@ impl EntityId_t {
 	}
 
 	pub fn size (&self) -> u16 {
-		4 as u16
+		4
+	}
+
+	/// The 4 octets of the entity id (key followed by kind), as they appear
+	/// on the wire.
+	pub fn to_bytes(&self) -> [u8; 4] {
+		[self.key[0], self.key[1], self.key[2], self.kind.0]
+	}
+
+	/// Reconstructs an entity id from the 4 octets found on the wire.
+	pub fn from_bytes(b: [u8; 4]) -> EntityId_t {
+		EntityId_t {key: [b[0], b[1], b[2]], kind: EntityKind_t(b[3])}
 	}
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EntityKind_t(u8);
 
 pub const ENTITY_KIND_WRITER_WITH_KEY: EntityKind_t =			EntityKind_t(0x02);
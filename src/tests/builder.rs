@@ -0,0 +1,112 @@
+use bytes::Bytes;
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::builder::*;
+use rtps::messages::header::Header;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::{Submessage, Time_t};
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+
+fn heartbeat() -> Heartbeat {
+	Heartbeat::new(false, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		SequenceNumber_t::from(1),
+		From::from(1))
+}
+
+/// A short name for each submessage within a datagram.
+fn kinds(datagram: &Bytes) -> Vec<&'static str> {
+	parse_message(datagram.clone()).unwrap().submessages.iter().map(|s| match *s {
+		Submessage::InfoDestination(_) => "dst",
+		Submessage::InfoTimestamp(ref t) if t.invalidates() => "ts-",
+		Submessage::InfoTimestamp(_) => "ts",
+		Submessage::Heartbeat(_) => "hb",
+		Submessage::Data(_) => "data",
+		_ => "other",
+	}).collect()
+}
+
+#[test]
+fn context_only_inserted_on_change() {
+	let mut builder = MessageBuilder::new(Header::new(GUIDPrefix_t::new()));
+	builder.set_destination(GUIDPrefix_t::new());
+	builder.set_timestamp(Some(Time_t::new(10, 0)));
+	builder.add(&heartbeat()).unwrap();
+	builder.add(&heartbeat()).unwrap();
+
+	builder.set_timestamp(Some(Time_t::new(10, 0)));
+	builder.add(&heartbeat()).unwrap();
+
+	builder.set_timestamp(None);
+	builder.set_destination(GUIDPREFIX_UNKNOWN);
+	builder.add(&heartbeat()).unwrap();
+
+	let datagrams = builder.finish();
+	assert_eq!(datagrams.len(), 1);
+	assert_eq!(kinds(&datagrams[0]), vec!["dst", "ts", "hb", "hb", "hb", "dst", "ts-", "hb"]);
+}
+
+#[test]
+fn context_repeated_in_new_datagram() {
+	// Room for the header, InfoDestination, InfoTimestamp and two heartbeats.
+	let mut builder = MessageBuilder::with_max_size(Header::new(GUIDPrefix_t::new()), 20 + 16 + 12 + 64);
+	builder.set_destination(GUIDPrefix_t::new());
+	builder.set_timestamp(Some(Time_t::now()));
+	for _ in 0..5 {
+		builder.add(&heartbeat()).unwrap();
+	}
+
+	let datagrams = builder.finish();
+	assert_eq!(datagrams.len(), 3);
+	assert_eq!(datagrams[0].len(), 112);
+	assert_eq!(kinds(&datagrams[0]), vec!["dst", "ts", "hb", "hb"]);
+	assert_eq!(kinds(&datagrams[1]), vec!["dst", "ts", "hb", "hb"]);
+	assert_eq!(kinds(&datagrams[2]), vec!["dst", "ts", "hb"]);
+}
+
+#[test]
+fn oversized_submessage() {
+	let data = Data::new(false, true, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		None,
+		Some(Bytes::from(vec![0u8; 2000])));
+
+	let mut builder = MessageBuilder::new(Header::new(GUIDPrefix_t::new()));
+	builder.add(&heartbeat()).unwrap();
+	match builder.add(&data) {
+		Err(BuildError::SubmessageTooLarge{size, max_size}) => {
+			assert_eq!(size, 20 + 24 + 2000);
+			assert_eq!(max_size, DEFAULT_MAX_MESSAGE_SIZE);
+		},
+		_ => panic!("expected SubmessageTooLarge"),
+	}
+
+	// The heartbeat was still sent, on it's own.
+	let datagrams = builder.finish();
+	assert_eq!(datagrams.len(), 1);
+	assert_eq!(kinds(&datagrams[0]), vec!["hb"]);
+}
+
+#[test]
+fn overlong_submessage_ends_datagram() {
+	let data = Data::new(false, true, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		None,
+		Some(Bytes::from(vec![0u8; 70000])));
+
+	let mut builder = MessageBuilder::with_max_size(Header::new(GUIDPrefix_t::new()), 200000);
+	builder.add(&heartbeat()).unwrap();
+	builder.add(&data).unwrap();
+	builder.add(&heartbeat()).unwrap();
+	let datagrams = builder.finish();
+	assert_eq!(datagrams.len(), 2);
+	assert_eq!(kinds(&datagrams[0]), vec!["hb", "data"]);
+	assert_eq!(kinds(&datagrams[1]), vec!["hb"]);
+}
@@ -0,0 +1,196 @@
+use bytes::Bytes;
+use cdr;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+use cdr::decoder::*;
+
+#[derive(Debug, PartialEq)]
+struct Point {
+	x: i32,
+	y: i32,
+}
+
+impl CdrSerialize for Point {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		self.x.serialize(enc);
+		self.y.serialize(enc);
+	}
+}
+
+impl CdrDeserialize for Point {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		Ok(Point {x: i32::deserialize(dec)?, y: i32::deserialize(dec)?})
+	}
+}
+
+#[derive(Debug, PartialEq)]
+struct Shape {
+	color: String,
+	size: u8,
+	id: u64,
+	origin: Point,
+	path: Vec<Point>,
+	tags: [u16; 3],
+	scale: f64,
+}
+
+impl CdrSerialize for Shape {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		self.color.serialize(enc);
+		self.size.serialize(enc);
+		self.id.serialize(enc);
+		self.origin.serialize(enc);
+		self.path.serialize(enc);
+		self.tags.serialize(enc);
+		self.scale.serialize(enc);
+	}
+}
+
+impl CdrDeserialize for Shape {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		Ok(Shape {
+			color: String::deserialize(dec)?,
+			size: u8::deserialize(dec)?,
+			id: u64::deserialize(dec)?,
+			origin: Point::deserialize(dec)?,
+			path: Vec::deserialize(dec)?,
+			tags: <[u16; 3]>::deserialize(dec)?,
+			scale: f64::deserialize(dec)?,
+		})
+	}
+}
+
+/// A mutable type, encoded as a parameter list.
+#[derive(Debug, PartialEq)]
+struct Settings {
+	name: String,
+	limit: Option<u32>,
+	blob: Vec<u8>,
+}
+
+impl CdrSerialize for Settings {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let members = enc.begin_members();
+		let m = enc.begin_member(1, true);
+		self.name.serialize(enc);
+		enc.end_member(m);
+		enc.put_optional(2, &self.limit);
+		let m = enc.begin_member(0x4000_0003, false);
+		self.blob.serialize(enc);
+		enc.end_member(m);
+		enc.end_members(members);
+	}
+}
+
+impl CdrDeserialize for Settings {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let mut s = Settings {name: String::new(), limit: None, blob: Vec::new()};
+		let members = dec.begin_members()?;
+		while let Some(mut m) = dec.next_member()? {
+			match m.id {
+				1 => s.name = String::deserialize(&mut m.value)?,
+				2 => s.limit = if m.value.remaining() == 0 {None} else {Some(u32::deserialize(&mut m.value)?)},
+				0x4000_0003 => s.blob = Vec::deserialize(&mut m.value)?,
+				_ if m.must_understand => return Err(CdrError::Invalid("unknown member")),
+				_ => {},
+			}
+		}
+		dec.end_members(members)?;
+		Ok(s)
+	}
+}
+
+fn shape() -> Shape {
+	Shape {
+		color: String::from("BLUE"),
+		size: 30,
+		id: 0x0102030405060708,
+		origin: Point {x: -1, y: 2},
+		path: vec![Point {x: 3, y: 4}, Point {x: 5, y: 6}],
+		tags: [7, 8, 9],
+		scale: 1.5,
+	}
+}
+
+#[test]
+fn aligned_big_endian_layout() {
+	let payload = cdr::to_payload(&shape(), Encapsulation::CDR_BE);
+	assert_eq!(&payload[0..4], &[0x00, 0x00, 0, 0]);
+	let body = &payload[4..];
+
+	assert_eq!(&body[0..9], &[0, 0, 0, 5, b'B', b'L', b'U', b'E', 0]);
+	assert_eq!(body[9], 30);
+	// u64 aligned to 8, after 6 bytes of padding.
+	assert_eq!(&body[10..16], &[0; 6]);
+	assert_eq!(&body[16..24], &[1, 2, 3, 4, 5, 6, 7, 8]);
+	assert_eq!(&body[24..32], &[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 2]);
+	assert_eq!(&body[32..36], &[0, 0, 0, 2]);
+	assert_eq!(&body[52..58], &[0, 7, 0, 8, 0, 9]);
+	assert_eq!(&body[58..64], &[0; 6]);
+	assert_eq!(body.len(), 72);
+}
+
+#[test]
+fn struct_round_trip() {
+	for e in &[Encapsulation::CDR_BE, Encapsulation::CDR_LE] {
+		let payload = cdr::to_payload(&shape(), *e);
+		assert_eq!(cdr::from_payload::<Shape>(payload).unwrap(), shape());
+	}
+
+	let le = cdr::to_payload(&shape(), Encapsulation::CDR_LE);
+	assert_eq!(&le[0..4], &[0x00, 0x01, 0, 0]);
+	assert_eq!(&le[4..8], &[5, 0, 0, 0]);
+}
+
+#[test]
+fn parameter_list_round_trip() {
+	let settings = Settings {name: String::from("a"), limit: Some(9), blob: vec![1; 70000]};
+	let empty = Settings {name: String::from("b"), limit: None, blob: Vec::new()};
+
+	for e in &[Encapsulation::PL_CDR_BE, Encapsulation::PL_CDR_LE] {
+		for s in &[&settings, &empty] {
+			let payload = cdr::to_payload(*s, *e);
+			assert_eq!(payload.len() % 4, 0);
+			assert_eq!(&cdr::from_payload::<Settings>(payload).unwrap(), *s);
+		}
+	}
+
+	let payload = cdr::to_payload(&empty, Encapsulation::PL_CDR_BE);
+	assert_eq!(&payload[4..], &[0x40, 0x01, 0, 8, 0, 0, 0, 2, b'b', 0, 0, 0,
+								0x00, 0x02, 0, 0,
+								0x3f, 0x01, 0, 8, 0x40, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 0,
+								0x3f, 0x02, 0, 0][..]);
+}
+
+#[test]
+fn unknown_members_are_skipped() {
+	// An unknown member, an ignored member, then the name.
+	let payload = Bytes::from(&[0x00, 0x02, 0, 0,
+								0x00, 0x09, 0, 4, 1, 2, 3, 4,
+								0x3f, 0x03, 0, 4, 0, 0, 0, 0,
+								0x40, 0x01, 0, 8, 0, 0, 0, 2, b'c', 0, 0, 0,
+								0x3f, 0x02, 0, 0][..]);
+	let s = cdr::from_payload::<Settings>(payload).unwrap();
+	assert_eq!(s.name, "c");
+
+	let rejected = Bytes::from(&[0x00, 0x02, 0, 0, 0x40, 0x09, 0, 0, 0x3f, 0x02, 0, 0][..]);
+	assert!(cdr::from_payload::<Settings>(rejected).is_err());
+}
+
+#[test]
+fn invalid_payloads() {
+	let unknown = Bytes::from(&[0x00, 0x04, 0, 0, 1][..]);
+	assert_eq!(cdr::from_payload::<u8>(unknown).err(), Some(CdrError::UnsupportedEncapsulation(4)));
+
+	let short = Bytes::from(&[0x00, 0x00, 0, 0, 0, 0][..]);
+	assert_eq!(cdr::from_payload::<u32>(short).err(), Some(CdrError::UnexpectedEnd {needed: 4, remaining: 2}));
+
+	let bad_bool = Bytes::from(&[0x00, 0x00, 0, 0, 2][..]);
+	assert!(cdr::from_payload::<bool>(bad_bool).is_err());
+
+	let huge_sequence = Bytes::from(&[0x00, 0x00, 0, 0, 0xff, 0xff, 0xff, 0xff][..]);
+	assert!(cdr::from_payload::<Vec<u8>>(huge_sequence).is_err());
+
+	let unterminated = Bytes::from(&[0x00, 0x00, 0, 0, 0, 0, 0, 1, b'a'][..]);
+	assert!(cdr::from_payload::<String>(unterminated).is_err());
+}
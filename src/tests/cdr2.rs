@@ -0,0 +1,274 @@
+use bytes::Bytes;
+use cdr;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+use cdr::decoder::*;
+
+#[derive(Debug, PartialEq)]
+struct Point {
+	x: i32,
+	y: i32,
+}
+
+impl CdrSerialize for Point {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		self.x.serialize(enc);
+		self.y.serialize(enc);
+	}
+}
+
+impl CdrDeserialize for Point {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		Ok(Point {x: i32::deserialize(dec)?, y: i32::deserialize(dec)?})
+	}
+}
+
+/// A final type.
+#[derive(Debug, PartialEq)]
+struct Track {
+	size: u8,
+	id: u64,
+	path: Vec<Point>,
+	label: Option<String>,
+}
+
+impl CdrSerialize for Track {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		self.size.serialize(enc);
+		self.id.serialize(enc);
+		self.path.serialize(enc);
+		enc.put_optional(4, &self.label);
+	}
+}
+
+impl CdrDeserialize for Track {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		Ok(Track {
+			size: u8::deserialize(dec)?,
+			id: u64::deserialize(dec)?,
+			path: Vec::deserialize(dec)?,
+			label: dec.get_optional(4)?,
+		})
+	}
+}
+
+/// The first version of an appendable type.
+#[derive(Debug, PartialEq)]
+struct PositionV1 {
+	x: i32,
+}
+
+impl CdrSerialize for PositionV1 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let d = enc.begin_delimited();
+		self.x.serialize(enc);
+		enc.end_delimited(d);
+	}
+}
+
+impl CdrDeserialize for PositionV1 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = dec.begin_delimited()?;
+		let p = PositionV1 {x: i32::deserialize(dec)?};
+		dec.end_delimited(d)?;
+		Ok(p)
+	}
+}
+
+/// The second version of the appendable type, with a member appended.
+#[derive(Debug, PartialEq)]
+struct PositionV2 {
+	x: i32,
+	z: i32,
+}
+
+impl CdrSerialize for PositionV2 {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let d = enc.begin_delimited();
+		self.x.serialize(enc);
+		self.z.serialize(enc);
+		enc.end_delimited(d);
+	}
+}
+
+impl CdrDeserialize for PositionV2 {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let d = dec.begin_delimited()?;
+		let x = i32::deserialize(dec)?;
+		let z = if dec.remaining() == 0 {0} else {i32::deserialize(dec)?};
+		dec.end_delimited(d)?;
+		Ok(PositionV2 {x, z})
+	}
+}
+
+/// A mutable type. Absent optional members are omitted.
+#[derive(Debug, PartialEq)]
+struct Settings {
+	name: String,
+	limit: Option<u64>,
+	positions: Vec<PositionV1>,
+}
+
+impl CdrSerialize for Settings {
+	fn serialize(&self, enc: &mut CdrEncoder) {
+		let members = enc.begin_members();
+		let m = enc.begin_member(1, true);
+		self.name.serialize(enc);
+		enc.end_member(m);
+		if let Some(limit) = self.limit {
+			let m = enc.begin_member(2, false);
+			limit.serialize(enc);
+			enc.end_member(m);
+		}
+		let m = enc.begin_member(3, false);
+		self.positions.serialize(enc);
+		enc.end_member(m);
+		enc.end_members(members);
+	}
+}
+
+impl CdrDeserialize for Settings {
+	fn deserialize(dec: &mut CdrDecoder) -> Result<Self, CdrError> {
+		let mut s = Settings {name: String::new(), limit: None, positions: Vec::new()};
+		let members = dec.begin_members()?;
+		while let Some(mut m) = dec.next_member()? {
+			match m.id {
+				1 => s.name = String::deserialize(&mut m.value)?,
+				2 => s.limit = Some(u64::deserialize(&mut m.value)?),
+				3 => s.positions = Vec::deserialize(&mut m.value)?,
+				_ if m.must_understand => return Err(CdrError::Invalid("unknown member")),
+				_ => {},
+			}
+		}
+		dec.end_members(members)?;
+		Ok(s)
+	}
+}
+
+fn track() -> Track {
+	Track {
+		size: 7,
+		id: 0x0102030405060708,
+		path: vec![Point {x: 1, y: 2}],
+		label: Some(String::from("ab")),
+	}
+}
+
+#[test]
+fn final_type_layout() {
+	let payload = cdr::to_payload(&track(), Encapsulation::CDR2_BE);
+	// Padded by 1 byte, recorded in the options.
+	assert_eq!(&payload[0..4], &[0x00, 0x10, 0, 1]);
+	assert_eq!(&payload[4..], &[7, 0, 0, 0,
+								// u64 aligned to 4.
+								1, 2, 3, 4, 5, 6, 7, 8,
+								// DHEADER, then the sequence of non-primitives.
+								0, 0, 0, 12, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2,
+								// is_present, then the optional string.
+								1, 0, 0, 0, 0, 0, 0, 3, b'a', b'b', 0, 0][..]);
+
+	let absent = Track {label: None, ..track()};
+	for e in &[Encapsulation::CDR2_BE, Encapsulation::CDR2_LE] {
+		for t in &[track(), Track {label: None, ..track()}] {
+			let payload = cdr::to_payload(t, *e);
+			assert_eq!(payload.len() % 4, 0);
+			assert_eq!(&cdr::from_payload::<Track>(payload).unwrap(), t);
+		}
+	}
+	assert_eq!(cdr::to_payload(&absent, Encapsulation::CDR2_LE).len(), 33 + 3);
+}
+
+#[test]
+fn appendable_types_evolve() {
+	let v2 = PositionV2 {x: 5, z: 6};
+	let payload = cdr::to_payload(&v2, Encapsulation::D_CDR2_LE);
+	assert_eq!(&payload[..], &[0x00, 0x15, 0, 0, 8, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0][..]);
+
+	// An old reader skips the appended member.
+	let v1: Vec<PositionV1> = cdr::from_payload(
+		cdr::to_payload(&vec![v2], Encapsulation::D_CDR2_BE)).unwrap();
+	assert_eq!(v1, vec![PositionV1 {x: 5}]);
+
+	// A new reader sees it is missing.
+	let v2: PositionV2 = cdr::from_payload(
+		cdr::to_payload(&PositionV1 {x: 3}, Encapsulation::D_CDR2_BE)).unwrap();
+	assert_eq!(v2, PositionV2 {x: 3, z: 0});
+
+	// XCDR1 has no DHEADER.
+	let xcdr1 = cdr::to_payload(&PositionV1 {x: 3}, Encapsulation::CDR_BE);
+	assert_eq!(&xcdr1[4..], &[0, 0, 0, 3]);
+}
+
+#[test]
+fn mutable_type_round_trip() {
+	let settings = Settings {
+		name: String::from("a"),
+		limit: Some(9),
+		positions: vec![PositionV1 {x: 1}, PositionV1 {x: 2}],
+	};
+	let empty = Settings {name: String::from("b"), limit: None, positions: Vec::new()};
+
+	for e in &[Encapsulation::PL_CDR2_BE, Encapsulation::PL_CDR2_LE,
+			   Encapsulation::PL_CDR_BE, Encapsulation::PL_CDR_LE] {
+		for s in &[&settings, &empty] {
+			let payload = cdr::to_payload(*s, *e);
+			assert_eq!(&cdr::from_payload::<Settings>(payload).unwrap(), *s);
+		}
+	}
+
+	let payload = cdr::to_payload(&empty, Encapsulation::PL_CDR2_BE);
+	assert_eq!(&payload[..], &[0x00, 0x12, 0, 0,
+							   0, 0, 0, 32,
+							   0xc0, 0, 0, 1, 0, 0, 0, 6, 0, 0, 0, 2, b'b', 0,
+							   0, 0,
+							   0x40, 0, 0, 3, 0, 0, 0, 8, 0, 0, 0, 4, 0, 0, 0, 0][..]);
+}
+
+#[test]
+fn length_codes() {
+	// Members written with length codes other than NEXTINT, as other
+	// implementations do.
+	let payload = Bytes::from(&[0x00, 0x13, 0, 0,
+								53, 0, 0, 0,
+								// LC 5, the string length is the NEXTINT.
+								0x01, 0, 0, 0x50, 2, 0, 0, 0, b'c', 0, 0, 0,
+								// LC 3, an 8 byte member.
+								0x02, 0, 0, 0x30, 4, 0, 0, 0, 0, 0, 0, 0,
+								// LC 5, the DHEADER of the sequence is the NEXTINT.
+								0x03, 0, 0, 0x50, 4, 0, 0, 0, 0, 0, 0, 0,
+								// LC 6, an unknown sequence of 4 byte elements.
+								0x09, 0, 0, 0x60, 1, 0, 0, 0, 1, 2, 3, 4,
+								// LC 0, an unknown 1 byte member.
+								0x0a, 0, 0, 0x00, 0xff][..]);
+	let s = cdr::from_payload::<Settings>(payload).unwrap();
+	assert_eq!(s, Settings {name: String::from("c"), limit: Some(4), positions: Vec::new()});
+
+	// The DHEADER is longer than the payload.
+	let short = Bytes::from(&[0x00, 0x13, 0, 0, 44, 0, 0, 0, 0x01, 0, 0, 0x00][..]);
+	assert!(cdr::from_payload::<Settings>(short).is_err());
+
+	// An unknown member the reader must understand.
+	let rejected = Bytes::from(&[0x00, 0x13, 0, 0, 5, 0, 0, 0, 0x09, 0, 0, 0x80, 0][..]);
+	assert!(cdr::from_payload::<Settings>(rejected).is_err());
+}
+
+#[test]
+fn multi_dimensional_arrays() {
+	// Written as a single array of the innermost element type, so there is
+	// at most one DHEADER, and none for primitives.
+	let plain: [[i32; 2]; 2] = [[1, 2], [3, 4]];
+	let payload = cdr::to_payload(&plain, Encapsulation::CDR2_BE);
+	assert_eq!(&payload[4..], &[0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4][..]);
+	assert_eq!(cdr::from_payload::<[[i32; 2]; 2]>(payload).unwrap(), plain);
+
+	let points = [[Point {x: 1, y: 2}], [Point {x: 3, y: 4}]];
+	let payload = cdr::to_payload(&points, Encapsulation::CDR2_BE);
+	assert_eq!(&payload[4..], &[0, 0, 0, 16, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4][..]);
+	assert_eq!(cdr::from_payload::<[[Point; 1]; 2]>(payload).unwrap(), points);
+
+	// The elements of a sequence of arrays aren't primitive.
+	let rows = vec![[1i32, 2]];
+	let payload = cdr::to_payload(&rows, Encapsulation::CDR2_BE);
+	assert_eq!(&payload[4..], &[0, 0, 0, 12, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2][..]);
+	assert_eq!(cdr::from_payload::<Vec<[i32; 2]>>(payload).unwrap(), rows);
+}
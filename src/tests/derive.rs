@@ -0,0 +1,84 @@
+use cdr;
+use cdr::encapsulation::*;
+use dcps::dynamic_type::*;
+use dcps::type_support::DdsType;
+use rtps::*;
+
+#[derive(DdsType, Debug, PartialEq)]
+#[dds(type_name = "ShapeType")]
+struct Shape {
+	#[key]
+	color: String,
+	x: i32,
+	y: i32,
+	size: i32,
+}
+
+#[derive(DdsType, Debug, PartialEq)]
+struct Reading(#[key] u32, #[key] u8, f64);
+
+#[derive(DdsType, Debug, PartialEq)]
+struct Heartbeat {
+	count: u64,
+	labels: Vec<String>,
+}
+
+fn shape(color: &str, x: i32) -> Shape {
+	Shape {color: String::from(color), x, y: 2, size: 30}
+}
+
+#[test]
+fn type_name_and_topic_kind() {
+	assert_eq!(Shape::type_name(), "ShapeType");
+	assert_eq!(Shape::topic_kind(), TopicKind_t::WITH_KEY);
+	assert_eq!(Reading::type_name(), "Reading");
+	assert_eq!(Reading::topic_kind(), TopicKind_t::WITH_KEY);
+	assert_eq!(Heartbeat::type_name(), "Heartbeat");
+	assert_eq!(Heartbeat::topic_kind(), TopicKind_t::NO_KEY);
+}
+
+#[test]
+fn derived_serialization_round_trip() {
+	let s = shape("RED", 1);
+	let payload = cdr::to_payload(&s, Encapsulation::CDR_BE);
+	assert_eq!(&payload[4..], &[0, 0, 0, 4, b'R', b'E', b'D', 0,
+								0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 30][..]);
+	assert_eq!(cdr::from_payload::<Shape>(payload).unwrap(), s);
+
+	let r = Reading(7, 3, 2.5);
+	let h = Heartbeat {count: 9, labels: vec![String::from("a")]};
+	for e in &[Encapsulation::CDR_LE, Encapsulation::CDR2_BE] {
+		assert_eq!(cdr::from_payload::<Reading>(cdr::to_payload(&r, *e)).unwrap(), r);
+		assert_eq!(cdr::from_payload::<Heartbeat>(cdr::to_payload(&h, *e)).unwrap(), h);
+	}
+}
+
+#[test]
+fn keys_identify_instances() {
+	assert_eq!(&shape("RED", 1).key()[..], &[0, 0, 0, 4, b'R', b'E', b'D', 0][..]);
+	assert_eq!(&Reading(7, 3, 2.5).key()[..], &[0, 0, 0, 7, 3][..]);
+	assert!(Heartbeat {count: 1, labels: Vec::new()}.key().is_empty());
+
+	assert_eq!(shape("RED", 1).instance_handle(), shape("RED", 5).instance_handle());
+	assert!(shape("RED", 1).instance_handle() != shape("BLUE", 1).instance_handle());
+	assert_eq!(Heartbeat {count: 1, labels: Vec::new()}.instance_handle(), HANDLE_NIL);
+}
+
+#[test]
+fn derived_types_describe_themselves() {
+	let ty = Shape::dynamic_type();
+	assert_eq!(ty.name(), "ShapeType");
+	assert_eq!(ty.kind(), TypeKind::TK_STRUCTURE);
+	let color = ty.member_by_name("color").unwrap();
+	assert!(color.is_key);
+	assert_eq!(color.member_type, DynamicType::string(None));
+	assert_eq!(ty.member_by_name("size").unwrap().id, 3);
+
+	let reading = Reading::dynamic_type();
+	assert_eq!(reading.member_by_name("2").unwrap().member_type, DynamicType::primitive(TypeKind::TK_FLOAT64));
+	let heartbeat = Heartbeat::dynamic_type();
+	assert_eq!(heartbeat.member_by_name("labels").unwrap().member_type,
+			   DynamicType::sequence(DynamicType::string(None), None));
+
+	assert!(Shape::type_information() != Reading::type_information());
+}
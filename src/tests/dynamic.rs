@@ -0,0 +1,224 @@
+use bytes::Bytes;
+use cdr;
+use cdr::encapsulation::*;
+use dcps::dynamic_type::*;
+use dcps::dynamic_data::*;
+use tests::idl_shapes::shapes::*;
+use tests::shape;
+use tests::idl_shapes::shapes::extensible::*;
+
+fn int32() -> DynamicType {
+	DynamicType::primitive(TypeKind::TK_INT32)
+}
+
+fn shape_kind() -> DynamicType {
+	DynamicType::enumeration("shapes::ShapeKind", &["CIRCLE", "SQUARE", "TRIANGLE"])
+}
+
+fn point() -> DynamicType {
+	DynamicType::structure("shapes::Point", Extensibility::Final, None, vec![
+		MemberDescriptor::new("x", 0, int32()),
+		MemberDescriptor::new("y", 1, int32()),
+	])
+}
+
+/// shapes::ShapeType, as described by shapes.idl.
+fn shape_type() -> DynamicType {
+	DynamicType::structure("shapes::ShapeType", Extensibility::Final, None, vec![
+		MemberDescriptor {
+			is_key: true,
+			..MemberDescriptor::new("color", 0, DynamicType::alias("shapes::Color", DynamicType::string(Some(8))))
+		},
+		MemberDescriptor::new("x", 1, int32()),
+		MemberDescriptor::new("y", 2, int32()),
+		MemberDescriptor::new("shapesize", 3, int32()),
+		MemberDescriptor::new("kind", 4, shape_kind()),
+		MemberDescriptor::new("trail", 5, DynamicType::sequence(point(), Some(4))),
+		MemberDescriptor::new("transform", 6, DynamicType::array(int32(), &[2, 3])),
+		MemberDescriptor::new("type", 7, DynamicType::primitive(TypeKind::TK_UINT64)),
+	])
+}
+
+/// shapes::extensible::Drawing, as described by shapes.idl.
+fn drawing_type() -> DynamicType {
+	let outline = DynamicType::union("shapes::extensible::Outline", Extensibility::Final, shape_kind(), vec![
+		MemberDescriptor {label: vec![0], ..MemberDescriptor::new("radius", 0, int32())},
+		MemberDescriptor {label: vec![1, 2], ..MemberDescriptor::new("corners", 1, DynamicType::sequence(point(), Some(3)))},
+	]);
+	let label = DynamicType::union("shapes::extensible::Label", Extensibility::Appendable,
+								   DynamicType::primitive(TypeKind::TK_INT16), vec![
+		MemberDescriptor {label: vec![1], ..MemberDescriptor::new("code", 0, DynamicType::string(Some(4)))},
+		MemberDescriptor {label: vec![2], ..MemberDescriptor::new("initial", 1, DynamicType::primitive(TypeKind::TK_CHAR8))},
+		MemberDescriptor {is_default_label: true, ..MemberDescriptor::new("text", 2, DynamicType::string(None))},
+	]);
+	let base = DynamicType::structure("shapes::extensible::Base", Extensibility::Appendable, None, vec![
+		MemberDescriptor {is_key: true, ..MemberDescriptor::new("id", 0, int32())},
+		MemberDescriptor {is_optional: true, ..MemberDescriptor::new("note", 1, DynamicType::string(None))},
+	]);
+	let marker = DynamicType::structure("shapes::extensible::Marker", Extensibility::Appendable, Some(base), vec![
+		MemberDescriptor {
+			default_value: Some(Value::Int16(2)),
+			..MemberDescriptor::new("layer", 2, DynamicType::primitive(TypeKind::TK_INT16))
+		},
+		MemberDescriptor::new("label", 3, label),
+	]);
+	DynamicType::structure("shapes::extensible::Drawing", Extensibility::Mutable, None, vec![
+		MemberDescriptor {is_key: true, ..MemberDescriptor::new("name", 10, DynamicType::string(Some(8)))},
+		MemberDescriptor::new("outline", 11, outline),
+		MemberDescriptor {is_optional: true, ..MemberDescriptor::new("opacity", 12, DynamicType::primitive(TypeKind::TK_FLOAT64))},
+		MemberDescriptor {default_value: Some(Value::Enum(1)), ..MemberDescriptor::new("kind", 13, shape_kind())},
+		MemberDescriptor::new("markers", 14, DynamicType::sequence(marker, None)),
+	])
+}
+
+#[test]
+fn reads_generated_types() {
+	for e in &[Encapsulation::CDR_BE, Encapsulation::CDR_LE, Encapsulation::CDR2_LE] {
+		let payload = cdr::to_payload(&shape(), *e);
+		let sample = DynamicData::from_payload(&shape_type(), payload.clone()).unwrap();
+		assert_eq!(sample.get("color"), Some(&Value::String(String::from("RED"))));
+		assert_eq!(sample.get("kind"), Some(&Value::Enum(2)));
+		assert_eq!(sample.get("trail"), Some(&Value::Sequence(vec![
+			Value::Struct(vec![(String::from("x"), Value::Int32(3)), (String::from("y"), Value::Int32(4))])
+		])));
+		assert_eq!(sample.get("type"), Some(&Value::UInt64(7)));
+		assert_eq!(sample.get("missing"), None);
+
+		// Written back exactly as the generated type writes it.
+		assert_eq!(sample.to_payload(*e), payload);
+	}
+
+	let drawing = Drawing {
+		name: String::from("sketch"),
+		outline: Outline::corners(vec![Point {x: 0, y: 1}]),
+		opacity: None,
+		kind: ShapeKind::CIRCLE,
+		markers: vec![
+			Marker {id: 1, note: Some(String::from("n")), layer: 3, label: Label::initial(b'q')},
+			Marker {id: 2, note: None, layer: 2, label: Label::text(String::from("start"))},
+		],
+	};
+	for e in &[Encapsulation::PL_CDR_LE, Encapsulation::PL_CDR2_BE] {
+		let payload = cdr::to_payload(&drawing, *e);
+		let sample = DynamicData::from_payload(&drawing_type(), payload.clone()).unwrap();
+		assert_eq!(sample.get("outline.corners"), Some(&Value::Sequence(vec![
+			Value::Struct(vec![(String::from("x"), Value::Int32(0)), (String::from("y"), Value::Int32(1))])
+		])));
+		assert_eq!(sample.get("outline.radius"), None);
+		assert_eq!(sample.get("opacity"), None);
+		assert_eq!(sample.to_payload(*e), payload);
+	}
+}
+
+#[test]
+fn field_access_by_name() {
+	let mut sample = DynamicData::new(shape_type());
+	assert_eq!(sample.get("color"), Some(&Value::String(String::new())));
+	assert_eq!(sample.get("transform"), Some(&Value::Sequence(vec![
+		Value::Sequence(vec![Value::Int32(0); 3]),
+		Value::Sequence(vec![Value::Int32(0); 3]),
+	])));
+
+	sample.set("color", Value::String(String::from("RED"))).unwrap();
+	sample.set("x", Value::Int32(1)).unwrap();
+	sample.set("y", Value::Int32(2)).unwrap();
+	sample.set("shapesize", Value::Int32(30)).unwrap();
+	sample.set("kind", Value::Enum(2)).unwrap();
+	sample.set("trail", Value::Sequence(vec![Value::default_for(&point())])).unwrap();
+	sample.set("trail", Value::Sequence(Vec::new())).unwrap();
+	sample.set("transform", Value::Sequence(vec![
+		Value::Sequence(vec![Value::Int32(1), Value::Int32(0), Value::Int32(0)]),
+		Value::Sequence(vec![Value::Int32(0), Value::Int32(1), Value::Int32(0)]),
+	])).unwrap();
+	sample.set("type", Value::UInt64(7)).unwrap();
+
+	let generated: ShapeType = cdr::from_payload(sample.to_payload(Encapsulation::CDR_BE)).unwrap();
+	assert_eq!(generated, ShapeType {trail: Vec::new(), ..shape()});
+
+	assert_eq!(sample.set("x", Value::Int64(1)), Err(DynamicError::WrongType(String::from("x"))));
+	assert_eq!(sample.set("color", Value::String(String::from("ULTRAVIOLET"))),
+			   Err(DynamicError::WrongType(String::from("color"))));
+	assert_eq!(sample.set("kind", Value::Enum(3)), Err(DynamicError::WrongType(String::from("kind"))));
+	assert_eq!(sample.set("z", Value::Int32(1)), Err(DynamicError::UnknownMember(String::from("z"))));
+	assert_eq!(sample.set("x.y", Value::Int32(1)), Err(DynamicError::UnknownMember(String::from("x.y"))));
+	assert_eq!(sample.clear("x"), Err(DynamicError::MissingMember(String::from("x"))));
+}
+
+#[test]
+fn unions_and_optional_members() {
+	let mut sample = DynamicData::new(drawing_type());
+	// Optional members start out absent, and defaults are used.
+	assert_eq!(sample.get("opacity"), None);
+	assert_eq!(sample.get("kind"), Some(&Value::Enum(1)));
+	assert_eq!(sample.get("outline.radius"), Some(&Value::Int32(0)));
+
+	// Setting a member of a union selects it.
+	sample.set("outline.corners", Value::Sequence(Vec::new())).unwrap();
+	assert_eq!(sample.get("outline.radius"), None);
+	assert_eq!(sample.get("outline.corners"), Some(&Value::Sequence(Vec::new())));
+	sample.set("opacity", Value::Float64(0.25)).unwrap();
+	sample.set("name", Value::String(String::from("n"))).unwrap();
+
+	let drawing: Drawing = cdr::from_payload(sample.to_payload(Encapsulation::PL_CDR2_LE)).unwrap();
+	assert_eq!(drawing.outline, Outline::corners(Vec::new()));
+	assert_eq!(drawing.opacity, Some(0.25));
+	sample.clear("opacity").unwrap();
+	let drawing: Drawing = cdr::from_payload(sample.to_payload(Encapsulation::PL_CDR_BE)).unwrap();
+	assert_eq!(drawing.opacity, None);
+
+	// The default member of a union is written with an unused discriminator.
+	let marker_type = drawing_type().member_by_name("markers").unwrap().member_type.element_type().unwrap().clone();
+	let mut marker = DynamicData::new(marker_type.clone());
+	assert_eq!(marker.get("layer"), Some(&Value::Int16(2)));
+	assert_eq!(marker.get("id"), Some(&Value::Int32(0)));
+	marker.set("label.text", Value::String(String::from("t"))).unwrap();
+	let generated: Marker = cdr::from_payload(marker.to_payload(Encapsulation::D_CDR2_BE)).unwrap();
+	assert_eq!(generated.label, Label::text(String::from("t")));
+
+	// Values are checked against the type.
+	let wrong = Value::Union {discriminator: Box::new(Value::Int16(1)), member: None};
+	assert!(marker.set("label", wrong).is_err());
+	assert!(DynamicData::from_value(marker_type, Value::Struct(Vec::new())).is_err());
+}
+
+#[test]
+fn rejects_invalid_payloads() {
+	let long = ShapeType {color: String::from("ULTRAVIOLET"), ..shape()};
+	let payload = cdr::to_payload(&long, Encapsulation::CDR_BE);
+	assert!(DynamicData::from_payload(&shape_type(), payload).is_err());
+
+	let bad_kind = cdr::to_payload(&ShapeType {kind: ShapeKind::CIRCLE, ..shape()}, Encapsulation::CDR_BE);
+	let mut bytes = bad_kind.to_vec();
+	// kind follows the color (8 bytes) and three longs.
+	bytes[4 + 8 + 12 + 3] = 9;
+	assert!(DynamicData::from_payload(&shape_type(), Bytes::from(bytes)).is_err());
+
+	// A mutable type must have it's members unless they have a default.
+	let empty = Bytes::from(&[0x00, 0x12, 0, 0, 0, 0, 0, 0][..]);
+	assert!(DynamicData::from_payload(&drawing_type(), empty).is_err());
+}
+
+#[test]
+fn multi_dimensional_arrays() {
+	let ty = DynamicType::structure("Grids", Extensibility::Final, None, vec![
+		MemberDescriptor::new("plain", 0, DynamicType::array(int32(), &[2, 2])),
+		MemberDescriptor::new("points", 1, DynamicType::array(point(), &[2, 1])),
+	]);
+	let p = |x, y| Value::Struct(vec![(String::from("x"), Value::Int32(x)), (String::from("y"), Value::Int32(y))]);
+	let value = Value::Struct(vec![
+		(String::from("plain"), Value::Sequence(vec![
+			Value::Sequence(vec![Value::Int32(1), Value::Int32(2)]),
+			Value::Sequence(vec![Value::Int32(3), Value::Int32(4)]),
+		])),
+		(String::from("points"), Value::Sequence(vec![
+			Value::Sequence(vec![p(5, 6)]),
+			Value::Sequence(vec![p(7, 8)]),
+		])),
+	]);
+
+	// Only the array of structures has a DHEADER, for all of it's dimensions.
+	let payload = DynamicData::from_value(ty.clone(), value.clone()).unwrap().to_payload(Encapsulation::CDR2_BE);
+	assert_eq!(&payload[4..], &[0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4,
+								0, 0, 0, 16, 0, 0, 0, 5, 0, 0, 0, 6, 0, 0, 0, 7, 0, 0, 0, 8][..]);
+	assert_eq!(DynamicData::from_payload(&ty, payload).unwrap().value(), &value);
+}
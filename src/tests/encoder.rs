@@ -0,0 +1,109 @@
+use bytes::{Bytes, BytesMut};
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::encoder::*;
+use rtps::messages::header::Header;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::{Submessage, TIME_ZERO};
+use rtps::messages::submessage::header::*;
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use rtps::messages::submessage::info_timestamp::InfoTimestamp;
+
+#[test]
+fn message_header() {
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPREFIX_UNKNOWN), &[], &mut buf).unwrap();
+
+	assert_eq!(buf.len(), 20);
+	assert_eq!(&buf[0..4], b"RTPS");
+	assert_eq!(&buf[4..6], &[2, 2]);
+	assert_eq!(&buf[6..20], &[0; 14]);
+}
+
+#[test]
+fn heartbeat_little_endian() {
+	let hb = Heartbeat::new(true, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		SequenceNumber_t::from(0x1_0000_0002),
+		From::from(7));
+
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPREFIX_UNKNOWN), &[&hb], &mut buf).unwrap();
+	let sub = &buf[20..];
+
+	assert_eq!(sub.len(), 32);
+	assert_eq!(&sub[0..4], &[0x07, 0x03, 28, 0]);
+	assert_eq!(&sub[4..8], &[0, 0, 0, 0]);
+	assert_eq!(&sub[8..12], &[0, 0, 3, 0xc2]);
+	assert_eq!(&sub[12..20], &[0, 0, 0, 0, 1, 0, 0, 0]);
+	assert_eq!(&sub[20..28], &[1, 0, 0, 0, 2, 0, 0, 0]);
+	assert_eq!(&sub[28..32], &[7, 0, 0, 0]);
+}
+
+#[test]
+fn data_big_endian_padded() {
+	let mut data = Data::new(false, true, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(3),
+		None,
+		Some(Bytes::from(&b"hello"[..])));
+	data.header.flags = DataFlags {endianness: Endianness::BigEndian, ..data.flags()}.bits();
+
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPREFIX_UNKNOWN), &[&data], &mut buf).unwrap();
+	let sub = &buf[20..];
+
+	// 20 bytes of fixed fields and 5 bytes of payload, padded to 28.
+	assert_eq!(sub.len(), 32);
+	assert_eq!(&sub[0..4], &[0x15, 0x04, 0, 28]);
+	assert_eq!(&sub[4..8], &[0, 0, 0, 16]);
+	assert_eq!(&sub[16..24], &[0, 0, 0, 0, 0, 0, 0, 3]);
+	assert_eq!(&sub[24..32], b"hello\0\0\0");
+}
+
+#[test]
+fn info_timestamp_invalidate_omits_timestamp() {
+	let its = InfoTimestamp::new(true, TIME_ZERO);
+
+	let mut buf = BytesMut::new();
+	let mut enc = Encoder::new(&mut buf);
+	its.encode(&mut enc);
+
+	assert_eq!(&buf[..], &[0x09, 0x03, 0, 0]);
+}
+
+#[test]
+fn overlong_submessages_must_be_last() {
+	let data = Data::new(false, true, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		None,
+		Some(Bytes::from(vec![7u8; 70000])));
+	let hb = Heartbeat::new(true, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		SequenceNumber_t::from(1),
+		From::from(1));
+
+	// The length is left as 0, meaning the rest of the message.
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPREFIX_UNKNOWN), &[&hb, &data], &mut buf).unwrap();
+	assert_eq!(&buf[52..56], &[0x15, 0x05, 0, 0]);
+	let msg = parse_message(buf.freeze()).unwrap();
+	match msg.submessages[1] {
+		Submessage::Data(ref d) => assert_eq!(d.serialized_payload.as_ref().unwrap().len(), 70000),
+		_ => panic!("expected Data"),
+	}
+
+	// Anything following it would be taken as part of it.
+	let mut buf = BytesMut::from(&b"queued"[..]);
+	assert_eq!(encode_message(&Header::new(GUIDPREFIX_UNKNOWN), &[&data, &hb], &mut buf),
+		Err(EncodeError::SubmessageTooLong {index: 0, length: 70020}));
+	assert_eq!(&buf[..], b"queued");
+}
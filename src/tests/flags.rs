@@ -0,0 +1,176 @@
+use std::fmt::Debug;
+use std::net::SocketAddr;
+use bytes::{Bytes, BytesMut};
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::header::Header;
+use rtps::messages::submessage::{Submessage, FragmentNumber_t, TIME_ZERO};
+use rtps::messages::submessage::header::*;
+use rtps::messages::submessage::element::*;
+use rtps::messages::submessage::ack_nack::AckNack;
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::data_frag::DataFragment;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use rtps::messages::submessage::info_reply::InfoReply;
+use rtps::messages::submessage::info_timestamp::InfoTimestamp;
+
+const ENDIANNESS: [Endianness; 2] = [Endianness::BigEndian, Endianness::LittleEndian];
+const BOOLS: [bool; 2] = [false, true];
+
+/// Every possible octet maps to flags which only keep the defined bits.
+fn check_bits<F: SubmessageFlags + PartialEq + Debug>(defined: u8) {
+	for bits in 0..=255u8 {
+		let flags = F::from_bits(bits);
+		assert_eq!(flags.bits(), bits & defined);
+		assert_eq!(F::from_bits(flags.bits()), flags);
+	}
+}
+
+fn round_trip(s: &dyn Encode) -> Submessage {
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPREFIX_UNKNOWN), &[s], &mut buf).unwrap();
+	let mut msg = parse_message(buf.freeze()).unwrap();
+	assert_eq!(msg.submessages.len(), 1);
+	msg.submessages.remove(0)
+}
+
+fn sn(n: i64) -> SequenceNumber_t {
+	SequenceNumber_t::from(n)
+}
+
+#[test]
+fn defined_bits() {
+	check_bits::<AckNackFlags>(0x03);
+	check_bits::<DataFlags>(0x0f);
+	check_bits::<DataFragFlags>(0x07);
+	check_bits::<GapFlags>(0x01);
+	check_bits::<HeartbeatFlags>(0x07);
+	check_bits::<HeartbeatFragFlags>(0x01);
+	check_bits::<InfoDestinationFlags>(0x01);
+	check_bits::<InfoReplyFlags>(0x03);
+	check_bits::<InfoReplyIp4Flags>(0x03);
+	check_bits::<InfoSourceFlags>(0x01);
+	check_bits::<InfoTimestampFlags>(0x03);
+	check_bits::<NackFragFlags>(0x01);
+	check_bits::<PadFlags>(0x01);
+}
+
+#[test]
+fn data_flags() {
+	for &e in &ENDIANNESS {
+	for &inline_qos in &BOOLS {
+	for &data in &BOOLS {
+	for &key in &BOOLS {
+		let qos = if inline_qos {Some(ParameterList::new())} else {None};
+		let payload = if data || key {Some(Bytes::from(&b"abcd"[..]))} else {None};
+		let mut d = Data::new(inline_qos, data, key,
+			ENTITYID_UNKNOWN, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER, sn(1), qos, payload);
+		let flags = DataFlags {endianness: e, inline_qos, data, key};
+		assert_eq!(d.flags(), DataFlags {endianness: Endianness::LittleEndian, ..flags});
+		d.header.flags = flags.bits();
+
+		match round_trip(&d) {
+			Submessage::Data(r) => {
+				assert_eq!(r.flags(), flags);
+				assert_eq!(r.has_inline_qos(), inline_qos);
+				assert_eq!(r.has_data(), data);
+				assert_eq!(r.has_key(), key);
+				assert_eq!(r.inline_qos.is_some(), inline_qos);
+				assert_eq!(r.serialized_payload.is_some(), data || key);
+			},
+			_ => panic!("expected data"),
+		}
+	}}}}
+}
+
+#[test]
+fn data_frag_flags() {
+	for &e in &ENDIANNESS {
+	for &inline_qos in &BOOLS {
+	for &key in &BOOLS {
+		let qos = if inline_qos {Some(ParameterList::new())} else {None};
+		let mut d = DataFragment::new(inline_qos, key,
+			ENTITYID_UNKNOWN, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER, sn(1),
+			FragmentNumber_t::from(1), 1, 4, 4, qos, Some(Bytes::from(&b"abcd"[..])));
+		let flags = DataFragFlags {endianness: e, inline_qos, key};
+		d.data_header.header.flags = flags.bits();
+
+		match round_trip(&d) {
+			Submessage::DataFrag(r) => {
+				assert_eq!(r.flags(), flags);
+				assert_eq!(r.has_inline_qos(), inline_qos);
+				assert_eq!(r.has_key(), key);
+			},
+			_ => panic!("expected data frag"),
+		}
+	}}}
+}
+
+#[test]
+fn heartbeat_and_acknack_flags() {
+	for &e in &ENDIANNESS {
+	for &is_final in &BOOLS {
+		for &liveliness in &BOOLS {
+			let mut hb = Heartbeat::new(is_final, liveliness,
+				ENTITYID_UNKNOWN, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER, sn(1), sn(1), From::from(1));
+			let flags = HeartbeatFlags {endianness: e, is_final, liveliness};
+			hb.header.flags = flags.bits();
+
+			match round_trip(&hb) {
+				Submessage::Heartbeat(r) => {
+					assert_eq!(r.flags(), flags);
+					assert_eq!(r.is_final(), is_final);
+					assert_eq!(r.has_liveliness(), liveliness);
+				},
+				_ => panic!("expected heartbeat"),
+			}
+		}
+
+		let mut an = AckNack::new(is_final,
+			ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+			SequenceNumberSet::new(sn(1)), From::from(1));
+		let flags = AckNackFlags {endianness: e, is_final};
+		an.header.flags = flags.bits();
+
+		match round_trip(&an) {
+			Submessage::AckNack(r) => {
+				assert_eq!(r.flags(), flags);
+				assert_eq!(r.is_final(), is_final);
+			},
+			_ => panic!("expected acknack"),
+		}
+	}}
+}
+
+#[test]
+fn info_flags() {
+	for &e in &ENDIANNESS {
+	for &flag in &BOOLS {
+		let mut its = InfoTimestamp::new(flag, TIME_ZERO);
+		let flags = InfoTimestampFlags {endianness: e, invalidate: flag};
+		its.header.flags = flags.bits();
+
+		match round_trip(&its) {
+			Submessage::InfoTimestamp(r) => {
+				assert_eq!(r.flags(), flags);
+				assert_eq!(r.invalidates(), flag);
+			},
+			_ => panic!("expected info timestamp"),
+		}
+
+		let locators = LocatorList(vec![Locator_t::from("239.255.0.1:7400".parse::<SocketAddr>().unwrap())]);
+		let mut ir = InfoReply::new(flag, LocatorList(Vec::new()), locators);
+		let flags = InfoReplyFlags {endianness: e, multicast: flag};
+		ir.header.flags = flags.bits();
+
+		match round_trip(&ir) {
+			Submessage::InfoReply(r) => {
+				assert_eq!(r.flags(), flags);
+				assert_eq!(r.multicast_locator_list.0.len(), flag as usize);
+			},
+			_ => panic!("expected info reply"),
+		}
+	}}
+}
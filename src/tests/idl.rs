@@ -0,0 +1,174 @@
+use bytes::Bytes;
+use cdr;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+use dcps::type_support::DdsType;
+use idl;
+use rtps::*;
+use rtps::messages::submessage::KeyHash_t;
+use rtps::messages::submessage::header::Endianness;
+use tests::idl_shapes::shapes::*;
+use tests::shape;
+use tests::idl_shapes::shapes::sensors::Reading;
+use tests::idl_shapes::shapes::extensible::*;
+
+#[test]
+fn generated_code_is_current() {
+	let generated = idl::compile(include_str!("shapes.idl")).unwrap();
+	assert_eq!(generated, include_str!("idl_shapes.rs"));
+}
+
+#[test]
+fn generated_types_round_trip() {
+	assert_eq!(MAX_POINTS, 4);
+	assert_eq!(DEFAULT_COLOR, "BLUE");
+	assert_eq!(SEPARATOR, b',');
+
+	for e in &[Encapsulation::CDR_BE, Encapsulation::CDR2_LE] {
+		let payload = cdr::to_payload(&shape(), *e);
+		assert_eq!(cdr::from_payload::<ShapeType>(payload).unwrap(), shape());
+	}
+
+	let reading = Reading {
+		id: 3,
+		kind: ShapeKind::TRIANGLE,
+		samples: vec![0.5, 1.5],
+		label: String::from("probe"),
+		valid: true,
+		flags: [1, 2],
+	};
+	let payload = cdr::to_payload(&reading, Encapsulation::CDR_LE);
+	assert_eq!(cdr::from_payload::<Reading>(payload).unwrap(), reading);
+
+	// Bounds are enforced when decoding.
+	let long = ShapeType {color: String::from("ULTRAVIOLET"), ..shape()};
+	assert!(cdr::from_payload::<ShapeType>(cdr::to_payload(&long, Encapsulation::CDR_BE)).is_err());
+	let bad_kind = cdr::to_payload(&9u32, Encapsulation::CDR_BE);
+	assert!(cdr::from_payload::<ShapeKind>(bad_kind).is_err());
+}
+
+#[test]
+fn generated_topic_types() {
+	assert_eq!(ShapeType::type_name(), "shapes::ShapeType");
+	assert_eq!(ShapeType::topic_kind(), TopicKind_t::WITH_KEY);
+	assert_eq!(Reading::type_name(), "shapes::sensors::Reading");
+
+	// The bounded color can't exceed 16 bytes, so isn't hashed.
+	assert_eq!(ShapeType::key_max_size(), Some(13));
+	assert_eq!(shape().key_hash(), KeyHash_t([0, 0, 0, 4, b'R', b'E', b'D', 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+	assert_eq!(Reading::key_max_size(), Some(8));
+}
+
+fn drawing() -> Drawing {
+	Drawing {
+		name: String::from("sketch"),
+		outline: Outline::corners(vec![Point {x: 0, y: 0}, Point {x: 1, y: 1}]),
+		opacity: Some(0.5),
+		kind: ShapeKind::CIRCLE,
+		markers: vec![
+			Marker {id: 1, note: None, layer: 3, label: Label::code(String::from("A1"))},
+			Marker {id: 2, note: Some(String::from("x")), layer: 2, label: Label::text(String::from("start"))},
+		],
+	}
+}
+
+#[test]
+fn generated_unions() {
+	for e in &[Encapsulation::CDR_LE, Encapsulation::CDR2_BE] {
+		for o in &[Outline::radius(5), Outline::corners(vec![Point {x: 1, y: 2}])] {
+			assert_eq!(&cdr::from_payload::<Outline>(cdr::to_payload(o, *e)).unwrap(), o);
+		}
+	}
+
+	// The first label selects the member.
+	let radius = cdr::to_payload(&Outline::radius(5), Encapsulation::CDR_BE);
+	assert_eq!(&radius[4..], &[0, 0, 0, 0, 0, 0, 0, 5]);
+	let corners = cdr::to_payload(&Outline::corners(Vec::new()), Encapsulation::CDR_BE);
+	assert_eq!(&corners[4..], &[0, 0, 0, 1, 0, 0, 0, 0]);
+
+	// The default member is written with a discriminator no other member
+	// uses, and read for any such discriminator.
+	let text = cdr::to_payload(&Label::text(String::from("t")), Encapsulation::D_CDR2_BE);
+	assert_eq!(&text[4..], &[0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 2, b't', 0, 0, 0]);
+	let other = Bytes::from(&[0, 0x14, 0, 0, 0, 0, 0, 10, 0, 7, 0, 0, 0, 0, 0, 2, b'u', 0][..]);
+	assert_eq!(cdr::from_payload::<Label>(other).unwrap(), Label::text(String::from("u")));
+	assert!(cdr::from_payload::<Label>(cdr::to_payload(&Label::code(String::from("ABCDE")),
+														Encapsulation::D_CDR2_BE)).is_err());
+}
+
+#[test]
+fn generated_extensible_types() {
+	assert_eq!(Drawing::encapsulation(CdrVersion::XCDR2, Endianness::LittleEndian), Encapsulation::PL_CDR2_LE);
+	assert_eq!(Marker::encapsulation(CdrVersion::XCDR2, Endianness::BigEndian), Encapsulation::D_CDR2_BE);
+	assert_eq!(ShapeType::encapsulation(CdrVersion::XCDR1, Endianness::BigEndian), Encapsulation::CDR_BE);
+
+	for e in &[Encapsulation::PL_CDR_BE, Encapsulation::PL_CDR2_LE] {
+		let absent = Drawing {opacity: None, ..drawing()};
+		for d in &[drawing(), absent] {
+			assert_eq!(&cdr::from_payload::<Drawing>(cdr::to_payload(d, *e)).unwrap(), d);
+		}
+	}
+
+	// Inherited members come first, and the key is inherited too.
+	let marker = drawing().markers[1].clone();
+	assert_eq!(Marker::key_max_size(), Some(4));
+	assert_eq!(marker.key(), Bytes::from(&[0, 0, 0, 2][..]));
+
+	// An old reader of the base type skips the appended members.
+	let base: Base = cdr::from_payload(cdr::to_payload(&marker, Encapsulation::D_CDR2_LE)).unwrap();
+	assert_eq!(base, Base {id: 2, note: Some(String::from("x"))});
+
+	// Missing members of a mutable type take their default, unless they
+	// have none.
+	let mut enc = CdrEncoder::new(Encapsulation::PL_CDR2_BE);
+	let members = enc.begin_members();
+	for &(id, value) in &[(10, &String::from("a") as &dyn CdrSerialize), (11, &Outline::radius(1)),
+						  (14, &Vec::<Marker>::new())] {
+		let m = enc.begin_member(id, false);
+		value.serialize(&mut enc);
+		enc.end_member(m);
+	}
+	enc.end_members(members);
+	let d: Drawing = cdr::from_payload(enc.finish()).unwrap();
+	assert_eq!((d.opacity, d.kind), (None, DEFAULT_KIND));
+
+	let mut enc = CdrEncoder::new(Encapsulation::PL_CDR2_BE);
+	let members = enc.begin_members();
+	enc.end_members(members);
+	assert!(cdr::from_payload::<Drawing>(enc.finish()).is_err());
+}
+
+#[test]
+fn compile_errors() {
+	let syntax = idl::compile("module m {\n  struct S { long; };\n};").unwrap_err();
+	assert_eq!((syntax.line, syntax.column), (2, 18));
+
+	// Errors found after parsing point at the declaration they were found in.
+	let unknown = idl::compile("struct S { Missing m; };").unwrap_err();
+	assert_eq!(unknown.message, "unknown name Missing");
+	assert_eq!(unknown.to_string(), "1:20: unknown name Missing");
+
+	let duplicate = idl::compile("struct S { long a; };\nstruct S { long b; };").unwrap_err();
+	assert_eq!((duplicate.line, duplicate.column), (2, 8));
+	let range = idl::compile("module m {\n  const octet C = 256;\n};").unwrap_err();
+	assert_eq!((range.line, range.column), (2, 15));
+	let label = idl::compile("union U switch (long) {\n  case 1: long a;\n  case 1: long b;\n};").unwrap_err();
+	assert_eq!((label.line, label.column), (3, 16));
+	assert!(idl::compile("const long N = 0; struct S { long a[N]; };").is_err());
+	assert!(idl::compile("struct S { wstring w; };").is_err());
+
+	// Modules may be reopened, and names resolve outwards.
+	let reopened = idl::compile("module m { const long A = 1; }; module m { const long B = A + 1; };");
+	let reopened = reopened.unwrap();
+	assert!(reopened.contains("pub const B: i32 = 2;"));
+	assert_eq!(reopened.matches("pub mod m").count(), 1);
+
+	assert!(idl::compile("@mutable union U switch (long) { case 1: long a; };").is_err());
+	assert!(idl::compile("union U switch (long) { case 1: long a; case 1: long b; };").is_err());
+	assert!(idl::compile("union U switch (boolean) { case TRUE: long a; case FALSE: long b; default: long c; };").is_err());
+	assert!(idl::compile("union U switch (string) { case 1: long a; };").is_err());
+	assert!(idl::compile("struct S { @key @optional long a; };").is_err());
+	assert!(idl::compile("struct S { @id(1) long a; long b; @id(2) long c; };").is_err());
+	assert!(idl::compile("@appendable struct B { long a; }; @mutable struct S : B { long b; };").is_err());
+	assert!(idl::compile("struct A : B { long a; }; struct B : A { long b; };").is_err());
+}
@@ -0,0 +1,623 @@
+// Generated by idlc, do not edit.
+
+pub const MAX_COLOR_LEN: i32 = 8;
+
+#[allow(non_snake_case)]
+pub mod shapes {
+	pub const MAX_POINTS: i16 = 4;
+
+	pub const DEFAULT_COLOR: &str = "BLUE";
+
+	pub const SEPARATOR: u8 = b',';
+
+	pub const SCALE: f64 = 1.5f64;
+
+	#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+	#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+	pub enum ShapeKind {
+		CIRCLE,
+		SQUARE,
+		TRIANGLE,
+	}
+
+	impl ::dds_omg::cdr::encoder::CdrSerialize for ShapeKind {
+		fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+			enc.put_u32(*self as u32)
+		}
+
+		fn max_end_position(position: usize) -> Option<usize> {
+			<u32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)
+		}
+	}
+
+	impl ::dds_omg::cdr::decoder::CdrDeserialize for ShapeKind {
+		fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+			match dec.get_u32()? {
+				0 => Ok(ShapeKind::CIRCLE),
+				1 => Ok(ShapeKind::SQUARE),
+				2 => Ok(ShapeKind::TRIANGLE),
+				_ => Err(::dds_omg::cdr::decoder::CdrError::Invalid("unknown shapes::ShapeKind enumerator")),
+			}
+		}
+	}
+
+	impl ::dds_omg::dcps::dynamic_type::DescribeType for ShapeKind {
+		fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+			::dds_omg::dcps::dynamic_type::DynamicType::enumeration("shapes::ShapeKind", &["CIRCLE", "SQUARE", "TRIANGLE"])
+		}
+	}
+
+	pub const DEFAULT_KIND: ShapeKind = ShapeKind::SQUARE;
+
+	#[allow(non_camel_case_types)]
+	pub type Color = String;
+
+	#[allow(non_camel_case_types)]
+	pub type Matrix = [[i32; 3]; 2];
+
+	#[allow(non_camel_case_types, non_snake_case)]
+	#[derive(Clone, Debug, PartialEq)]
+	pub struct Point {
+		pub x: i32,
+		pub y: i32,
+	}
+
+	impl ::dds_omg::cdr::encoder::CdrSerialize for Point {
+		fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.x, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.y, enc);
+		}
+
+		fn max_end_position(position: usize) -> Option<usize> {
+			let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			Some(position)
+		}
+	}
+
+	impl ::dds_omg::cdr::decoder::CdrDeserialize for Point {
+		fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+			let v_x: i32 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			let v_y: i32 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			Ok(Point {x: v_x, y: v_y})
+		}
+	}
+
+	impl ::dds_omg::dcps::dynamic_type::DescribeType for Point {
+		fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+			::dds_omg::dcps::dynamic_type::DynamicType::structure("shapes::Point", ::dds_omg::cdr::encapsulation::Extensibility::Final, None,
+				vec![
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("x", 0, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32)),
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("y", 1, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32)),
+				],
+			)
+		}
+	}
+
+	#[allow(non_camel_case_types, non_snake_case)]
+	#[derive(Clone, Debug, PartialEq)]
+	pub struct ShapeType {
+		pub color: Color,
+		pub x: i32,
+		pub y: i32,
+		pub shapesize: i32,
+		pub kind: ShapeKind,
+		pub trail: Vec<Point>,
+		pub transform: Matrix,
+		pub r#type: u64,
+	}
+
+	impl ::dds_omg::cdr::encoder::CdrSerialize for ShapeType {
+		fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.color, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.x, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.y, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.shapesize, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.kind, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.trail, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.transform, enc);
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.r#type, enc);
+		}
+
+		fn max_end_position(position: usize) -> Option<usize> {
+			let position = <u32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = position + 9;
+			let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = <ShapeKind as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = <u32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = (0..4).try_fold(position, |position, _| { let position = <Point as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?; Some(position) })?;
+			let position = (0..2).try_fold(position, |position, _| { let position = (0..3).try_fold(position, |position, _| { let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?; Some(position) })?; Some(position) })?;
+			let position = <u64 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			Some(position)
+		}
+	}
+
+	impl ::dds_omg::cdr::decoder::CdrDeserialize for ShapeType {
+		fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+			let v_color: Color = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			if v_color.len() > 8 {
+				return Err(::dds_omg::cdr::decoder::CdrError::Invalid("color exceeds it's bound"));
+			}
+			let v_x: i32 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			let v_y: i32 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			let v_shapesize: i32 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			let v_kind: ShapeKind = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			let v_trail: Vec<Point> = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			if v_trail.len() > 4 {
+				return Err(::dds_omg::cdr::decoder::CdrError::Invalid("trail exceeds it's bound"));
+			}
+			let v_transform: Matrix = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			let v_type: u64 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+			Ok(ShapeType {color: v_color, x: v_x, y: v_y, shapesize: v_shapesize, kind: v_kind, trail: v_trail, transform: v_transform, r#type: v_type})
+		}
+	}
+
+	impl ::dds_omg::dcps::dynamic_type::DescribeType for ShapeType {
+		fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+			::dds_omg::dcps::dynamic_type::DynamicType::structure("shapes::ShapeType", ::dds_omg::cdr::encapsulation::Extensibility::Final, None,
+				vec![
+					::dds_omg::dcps::dynamic_type::MemberDescriptor {is_key: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("color", 0, ::dds_omg::dcps::dynamic_type::DynamicType::alias("shapes::Color", ::dds_omg::dcps::dynamic_type::DynamicType::string(Some(8))))},
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("x", 1, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32)),
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("y", 2, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32)),
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("shapesize", 3, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32)),
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("kind", 4, <ShapeKind as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type()),
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("trail", 5, ::dds_omg::dcps::dynamic_type::DynamicType::sequence(<Point as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type(), Some(4))),
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("transform", 6, ::dds_omg::dcps::dynamic_type::DynamicType::alias("shapes::Matrix", ::dds_omg::dcps::dynamic_type::DynamicType::array(::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32), &[2, 3]))),
+					::dds_omg::dcps::dynamic_type::MemberDescriptor::new("type", 7, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_UINT64)),
+				],
+			)
+		}
+	}
+
+	impl ::dds_omg::dcps::type_support::DdsType for ShapeType {
+		fn type_name() -> &'static str {
+			"shapes::ShapeType"
+		}
+
+		fn topic_kind() -> ::dds_omg::rtps::TopicKind_t {
+			::dds_omg::rtps::TopicKind_t::WITH_KEY
+		}
+
+		fn serialize_key(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+			::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.color, enc);
+		}
+
+		fn key_max_size() -> Option<usize> {
+			let position = 0;
+			let position = <u32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+			let position = position + 9;
+			Some(position)
+		}
+	}
+
+	#[allow(non_snake_case)]
+	pub mod sensors {
+		#[allow(non_camel_case_types, non_snake_case)]
+		#[derive(Clone, Debug, PartialEq)]
+		pub struct Reading {
+			pub id: u16,
+			pub kind: super::ShapeKind,
+			pub samples: Vec<f64>,
+			pub label: String,
+			pub valid: bool,
+			pub flags: [u8; 2],
+		}
+
+		impl ::dds_omg::cdr::encoder::CdrSerialize for Reading {
+			fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.id, enc);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.kind, enc);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.samples, enc);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.label, enc);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.valid, enc);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.flags, enc);
+			}
+
+			fn max_end_position(_position: usize) -> Option<usize> {
+				None
+			}
+		}
+
+		impl ::dds_omg::cdr::decoder::CdrDeserialize for Reading {
+			fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+				let v_id: u16 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				let v_kind: super::ShapeKind = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				let v_samples: Vec<f64> = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				let v_label: String = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				let v_valid: bool = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				let v_flags: [u8; 2] = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				Ok(Reading {id: v_id, kind: v_kind, samples: v_samples, label: v_label, valid: v_valid, flags: v_flags})
+			}
+		}
+
+		impl ::dds_omg::dcps::dynamic_type::DescribeType for Reading {
+			fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+				::dds_omg::dcps::dynamic_type::DynamicType::structure("shapes::sensors::Reading", ::dds_omg::cdr::encapsulation::Extensibility::Final, None,
+					vec![
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {is_key: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("id", 0, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_UINT16))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {is_key: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("kind", 1, <super::ShapeKind as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type())},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor::new("samples", 2, ::dds_omg::dcps::dynamic_type::DynamicType::sequence(::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_FLOAT64), None)),
+						::dds_omg::dcps::dynamic_type::MemberDescriptor::new("label", 3, ::dds_omg::dcps::dynamic_type::DynamicType::string(None)),
+						::dds_omg::dcps::dynamic_type::MemberDescriptor::new("valid", 4, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_BOOLEAN)),
+						::dds_omg::dcps::dynamic_type::MemberDescriptor::new("flags", 5, ::dds_omg::dcps::dynamic_type::DynamicType::array(::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_BYTE), &[2])),
+					],
+				)
+			}
+		}
+
+		impl ::dds_omg::dcps::type_support::DdsType for Reading {
+			fn type_name() -> &'static str {
+				"shapes::sensors::Reading"
+			}
+
+			fn topic_kind() -> ::dds_omg::rtps::TopicKind_t {
+				::dds_omg::rtps::TopicKind_t::WITH_KEY
+			}
+
+			fn serialize_key(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.id, enc);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.kind, enc);
+			}
+
+			fn key_max_size() -> Option<usize> {
+				let position = 0;
+				let position = <u16 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+				let position = <super::ShapeKind as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+				Some(position)
+			}
+		}
+	}
+
+	#[allow(non_snake_case)]
+	pub mod extensible {
+		#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+		#[derive(Clone, Debug, PartialEq)]
+		pub enum Outline {
+			radius(i32),
+			corners(Vec<super::Point>),
+		}
+
+		impl ::dds_omg::cdr::encoder::CdrSerialize for Outline {
+			fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				match *self {
+					Outline::radius(ref v) => {
+						<super::ShapeKind as ::dds_omg::cdr::encoder::CdrSerialize>::serialize(&super::ShapeKind::CIRCLE, enc);
+						::dds_omg::cdr::encoder::CdrSerialize::serialize(v, enc);
+					},
+					Outline::corners(ref v) => {
+						<super::ShapeKind as ::dds_omg::cdr::encoder::CdrSerialize>::serialize(&super::ShapeKind::SQUARE, enc);
+						::dds_omg::cdr::encoder::CdrSerialize::serialize(v, enc);
+					},
+				}
+			}
+
+			fn max_end_position(position: usize) -> Option<usize> {
+				let position = <super::ShapeKind as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+				let mut end = position;
+				{
+					let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+					end = end.max(position);
+				}
+				{
+					let position = <u32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+					let position = (0..3).try_fold(position, |position, _| { let position = <super::Point as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?; Some(position) })?;
+					end = end.max(position);
+				}
+				Some(end)
+			}
+		}
+
+		impl ::dds_omg::cdr::decoder::CdrDeserialize for Outline {
+			fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+				let v = match <super::ShapeKind as ::dds_omg::cdr::decoder::CdrDeserialize>::deserialize(dec)? {
+					super::ShapeKind::CIRCLE => Outline::radius(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?),
+					super::ShapeKind::SQUARE | super::ShapeKind::TRIANGLE => {
+						let v_corners: Vec<super::Point> = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+						if v_corners.len() > 3 {
+							return Err(::dds_omg::cdr::decoder::CdrError::Invalid("corners exceeds it's bound"));
+						}
+						Outline::corners(v_corners)
+					},
+				};
+				Ok(v)
+			}
+		}
+
+		impl ::dds_omg::dcps::dynamic_type::DescribeType for Outline {
+			fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+				::dds_omg::dcps::dynamic_type::DynamicType::union("shapes::extensible::Outline", ::dds_omg::cdr::encapsulation::Extensibility::Final, <super::ShapeKind as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type(),
+					vec![
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {label: vec![0], ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("radius", 1, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {label: vec![1, 2], ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("corners", 2, ::dds_omg::dcps::dynamic_type::DynamicType::sequence(<super::Point as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type(), Some(3)))},
+					],
+				)
+			}
+		}
+
+		#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+		#[derive(Clone, Debug, PartialEq)]
+		pub enum Label {
+			code(String),
+			initial(u8),
+			text(String),
+		}
+
+		impl ::dds_omg::cdr::encoder::CdrSerialize for Label {
+			fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				let d = enc.begin_delimited();
+				match *self {
+					Label::code(ref v) => {
+						<i16 as ::dds_omg::cdr::encoder::CdrSerialize>::serialize(&1, enc);
+						::dds_omg::cdr::encoder::CdrSerialize::serialize(v, enc);
+					},
+					Label::initial(ref v) => {
+						<i16 as ::dds_omg::cdr::encoder::CdrSerialize>::serialize(&2, enc);
+						::dds_omg::cdr::encoder::CdrSerialize::serialize(v, enc);
+					},
+					Label::text(ref v) => {
+						<i16 as ::dds_omg::cdr::encoder::CdrSerialize>::serialize(&0, enc);
+						::dds_omg::cdr::encoder::CdrSerialize::serialize(v, enc);
+					},
+				}
+				enc.end_delimited(d);
+			}
+
+			fn max_end_position(_position: usize) -> Option<usize> {
+				None
+			}
+		}
+
+		impl ::dds_omg::cdr::decoder::CdrDeserialize for Label {
+			fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+				let d = dec.begin_delimited()?;
+				let v = match <i16 as ::dds_omg::cdr::decoder::CdrDeserialize>::deserialize(dec)? {
+					1 => {
+						let v_code: String = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+						if v_code.len() > 4 {
+							return Err(::dds_omg::cdr::decoder::CdrError::Invalid("code exceeds it's bound"));
+						}
+						Label::code(v_code)
+					},
+					2 => Label::initial(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?),
+					_ => Label::text(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?),
+				};
+				dec.end_delimited(d)?;
+				Ok(v)
+			}
+		}
+
+		impl ::dds_omg::dcps::dynamic_type::DescribeType for Label {
+			fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+				::dds_omg::dcps::dynamic_type::DynamicType::union("shapes::extensible::Label", ::dds_omg::cdr::encapsulation::Extensibility::Appendable, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT16),
+					vec![
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {label: vec![1], ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("code", 1, ::dds_omg::dcps::dynamic_type::DynamicType::string(Some(4)))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {label: vec![2], ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("initial", 2, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_CHAR8))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {is_default_label: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("text", 3, ::dds_omg::dcps::dynamic_type::DynamicType::string(None))},
+					],
+				)
+			}
+		}
+
+		#[allow(non_camel_case_types, non_snake_case)]
+		#[derive(Clone, Debug, PartialEq)]
+		pub struct Base {
+			pub id: i32,
+			pub note: Option<String>,
+		}
+
+		impl ::dds_omg::cdr::encoder::CdrSerialize for Base {
+			fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				let d = enc.begin_delimited();
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.id, enc);
+				enc.put_optional(1, &self.note);
+				enc.end_delimited(d);
+			}
+
+			fn max_end_position(_position: usize) -> Option<usize> {
+				None
+			}
+		}
+
+		impl ::dds_omg::cdr::decoder::CdrDeserialize for Base {
+			fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+				let d = dec.begin_delimited()?;
+				let v_id: i32 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				let v_note: Option<String> = if dec.remaining() == 0 {None} else {dec.get_optional(1)?};
+				dec.end_delimited(d)?;
+				Ok(Base {id: v_id, note: v_note})
+			}
+		}
+
+		impl ::dds_omg::dcps::dynamic_type::DescribeType for Base {
+			fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+				::dds_omg::dcps::dynamic_type::DynamicType::structure("shapes::extensible::Base", ::dds_omg::cdr::encapsulation::Extensibility::Appendable, None,
+					vec![
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {is_key: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("id", 0, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT32))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {is_optional: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("note", 1, ::dds_omg::dcps::dynamic_type::DynamicType::string(None))},
+					],
+				)
+			}
+		}
+
+		#[allow(non_camel_case_types, non_snake_case)]
+		#[derive(Clone, Debug, PartialEq)]
+		pub struct Marker {
+			pub id: i32,
+			pub note: Option<String>,
+			pub layer: i16,
+			pub label: Label,
+		}
+
+		impl ::dds_omg::cdr::encoder::CdrSerialize for Marker {
+			fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				let d = enc.begin_delimited();
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.id, enc);
+				enc.put_optional(1, &self.note);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.layer, enc);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.label, enc);
+				enc.end_delimited(d);
+			}
+
+			fn max_end_position(_position: usize) -> Option<usize> {
+				None
+			}
+		}
+
+		impl ::dds_omg::cdr::decoder::CdrDeserialize for Marker {
+			fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+				let d = dec.begin_delimited()?;
+				let v_id: i32 = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				let v_note: Option<String> = if dec.remaining() == 0 {None} else {dec.get_optional(1)?};
+				let v_layer: i16 = if dec.remaining() == 0 {2} else {::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?};
+				let v_label: Label = ::dds_omg::cdr::decoder::CdrDeserialize::deserialize(dec)?;
+				dec.end_delimited(d)?;
+				Ok(Marker {id: v_id, note: v_note, layer: v_layer, label: v_label})
+			}
+		}
+
+		impl ::dds_omg::dcps::dynamic_type::DescribeType for Marker {
+			fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+				::dds_omg::dcps::dynamic_type::DynamicType::structure("shapes::extensible::Marker", ::dds_omg::cdr::encapsulation::Extensibility::Appendable, Some(<Base as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type()),
+					vec![
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {default_value: Some(::dds_omg::dcps::dynamic_data::Value::Int16(2)), ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("layer", 2, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_INT16))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor::new("label", 3, <Label as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type()),
+					],
+				)
+			}
+		}
+
+		impl ::dds_omg::dcps::type_support::DdsType for Marker {
+			fn type_name() -> &'static str {
+				"shapes::extensible::Marker"
+			}
+
+			fn topic_kind() -> ::dds_omg::rtps::TopicKind_t {
+				::dds_omg::rtps::TopicKind_t::WITH_KEY
+			}
+
+			fn serialize_key(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.id, enc);
+			}
+
+			fn key_max_size() -> Option<usize> {
+				let position = 0;
+				let position = <i32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+				Some(position)
+			}
+
+			fn extensibility() -> ::dds_omg::cdr::encapsulation::Extensibility {
+				::dds_omg::cdr::encapsulation::Extensibility::Appendable
+			}
+		}
+
+		#[allow(non_camel_case_types, non_snake_case)]
+		#[derive(Clone, Debug, PartialEq)]
+		pub struct Drawing {
+			pub name: String,
+			pub outline: Outline,
+			pub opacity: Option<f64>,
+			pub kind: super::ShapeKind,
+			pub markers: Vec<Marker>,
+		}
+
+		impl ::dds_omg::cdr::encoder::CdrSerialize for Drawing {
+			fn serialize(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				let members = enc.begin_members();
+				let m = enc.begin_member(10, true);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.name, enc);
+				enc.end_member(m);
+				let m = enc.begin_member(11, false);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.outline, enc);
+				enc.end_member(m);
+				if let Some(ref v) = self.opacity {
+					let m = enc.begin_member(12, false);
+					::dds_omg::cdr::encoder::CdrSerialize::serialize(v, enc);
+					enc.end_member(m);
+				}
+				let m = enc.begin_member(13, false);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.kind, enc);
+				enc.end_member(m);
+				let m = enc.begin_member(14, false);
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.markers, enc);
+				enc.end_member(m);
+				enc.end_members(members);
+			}
+
+			fn max_end_position(_position: usize) -> Option<usize> {
+				None
+			}
+		}
+
+		impl ::dds_omg::cdr::decoder::CdrDeserialize for Drawing {
+			fn deserialize(dec: &mut ::dds_omg::cdr::decoder::CdrDecoder) -> Result<Self, ::dds_omg::cdr::decoder::CdrError> {
+				let mut v_name: Option<String> = None;
+				let mut v_outline: Option<Outline> = None;
+				let mut v_opacity: Option<f64> = None;
+				let mut v_kind: Option<super::ShapeKind> = None;
+				let mut v_markers: Option<Vec<Marker>> = None;
+				let members = dec.begin_members()?;
+				while let Some(mut m) = dec.next_member()? {
+					match m.id {
+						10 => v_name = Some(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(&mut m.value)?),
+						11 => v_outline = Some(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(&mut m.value)?),
+						12 => v_opacity = Some(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(&mut m.value)?),
+						13 => v_kind = Some(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(&mut m.value)?),
+						14 => v_markers = Some(::dds_omg::cdr::decoder::CdrDeserialize::deserialize(&mut m.value)?),
+						_ if m.must_understand => return Err(::dds_omg::cdr::decoder::CdrError::Invalid("unknown member of shapes::extensible::Drawing")),
+						_ => {},
+					}
+				}
+				dec.end_members(members)?;
+				let v_name: String = v_name.ok_or(::dds_omg::cdr::decoder::CdrError::Invalid("name is missing"))?;
+				if v_name.len() > 8 {
+					return Err(::dds_omg::cdr::decoder::CdrError::Invalid("name exceeds it's bound"));
+				}
+				let v_outline: Outline = v_outline.ok_or(::dds_omg::cdr::decoder::CdrError::Invalid("outline is missing"))?;
+				let v_kind: super::ShapeKind = v_kind.unwrap_or(super::ShapeKind::SQUARE);
+				let v_markers: Vec<Marker> = v_markers.ok_or(::dds_omg::cdr::decoder::CdrError::Invalid("markers is missing"))?;
+				Ok(Drawing {name: v_name, outline: v_outline, opacity: v_opacity, kind: v_kind, markers: v_markers})
+			}
+		}
+
+		impl ::dds_omg::dcps::dynamic_type::DescribeType for Drawing {
+			fn dynamic_type() -> ::dds_omg::dcps::dynamic_type::DynamicType {
+				::dds_omg::dcps::dynamic_type::DynamicType::structure("shapes::extensible::Drawing", ::dds_omg::cdr::encapsulation::Extensibility::Mutable, None,
+					vec![
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {is_key: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("name", 10, ::dds_omg::dcps::dynamic_type::DynamicType::string(Some(8)))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor::new("outline", 11, <Outline as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type()),
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {is_optional: true, ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("opacity", 12, ::dds_omg::dcps::dynamic_type::DynamicType::primitive(::dds_omg::dcps::dynamic_type::TypeKind::TK_FLOAT64))},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor {default_value: Some(::dds_omg::dcps::dynamic_data::Value::Enum(super::ShapeKind::SQUARE as u32)), ..::dds_omg::dcps::dynamic_type::MemberDescriptor::new("kind", 13, <super::ShapeKind as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type())},
+						::dds_omg::dcps::dynamic_type::MemberDescriptor::new("markers", 14, ::dds_omg::dcps::dynamic_type::DynamicType::sequence(<Marker as ::dds_omg::dcps::dynamic_type::DescribeType>::dynamic_type(), None)),
+					],
+				)
+			}
+		}
+
+		impl ::dds_omg::dcps::type_support::DdsType for Drawing {
+			fn type_name() -> &'static str {
+				"shapes::extensible::Drawing"
+			}
+
+			fn topic_kind() -> ::dds_omg::rtps::TopicKind_t {
+				::dds_omg::rtps::TopicKind_t::WITH_KEY
+			}
+
+			fn serialize_key(&self, enc: &mut ::dds_omg::cdr::encoder::CdrEncoder) {
+				::dds_omg::cdr::encoder::CdrSerialize::serialize(&self.name, enc);
+			}
+
+			fn key_max_size() -> Option<usize> {
+				let position = 0;
+				let position = <u32 as ::dds_omg::cdr::encoder::CdrSerialize>::max_end_position(position)?;
+				let position = position + 9;
+				Some(position)
+			}
+
+			fn extensibility() -> ::dds_omg::cdr::encapsulation::Extensibility {
+				::dds_omg::cdr::encapsulation::Extensibility::Mutable
+			}
+		}
+	}
+}
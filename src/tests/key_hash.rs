@@ -0,0 +1,72 @@
+use dcps::type_support::DdsType;
+use rtps::*;
+use rtps::messages::submessage::KeyHash_t;
+
+#[derive(DdsType)]
+struct Shape {
+	#[key]
+	color: String,
+	x: i32,
+}
+
+#[derive(DdsType)]
+struct Sensor {
+	#[key]
+	kind: u8,
+	#[key]
+	id: u64,
+	value: f32,
+}
+
+#[derive(DdsType)]
+struct Wide {
+	#[key]
+	ids: [u64; 2],
+	#[key]
+	flag: bool,
+}
+
+#[derive(DdsType)]
+struct Unkeyed {
+	value: u32,
+}
+
+#[test]
+fn short_keys_are_padded() {
+	// The u64 is aligned to 8, so the key can be at most 16 bytes.
+	assert_eq!(Sensor::key_max_size(), Some(16));
+	let s = Sensor {kind: 3, id: 0x0102, value: 1.0};
+	assert_eq!(s.key_hash(), KeyHash_t([3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2]));
+
+	assert_eq!(Unkeyed::key_max_size(), Some(0));
+	assert_eq!(Unkeyed {value: 1}.key_hash(), KeyHash_t([0; 16]));
+}
+
+#[test]
+fn long_keys_are_hashed() {
+	// Unbounded, so hashed even though the key itself is short.
+	assert_eq!(Shape::key_max_size(), None);
+	let s = Shape {color: String::from("RED"), x: 1};
+	assert_eq!(s.key_hash(), KeyHash_t([211, 109, 232, 101, 250, 194, 149, 21,
+										95, 24, 223, 113, 87, 178, 23, 230]));
+
+	assert_eq!(Wide::key_max_size(), Some(17));
+	let w = Wide {ids: [1, 2], flag: true};
+	assert_eq!(KeyHash_t::compute(&w.key(), Some(17)), w.key_hash());
+	assert_eq!(w.key().len(), 17);
+
+	// A short key of a type whose keys may be long is still hashed.
+	assert!(KeyHash_t::compute(&[1], Some(17)) != KeyHash_t::compute(&[1], Some(16)));
+}
+
+#[test]
+fn instance_handles_follow_key_hash() {
+	let a = Sensor {kind: 1, id: 2, value: 1.0};
+	let b = Sensor {kind: 1, id: 2, value: 5.0};
+	let c = Sensor {kind: 1, id: 3, value: 1.0};
+	assert_eq!(a.instance_handle(), b.instance_handle());
+	assert_eq!(a.instance_handle(), InstanceHandle_t::from(a.key_hash()));
+	assert!(a.instance_handle() != c.instance_handle());
+	assert!(a.instance_handle() != HANDLE_NIL);
+	assert_eq!(Unkeyed {value: 1}.instance_handle(), HANDLE_NIL);
+}
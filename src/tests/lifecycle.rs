@@ -0,0 +1,121 @@
+use std::net::SocketAddr;
+use bytes::{Bytes, BytesMut};
+use cdr;
+use cdr::encapsulation::*;
+use cdr::encoder::*;
+use dcps::type_support::DdsType;
+use rtps::*;
+use rtps::guid::*;
+use rtps::cache_change::CacheChange;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::header::Header;
+use rtps::messages::receiver::*;
+use rtps::messages::submessage::*;
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::parameter::*;
+use tests::idl_shapes::shapes::*;
+use tests::shape;
+
+fn change(kind: ChangeKind_t, data: Bytes, key_hash: Option<KeyHash_t>) -> CacheChange {
+	CacheChange {
+		kind,
+		writer_guid: GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER),
+		instance_handle: key_hash.map_or(HANDLE_NIL, InstanceHandle_t::from),
+		key_hash,
+		sequence_number: SequenceNumber_t::from(4),
+		data_value: data
+	}
+}
+
+/// Sends data within a message from the participant of writer, and returns
+/// the change it is received as.
+fn receive(writer: &GUID_t, data: &Data) -> CacheChange {
+	let local = GUIDPrefix_t::new();
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(*writer.prefix()), &[data], &mut buf).unwrap();
+
+	let source: SocketAddr = "10.0.0.7:7400".parse().unwrap();
+	let mut received = None;
+	MessageReceiver::new(local).receive(parse_message(buf.freeze()).unwrap(), &source, |r| {
+		if let Submessage::Data(ref d) = r.submessage {
+			received = Some(d.to_change(r.source));
+		}
+	});
+	received.unwrap()
+}
+
+#[test]
+fn status_info_follows_change_kind() {
+	for kind in &[ChangeKind_t::ALIVE, ChangeKind_t::NOT_ALIVE_DISPOSED, ChangeKind_t::NOT_ALIVE_UNREGISTERED] {
+		assert_eq!(ChangeKind_t::from(StatusInfo_t::from(*kind)), *kind);
+	}
+	assert_eq!(StatusInfo_t::from(ChangeKind_t::NOT_ALIVE_DISPOSED), StatusInfo_t([0, 0, 0, 1]));
+	assert_eq!(StatusInfo_t::from(ChangeKind_t::NOT_ALIVE_UNREGISTERED), StatusInfo_t([0, 0, 0, 2]));
+	assert_eq!(ChangeKind_t::from(StatusInfo_t([0, 0, 0, 3])), ChangeKind_t::NOT_ALIVE_DISPOSED);
+	// The filtered flag (0x04) isn't a change to lifecycle.
+	assert_eq!(ChangeKind_t::from(StatusInfo_t([0, 0, 0, 4])), ChangeKind_t::ALIVE);
+}
+
+#[test]
+fn dispose_is_sent_as_key_only_data() {
+	let s = shape();
+	let mut enc = CdrEncoder::new(Encapsulation::CDR_LE);
+	s.serialize_key(&mut enc);
+	let key = enc.finish();
+	let disposed = change(ChangeKind_t::NOT_ALIVE_DISPOSED, key.clone(), Some(s.key_hash()));
+
+	let data = Data::from_change(ENTITYID_UNKNOWN, &disposed, false);
+	assert!(data.has_key() && !data.has_data() && data.has_inline_qos());
+	assert_eq!(data.serialized_payload, Some(key.clone()));
+	let qos = data.inline_qos.as_ref().unwrap();
+	match qos.get(PID_KEY_HASH) {
+		Some(&Parameter::KeyHash(k)) => assert_eq!(k, s.key_hash()),
+		_ => panic!("expected key hash"),
+	}
+	match qos.get(PID_STATUS_INFO) {
+		Some(&Parameter::StatusInfo(i)) => assert_eq!(i, StatusInfo_t([0, 0, 0, 1])),
+		_ => panic!("expected status info"),
+	}
+
+	let received = receive(&disposed.writer_guid, &data);
+	assert!(received == disposed);
+	assert_eq!(received.instance_handle, s.instance_handle());
+	assert_eq!(received.data_value, key);
+}
+
+#[test]
+fn unregister_without_key() {
+	let unregistered = change(ChangeKind_t::NOT_ALIVE_UNREGISTERED, Bytes::new(), None);
+	let data = Data::from_change(ENTITYID_UNKNOWN, &unregistered, true);
+	assert!(!data.has_key() && !data.has_data());
+	assert_eq!(data.serialized_payload, None);
+	assert_eq!(data.key_hash(), None);
+	assert_eq!(data.change_kind(), ChangeKind_t::NOT_ALIVE_UNREGISTERED);
+
+	let received = receive(&unregistered.writer_guid, &data);
+	assert_eq!(received.kind, ChangeKind_t::NOT_ALIVE_UNREGISTERED);
+	assert_eq!(received.instance_handle, HANDLE_NIL);
+	assert!(received.data_value.is_empty());
+}
+
+#[test]
+fn alive_changes_carry_the_value() {
+	let s = shape();
+	let payload = cdr::to_payload(&s, Encapsulation::CDR_BE);
+	let alive = change(ChangeKind_t::ALIVE, payload.clone(), Some(s.key_hash()));
+
+	// The key hash is only sent to readers expecting inline QoS.
+	let data = Data::from_change(ENTITYID_UNKNOWN, &alive, false);
+	assert!(data.has_data() && !data.has_key() && !data.has_inline_qos());
+	let received = receive(&alive.writer_guid, &data);
+	assert_eq!(received.kind, ChangeKind_t::ALIVE);
+	assert_eq!(received.instance_handle, HANDLE_NIL);
+	assert_eq!(cdr::from_payload::<ShapeType>(received.data_value).unwrap(), s);
+
+	let data = Data::from_change(ENTITYID_UNKNOWN, &alive, true);
+	assert_eq!(data.key_hash(), Some(s.key_hash()));
+	let received = receive(&alive.writer_guid, &data);
+	assert!(received == alive);
+	assert_eq!(received.data_value, payload);
+}
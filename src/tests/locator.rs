@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use bytes::BytesMut;
+use rtps::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::header::Endianness;
+
+fn round_trip(locator: &Locator_t) -> Locator_t {
+	let mut buf = BytesMut::new();
+	locator.encode(&mut Encoder::new(&mut buf));
+	assert_eq!(buf.len(), locator.size() as usize);
+	Locator_t::decode(&mut Decoder::new(buf.freeze(), Endianness::BigEndian)).unwrap()
+}
+
+#[test]
+fn udp_v4_wire_format() {
+	let addr: SocketAddr = "192.168.1.20:7410".parse().unwrap();
+	let locator = Locator_t::from(addr);
+
+	let mut buf = BytesMut::new();
+	locator.encode(&mut Encoder::new(&mut buf));
+	assert_eq!(&buf[..], &[0, 0, 0, 1, 0, 0, 0x1c, 0xf2,
+						   0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 192, 168, 1, 20][..]);
+
+	assert_eq!(round_trip(&locator), locator);
+	assert_eq!(locator.socket_addr(), Some(addr));
+}
+
+#[test]
+fn socket_addr_conversions() {
+	let v6: SocketAddr = "[ff02::1]:7400".parse().unwrap();
+	let locator = Locator_t::from(v6);
+	assert_eq!(locator.kind(), LOCATOR_KIND_UDP_V6);
+	assert!(locator.is_multicast());
+	assert_eq!(round_trip(&locator).socket_addr(), Some(v6));
+
+	let v4: SocketAddr = "239.255.0.1:7400".parse().unwrap();
+	assert!(Locator_t::from(v4).is_multicast());
+	assert!(!Locator_t::from("10.0.0.1:7400".parse::<SocketAddr>().unwrap()).is_multicast());
+
+	assert_eq!(LOCATOR_INVALID.socket_addr(), None);
+	assert_eq!(round_trip(&LOCATOR_INVALID), LOCATOR_INVALID);
+}
+
+#[test]
+fn reserved_and_vendor_kinds() {
+	let vendor = Locator_t::from_parts(LOCATOR_KIND_VENDOR_SPECIFIC | 0x10, 9, [3; 16]).unwrap();
+	assert!(vendor.is_vendor_specific());
+	assert_eq!(round_trip(&vendor), vendor);
+	assert_eq!(vendor.socket_addr(), None);
+
+	let reserved = Locator_t::from_parts(LOCATOR_KIND_RESERVED, 0, [0; 16]).unwrap();
+	assert!(!reserved.is_vendor_specific());
+	assert_eq!(reserved.kind(), LOCATOR_KIND_RESERVED);
+
+	assert!(Locator_t::from_parts(LOCATOR_KIND_UDP_V4, 0x10000, [0; 16]).is_none());
+	assert!(Locator_t::from_parts(LOCATOR_KIND_VENDOR_SPECIFIC, 0x10000, [0; 16]).is_some());
+
+	let mut set = HashSet::new();
+	set.insert(vendor);
+	set.insert(reserved);
+	set.insert(vendor);
+	assert_eq!(set.len(), 2);
+}
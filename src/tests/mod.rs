@@ -1,4 +1,91 @@
+mod builder;
+mod cdr;
+mod cdr2;
+mod derive;
+mod dynamic;
+mod encoder;
+mod flags;
+mod idl;
+mod idl_shapes;
+mod key_hash;
+mod lifecycle;
+mod locator;
+mod parameter;
+mod parser;
+mod reader;
+mod receiver;
+mod sets;
+mod stateless_reader;
+mod stateless_writer;
+mod time;
+mod type_object;
+mod writer;
+
+use std::sync::Arc;
+use rtps::*;
+use rtps::guid::*;
+use rtps::entity::Entity;
+use rtps::entity::endpoint::Endpoint;
+use rtps::entity::participant::Participant;
+use tests::idl_shapes::shapes::{Point, ShapeKind, ShapeType};
+
+/// A participant without endpoints of it's own, for endpoints under test.
+pub struct TestParticipant {
+	guid: GUID_t,
+}
+
+impl Entity for TestParticipant {
+	fn guid(&self) -> &GUID_t {
+		&self.guid
+	}
+}
+
+impl Participant for TestParticipant {
+	fn default_unicast_locator_list(&self) -> Vec<&Locator_t> {
+		Vec::new()
+	}
+
+	fn default_multicast_locator_list(&self) -> Vec<&Locator_t> {
+		Vec::new()
+	}
+
+	fn protocol_version(&self) -> &ProtocolVersion_t {
+		&PROTOCOL_VERSION
+	}
+
+	fn vendor_id(&self) -> &VendorId_t {
+		&VENDORID_UNKNOWN
+	}
+
+	fn endpoints(&self) -> Vec<Arc<dyn Endpoint>> {
+		Vec::new()
+	}
+}
+
+/// Creates a participant with a random GUID prefix.
+pub fn participant() -> Arc<dyn Participant> {
+	Arc::new(TestParticipant {guid: GUID_t::new(GUIDPrefix_t::new(), ENTITYID_PARTICIPANT)})
+}
+
+/// A shape with every member set to something other than it's default.
+pub fn shape() -> ShapeType {
+	ShapeType {
+		color: String::from("RED"),
+		x: 1,
+		y: 2,
+		shapesize: 30,
+		kind: ShapeKind::TRIANGLE,
+		trail: vec![Point {x: 3, y: 4}],
+		transform: [[1, 0, 0], [0, 1, 0]],
+		r#type: 7,
+	}
+}
+
+/// Sequence numbers, for comparing against those held by an endpoint.
+pub fn sns(numbers: &[i64]) -> Vec<SequenceNumber_t> {
+	numbers.iter().map(|n| SequenceNumber_t::from(*n)).collect()
+}
 
 #[test]
 fn it_works() {
-}
\ No newline at end of file
+}
@@ -0,0 +1,131 @@
+use bytes::{Bytes, BytesMut};
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::*;
+use rtps::messages::submessage::header::Endianness;
+use rtps::messages::submessage::element::*;
+use rtps::messages::submessage::parameter::*;
+
+fn round_trip(list: &ParameterList, endianness: Endianness) -> ParameterList {
+	let mut buf = BytesMut::new();
+	{
+		let mut enc = Encoder::new(&mut buf);
+		enc.set_endianness(endianness);
+		list.encode(&mut enc);
+	}
+	assert_eq!(buf.len(), list.size() as usize);
+	assert_eq!(buf.len() % 4, 0);
+
+	let mut dec = Decoder::new(buf.freeze(), endianness);
+	let decoded = ParameterList::decode(&mut dec).unwrap();
+	assert_eq!(dec.remaining(), 0);
+	decoded
+}
+
+fn sample_list() -> ParameterList {
+	let mut list = ParameterList::new();
+	list.push(Parameter::TopicName(String::from("Square")));
+	list.push(Parameter::Reliability(ReliabilityKind_t::RELIABLE, TIME_ZERO));
+	list.push(Parameter::Durability(DurabilityKind::TRANSIENT_LOCAL));
+	list.push(Parameter::Partition(vec![String::from("a"), String::from("bcdef")]));
+	list.push(Parameter::KeyHash(KeyHash_t([9; 16])));
+	list.push(Parameter::ExpectsInlineQos(true));
+	list.push(Parameter::EndpointGuid(GUID_t::new(GUIDPrefix_t::new(), ENTITYID_PARTICIPANT)));
+	list
+}
+
+fn check_sample(list: &ParameterList) {
+	assert_eq!(list.0.len(), 7);
+
+	match list.get(PID_TOPIC_NAME) {
+		Some(Parameter::TopicName(s)) => assert_eq!(s, "Square"),
+		_ => panic!("expected topic name"),
+	}
+	match list.get(PID_RELIABILITY) {
+		Some(&Parameter::Reliability(kind, _)) => assert_eq!(kind, ReliabilityKind_t::RELIABLE),
+		_ => panic!("expected reliability"),
+	}
+	match list.get(PID_PARTITION) {
+		Some(Parameter::Partition(names)) => assert_eq!(names, &["a", "bcdef"]),
+		_ => panic!("expected partition"),
+	}
+	match list.get(PID_KEY_HASH) {
+		Some(&Parameter::KeyHash(k)) => assert_eq!(k, KeyHash_t([9; 16])),
+		_ => panic!("expected key hash"),
+	}
+	match list.get(PID_EXPECTS_INLINE_QOS) {
+		Some(&Parameter::ExpectsInlineQos(b)) => assert!(b),
+		_ => panic!("expected expects inline qos"),
+	}
+}
+
+#[test]
+fn typed_round_trip() {
+	check_sample(&round_trip(&sample_list(), Endianness::LittleEndian));
+	check_sample(&round_trip(&sample_list(), Endianness::BigEndian));
+}
+
+#[test]
+fn topic_name_is_padded() {
+	let mut list = ParameterList::new();
+	list.push(Parameter::TopicName(String::from("Square")));
+
+	let mut buf = BytesMut::new();
+	list.encode(&mut Encoder::new(&mut buf));
+	assert_eq!(&buf[..], &[0x00, 0x05, 0x00, 0x0c,
+						   0, 0, 0, 7, b'S', b'q', b'u', b'a', b'r', b'e', 0, 0,
+						   0x00, 0x01, 0x00, 0x00][..]);
+}
+
+#[test]
+fn sizes_match_encoding() {
+	let locator = Locator_t::from("10.0.0.7:7400".parse::<::std::net::SocketAddr>().unwrap());
+	let params = vec![
+		Parameter::ParticipantLeaseDuration(Time_t::new(100, 0)),
+		Parameter::TypeName(String::from("ShapeType")),
+		Parameter::OwnershipStrength(3),
+		Parameter::ProtocolVersion(PROTOCOL_VERSION),
+		Parameter::VendorId(VENDORID_UNKNOWN),
+		Parameter::Liveliness(LivelinessKind::AUTOMATIC, TIME_ZERO),
+		Parameter::Partition(Vec::new()),
+		Parameter::Partition(vec![String::from("abc"), String::from(""), String::from("de")]),
+		Parameter::UserData(vec![1, 2, 3]),
+		Parameter::MetatrafficUnicastLocator(locator),
+		Parameter::History(HistoryKind::KEEP_LAST, 1),
+		Parameter::ResourceLimits(-1, -1, -1),
+		Parameter::ParticipantBuiltinEndpoints(BuiltinEndpointSet_t(0x3f)),
+		Parameter::GroupEntityId(ENTITYID_PARTICIPANT),
+		Parameter::StatusInfo(StatusInfo_t([0, 0, 0, 1])),
+		Parameter::Other(PID_COHERENT_SET, Bytes::from(&[1, 2, 3, 4, 5][..])),
+	];
+
+	for p in params {
+		let mut buf = BytesMut::new();
+		p.encode(&mut Encoder::new(&mut buf));
+		assert_eq!(buf.len(), p.size() as usize);
+	}
+}
+
+#[test]
+fn unknown_parameters() {
+	// An unknown parameter, a vendor specific parameter, then the sentinel.
+	let skipped = BytesMut::from(&[0x00, 0x7f, 0x00, 0x04, 1, 2, 3, 4,
+								   0x80, 0x01, 0x00, 0x00,
+								   0x00, 0x01, 0x00, 0x00][..]);
+	let list = ParameterList::decode(&mut Decoder::new(skipped.freeze(), Endianness::BigEndian));
+	assert_eq!(list.unwrap().0.len(), 0);
+
+	// The same unknown parameter, marked as must-understand.
+	let rejected = BytesMut::from(&[0x40, 0x7f, 0x00, 0x04, 1, 2, 3, 4,
+									0x00, 0x01, 0x00, 0x00][..]);
+	let list = ParameterList::decode(&mut Decoder::new(rejected.freeze(), Endianness::BigEndian));
+	assert!(list.is_err());
+
+	// A vendor specific parameter marked as must-understand is still skipped.
+	let vendor = BytesMut::from(&[0xc0, 0x7f, 0x00, 0x04, 1, 2, 3, 4,
+								  0x00, 0x01, 0x00, 0x00][..]);
+	let list = ParameterList::decode(&mut Decoder::new(vendor.freeze(), Endianness::BigEndian));
+	assert_eq!(list.unwrap().0.len(), 0);
+}
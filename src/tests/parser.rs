@@ -0,0 +1,172 @@
+use bytes::{Bytes, BytesMut};
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::header::Header;
+use rtps::messages::submessage::{Submessage, TIME_ZERO};
+use rtps::messages::submessage::header::*;
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use rtps::messages::submessage::info_reply_ip4::InfoReplyIp4;
+use rtps::messages::submessage::info_timestamp::InfoTimestamp;
+use rtps::messages::submessage::unknown::UnknownSubmessage;
+
+fn heartbeat(first_sn: i64) -> Heartbeat {
+	Heartbeat::new(false, true,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(first_sn),
+		SequenceNumber_t::from(5),
+		From::from(1))
+}
+
+fn sample_message() -> Bytes {
+	sample_message_with_payload(Bytes::from(&b"hello"[..]))
+}
+
+fn sample_message_with_payload(payload: Bytes) -> Bytes {
+	let mut data = Data::new(false, true, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(3),
+		None,
+		Some(payload));
+	data.header.flags = DataFlags {endianness: Endianness::BigEndian, ..data.flags()}.bits();
+
+	let its = InfoTimestamp::new(false, TIME_ZERO);
+	let hb = heartbeat(1);
+
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPrefix_t::new()), &[&its, &data, &hb], &mut buf).unwrap();
+	buf.freeze()
+}
+
+#[test]
+fn round_trip() {
+	let datagram = sample_message();
+	let msg = parse_message(datagram.clone()).unwrap();
+	assert_eq!(msg.submessages.len(), 3);
+
+	let refs: Vec<&dyn Encode> = msg.submessages.iter().map(|s| s as &dyn Encode).collect();
+	let mut buf = BytesMut::new();
+	encode_message(&msg.header, &refs, &mut buf).unwrap();
+	assert_eq!(&buf[..], &datagram[..]);
+}
+
+#[test]
+fn payload_is_not_copied() {
+	// Small slices are stored inline by the bytes crate, so use a payload
+	// large enough to be shared.
+	let datagram = sample_message_with_payload(Bytes::from(vec![7u8; 64]));
+	let msg = parse_message(datagram.clone()).unwrap();
+
+	match msg.submessages[1] {
+		Submessage::Data(ref d) => {
+			let payload = d.serialized_payload.as_ref().unwrap();
+			assert_eq!(&payload[..], &[7u8; 64][..]);
+
+			let start = datagram.as_ptr() as usize;
+			let p = payload.as_ptr() as usize;
+			assert!(p >= start && p < start + datagram.len());
+		},
+		_ => panic!("expected Data"),
+	}
+}
+
+#[test]
+fn invalid_header() {
+	let mut datagram = BytesMut::from(&sample_message()[..]);
+	datagram[0] = b'X';
+	assert_eq!(MessageParser::new(datagram.freeze()).err(),
+		Some(ParseError::InvalidProtocolId([b'X', b'T', b'P', b'S'])));
+
+	let short = Bytes::from(&b"RTPS\x02\x02"[..]);
+	assert_eq!(MessageParser::new(short).err(),
+		Some(ParseError::UnexpectedEnd {needed: 2, remaining: 0}));
+}
+
+#[test]
+fn length_past_end_invalidates_remainder() {
+	let mut datagram = BytesMut::from(&sample_message()[..]);
+	// InfoTimestamp is little endian, claim 0xff00 bytes follow.
+	datagram[23] = 0xff;
+
+	let mut parser = MessageParser::new(datagram.freeze()).unwrap();
+	match parser.next() {
+		Some(Err(ParseError::InvalidSubmessageLength{submessage_id, ..})) =>
+			assert_eq!(submessage_id, 0x09),
+		_ => panic!("expected InvalidSubmessageLength"),
+	}
+	assert!(parser.next().is_none());
+}
+
+#[test]
+fn invalid_submessages_are_skipped_and_unknown_kept() {
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPrefix_t::new()), &[&heartbeat(0), &heartbeat(2)], &mut buf).unwrap();
+	// Unknown submessage id with 4 bytes of contents.
+	buf.extend_from_slice(&[0x70, 0x01, 4, 0, 1, 2, 3, 4]);
+
+	let results: Vec<_> = MessageParser::new(buf.freeze()).unwrap().collect();
+	assert_eq!(results.len(), 3);
+	assert_eq!(results[0].as_ref().err(), Some(&ParseError::InvalidSubmessage {
+		submessage_id: 0x07,
+		reason: "first_sn must be positive"
+	}));
+	assert!(results[1].is_ok());
+	match results[2] {
+		Ok(Submessage::Unknown(ref u)) => {
+			assert_eq!(u.header.submessage_id, 0x70);
+			assert!(!u.is_vendor_specific());
+			assert_eq!(&u.contents[..], &[1, 2, 3, 4]);
+		},
+		_ => panic!("expected unknown submessage"),
+	}
+}
+
+#[test]
+fn vendor_specific_submessages_are_forwarded() {
+	let vendor = UnknownSubmessage::new(0x81, 0x03, Bytes::from(&[9, 8, 7, 6, 5, 4, 3, 2][..]));
+	let hb = heartbeat(1);
+
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPrefix_t::new()), &[&vendor, &hb], &mut buf).unwrap();
+	let original = buf.freeze();
+	let msg = parse_message(original.clone()).unwrap();
+	assert_eq!(msg.submessages.len(), 2);
+	match msg.submessages[0] {
+		Submessage::Unknown(ref u) => assert!(u.is_vendor_specific()),
+		_ => panic!("expected unknown submessage"),
+	}
+
+	// Re-encoding the parsed message reproduces the original exactly.
+	let submessages: Vec<&dyn Encode> = msg.submessages.iter().map(|s| s as &dyn Encode).collect();
+	let mut forwarded = BytesMut::new();
+	encode_message(&msg.header, &submessages, &mut forwarded).unwrap();
+	assert_eq!(&forwarded[..], &original[..]);
+}
+
+#[test]
+fn info_reply_ip4() {
+	let unicast = LocatorUDPv4_t {address: 0x7f000001, port: 7411};
+	let multicast = LocatorUDPv4_t {address: 0xefff0001, port: 7400};
+	let irip4 = InfoReplyIp4::new(unicast, Some(multicast));
+
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPrefix_t::new()), &[&irip4], &mut buf).unwrap();
+	assert_eq!(&buf[20..], &[0x0d, 0x03, 16, 0,
+							 1, 0, 0, 0x7f, 0xf3, 0x1c, 0, 0,
+							 1, 0, 0xff, 0xef, 0xe8, 0x1c, 0, 0][..]);
+
+	let msg = parse_message(buf.freeze()).unwrap();
+	match msg.submessages[0] {
+		Submessage::InfoReplyIp4(ref r) => {
+			assert_eq!(r.unicast_locator, unicast);
+			assert_eq!(r.multicast_locator, Some(multicast));
+			assert_eq!(r.unicast_locator.to_locator().unwrap().socket_addr(),
+					   Some("127.0.0.1:7411".parse().unwrap()));
+		},
+		_ => panic!("expected InfoReplyIp4"),
+	}
+}
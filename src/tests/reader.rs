@@ -0,0 +1,348 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use bytes::Bytes;
+use tokio_core::reactor::Core;
+use rtps::*;
+use rtps::guid::*;
+use rtps::cache_change::CacheChange;
+use rtps::entity::Entity;
+use rtps::entity::endpoint::Endpoint;
+use rtps::entity::endpoint::reader::*;
+use rtps::entity::endpoint::stateless_writer::*;
+use rtps::messages::parser::*;
+use rtps::messages::receiver::*;
+use rtps::entity::endpoint::SendError;
+use rtps::messages::submessage::{Submessage, Time_t, Count_t, KeyHash_t};
+use rtps::messages::submessage::element::SequenceNumberSet;
+use rtps::messages::submessage::ack_nack::AckNack;
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::gap::Gap;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use tests::{participant, sns};
+
+fn reader(core: &Core, prefix: GUIDPrefix_t, reliability: ReliabilityKind_t) -> Reader {
+	Reader::new(GUID_t::new(prefix, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER),
+		Vec::new(),
+		Vec::new(),
+		reliability,
+		TopicKind_t::WITH_KEY,
+		true,
+		Time_t::new(0, 0x2000_0000),
+		Time_t::new(0, 0x1000_0000),
+		participant(),
+		false,
+		&core.handle())
+}
+
+fn writer_guid() -> GUID_t {
+	GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER)
+}
+
+/// Matches r with a new writer, returning it's GUID.
+fn matched(r: &mut Reader, locators: Vec<Locator_t>) -> GUID_t {
+	let guid = writer_guid();
+	r.matched_writer_add(WriterProxy::new(guid, locators, Vec::new()));
+	guid
+}
+
+fn heartbeat(first: i64, last: i64, count: i32, is_final: bool) -> Heartbeat {
+	Heartbeat::new(is_final, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(first),
+		SequenceNumber_t::from(last),
+		Count_t::from(count))
+}
+
+/// The AckNacks sent to writer.
+fn acknacks(messages: &[(GUID_t, Result<Bytes, SendError>)], writer: GUID_t) -> Vec<AckNack> {
+	let mut out = Vec::new();
+	for &(guid, ref msg) in messages {
+		assert_eq!(guid, writer);
+		for s in parse_message(msg.as_ref().unwrap().clone()).unwrap().submessages {
+			if let Submessage::AckNack(a) = s {
+				assert_eq!(a.writer_id, *writer.entity_id());
+				assert_eq!(a.reader_id, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER);
+				out.push(a);
+			}
+		}
+	}
+	out
+}
+
+fn change(writer: GUID_t, kind: ChangeKind_t, sn: i64, value: &'static [u8]) -> CacheChange {
+	let key_hash = KeyHash_t([sn as u8; 16]);
+	CacheChange {
+		kind,
+		writer_guid: writer,
+		instance_handle: InstanceHandle_t::from(key_hash),
+		key_hash: Some(key_hash),
+		sequence_number: SequenceNumber_t::from(sn),
+		data_value: Bytes::from(value)
+	}
+}
+
+#[test]
+fn data_is_added_to_the_reader_cache() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::BEST_EFFORT);
+	assert_eq!(r.guid().entity_id(), &ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER);
+	assert_eq!(r.reliability_level(), &ReliabilityKind_t::BEST_EFFORT);
+	assert!(r.expects_inline_qos());
+	assert_eq!(r.heartbeat_response_delay(), Time_t::new(0, 0x2000_0000));
+	assert_eq!(r.heartbeat_suppression_duration(), Time_t::new(0, 0x1000_0000));
+
+	let writer = matched(&mut r, Vec::new());
+	let alive = change(writer, ChangeKind_t::ALIVE, 1, b"sample");
+	let unmatched = writer_guid();
+	assert!(!r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(unmatched, ChangeKind_t::ALIVE, 1, b"other"), true), unmatched));
+	let disposed = change(writer, ChangeKind_t::NOT_ALIVE_DISPOSED, 2, b"key");
+	assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &alive, true), writer));
+	assert!(r.process_data(&Data::from_change(ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER, &disposed, true), writer));
+
+	let changes = r.reader_cache().get_changes();
+	assert_eq!(changes.len(), 2);
+	assert!(*changes[0] == alive && *changes[1] == disposed);
+	assert_eq!(changes[0].data_value, Bytes::from(&b"sample"[..]));
+	assert_eq!(changes[1].key_hash, disposed.key_hash);
+}
+
+#[test]
+fn data_for_other_readers_and_duplicates_are_ignored() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::RELIABLE);
+	let writer = matched(&mut r, Vec::new());
+	let c = change(writer, ChangeKind_t::ALIVE, 1, b"sample");
+	assert!(!r.process_data(&Data::from_change(ENTITYID_SEDP_BUILTIN_TOPIC_READER, &c, false), writer));
+	assert!(r.reader_cache().get_changes().is_empty());
+
+	assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &c, false), writer));
+	assert!(!r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &c, false), writer));
+
+	// Sequence numbers are per writer.
+	let other = matched(&mut r, Vec::new());
+	assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(other, ChangeKind_t::ALIVE, 1, b"other"), false), other));
+	assert_eq!(r.reader_cache().get_changes().len(), 2);
+	assert!(r.reader_cache().get_change(&other, SequenceNumber_t::from(1)).is_some());
+	assert!(r.reader_cache().get_change(&other, SequenceNumber_t::from(2)).is_none());
+}
+
+#[test]
+fn changes_sent_by_a_writer_are_received() {
+	let core = Core::new().unwrap();
+	let locator = Locator_t::UDP_V4 {port: 7411, address: [127, 0, 0, 1]};
+	let writer_guid = GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER);
+	let mut w = StatelessWriter::new(writer_guid,
+		Vec::new(),
+		Vec::new(),
+		ReliabilityKind_t::BEST_EFFORT,
+		TopicKind_t::WITH_KEY,
+		true,
+		Time_t::new(1, 0),
+		Time_t::new(0, 0),
+		DURATION_INFINITE,
+		participant(),
+		false,
+		&core.handle());
+	w.reader_locator_add(locator, false);
+	w.push_change(ChangeKind_t::ALIVE, Bytes::from(&b"tick"[..]), HANDLE_NIL, None);
+	w.push_change(ChangeKind_t::ALIVE, Bytes::from(&b"tock"[..]), HANDLE_NIL, None);
+
+	let local = GUIDPrefix_t::new();
+	let mut r = reader(&core, local, ReliabilityKind_t::BEST_EFFORT);
+	r.matched_writer_add(WriterProxy::new(writer_guid, Vec::new(), Vec::new()));
+	let mut receiver = MessageReceiver::new(local);
+	let source: SocketAddr = "127.0.0.1:7410".parse().unwrap();
+	for (_, msg) in w.messages(Time_t::new(10, 0)) {
+		receiver.receive(parse_message(msg.unwrap()).unwrap(), &source, |received| {
+			if let Submessage::Data(ref d) = received.submessage {
+				assert!(r.process_data(d, received.source));
+			}
+		});
+	}
+
+	let changes: Vec<Arc<CacheChange>> = r.reader_cache().get_changes();
+	assert_eq!(changes.len(), 2);
+	assert!(changes.iter().all(|c| c.writer_guid == writer_guid));
+	assert_eq!(changes[1].data_value, Bytes::from(&b"tock"[..]));
+}
+
+#[test]
+fn best_effort_readers_skip_over_lost_changes() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::BEST_EFFORT);
+	let writer = matched(&mut r, Vec::new());
+	for sn in &[1, 3] {
+		assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(writer, ChangeKind_t::ALIVE, *sn, b"data"), false), writer));
+	}
+	assert!(!r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(writer, ChangeKind_t::ALIVE, 2, b"late"), false), writer));
+
+	let proxy = r.matched_writer_lookup(writer).unwrap();
+	assert_eq!(proxy.available_changes_max(), SequenceNumber_t::from(3));
+	assert_eq!(proxy.status(SequenceNumber_t::from(2)), ChangeFromWriterStatusKind::LOST);
+	assert_eq!(proxy.status(SequenceNumber_t::from(3)), ChangeFromWriterStatusKind::RECEIVED);
+	assert_eq!(proxy.status(SequenceNumber_t::from(4)), ChangeFromWriterStatusKind::UNKNOWN);
+
+	// Best-effort readers don't acknowledge anything.
+	r.process_heartbeat(&heartbeat(1, 5, 1, false), writer, Time_t::new(10, 0));
+	assert!(r.matched_writer_lookup(writer).unwrap().missing_changes().is_empty());
+	assert!(r.messages(Time_t::new(20, 0)).is_empty());
+}
+
+#[test]
+fn heartbeats_and_gaps_update_change_status() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::RELIABLE);
+	let writer = matched(&mut r, Vec::new());
+	assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(writer, ChangeKind_t::ALIVE, 2, b"data"), false), writer));
+
+	r.process_heartbeat(&heartbeat(1, 5, 1, false), writer, Time_t::new(10, 0));
+	assert_eq!(r.matched_writer_lookup(writer).unwrap().missing_changes(), sns(&[1, 3, 4, 5]));
+	assert_eq!(r.matched_writer_lookup(writer).unwrap().available_changes_max(), SequenceNumber_t::from(0));
+
+	// 3 is irrelevant to the reader, and the writer no longer has 1.
+	let gap = Gap::new(ENTITYID_UNKNOWN, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(3), SequenceNumberSet::new(SequenceNumber_t::from(4)));
+	r.process_gap(&gap, writer);
+	let proxy = r.matched_writer_lookup(writer).unwrap();
+	assert_eq!(proxy.status(SequenceNumber_t::from(2)), ChangeFromWriterStatusKind::RECEIVED);
+	assert!(!proxy.change_from_writer(SequenceNumber_t::from(3)).unwrap().is_relevant());
+	r.process_heartbeat(&heartbeat(2, 5, 2, false), writer, Time_t::new(11, 0));
+	let proxy = r.matched_writer_lookup(writer).unwrap();
+	assert_eq!(proxy.missing_changes(), sns(&[4, 5]));
+	assert_eq!(proxy.available_changes_max(), SequenceNumber_t::from(3));
+	assert_eq!(proxy.status(SequenceNumber_t::from(1)), ChangeFromWriterStatusKind::LOST);
+	assert_eq!(proxy.status(SequenceNumber_t::from(2)), ChangeFromWriterStatusKind::RECEIVED);
+	assert_eq!(proxy.status(SequenceNumber_t::from(3)), ChangeFromWriterStatusKind::RECEIVED);
+	assert!(proxy.change_from_writer(SequenceNumber_t::from(3)).is_none());
+
+	// The AckNack acknowledges everything available, and requests the rest.
+	let messages = r.messages(Time_t::new(11, 0x2000_0000));
+	let sent = acknacks(&messages, writer);
+	assert_eq!(sent.len(), 1);
+	assert!(!sent[0].is_final());
+	assert_eq!(sent[0].reader_sn_state.bitmap_base(), SequenceNumber_t::from(4));
+	assert_eq!(sent[0].reader_sn_state.iter().collect::<Vec<_>>(), sns(&[4, 5]));
+	assert_eq!(sent[0].count, Count_t::from(1));
+
+	// Once received, nothing is missing.
+	for sn in &[5, 4] {
+		assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(writer, ChangeKind_t::ALIVE, *sn, b"data"), false), writer));
+	}
+	assert_eq!(r.matched_writer_lookup(writer).unwrap().available_changes_max(), SequenceNumber_t::from(5));
+	assert_eq!(r.reader_cache().get_changes().len(), 3);
+}
+
+#[test]
+fn acknacks_are_sent_after_heartbeat_response_delay() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::RELIABLE);
+	let writer = matched(&mut r, Vec::new());
+
+	// Final heartbeats need no response unless changes are missing.
+	r.process_heartbeat(&heartbeat(1, 0, 1, true), writer, Time_t::new(10, 0));
+	assert!(r.messages(Time_t::new(20, 0)).is_empty());
+
+	r.process_heartbeat(&heartbeat(1, 2, 2, true), writer, Time_t::new(20, 0));
+	assert!(r.messages(Time_t::new(20, 0x1000_0000)).is_empty());
+	let sent = acknacks(&r.messages(Time_t::new(20, 0x2000_0000)), writer);
+	assert_eq!(sent.len(), 1);
+	assert_eq!(sent[0].reader_sn_state.iter().collect::<Vec<_>>(), sns(&[1, 2]));
+	assert!(r.messages(Time_t::new(30, 0)).is_empty());
+
+	// Duplicate heartbeats, and those that follow too soon, are ignored.
+	r.process_heartbeat(&heartbeat(1, 2, 2, false), writer, Time_t::new(30, 0));
+	r.process_heartbeat(&heartbeat(3, 3, 3, false), writer, Time_t::new(30, 0));
+	r.process_heartbeat(&heartbeat(3, 3, 4, false), writer, Time_t::new(30, 0x0800_0000));
+	let sent = acknacks(&r.messages(Time_t::new(31, 0)), writer);
+	assert_eq!(sent.len(), 1);
+	assert_eq!(sent[0].count, Count_t::from(2));
+	assert_eq!(sent[0].reader_sn_state.iter().collect::<Vec<_>>(), sns(&[3]));
+
+	// Non-final heartbeats are always responded to.
+	assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(writer, ChangeKind_t::ALIVE, 3, b"data"), false), writer));
+	r.process_heartbeat(&heartbeat(3, 3, 5, false), writer, Time_t::new(40, 0));
+	let sent = acknacks(&r.messages(Time_t::new(41, 0)), writer);
+	assert!(sent[0].is_final() && sent[0].reader_sn_state.is_empty());
+	assert_eq!(sent[0].reader_sn_state.bitmap_base(), SequenceNumber_t::from(4));
+
+	// Writers without locators can't be sent to.
+	r.process_heartbeat(&heartbeat(3, 3, 6, false), writer, Time_t::new(50, 0));
+	let errors = r.send(Time_t::new(51, 0));
+	assert_eq!(errors.len(), 1);
+	match errors[0] {
+		(g, SendError::NoLocator) => assert_eq!(g, writer),
+		_ => panic!("expected no locator"),
+	}
+}
+
+#[test]
+fn acknacks_are_sent_to_every_locator() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::RELIABLE);
+	let other = Locator_t::Other {kind: 0x8000_0001u32 as i32, port: 1, address: [0; 16]};
+	let another = Locator_t::Other {kind: 0x8000_0001u32 as i32, port: 2, address: [0; 16]};
+	let writer = matched(&mut r, vec![other, another]);
+
+	// Failing to send to one locator doesn't stop sending to the rest.
+	r.process_heartbeat(&heartbeat(1, 1, 1, false), writer, Time_t::new(10, 0));
+	let errors = r.send(Time_t::new(20, 0));
+	assert_eq!(errors.len(), 2);
+	match (&errors[0], &errors[1]) {
+		(&(g, SendError::UnsupportedLocator(l)), &(h, SendError::UnsupportedLocator(m))) => {
+			assert_eq!((g, h), (writer, writer));
+			assert_eq!((l, m), (other, another));
+		},
+		_ => panic!("expected errors for every locator"),
+	}
+}
+
+#[test]
+fn heartbeat_counts_wrap_around() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::RELIABLE);
+	let writer = matched(&mut r, Vec::new());
+
+	r.process_heartbeat(&heartbeat(1, 1, i32::MAX, false), writer, Time_t::new(10, 0));
+	r.process_heartbeat(&heartbeat(1, 2, i32::MIN, false), writer, Time_t::new(20, 0));
+	assert_eq!(r.matched_writer_lookup(writer).unwrap().missing_changes(), sns(&[1, 2]));
+	r.process_heartbeat(&heartbeat(1, 3, i32::MAX, false), writer, Time_t::new(30, 0));
+	assert_eq!(r.matched_writer_lookup(writer).unwrap().missing_changes(), sns(&[1, 2]));
+}
+
+#[test]
+fn only_changes_an_acknack_can_request_are_tracked() {
+	let core = Core::new().unwrap();
+	let mut r = reader(&core, GUIDPrefix_t::new(), ReliabilityKind_t::RELIABLE);
+	let writer = matched(&mut r, Vec::new());
+
+	// A writer announcing every sequence number only has the first 256
+	// requested.
+	r.process_heartbeat(&heartbeat(1, i64::MAX, 1, false), writer, Time_t::new(10, 0));
+	let proxy = r.matched_writer_lookup(writer).unwrap();
+	assert_eq!(proxy.missing_changes().len(), 256);
+	assert_eq!(proxy.missing_changes().last(), Some(&SequenceNumber_t::from(256)));
+	assert_eq!(proxy.status(SequenceNumber_t::from(i64::MAX)), ChangeFromWriterStatusKind::MISSING);
+
+	// Changes too far ahead are ignored until they can be requested.
+	let far = change(writer, ChangeKind_t::ALIVE, 300, b"data");
+	assert!(!r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &far, false), writer));
+	assert!(r.process_data(&Data::from_change(ENTITYID_UNKNOWN, &change(writer, ChangeKind_t::ALIVE, 2, b"data"), false), writer));
+
+	// Gaps further ahead are only tracked within the window, but a gap
+	// following the available changes applies to its whole range at once.
+	let gap = Gap::new(ENTITYID_UNKNOWN, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(10), SequenceNumberSet::new(SequenceNumber_t::from(12)));
+	r.process_gap(&gap, writer);
+	assert_eq!(r.matched_writer_lookup(writer).unwrap().missing_changes()[..8], sns(&[1, 3, 4, 5, 6, 7, 8, 9])[..]);
+	let gap = Gap::new(ENTITYID_UNKNOWN, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1), SequenceNumberSet::new(SequenceNumber_t::from(i64::MAX - 1)));
+	r.process_gap(&gap, writer);
+	let proxy = r.matched_writer_lookup(writer).unwrap();
+	assert_eq!(proxy.available_changes_max(), SequenceNumber_t::from(i64::MAX - 2));
+	assert_eq!(proxy.missing_changes(), sns(&[i64::MAX - 1, i64::MAX]));
+	assert!(proxy.change_from_writer(SequenceNumber_t::from(2)).is_none());
+
+	let sent = acknacks(&r.messages(Time_t::new(10, 0x2000_0000)), writer);
+	assert_eq!(sent[0].reader_sn_state.bitmap_base(), SequenceNumber_t::from(i64::MAX - 1));
+}
@@ -0,0 +1,152 @@
+use std::net::SocketAddr;
+use rtps::*;
+use rtps::guid::*;
+use rtps::messages::Message;
+use rtps::messages::header::Header;
+use rtps::messages::receiver::*;
+use rtps::messages::submessage::{Submessage, Time_t, TIME_ZERO};
+use rtps::messages::submessage::data::Data;
+use rtps::messages::submessage::heartbeat::Heartbeat;
+use rtps::messages::submessage::element::LocatorList;
+use rtps::messages::submessage::info_destination::InfoDestination;
+use rtps::messages::submessage::info_reply::InfoReply;
+use rtps::messages::submessage::info_reply_ip4::InfoReplyIp4;
+use rtps::messages::submessage::info_source::InfoSource;
+use rtps::messages::submessage::info_timestamp::InfoTimestamp;
+
+fn data() -> Submessage {
+	Submessage::Data(Data::new(false, true, false,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		None,
+		None))
+}
+
+fn heartbeat() -> Submessage {
+	Submessage::Heartbeat(Heartbeat::new(false, false,
+		ENTITYID_UNKNOWN,
+		ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER,
+		SequenceNumber_t::from(1),
+		SequenceNumber_t::from(1),
+		From::from(1)))
+}
+
+struct Dispatched {
+	destination: GUID_t,
+	source: GUID_t,
+	timestamp: Option<Time_t>,
+	reply_to: usize,
+	multicast_reply_to: usize,
+}
+
+fn receive(local: GUIDPrefix_t, msg: Message) -> Vec<Dispatched> {
+	let source: SocketAddr = "10.0.0.7:7400".parse().unwrap();
+	let mut out = Vec::new();
+	MessageReceiver::new(local).receive(msg, &source, |r| out.push(Dispatched {
+		destination: r.destination,
+		source: r.source,
+		timestamp: r.timestamp,
+		reply_to: r.unicast_reply_locator_list.len(),
+		multicast_reply_to: r.multicast_reply_locator_list.len()
+	}));
+	out
+}
+
+#[test]
+fn resolves_guids_and_timestamp() {
+	let local = GUIDPrefix_t::new();
+	let remote = GUIDPrefix_t::new();
+	let msg = Message {
+		header: Header::new(remote),
+		submessages: vec![
+			data(),
+			Submessage::InfoTimestamp(InfoTimestamp::new(false, TIME_ZERO)),
+			heartbeat(),
+			Submessage::InfoTimestamp(InfoTimestamp::new(true, TIME_ZERO)),
+			data(),
+		]
+	};
+
+	let out = receive(local, msg);
+	assert_eq!(out.len(), 3);
+
+	assert_eq!(out[0].destination, GUID_t::new(local, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER));
+	assert_eq!(out[0].source, GUID_t::new(remote, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER));
+	assert_eq!(out[0].timestamp, None);
+	assert_eq!(out[0].reply_to, 1);
+
+	assert_eq!(out[1].destination, GUID_t::new(local, ENTITYID_UNKNOWN));
+	assert_eq!(out[1].timestamp, Some(TIME_ZERO));
+
+	assert_eq!(out[2].timestamp, None);
+}
+
+#[test]
+fn info_destination_and_source() {
+	let local = GUIDPrefix_t::new();
+	let remote = GUIDPrefix_t::new();
+	let relayed = GUIDPrefix_t::new();
+	let msg = Message {
+		header: Header::new(remote),
+		submessages: vec![
+			Submessage::InfoDestination(InfoDestination::new(GUIDPrefix_t::new())),
+			data(),
+			Submessage::InfoDestination(InfoDestination::new(GUIDPREFIX_UNKNOWN)),
+			Submessage::InfoSource(InfoSource::new(PROTOCOL_VERSION, VENDORID_UNKNOWN, relayed)),
+			heartbeat(),
+		]
+	};
+
+	let out = receive(local, msg);
+	assert_eq!(out.len(), 1);
+	assert_eq!(out[0].destination, GUID_t::new(local, ENTITYID_UNKNOWN));
+	assert_eq!(out[0].source, GUID_t::new(relayed, ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER));
+	assert_eq!(out[0].reply_to, 0);
+}
+
+#[test]
+fn info_reply_without_multicast_clears_multicast_locators() {
+	let unicast: SocketAddr = "10.0.0.8:7410".parse().unwrap();
+	let multicast: SocketAddr = "239.255.0.1:7400".parse().unwrap();
+	let msg = Message {
+		header: Header::new(GUIDPrefix_t::new()),
+		submessages: vec![
+			Submessage::InfoReply(InfoReply::new(true,
+				LocatorList(vec![Locator_t::from(unicast)]),
+				LocatorList(vec![Locator_t::from(multicast)]))),
+			data(),
+			Submessage::InfoReply(InfoReply::new(false,
+				LocatorList(vec![Locator_t::from(unicast)]),
+				LocatorList(Vec::new()))),
+			data(),
+		]
+	};
+
+	let out = receive(GUIDPrefix_t::new(), msg);
+	assert_eq!(out.len(), 2);
+	assert_eq!(out[0].multicast_reply_to, 1);
+	assert_eq!(out[1].reply_to, 1);
+	assert_eq!(out[1].multicast_reply_to, 0);
+}
+
+#[test]
+fn info_reply_ip4_without_multicast_clears_multicast_locators() {
+	let unicast = LocatorUDPv4_t {address: 0x0a00_0008, port: 7410};
+	let multicast = LocatorUDPv4_t {address: 0xefff_0001, port: 7400};
+	let msg = Message {
+		header: Header::new(GUIDPrefix_t::new()),
+		submessages: vec![
+			Submessage::InfoReplyIp4(InfoReplyIp4::new(unicast, Some(multicast))),
+			data(),
+			Submessage::InfoReplyIp4(InfoReplyIp4::new(unicast, None)),
+			data(),
+		]
+	};
+
+	let out = receive(GUIDPrefix_t::new(), msg);
+	assert_eq!(out.len(), 2);
+	assert_eq!(out[0].multicast_reply_to, 1);
+	assert_eq!(out[1].reply_to, 1);
+	assert_eq!(out[1].multicast_reply_to, 0);
+}
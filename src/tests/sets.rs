@@ -0,0 +1,102 @@
+use bytes::BytesMut;
+use rtps::*;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::*;
+use rtps::messages::submessage::header::Endianness;
+use rtps::messages::submessage::element::*;
+
+fn sn(n: i64) -> SequenceNumber_t {
+	SequenceNumber_t::from(n)
+}
+
+#[test]
+fn sequence_number_set_operations() {
+	let mut set = SequenceNumberSet::from_numbers(sn(100), vec![sn(140), sn(100), sn(131)]).unwrap();
+	assert_eq!(set.num_bits(), 41);
+	assert_eq!(set.size(), 12 + 8);
+	assert!(set.contains(sn(131)));
+	assert!(!set.contains(sn(132)));
+	assert!(!set.contains(sn(99)));
+	assert_eq!(set.iter().collect::<Vec<_>>(), vec![sn(100), sn(131), sn(140)]);
+
+	assert!(set.insert(sn(355)));
+	assert_eq!(set.num_bits(), 256);
+	assert!(!set.insert(sn(356)));
+	assert!(!set.insert(sn(99)));
+
+	assert!(SequenceNumberSet::from_numbers(sn(1), vec![sn(257)]).is_none());
+	assert!(SequenceNumberSet::new(sn(1)).is_empty());
+}
+
+#[test]
+fn sequence_number_set_extremes() {
+	// Subtracting bitmap_base would overflow.
+	let mut set = SequenceNumberSet::new(sn(i64::MAX));
+	assert!(!set.contains(sn(i64::MIN)));
+	assert!(!set.insert(sn(i64::MIN)));
+	assert!(set.insert(sn(i64::MAX)));
+	assert_eq!(set.iter().collect::<Vec<_>>(), vec![sn(i64::MAX)]);
+
+	let mut set = SequenceNumberSet::new(sn(1));
+	assert!(!set.contains(sn(i64::MIN)));
+	assert!(!set.insert(sn(i64::MIN)));
+	assert!(set.is_empty());
+
+	// Bits past the largest sequence number are skipped.
+	let near_max = BytesMut::from(&[0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+									0, 0, 0, 2,
+									0xc0, 0, 0, 0][..]);
+	let set = SequenceNumberSet::decode(&mut Decoder::new(near_max.freeze(), Endianness::BigEndian)).unwrap();
+	assert_eq!(set.iter().collect::<Vec<_>>(), vec![sn(i64::MAX)]);
+}
+
+#[test]
+fn sequence_number_set_wire_format() {
+	let set = SequenceNumberSet::from_numbers(sn(1), vec![sn(1), sn(34)]).unwrap();
+
+	let mut buf = BytesMut::new();
+	set.encode(&mut Encoder::new(&mut buf));
+	assert_eq!(&buf[..], &[0, 0, 0, 0, 0, 0, 0, 1,
+						   0, 0, 0, 34,
+						   0x80, 0, 0, 0,
+						   0x40, 0, 0, 0][..]);
+
+	let mut dec = Decoder::new(buf.freeze(), Endianness::BigEndian);
+	assert_eq!(SequenceNumberSet::decode(&mut dec).unwrap(), set);
+}
+
+#[test]
+fn fragment_number_set_round_trip() {
+	let frags = vec![FragmentNumber_t::from(3), FragmentNumber_t::from(7), FragmentNumber_t::from(200)];
+	let set = FragmentNumberSet::from_numbers(FragmentNumber_t::from(3), frags.clone()).unwrap();
+	assert_eq!(set.num_bits(), 198);
+
+	let mut buf = BytesMut::new();
+	{
+		let mut enc = Encoder::new(&mut buf);
+		enc.set_endianness(Endianness::LittleEndian);
+		set.encode(&mut enc);
+	}
+	assert_eq!(buf.len(), set.size() as usize);
+
+	let mut dec = Decoder::new(buf.freeze(), Endianness::LittleEndian);
+	let decoded = FragmentNumberSet::decode(&mut dec).unwrap();
+	assert_eq!(decoded.iter().collect::<Vec<_>>(), frags);
+}
+
+#[test]
+fn invalid_sets_are_rejected() {
+	// bitmap_base of zero.
+	let zero_base = BytesMut::from(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0][..]);
+	assert!(SequenceNumberSet::decode(&mut Decoder::new(zero_base.freeze(), Endianness::BigEndian)).is_err());
+
+	// More than 256 bits.
+	let too_long = BytesMut::from(&[0, 0, 0, 1, 0, 0, 1, 1][..]);
+	assert!(FragmentNumberSet::decode(&mut Decoder::new(too_long.freeze(), Endianness::BigEndian)).is_err());
+
+	// Bits past num_bits are ignored.
+	let trailing = BytesMut::from(&[0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0xff, 0xff, 0xff, 0xff][..]);
+	let set = SequenceNumberSet::decode(&mut Decoder::new(trailing.freeze(), Endianness::BigEndian)).unwrap();
+	assert_eq!(set.iter().collect::<Vec<_>>(), vec![sn(1)]);
+}
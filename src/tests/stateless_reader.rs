@@ -0,0 +1,123 @@
+use std::net::SocketAddr;
+use bytes::Bytes;
+use tokio_core::reactor::Core;
+use rtps::*;
+use rtps::guid::*;
+use rtps::cache_change::CacheChange;
+use rtps::entity::endpoint::Endpoint;
+use rtps::entity::endpoint::stateless_reader::*;
+use rtps::entity::endpoint::stateless_writer::*;
+use rtps::messages::parser::*;
+use rtps::messages::receiver::*;
+use rtps::messages::submessage::{Submessage, Time_t};
+use rtps::messages::submessage::data::Data;
+use tests::participant;
+
+fn reader(prefix: GUIDPrefix_t) -> StatelessReader {
+	StatelessReader::new(GUID_t::new(prefix, ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER),
+		Vec::new(),
+		vec![Locator_t::UDP_V4 {port: 7400, address: [239, 255, 0, 1]}],
+		TopicKind_t::WITH_KEY,
+		false,
+		participant())
+}
+
+fn data(reader_id: EntityId_t, writer: GUID_t, sn: i64) -> Data {
+	Data::from_change(reader_id, &CacheChange {
+		kind: ChangeKind_t::ALIVE,
+		writer_guid: writer,
+		instance_handle: HANDLE_NIL,
+		key_hash: None,
+		sequence_number: SequenceNumber_t::from(sn),
+		data_value: Bytes::from(&b"participant"[..])
+	}, false)
+}
+
+#[test]
+fn data_from_any_writer_is_accepted() {
+	let mut r = reader(GUIDPrefix_t::new());
+	assert_eq!(r.reliability_level(), &ReliabilityKind_t::BEST_EFFORT);
+	assert!(!r.expects_inline_qos());
+
+	// No writers are matched; sequence numbers needn't follow on.
+	let a = GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER);
+	let b = GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER);
+	assert!(r.process_data(&data(ENTITYID_UNKNOWN, a, 5), a));
+	assert!(r.process_data(&data(ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER, b, 5), b));
+	assert!(r.process_data(&data(ENTITYID_UNKNOWN, a, 2), a));
+	assert!(!r.process_data(&data(ENTITYID_SEDP_BUILTIN_PUBLICATIONS_READER, a, 6), a));
+
+	let changes = r.reader_cache().get_changes();
+	assert_eq!(changes.len(), 3);
+	assert_eq!((changes[0].writer_guid, changes[1].writer_guid), (a, b));
+	assert_eq!(changes[2].sequence_number, SequenceNumber_t::from(2));
+}
+
+#[test]
+fn only_recent_changes_are_remembered() {
+	let mut r = reader(GUIDPrefix_t::new());
+	let a = GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER);
+	for sn in 1..=DUPLICATE_WINDOW as i64 + 1 {
+		assert!(r.process_data(&data(ENTITYID_UNKNOWN, a, sn), a));
+	}
+
+	// A writer which restarts it's sequence numbers is received from again,
+	// once the changes it sent before are forgotten.
+	assert!(r.process_data(&data(ENTITYID_UNKNOWN, a, 1), a));
+	assert!(!r.process_data(&data(ENTITYID_UNKNOWN, a, 1), a));
+	assert!(!r.process_data(&data(ENTITYID_UNKNOWN, a, DUPLICATE_WINDOW as i64 + 1), a));
+}
+
+#[test]
+fn duplicates_are_ignored() {
+	let mut r = reader(GUIDPrefix_t::new());
+	let a = GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER);
+	let b = GUID_t::new(*a.prefix(), ENTITYID_SEDP_BUILTIN_PUBLICATIONS_WRITER);
+	assert!(r.process_data(&data(ENTITYID_UNKNOWN, a, 1), a));
+	assert!(!r.process_data(&data(ENTITYID_UNKNOWN, a, 1), a));
+	assert!(!r.process_data(&data(ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER, a, 1), a));
+	assert!(r.process_data(&data(ENTITYID_UNKNOWN, b, 1), b));
+	assert_eq!(r.reader_cache().get_changes().len(), 2);
+}
+
+#[test]
+fn participant_announcements_are_received_once() {
+	let core = Core::new().unwrap();
+	let multicast = Locator_t::UDP_V4 {port: 7400, address: [239, 255, 0, 1]};
+	let writer_guid = GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER);
+	let mut w = StatelessWriter::new(writer_guid,
+		Vec::new(),
+		vec![multicast],
+		ReliabilityKind_t::BEST_EFFORT,
+		TopicKind_t::WITH_KEY,
+		true,
+		Time_t::new(1, 0),
+		Time_t::new(0, 0),
+		Time_t::new(30, 0),
+		participant(),
+		false,
+		&core.handle());
+	w.reader_locator_add(multicast, false);
+	w.push_change(ChangeKind_t::ALIVE, Bytes::from(&b"spdp"[..]), HANDLE_NIL, None);
+
+	// The announcement is resent each period, but only added once.
+	let local = GUIDPrefix_t::new();
+	let mut r = reader(local);
+	let mut receiver = MessageReceiver::new(local);
+	let source: SocketAddr = "192.168.1.2:7400".parse().unwrap();
+	let mut accepted = Vec::new();
+	for now in &[Time_t::new(100, 0), Time_t::new(130, 0)] {
+		for (_, msg) in w.messages(*now) {
+			receiver.receive(parse_message(msg.unwrap()).unwrap(), &source, |received| {
+				if let Submessage::Data(ref d) = received.submessage {
+					accepted.push(r.process_data(d, received.source));
+				}
+			});
+		}
+	}
+	assert_eq!(accepted, vec![true, false]);
+	let changes = r.reader_cache().get_changes();
+	assert_eq!(changes.len(), 1);
+	assert_eq!(changes[0].writer_guid, writer_guid);
+	assert_eq!(changes[0].data_value, Bytes::from(&b"spdp"[..]));
+}
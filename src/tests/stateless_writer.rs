@@ -0,0 +1,193 @@
+use std::net::SocketAddr;
+use bytes::{Bytes, BytesMut};
+use tokio_core::reactor::Core;
+use rtps::*;
+use rtps::guid::*;
+use rtps::entity::endpoint::SendError;
+use rtps::entity::endpoint::stateless_writer::*;
+use rtps::messages::encoder::encode_message;
+use rtps::messages::header::Header;
+use rtps::messages::parser::*;
+use rtps::messages::receiver::MessageReceiver;
+use rtps::messages::submessage::{Submessage, Time_t, TIME_ZERO};
+use rtps::messages::submessage::element::SequenceNumberSet;
+use rtps::messages::submessage::ack_nack::AckNack;
+use tests::{participant, sns};
+
+const UNICAST: Locator_t = Locator_t::UDP_V4 {port: 7411, address: [127, 0, 0, 1]};
+const MULTICAST: Locator_t = Locator_t::UDP_V4 {port: 7400, address: [239, 255, 0, 1]};
+
+fn writer(core: &Core, reliability: ReliabilityKind_t, resend_data_period: Duration_t) -> StatelessWriter {
+	StatelessWriter::new(GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER),
+		Vec::new(),
+		Vec::new(),
+		reliability,
+		TopicKind_t::WITH_KEY,
+		true,
+		Time_t::new(1, 0),
+		Time_t::new(0, 0x8000_0000),
+		resend_data_period,
+		participant(),
+		false,
+		&core.handle())
+}
+
+fn push(w: &mut StatelessWriter, value: &'static [u8]) {
+	w.push_change(ChangeKind_t::ALIVE, Bytes::from(value), HANDLE_NIL, None);
+}
+
+/// Sequence numbers of the Data, and the range announced by any Heartbeat,
+/// sent to locator.
+fn sent(messages: &[(Locator_t, Result<Bytes, SendError>)], locator: Locator_t)
+		-> (Vec<SequenceNumber_t>, Option<(SequenceNumber_t, SequenceNumber_t)>) {
+	let mut data = Vec::new();
+	let mut heartbeat = None;
+	for &(l, ref msg) in messages {
+		if l != locator {
+			continue;
+		}
+		for s in parse_message(msg.as_ref().unwrap().clone()).unwrap().submessages {
+			match s {
+				Submessage::Data(d) => {
+					assert_eq!(d.reader_id, ENTITYID_UNKNOWN);
+					data.push(d.writer_sn);
+				},
+				Submessage::Heartbeat(h) => heartbeat = Some((h.first_sn, h.last_sn)),
+				_ => {},
+			}
+		}
+	}
+	(data, heartbeat)
+}
+
+fn range(first: i64, last: i64) -> Option<(SequenceNumber_t, SequenceNumber_t)> {
+	Some((SequenceNumber_t::from(first), SequenceNumber_t::from(last)))
+}
+
+#[test]
+fn changes_are_sent_once_per_locator() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::BEST_EFFORT, DURATION_INFINITE);
+	push(&mut w, b"before");
+	w.reader_locator_add(UNICAST, false);
+	w.reader_locator_add(MULTICAST, false);
+	w.reader_locator_add(MULTICAST, false);
+	push(&mut w, b"after");
+	assert_eq!(w.reader_locator_lookup(&MULTICAST).unwrap().unsent_changes().len(), 2);
+
+	let now = Time_t::new(10, 0);
+	let messages = w.messages(now);
+	assert_eq!(messages.len(), 2);
+	assert_eq!(sent(&messages, UNICAST), (sns(&[1, 2]), None));
+	assert_eq!(sent(&messages, MULTICAST), (sns(&[1, 2]), None));
+	assert!(w.messages(now).is_empty());
+
+	push(&mut w, b"third");
+	w.reader_locator_remove(&UNICAST);
+	let messages = w.messages(now);
+	assert_eq!(sent(&messages, MULTICAST), (sns(&[3]), None));
+	assert_eq!(sent(&messages, UNICAST), (Vec::new(), None));
+}
+
+#[test]
+fn changes_are_resent_each_period() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::BEST_EFFORT, Time_t::new(30, 0));
+	w.reader_locator_add(MULTICAST, false);
+	push(&mut w, b"participant");
+
+	assert_eq!(sent(&w.messages(Time_t::new(100, 0)), MULTICAST).0, sns(&[1]));
+	assert!(w.messages(Time_t::new(129, 0)).is_empty());
+	assert_eq!(sent(&w.messages(Time_t::new(130, 0)), MULTICAST).0, sns(&[1]));
+	assert!(w.messages(Time_t::new(131, 0)).is_empty());
+}
+
+#[test]
+fn reliable_writers_repair_requested_changes() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, DURATION_INFINITE);
+	w.reader_locator_add(UNICAST, true);
+	w.reader_locator_add(MULTICAST, false);
+	push(&mut w, b"one");
+	push(&mut w, b"two");
+
+	// Changes are pushed, and announced with a Heartbeat each period.
+	let now = Time_t::new(10, 0);
+	let messages = w.messages(now);
+	assert_eq!(sent(&messages, UNICAST), (sns(&[1, 2]), range(1, 2)));
+	assert_eq!(sent(&messages, MULTICAST), (sns(&[1, 2]), range(1, 2)));
+	assert!(w.messages(Time_t::new(10, 1)).is_empty());
+
+	// Requested changes are resent after nack_response_delay, only to where
+	// the request came from.
+	let mut missing = SequenceNumberSet::new(SequenceNumber_t::from(1));
+	missing.insert(SequenceNumber_t::from(1));
+	missing.insert(SequenceNumber_t::from(7));
+	let acknack = AckNack::new(false, ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER,
+		ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER, missing, From::from(1));
+	w.process_acknack(&acknack, &[UNICAST], Time_t::new(10, 2));
+	assert_eq!(w.reader_locator_lookup(&UNICAST).unwrap().requested_changes(), &[SequenceNumber_t::from(1)]);
+	assert!(w.messages(Time_t::new(10, 3)).is_empty());
+	let messages = w.messages(Time_t::new(10, 0x8000_0002));
+	assert_eq!(sent(&messages, UNICAST), (sns(&[1]), None));
+	assert_eq!(sent(&messages, MULTICAST), (Vec::new(), None));
+
+	let messages = w.messages(Time_t::new(11, 0));
+	assert_eq!(sent(&messages, UNICAST), (Vec::new(), range(1, 2)));
+}
+
+#[test]
+fn acknacks_are_answered_at_the_address_they_came_from() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, DURATION_INFINITE);
+	w.reader_locator_add(UNICAST, false);
+	w.reader_locator_add(MULTICAST, false);
+	push(&mut w, b"one");
+	w.messages(Time_t::new(10, 0));
+
+	// Readers send from a port of their choosing, which the reply locator
+	// leaves out.
+	let requested = SequenceNumberSet::from_numbers(SequenceNumber_t::from(1), vec![SequenceNumber_t::from(1)]).unwrap();
+	let acknack = AckNack::new(false, ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER,
+		ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER, requested, From::from(1));
+	let mut buf = BytesMut::new();
+	encode_message(&Header::new(GUIDPrefix_t::new()), &[&acknack], &mut buf).unwrap();
+	let local = GUIDPrefix_t::new();
+	let source: SocketAddr = "127.0.0.1:53124".parse().unwrap();
+	MessageReceiver::new(local).receive(parse_message(buf.freeze()).unwrap(), &source, |received| {
+		if let Submessage::AckNack(ref a) = received.submessage {
+			w.process_acknack(a, received.unicast_reply_locator_list, Time_t::new(10, 1));
+		}
+	});
+	assert_eq!(w.reader_locator_lookup(&UNICAST).unwrap().requested_changes(), &[SequenceNumber_t::from(1)]);
+	assert!(w.reader_locator_lookup(&MULTICAST).unwrap().requested_changes().is_empty());
+
+	let messages = w.messages(Time_t::new(10, 0x8000_0001));
+	assert_eq!(sent(&messages, UNICAST), (sns(&[1]), None));
+	assert_eq!(sent(&messages, MULTICAST), (Vec::new(), None));
+}
+
+#[test]
+fn best_effort_writers_ignore_acknacks() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::BEST_EFFORT, DURATION_INFINITE);
+	w.reader_locator_add(UNICAST, false);
+	push(&mut w, b"one");
+	assert_eq!(w.messages(TIME_ZERO).len(), 1);
+
+	let requested = SequenceNumberSet::from_numbers(SequenceNumber_t::from(1), vec![SequenceNumber_t::from(1)]).unwrap();
+	let acknack = AckNack::new(false, ENTITYID_UNKNOWN, ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER, requested, From::from(1));
+	w.process_acknack(&acknack, &[UNICAST], TIME_ZERO);
+	assert!(w.reader_locator_lookup(&UNICAST).unwrap().requested_changes().is_empty());
+	assert!(w.messages(Time_t::new(5, 0)).is_empty());
+
+	// Locators which aren't UDP can't be sent to.
+	let other = Locator_t::Other {kind: 0x8000_0001u32 as i32, port: 1, address: [0; 16]};
+	w.reader_locator_add(other, false);
+	let errors = w.send(Time_t::new(6, 0));
+	assert_eq!(errors.len(), 1);
+	match errors[0] {
+		(l, SendError::UnsupportedLocator(_)) => assert_eq!(l, other),
+		_ => panic!("expected an unsupported locator"),
+	}
+}
@@ -0,0 +1,64 @@
+use std::time::{Duration, UNIX_EPOCH};
+use rtps::*;
+use rtps::messages::submessage::*;
+
+#[test]
+fn duration_conversions() {
+	let d = Duration::new(5, 250_000_000);
+	let t = Time_t::from(d);
+	assert_eq!(t, Time_t::new(5, 0x4000_0000));
+	assert_eq!(t.to_duration(), Some(d));
+
+	// Every nanosecond value survives the trip through the fraction.
+	for nanos in &[0, 1, 999, 123_456_789, 999_999_999] {
+		let d = Duration::new(1, *nanos);
+		assert_eq!(Time_t::from(d).to_duration(), Some(d));
+	}
+
+	assert_eq!(Time_t::from(Duration::from_secs(u64::MAX)), DURATION_INFINITE);
+	assert_eq!(TIME_INFINITE.to_duration(), None);
+	assert_eq!(TIME_INVALID.to_duration(), None);
+}
+
+#[test]
+fn system_time_conversions() {
+	let t = UNIX_EPOCH + Duration::new(1_500_000_000, 500_000_000);
+	let time = Time_t::from(t);
+	assert_eq!(time, Time_t::new(1_500_000_000, 0x8000_0000));
+	assert_eq!(time.to_system_time(), Some(t));
+
+	let before = UNIX_EPOCH - Duration::new(1, 250_000_000);
+	let time = Time_t::from(before);
+	assert_eq!(time, Time_t::new(-2, 0xc000_0000));
+	assert_eq!(time.to_system_time(), Some(before));
+
+	assert!(Time_t::now() > Time_t::from(t));
+	assert_eq!(TIME_INVALID.to_system_time(), None);
+}
+
+#[test]
+fn arithmetic_and_ordering() {
+	let a = Time_t::new(1, 0xc000_0000);
+	let b = Time_t::new(2, 0x8000_0000);
+	assert_eq!(a + b, Time_t::new(4, 0x4000_0000));
+	assert_eq!(b - a, Time_t::new(0, 0xc000_0000));
+	assert_eq!(a - b, Time_t::new(-1, 0x4000_0000));
+	assert!(a < b);
+	assert!(TIME_INVALID < TIME_ZERO);
+	assert!(b < TIME_INFINITE);
+
+	let mut c = a;
+	c += a;
+	assert_eq!(c, Time_t::new(3, 0x8000_0000));
+
+	assert_eq!(a + TIME_INFINITE, TIME_INFINITE);
+	assert_eq!(TIME_INFINITE - a, TIME_INFINITE);
+	assert_eq!(a - TIME_INFINITE, TIME_INVALID);
+	assert_eq!(a + TIME_INVALID, TIME_INVALID);
+	assert_eq!(Time_t::new(0x7fff_fff0, 0) + Time_t::new(0x10, 0), TIME_INFINITE);
+
+	// One tick before zero shares it's encoding with TIME_INVALID.
+	assert_eq!(TIME_ZERO - Time_t::new(0, 1), TIME_INVALID);
+	assert_eq!(TIME_ZERO - Time_t::new(0, 2), Time_t::new(-1, 0xffff_fffe));
+	assert_eq!(Time_t::from(UNIX_EPOCH - Duration::new(0, 1)), Time_t::new(-1, 0xffff_fffc));
+}
@@ -0,0 +1,204 @@
+use bytes::{Bytes, BytesMut};
+use cdr;
+use cdr::encapsulation::*;
+use dcps::dynamic_type::*;
+use dcps::type_object::*;
+use dcps::type_support::DdsType;
+use rtps::messages::encoder::*;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::header::Endianness;
+use rtps::messages::submessage::element::*;
+use rtps::messages::submessage::parameter::*;
+use tests::idl_shapes::shapes::*;
+use tests::idl_shapes::shapes::extensible::*;
+
+fn int32() -> DynamicType {
+	DynamicType::primitive(TypeKind::TK_INT32)
+}
+
+fn point(extensibility: Extensibility, members: &[&str]) -> DynamicType {
+	DynamicType::structure("Point", extensibility, None, members.iter().enumerate()
+		.map(|(i, name)| MemberDescriptor::new(name, i as u32, int32()))
+		.collect())
+}
+
+fn round_trip(id: &TypeIdentifier) -> TypeIdentifier {
+	let payload = cdr::to_payload(id, Encapsulation::CDR2_LE);
+	cdr::from_payload(payload).unwrap()
+}
+
+#[test]
+fn fully_descriptive_identifiers() {
+	let id = TypeIdentifier::new(&int32(), EquivalenceKind::Minimal);
+	assert_eq!(id, TypeIdentifier::Primitive(TK_INT32));
+	assert!(!id.is_hash());
+
+	let small = TypeIdentifier::new(&DynamicType::string(Some(8)), EquivalenceKind::Minimal);
+	assert_eq!(small, TypeIdentifier::String(8));
+	assert_eq!(&cdr::to_payload(&small, Encapsulation::CDR2_LE)[4..6], &[TI_STRING8_SMALL, 8]);
+	let large = TypeIdentifier::new(&DynamicType::string(Some(300)), EquivalenceKind::Minimal);
+	assert_eq!(&cdr::to_payload(&large, Encapsulation::CDR2_LE)[4..], &[TI_STRING8_LARGE, 0, 0, 0, 0x2c, 1, 0, 0]);
+
+	let sequence = TypeIdentifier::new(&DynamicType::sequence(DynamicType::string(None), Some(4)),
+									   EquivalenceKind::Complete);
+	assert_eq!(sequence, TypeIdentifier::PlainSequence {
+		element_flags: TRY_CONSTRUCT1,
+		bound: 4,
+		element: Box::new(TypeIdentifier::String(0)),
+	});
+	let array = TypeIdentifier::new(&DynamicType::array(int32(), &[2, 300]), EquivalenceKind::Complete);
+	for id in &[small, large, sequence, array] {
+		assert_eq!(&round_trip(id), id);
+	}
+
+	// Aliases, structures and the like are identified by the hash of their
+	// type object, which differs between minimal and complete objects.
+	let minimal = TypeIdentifier::new(&ShapeType::dynamic_type(), EquivalenceKind::Minimal);
+	let complete = TypeIdentifier::new(&ShapeType::dynamic_type(), EquivalenceKind::Complete);
+	assert!(minimal.is_hash() && complete.is_hash());
+	assert_ne!(minimal, complete);
+	assert_eq!(round_trip(&minimal), minimal);
+}
+
+#[test]
+fn type_objects_describe_generated_types() {
+	for ty in &[ShapeType::dynamic_type(), Drawing::dynamic_type()] {
+		for kind in &[EquivalenceKind::Minimal, EquivalenceKind::Complete] {
+			let object = TypeObject::new(ty, *kind).unwrap();
+			assert_eq!(object.type_identifier(), TypeIdentifier::new(ty, *kind));
+			let decoded: TypeObject = cdr::from_payload(cdr::to_payload(&object, Encapsulation::CDR2_LE)).unwrap();
+			assert_eq!(decoded, object);
+		}
+	}
+	assert!(TypeObject::new(&int32(), EquivalenceKind::Minimal).is_none());
+
+	// Minimal objects leave out names.
+	let minimal = TypeObject::new(&point(Extensibility::Final, &["x", "y"]), EquivalenceKind::Minimal).unwrap();
+	assert_eq!(minimal.type_name, "");
+	match minimal.kind {
+		TypeObjectKind::Struct(_, ref members) => assert!(members.iter().all(|m| match m.name {
+			MemberName::Hash(_) => true,
+			MemberName::Name(_) => false,
+		})),
+		_ => panic!("expected a structure"),
+	}
+
+	// Complete objects can be turned back into the types they describe,
+	// other than the default values of members.
+	let mut library = TypeLibrary::new();
+	for ty in &[ShapeType::dynamic_type(), Drawing::dynamic_type()] {
+		library.insert_type(ty);
+		let info = TypeInformation::new(ty);
+		assert!(library.get(&info.minimal.typeid_with_size.type_id).is_some());
+		assert_eq!(library.dynamic_type(&info.minimal.typeid_with_size.type_id), None);
+		let rebuilt = library.dynamic_type(&info.complete.typeid_with_size.type_id).unwrap();
+		assert!(rebuilt.is_assignable_from(ty) && ty.is_assignable_from(&rebuilt));
+	}
+	let shape = library.dynamic_type(&TypeIdentifier::new(&ShapeType::dynamic_type(), EquivalenceKind::Complete));
+	assert_eq!(shape, Some(ShapeType::dynamic_type()));
+	assert_eq!(ShapeType::type_information(), TypeInformation::new(&ShapeType::dynamic_type()));
+}
+
+#[test]
+fn type_information_in_parameter_list() {
+	let info = Drawing::type_information();
+	assert!(info.complete.dependent_typeid_count > 0);
+	assert_eq!(info.complete.dependent_typeid_count as usize, info.complete.dependent_typeids.len());
+
+	for endianness in &[Endianness::BigEndian, Endianness::LittleEndian] {
+		let mut list = ParameterList::new();
+		list.push(Parameter::TypeInformation(info.clone()));
+		list.push(Parameter::TopicName(String::from("Drawing")));
+		let mut buf = BytesMut::new();
+		{
+			let mut enc = Encoder::new(&mut buf);
+			enc.set_endianness(*endianness);
+			list.encode(&mut enc);
+		}
+		assert_eq!(buf.len(), list.size() as usize);
+		assert_eq!(buf.len() % 4, 0);
+
+		let mut dec = Decoder::new(buf.freeze(), *endianness);
+		let decoded = ParameterList::decode(&mut dec).unwrap();
+		match decoded.get(PID_TYPE_INFORMATION) {
+			Some(Parameter::TypeInformation(t)) => assert_eq!(t, &info),
+			_ => panic!("expected type information"),
+		}
+		match decoded.get(PID_TOPIC_NAME) {
+			Some(Parameter::TopicName(s)) => assert_eq!(s, "Drawing"),
+			_ => panic!("expected topic name"),
+		}
+	}
+
+	// PID_TYPE_INFORMATION header, followed by a truncated value.
+	let mut dec = Decoder::new(Bytes::from(&[0x00, 0x75, 0, 4, 0, 0, 0, 8][..]), Endianness::BigEndian);
+	assert!(ParameterList::decode(&mut dec).is_err());
+}
+
+#[test]
+fn assignability() {
+	let final_xy = point(Extensibility::Final, &["x", "y"]);
+	let appendable_xy = point(Extensibility::Appendable, &["x", "y"]);
+	let appendable_xyz = point(Extensibility::Appendable, &["x", "y", "z"]);
+	let mutable_xyz = point(Extensibility::Mutable, &["x", "y", "z"]);
+
+	assert!(final_xy.is_assignable_from(&final_xy));
+	assert!(!final_xy.is_assignable_from(&point(Extensibility::Final, &["x", "y", "z"])));
+	assert!(!final_xy.is_assignable_from(&appendable_xy));
+	assert!(!final_xy.is_assignable_from(&point(Extensibility::Final, &["x", "z"])));
+
+	// Appendable types may add members at the end.
+	assert!(appendable_xy.is_assignable_from(&appendable_xyz));
+	assert!(appendable_xyz.is_assignable_from(&appendable_xy));
+	assert!(!appendable_xy.is_assignable_from(&point(Extensibility::Appendable, &["y", "x"])));
+
+	// Mutable types only need a member in common.
+	let mutable_z = DynamicType::structure("Point", Extensibility::Mutable, None, vec![
+		MemberDescriptor::new("z", 2, int32()),
+	]);
+	assert!(mutable_xyz.is_assignable_from(&mutable_z));
+	assert!(!mutable_z.is_assignable_from(&point(Extensibility::Mutable, &["x", "y"])));
+
+	// Members must agree on their types, and on being part of the key.
+	let keyed = DynamicType::structure("Point", Extensibility::Appendable, None, vec![
+		MemberDescriptor {is_key: true, ..MemberDescriptor::new("x", 0, int32())},
+		MemberDescriptor::new("y", 1, int32()),
+	]);
+	assert!(!keyed.is_assignable_from(&appendable_xy));
+	let wide = DynamicType::structure("Point", Extensibility::Appendable, None, vec![
+		MemberDescriptor::new("x", 0, DynamicType::primitive(TypeKind::TK_INT64)),
+		MemberDescriptor::new("y", 1, int32()),
+	]);
+	assert!(!wide.is_assignable_from(&appendable_xy));
+
+	// String bounds are ignored, and final enums must have the same literals.
+	assert!(DynamicType::string(Some(8)).is_assignable_from(&DynamicType::string(None)));
+	let kind = DynamicType::enumeration("ShapeKind", &["CIRCLE", "SQUARE", "TRIANGLE"]);
+	assert!(kind.is_assignable_from(&kind));
+	assert!(!kind.is_assignable_from(&DynamicType::enumeration("ShapeKind", &["CIRCLE", "SQUARE"])));
+	assert!(!kind.is_assignable_from(&DynamicType::enumeration("ShapeKind", &["SQUARE", "CIRCLE"])));
+
+	// Using the type information sent in discovery.
+	let mut library = TypeLibrary::new();
+	library.insert_type(&appendable_xy);
+	library.insert_type(&appendable_xyz);
+	let reader = TypeInformation::new(&appendable_xy);
+	assert!(is_assignable(&reader, &reader, &library));
+	assert!(is_assignable(&reader, &TypeInformation::new(&appendable_xyz), &library));
+	assert!(!is_assignable(&reader, &TypeInformation::new(&final_xy), &library));
+	assert!(!is_assignable(&reader, &ShapeType::type_information(), &library));
+}
+
+#[test]
+fn incomplete_descriptors_are_rejected() {
+	for kind in &[TypeKind::TK_SEQUENCE, TypeKind::TK_ARRAY, TypeKind::TK_ALIAS, TypeKind::TK_UNION] {
+		assert!(DynamicType::new(TypeDescriptor::new(*kind, "Incomplete"), Vec::new()).is_none());
+	}
+
+	let mut descriptor = TypeDescriptor::new(TypeKind::TK_SEQUENCE, "");
+	descriptor.element_type = Some(int32());
+	let sequence = DynamicType::new(descriptor, Vec::new()).unwrap();
+	assert_eq!(sequence, DynamicType::sequence(int32(), None));
+	assert_eq!(TypeIdentifier::new(&sequence, EquivalenceKind::Minimal),
+			   TypeIdentifier::new(&DynamicType::sequence(int32(), None), EquivalenceKind::Minimal));
+}
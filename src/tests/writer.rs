@@ -0,0 +1,410 @@
+use bytes::Bytes;
+use std::net::UdpSocket;
+use std::time::Duration;
+use futures::{future, Async};
+use tokio_core::reactor::Core;
+use rtps::*;
+use rtps::guid::*;
+use rtps::cache_change::CacheChange;
+use rtps::entity::endpoint::SendError;
+use rtps::entity::endpoint::writer::*;
+use rtps::messages::parser::*;
+use rtps::messages::submessage::{Submessage, Time_t, Count_t};
+use rtps::messages::submessage::element::SequenceNumberSet;
+use rtps::messages::submessage::ack_nack::AckNack;
+use tests::{participant, sns};
+
+const UNICAST: Locator_t = Locator_t::UDP_V4 {port: 7411, address: [127, 0, 0, 1]};
+
+fn writer(core: &Core, reliability: ReliabilityKind_t, push_mode: bool) -> Writer {
+	Writer::new(GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER),
+		Vec::new(),
+		Vec::new(),
+		reliability,
+		TopicKind_t::WITH_KEY,
+		push_mode,
+		Time_t::new(1, 0),
+		Time_t::new(0, 0x8000_0000),
+		Time_t::new(0, 0x4000_0000),
+		participant(),
+		false,
+		&core.handle())
+}
+
+fn relevant(_: &CacheChange) -> bool {
+	true
+}
+
+fn reader(w: &Writer, locators: Vec<Locator_t>, push_mode: bool) -> ReaderProxy {
+	let changes = w.writer_cache().get_changes();
+	ReaderProxy::new(GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER),
+		false,
+		locators,
+		Vec::new(),
+		true,
+		&changes,
+		relevant,
+		relevant,
+		push_mode)
+}
+
+fn push(w: &mut Writer, value: &'static [u8]) -> Vec<(GUID_t, SendError)> {
+	w.push_change(ChangeKind_t::ALIVE, Bytes::from(value), HANDLE_NIL, None)
+}
+
+fn statuses(w: &Writer, guid: GUID_t, numbers: &[i64]) -> Vec<ChangeForReaderStatusKind> {
+	let proxy = w.matched_reader_lookup(guid).unwrap();
+	numbers.iter()
+		.map(|n| proxy.change_for_reader(SequenceNumber_t::from(*n)).unwrap().status())
+		.collect()
+}
+
+/// Sequence numbers of the Data, and the count of any Heartbeat, sent to
+/// reader.
+fn sent(messages: &[(GUID_t, Result<Datagram, SendError>)], reader: GUID_t)
+		-> (Vec<SequenceNumber_t>, Option<Count_t>) {
+	let mut data = Vec::new();
+	let mut heartbeat = None;
+	for &(guid, ref msg) in messages {
+		if guid != reader {
+			continue;
+		}
+		for s in parse_message(msg.as_ref().unwrap().message.clone()).unwrap().submessages {
+			match s {
+				Submessage::Data(d) => {
+					assert_eq!(&d.reader_id, reader.entity_id());
+					data.push(d.writer_sn);
+				},
+				Submessage::Heartbeat(h) => heartbeat = Some(h.count),
+				_ => {},
+			}
+		}
+	}
+	(data, heartbeat)
+}
+
+fn acknack(base: i64, missing: &[i64], count: i32) -> AckNack {
+	let set = SequenceNumberSet::from_numbers(SequenceNumber_t::from(base), sns(missing)).unwrap();
+	AckNack::new(false, ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER,
+		ENTITYID_SEDP_BUILTIN_PARTICIPANT_WRITER, set, From::from(count))
+}
+
+#[test]
+fn new_changes_are_unsent_to_every_reader() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, true);
+	push(&mut w, b"before");
+	let a = reader(&w, vec![UNICAST], true);
+	let b = reader(&w, vec![UNICAST], true);
+	let (a_guid, b_guid) = (*a.remote_reader_guid(), *b.remote_reader_guid());
+	w.matched_reader_add(a);
+	w.matched_reader_add(b);
+	push(&mut w, b"after");
+
+	for guid in &[a_guid, b_guid] {
+		assert_eq!(statuses(&w, *guid, &[1, 2]), vec![ChangeForReaderStatusKind::UNSENT; 2]);
+	}
+	let messages = w.messages(Time_t::new(10, 0));
+	assert_eq!(sent(&messages, a_guid), (sns(&[1, 2]), Some(Count_t::from(1))));
+	assert_eq!(sent(&messages, b_guid), (sns(&[1, 2]), Some(Count_t::from(1))));
+	assert!(w.messages(Time_t::new(10, 1)).is_empty());
+	assert!(w.matched_reader_lookup(a_guid).unwrap().unsent_changes().is_empty());
+}
+
+#[test]
+fn heartbeats_are_sent_each_period() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, false);
+	assert!(w.messages(Time_t::new(1, 0)).is_empty());
+
+	let r = reader(&w, vec![UNICAST], false);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+	push(&mut w, b"one");
+	assert_eq!(statuses(&w, guid, &[1]), vec![ChangeForReaderStatusKind::UNACKNOWLEDGED]);
+
+	// Without push mode, changes are only announced.
+	assert_eq!(sent(&w.messages(Time_t::new(10, 0)), guid), (Vec::new(), Some(Count_t::from(1))));
+	assert!(w.messages(Time_t::new(10, 0x8000_0000)).is_empty());
+	assert_eq!(sent(&w.messages(Time_t::new(11, 0)), guid), (Vec::new(), Some(Count_t::from(2))));
+	assert_eq!(sent(&w.messages(Time_t::new(15, 0)), guid), (Vec::new(), Some(Count_t::from(3))));
+}
+
+#[test]
+fn acknacks_acknowledge_and_request_changes() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, true);
+	let r = reader(&w, vec![UNICAST], true);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+	push(&mut w, b"one");
+	push(&mut w, b"two");
+	push(&mut w, b"three");
+	assert_eq!(sent(&w.messages(Time_t::new(10, 0)), guid), (sns(&[1, 2, 3]), Some(Count_t::from(1))));
+
+	// Once nack_suppression_duration has passed, requests are answered after
+	// nack_response_delay.
+	w.process_acknack(&acknack(2, &[2], 1), guid, Time_t::new(11, 0));
+	use rtps::ChangeForReaderStatusKind::*;
+	assert_eq!(statuses(&w, guid, &[1, 2, 3]), vec![ACKNOWLEDGED, REQUESTED, UNACKNOWLEDGED]);
+	assert_eq!(sent(&w.messages(Time_t::new(11, 0x4000_0000)), guid), (Vec::new(), Some(Count_t::from(2))));
+	assert_eq!(sent(&w.messages(Time_t::new(11, 0x8000_0000)), guid), (sns(&[2]), None));
+	assert_eq!(statuses(&w, guid, &[1, 2, 3]), vec![ACKNOWLEDGED, UNDERWAY, UNACKNOWLEDGED]);
+
+	// Repeated AckNacks are ignored.
+	w.process_acknack(&acknack(4, &[], 1), guid, Time_t::new(12, 0));
+	assert_eq!(statuses(&w, guid, &[1, 2, 3]), vec![ACKNOWLEDGED, UNDERWAY, UNACKNOWLEDGED]);
+	w.process_acknack(&acknack(4, &[], 2), guid, Time_t::new(12, 0));
+	assert_eq!(statuses(&w, guid, &[1, 2, 3]), vec![ACKNOWLEDGED; 3]);
+}
+
+#[test]
+fn requests_for_missing_changes_are_answered_with_gaps() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, true);
+	push(&mut w, b"one");
+	push(&mut w, b"two");
+	push(&mut w, b"tri");
+
+	// The reader was matched after the first changes were written.
+	let r = ReaderProxy::new(GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER),
+		false, vec![UNICAST], Vec::new(), true, &[], relevant, relevant, true);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+	push(&mut w, b"four");
+	assert_eq!(sent(&w.messages(Time_t::new(10, 0)), guid), (sns(&[4]), Some(Count_t::from(1))));
+
+	// Changes which haven't been written yet aren't gaps.
+	w.process_acknack(&acknack(1, &[1, 2, 4, 5], 1), guid, Time_t::new(10, 0x4000_0000));
+	let messages = w.messages(Time_t::new(10, 0xc000_0000));
+	assert_eq!(sent(&messages, guid), (sns(&[4]), None));
+	let gaps: Vec<(SequenceNumber_t, Vec<SequenceNumber_t>)> = messages.iter()
+		.flat_map(|m| parse_message(m.1.as_ref().unwrap().message.clone()).unwrap().submessages)
+		.filter_map(|s| match s {
+			Submessage::Gap(g) => Some((g.gap_start, g.gap_list.iter().collect())),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(gaps, vec![(SequenceNumber_t::from(1), sns(&[2]))]);
+	assert!(w.messages(Time_t::new(10, 0xc000_0000)).is_empty());
+}
+
+#[test]
+fn gaps_are_split_to_cover_any_range() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, true);
+	for _ in 0..300 {
+		push(&mut w, b"old");
+	}
+	let r = ReaderProxy::new(GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER),
+		false, vec![UNICAST], Vec::new(), true, &[], relevant, relevant, true);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+
+	// Requests build up across AckNacks until they are answered.
+	w.process_acknack(&acknack(1, &[1, 2], 1), guid, Time_t::new(10, 0));
+	w.process_acknack(&acknack(290, &[290], 2), guid, Time_t::new(10, 0));
+	let messages = w.messages(Time_t::new(11, 0));
+	let gaps: Vec<(SequenceNumber_t, Vec<SequenceNumber_t>)> = messages.iter()
+		.flat_map(|m| parse_message(m.1.as_ref().unwrap().message.clone()).unwrap().submessages)
+		.filter_map(|s| match s {
+			Submessage::Gap(g) => Some((g.gap_start, g.gap_list.iter().collect())),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(gaps, vec![(SequenceNumber_t::from(1), sns(&[2])), (SequenceNumber_t::from(290), Vec::new())]);
+}
+
+#[test]
+fn changes_too_large_to_send_keep_their_status() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, true);
+	let r = reader(&w, vec![UNICAST], true);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+	push(&mut w, &[0; 2000]);
+	push(&mut w, b"small");
+
+	let messages = w.messages(Time_t::new(10, 0));
+	match messages[0] {
+		(g, Err(SendError::Build(_))) => assert_eq!(g, guid),
+		_ => panic!("expected the change not to fit"),
+	}
+	assert_eq!(sent(&messages[1..], guid), (sns(&[2]), Some(Count_t::from(1))));
+	use rtps::ChangeForReaderStatusKind::*;
+	assert_eq!(statuses(&w, guid, &[1, 2]), vec![UNSENT, UNDERWAY]);
+}
+
+#[test]
+fn acknack_counts_wrap_around() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, false);
+	let r = reader(&w, vec![UNICAST], false);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+	push(&mut w, b"one");
+	push(&mut w, b"two");
+
+	use rtps::ChangeForReaderStatusKind::*;
+	w.process_acknack(&acknack(2, &[], i32::MAX), guid, Time_t::new(10, 0));
+	assert_eq!(statuses(&w, guid, &[1, 2]), vec![ACKNOWLEDGED, UNACKNOWLEDGED]);
+	w.process_acknack(&acknack(3, &[], i32::MAX - 1), guid, Time_t::new(10, 0));
+	assert_eq!(statuses(&w, guid, &[1, 2]), vec![ACKNOWLEDGED, UNACKNOWLEDGED]);
+	w.process_acknack(&acknack(3, &[], i32::MIN), guid, Time_t::new(10, 0));
+	assert_eq!(statuses(&w, guid, &[1, 2]), vec![ACKNOWLEDGED; 2]);
+}
+
+#[test]
+fn requests_for_underway_changes_are_suppressed() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, true);
+	let r = reader(&w, vec![UNICAST], true);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+	push(&mut w, b"one");
+	w.messages(Time_t::new(10, 0));
+
+	// The reader can't have received the change yet.
+	w.process_acknack(&acknack(1, &[1], 1), guid, Time_t::new(10, 0x1000_0000));
+	assert_eq!(statuses(&w, guid, &[1]), vec![ChangeForReaderStatusKind::UNDERWAY]);
+	assert!(w.messages(Time_t::new(10, 0xa000_0000)).is_empty());
+
+	w.process_acknack(&acknack(1, &[1], 2), guid, Time_t::new(10, 0xa000_0000));
+	assert_eq!(statuses(&w, guid, &[1]), vec![ChangeForReaderStatusKind::REQUESTED]);
+
+	// Readers without locators can't be sent to.
+	let lost = reader(&w, Vec::new(), true);
+	let lost_guid = *lost.remote_reader_guid();
+	w.matched_reader_add(lost);
+	let errors = w.send(Time_t::new(10, 0xb000_0000));
+	assert_eq!(errors.len(), 1);
+	match errors[0] {
+		(g, SendError::NoLocator) => assert_eq!(g, lost_guid),
+		_ => panic!("expected no locator"),
+	}
+}
+
+#[test]
+fn best_effort_writers_send_changes_as_they_are_pushed() {
+	let core = Core::new().unwrap();
+	let mut w = writer(&core, ReliabilityKind_t::BEST_EFFORT, false);
+	let other = Locator_t::Other {kind: 0x8000_0001u32 as i32, port: 1, address: [0; 16]};
+	let another = Locator_t::Other {kind: 0x8000_0001u32 as i32, port: 2, address: [0; 16]};
+	let a = reader(&w, vec![other, another], false);
+	let b = reader(&w, Vec::new(), false);
+	let (a_guid, b_guid) = (*a.remote_reader_guid(), *b.remote_reader_guid());
+	w.matched_reader_add(a);
+	w.matched_reader_add(b);
+
+	// Errors sending to one locator or reader don't stop sending to the
+	// rest.
+	let errors = push(&mut w, b"one");
+	assert_eq!(errors.len(), 3);
+	match (&errors[0], &errors[1], &errors[2]) {
+		(&(g, SendError::UnsupportedLocator(l)),
+		 &(h, SendError::UnsupportedLocator(m)),
+		 &(i, SendError::NoLocator)) => {
+			assert_eq!((g, h, i), (a_guid, a_guid, b_guid));
+			assert_eq!((l, m), (other, another));
+		},
+		_ => panic!("expected errors for every locator"),
+	}
+	assert_eq!(statuses(&w, a_guid, &[1]), vec![ChangeForReaderStatusKind::UNDERWAY]);
+	assert!(w.messages(Time_t::new(10, 0)).is_empty());
+
+	// Best-effort writers ignore AckNacks.
+	w.process_acknack(&acknack(1, &[1], 1), a_guid, Time_t::new(10, 0));
+	assert_eq!(statuses(&w, a_guid, &[1]), vec![ChangeForReaderStatusKind::UNDERWAY]);
+	assert!(w.messages(Time_t::new(20, 0)).is_empty());
+}
+
+/// Receives a datagram sent to socket, as sent to reader.
+fn received(socket: &UdpSocket, reader: GUID_t) -> Vec<(GUID_t, Result<Datagram, SendError>)> {
+	let mut buf = [0; 1024];
+	let len = socket.recv(&mut buf).unwrap();
+	let datagram = Datagram {message: Bytes::from(&buf[..len]), changes: Vec::new(), gaps: Vec::new()};
+	vec![(reader, Ok(datagram))]
+}
+
+#[test]
+fn best_effort_changes_reach_reader_locators() {
+	let mut core = Core::new().unwrap();
+	let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+	socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	let port = socket.local_addr().unwrap().port();
+	let locator = Locator_t::UDP_V4 {port, address: [127, 0, 0, 1]};
+
+	let mut w = writer(&core, ReliabilityKind_t::BEST_EFFORT, true);
+	let r = reader(&w, vec![locator], true);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+
+	// The socket may not be ready for the first datagram, in which case the
+	// change remains unsent, and the task is woken once it is.
+	let mut attempts = 0;
+	core.run(future::poll_fn(|| {
+		attempts += 1;
+		let errors = if attempts == 1 {
+			push(&mut w, b"sample")
+		} else {
+			w.send(Time_t::new(10, attempts))
+		};
+		if errors.is_empty() {
+			return Ok(Async::Ready(()));
+		}
+		match errors[0] {
+			(g, SendError::Io(_)) => assert_eq!(g, guid),
+			_ => panic!("expected the socket not to be ready"),
+		}
+		assert_eq!(statuses(&w, guid, &[1]), vec![ChangeForReaderStatusKind::UNSENT]);
+		assert!(attempts < 10);
+		Ok::<_, ()>(Async::NotReady)
+	})).unwrap();
+	assert_eq!(statuses(&w, guid, &[1]), vec![ChangeForReaderStatusKind::UNDERWAY]);
+	assert_eq!(sent(&received(&socket, guid), guid), (sns(&[1]), None));
+}
+
+#[test]
+fn requested_changes_reach_reader_locators() {
+	let mut core = Core::new().unwrap();
+	let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+	socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	let port = socket.local_addr().unwrap().port();
+	let locator = Locator_t::UDP_V4 {port, address: [127, 0, 0, 1]};
+
+	let mut w = writer(&core, ReliabilityKind_t::RELIABLE, true);
+	push(&mut w, b"one");
+	let r = ReaderProxy::new(GUID_t::new(GUIDPrefix_t::new(), ENTITYID_SEDP_BUILTIN_PARTICIPANT_READER),
+		false, vec![locator], Vec::new(), true, &[], relevant, relevant, true);
+	let guid = *r.remote_reader_guid();
+	w.matched_reader_add(r);
+	push(&mut w, b"two");
+	w.messages(Time_t::new(10, 0));
+	w.process_acknack(&acknack(1, &[1, 2], 1), guid, Time_t::new(11, 0));
+
+	// If the socket isn't ready, the change is requested again, as is the
+	// Gap, rather than either being lost.
+	let mut attempts = 0;
+	core.run(future::poll_fn(|| {
+		attempts += 1;
+		let errors = w.send(Time_t::new(12, attempts));
+		if errors.is_empty() {
+			return Ok(Async::Ready(()));
+		}
+		assert_eq!(statuses(&w, guid, &[2]), vec![ChangeForReaderStatusKind::REQUESTED]);
+		assert!(attempts < 10);
+		Ok::<_, ()>(Async::NotReady)
+	})).unwrap();
+	assert_eq!(statuses(&w, guid, &[2]), vec![ChangeForReaderStatusKind::UNDERWAY]);
+
+	let messages = received(&socket, guid);
+	assert_eq!(sent(&messages, guid).0, sns(&[2]));
+	let gaps: Vec<SequenceNumber_t> = parse_message(messages[0].1.as_ref().unwrap().message.clone())
+		.unwrap().submessages.into_iter()
+		.filter_map(|s| match s {
+			Submessage::Gap(g) => Some(g.gap_start),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(gaps, sns(&[1]));
+}